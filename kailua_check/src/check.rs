@@ -2,6 +2,7 @@ use std::i32;
 use std::cmp;
 use std::ops;
 use std::str;
+use std::mem;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use take_mut::take;
@@ -9,12 +10,14 @@ use take_mut::take;
 use kailua_env::{Span, Spanned, WithLoc};
 use kailua_diag::{self, Result, Report, Reporter};
 use kailua_syntax::{Str, Name};
+use kailua_syntax::lex::NumSuffix;
 use kailua_syntax::ast::{self, NameRef, Var, TypeSpec, Kind, Sig, Ex, Exp, UnOp, BinOp, Table};
 use kailua_syntax::ast::{SelfParam, TypeScope, Args, St, Stmt, Block, K, Attr, M, MM, Varargs};
-use kailua_types::diag::{TypeReport, TypeReportHint, TypeReportMore};
-use kailua_types::ty::{Displayed, Display, TypeContext, TypeResolver};
+use kailua_types::diag::{TypeReport, TypeReportHint, TypeReportMore, TypeResult};
+use kailua_types::ty::{Displayed, Display, DisplayName, TypeContext, TypeResolver};
+use kailua_types::ty::TypeParamResolver;
 use kailua_types::ty::{Dyn, Nil, T, Ty, TySeq, SpannedTySeq, Lattice, Union, Dummy};
-use kailua_types::ty::{Key, Tables, Function, Functions};
+use kailua_types::ty::{Key, RVar, Tables, Function, Functions};
 use kailua_types::ty::{F, Slot, SlotSeq, SpannedSlotSeq, Tag, Class, ClassId};
 use kailua_types::ty::flags::*;
 use kailua_types::env::Types;
@@ -156,6 +159,81 @@ enum Cond {
     Not(Box<Cond>),
 }
 
+impl Cond {
+    fn negate(self) -> Cond {
+        match self {
+            Cond::Not(cond) => *cond,
+            Cond::Flags(info, flags) => Cond::Flags(info, T_ALL & !flags),
+            cond => Cond::Not(Box::new(cond)),
+        }
+    }
+
+    // returns the same condition with every slot replaced by `f`
+    fn map_slots<F: FnMut(&Spanned<Slot>) -> Spanned<Slot>>(&self, f: &mut F) -> Cond {
+        match *self {
+            Cond::Flags(ref info, flags) => Cond::Flags(f(info), flags),
+            Cond::And(ref lcond, ref rcond) => {
+                Cond::And(Box::new(lcond.map_slots(f)), Box::new(rcond.map_slots(f)))
+            }
+            Cond::Or(ref lcond, ref rcond) => {
+                Cond::Or(Box::new(lcond.map_slots(f)), Box::new(rcond.map_slots(f)))
+            }
+            Cond::Not(ref cond) => Cond::Not(Box::new(cond.map_slots(f))),
+        }
+    }
+}
+
+// finds the original slot if the slot is a narrowed copy in the list
+fn unnarrowed_slot(narrowed: &[(Slot, Option<Slot>)], slot: &Slot) -> Slot {
+    for &(ref orig, ref copy) in narrowed.iter().rev() {
+        if copy.as_ref().map_or(false, |copy| copy.is_same_as(slot)) {
+            return orig.clone();
+        }
+    }
+    slot.clone()
+}
+
+// the metamethod name for given unary operator, if any
+fn un_op_metamethod(op: UnOp) -> Option<&'static [u8]> {
+    match op {
+        UnOp::Neg => Some(b"__unm"),
+        UnOp::Len => Some(b"__len"),
+        UnOp::BNot => Some(b"__bnot"),
+        UnOp::Not => None,
+    }
+}
+
+// the metamethod name for given binary operator, if any,
+// and whether the operands should be swapped before calling the metamethod
+fn bin_op_metamethod(op: BinOp) -> Option<(&'static [u8], bool)> {
+    match op {
+        BinOp::Add => Some((b"__add", false)),
+        BinOp::Sub => Some((b"__sub", false)),
+        BinOp::Mul => Some((b"__mul", false)),
+        BinOp::Div => Some((b"__div", false)),
+        BinOp::Mod => Some((b"__mod", false)),
+        BinOp::Pow => Some((b"__pow", false)),
+        BinOp::IDiv => Some((b"__idiv", false)),
+        BinOp::BAnd => Some((b"__band", false)),
+        BinOp::BOr => Some((b"__bor", false)),
+        BinOp::BXor => Some((b"__bxor", false)),
+        BinOp::Shl => Some((b"__shl", false)),
+        BinOp::Shr => Some((b"__shr", false)),
+        BinOp::Cat => Some((b"__concat", false)),
+        BinOp::Eq | BinOp::Ne => Some((b"__eq", false)),
+        BinOp::Lt => Some((b"__lt", false)),
+        BinOp::Le => Some((b"__le", false)),
+        BinOp::Gt => Some((b"__lt", true)),
+        BinOp::Ge => Some((b"__le", true)),
+        BinOp::And | BinOp::Or => None,
+    }
+}
+
+// the type of LuaJIT 64-bit integer cdata
+fn int64_ty() -> Ty {
+    Ty::new(T::UserData).with_tag(Tag::Int64)
+}
+
 #[derive(Copy, Clone, Debug)]
 enum Bool {
     Unknown,
@@ -224,12 +302,21 @@ impl<'inp> PendingModules<'inp> {
 pub struct Checker<'inp, 'envr, 'env: 'envr, R: 'env> {
     env: &'envr mut Env<'env, R>,
     pending_modules: Vec<PendingModules<'inp>>,
+
+    // original slots and their narrowed copies, only applied when the slots are read.
+    // the copy is None when the original slot has been assigned after the narrowing.
+    narrowed: Vec<(Slot, Option<Slot>)>,
+
+    // the number of enclosing regions in the current function that may not be executed,
+    // e.g. `if` and loop bodies. metatables are tracked per table type regardless of
+    // the control flow, so `setmetatable` to existing tables is ignored inside them.
+    conditional: usize,
 }
 
 impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
     /// Creates a new checker from the per-file context.
     pub fn new(env: &'envr mut Env<'env, R>) -> Checker<'inp, 'envr, 'env, R> {
-        Checker { env: env, pending_modules: Vec::new() }
+        Checker { env: env, pending_modules: Vec::new(), narrowed: Vec::new(), conditional: 0 }
     }
 
     fn types(&mut self) -> &mut Types {
@@ -289,7 +376,18 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             }
         }
 
+        if let Some(name) = un_op_metamethod(op) {
+            if let Some(method) = self.get_metamethod(&info.unlift(), name) {
+                let args = SpannedSlotSeq::from(info.clone()).unlift();
+                return self.call_metamethod(&method.with_loc(expspan), args);
+            }
+        }
+
         match op {
+            UnOp::Neg if self.is_int64(&info.unlift()) => {
+                Ok(Slot::just(int64_ty()))
+            }
+
             UnOp::Neg => {
                 assert_sub!(&info, &T::Number);
 
@@ -312,6 +410,13 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 assert_sub!(&info, &(T::table() | T::String));
                 Ok(Slot::just(Ty::new(T::Integer)))
             }
+
+            UnOp::BNot => {
+                // technically a float with an exact integer representation is also allowed,
+                // but that's probably not what you want
+                assert_sub!(&info, &T::Integer);
+                Ok(Slot::just(Ty::new(T::Integer)))
+            }
         }
     }
 
@@ -359,8 +464,62 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             }
         }
 
+        // Lua only calls `__eq` when both operands are tables or userdata
+        let is_object = |slot: &Spanned<Slot>, checker: &mut Checker<R>| {
+            let flags = checker.env.get_type_bounds(&slot.unlift()).1;
+            let objflags = T_TABLE | T_USERDATA;
+            flags.intersects(objflags) && !flags.intersects(!(objflags | T_NOISY_NIL))
+        };
+        let use_metamethod = match op {
+            BinOp::Eq | BinOp::Ne => is_object(lhs, self) && is_object(rhs, self),
+            _ => true,
+        };
+
+        if let (true, Some((name, swapped))) = (use_metamethod, bin_op_metamethod(op)) {
+            // `a > b` and `a >= b` are same to `b < a` and `b <= a` respectively
+            let (lhs, rhs) = if swapped { (rhs, lhs) } else { (lhs, rhs) };
+
+            // the left operand is tried first as in Lua
+            let method = match self.get_metamethod(&lhs.unlift(), name) {
+                Some(method) => Some(method),
+                None => self.get_metamethod(&rhs.unlift(), name),
+            };
+
+            if let Some(method) = method {
+                let mut args = SpannedSlotSeq::from(lhs.clone());
+                args.head.push(rhs.clone());
+                let ret = self.call_metamethod(&method.with_loc(expspan), args.unlift())?;
+                return match op {
+                    // comparison operators always result in a boolean
+                    BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge =>
+                        Ok(Slot::just(Ty::new(T::Boolean))),
+                    _ => Ok(ret),
+                };
+            }
+        }
+
+        // LuaJIT 64-bit integers can be mixed with numbers, resulting in 64-bit integers
+        let lint64 = self.is_int64(&lhs.unlift());
+        let rint64 = self.is_int64(&rhs.unlift());
+        let int64_result = match op {
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div |
+            BinOp::Mod | BinOp::Pow | BinOp::IDiv => Some(int64_ty()),
+            BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => Some(Ty::new(T::Boolean)),
+            _ => None,
+        };
+        if let (true, Some(ty)) = (lint64 || rint64, int64_result) {
+            for &(operand, int64) in &[(lhs, lint64), (rhs, rint64)] {
+                if !int64 {
+                    if let Err(r) = operand.assert_sub(&T::Number, self.types()) {
+                        finalize(r, self)?;
+                    }
+                }
+            }
+            return Ok(Slot::just(ty));
+        }
+
         match op {
-            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Mod => {
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Mod | BinOp::IDiv => {
                 // ? + integer = integer, ? + number = ? + ? = number, number + integer = number
                 // see UnOp::Neg comment for the rationale
                 let lflags = self.env.get_type_bounds(&lhs.unlift()).1;
@@ -380,10 +539,17 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             }
 
             BinOp::Div | BinOp::Pow => {
+                // always results in a float, even when both operands are integers
                 assert_sub_both!(lhs, rhs, &T::Number);
                 Ok(Slot::just(Ty::new(T::Number)))
             }
 
+            BinOp::BAnd | BinOp::BOr | BinOp::BXor | BinOp::Shl | BinOp::Shr => {
+                // see UnOp::BNot comment for the rationale
+                assert_sub_both!(lhs, rhs, &T::Integer);
+                Ok(Slot::just(Ty::new(T::Integer)))
+            }
+
             BinOp::Cat => {
                 assert_sub_both!(lhs, rhs, &(T::Number | T::String));
 
@@ -522,32 +688,56 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             return Ok(Exitable::dummy());
         };
 
-        // check if generalize(func.args) :> args and gather generalize(func.returns)
+        let hint = if methodcall { TypeReportHint::MethodArgs } else { TypeReportHint::FuncArgs };
+
         let mut returns = match *functy.get_functions().unwrap() {
             Functions::Simple(ref f) => {
-                let generalize_tyseq = |seq: &TySeq, ctx: &mut TypeContext| {
-                    let head = seq.head.iter().map(|t| t.clone().generalize(ctx)).collect();
-                    let tail = seq.tail.as_ref().map(|t| t.clone().generalize(ctx));
-                    TySeq { head: head, tail: tail }
-                };
+                match self.match_callable(func, f, args) {
+                    Ok(Some(returns)) => returns,
+                    Ok(None) => return Ok(Exitable::diverging()),
+                    Err(r) => {
+                        self.env.error(func, m::CallToWrongType { func: self.display(func) })
+                                .report_types(r, hint)
+                                .done()?;
+                        return Ok(Exitable::dummy());
+                    },
+                }
+            },
 
-                let funcargs = generalize_tyseq(&f.args, self.types()).all_with_loc(func);
-                if let Err(r) = args.assert_sub(&funcargs, self.types()) {
-                    let hint = if methodcall {
-                        TypeReportHint::MethodArgs
-                    } else {
-                        TypeReportHint::FuncArgs
-                    };
-                    self.env.error(func, m::CallToWrongType { func: self.display(func) })
-                            .report_types(r, hint)
-                            .done()?;
-                    return Ok(Exitable::dummy());
+            // the first overload accepting given arguments is chosen.
+            // constraints made while checking non-matching overloads are rolled back.
+            Functions::Overloaded(ref fs) => {
+                let mut reports = Vec::new();
+                let mut matched = None;
+                for f in fs {
+                    self.types().save_constraints();
+                    match self.match_callable(func, f, args) {
+                        Ok(returns) => {
+                            self.types().commit_constraints();
+                            matched = Some(returns);
+                            break;
+                        }
+                        Err(r) => {
+                            self.types().rollback_constraints();
+                            reports.push((f, r));
+                        }
+                    }
                 }
 
-                if let Some(ref returns) = f.returns {
-                    generalize_tyseq(returns, self.types())
-                } else {
-                    return Ok(Exitable::diverging());
+                match matched {
+                    Some(Some(returns)) => returns,
+                    Some(None) => return Ok(Exitable::diverging()),
+                    None => {
+                        let mut more = self.env.error(func, m::CallToNoMatchingOverload {
+                            func: self.display(func),
+                        });
+                        for (f, r) in reports {
+                            more = more.note(func, m::OverloadNotMatched { func: self.display(f) })
+                                       .report_types(r, hint);
+                        }
+                        more.done()?;
+                        return Ok(Exitable::dummy());
+                    },
                 }
             },
 
@@ -575,13 +765,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                         Ty::new(T::Integer | T::Str(Cow::Owned(Str::from(b"n"[..].to_owned()))))
                     };
 
+                    // map<k, v> is handled by the generic overload
                     match tab.clone().unwrap() {
-                        // map<k, v> -> (k, v)
-                        T::Tables(Cow::Owned(Tables::Map(k, v))) =>
-                            (k, v.with_nil()),
-                        T::Tables(Cow::Borrowed(&Tables::Map(ref k, ref v))) =>
-                            (k.clone(), v.clone().with_nil()),
-
                         // vector<v> -> (integer, v)
                         T::Tables(Cow::Owned(Tables::Array(v))) =>
                             (Ty::new(T::Integer), v.with_nil()),
@@ -614,6 +799,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let knil = k.clone().with_nil();
                 let v = v.unlift().clone().without_nil();
                 *returns.ensure_at_mut(0) = Ty::new(T::func(Function {
+                    tparams: Vec::new(),
                     args: TySeq { head: vec![tab.clone(), k.clone()], tail: None },
                     argnames: Vec::new(),
                     returns: Some(TySeq { head: vec![knil, v], tail: None }),
@@ -626,6 +812,57 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         Ok(Exitable::new(returns))
     }
 
+    // checks if the function can be called with given arguments and returns the return types
+    // (or `None` if the function diverges) without reporting any error.
+    //
+    // the non-generic function is checked against generalize(func.args) :> args and
+    // returns generalize(func.returns); the generic function is instantiated first and
+    // its type parameters are resolved from arguments.
+    fn match_callable(&mut self, func: &Spanned<Ty>, f: &Function,
+                      args: &SpannedTySeq) -> TypeResult<Option<TySeq>> {
+        if f.tparams.is_empty() {
+            let generalize_tyseq = |seq: &TySeq, ctx: &mut TypeContext| {
+                let head = seq.head.iter().map(|t| t.clone().generalize(ctx)).collect();
+                let tail = seq.tail.as_ref().map(|t| t.clone().generalize(ctx));
+                TySeq { head: head, tail: tail }
+            };
+
+            let funcargs = generalize_tyseq(&f.args, self.types()).all_with_loc(func);
+            args.assert_sub(&funcargs, self.types())?;
+            return Ok(f.returns.as_ref().map(|returns| generalize_tyseq(returns, self.types())));
+        }
+
+        let (tvars, f) = f.instantiate(self.types());
+
+        // the literal type is coerced when it directly binds a type parameter,
+        // so that `function<T>(T, T)` can be called with two different literals
+        let mut args = args.clone();
+        for (i, arg) in args.head.iter_mut().enumerate() {
+            let param = if i < f.args.head.len() {
+                Some(&f.args.head[i])
+            } else {
+                f.args.tail.as_ref()
+            };
+            if param.and_then(|t| t.get_tvar()).map_or(false, |tv| tvars.contains(&tv)) {
+                take(&mut arg.base, |t| t.coerce());
+            }
+        }
+
+        let funcargs = f.args.clone().all_with_loc(func);
+        args.assert_sub(&funcargs, self.types())?;
+
+        // each type parameter resolves to the exact type, or the lower bound from arguments;
+        // any unconstrained type parameter is assumed to be `WHATEVER`
+        let resolved: Vec<_> = tvars.iter().map(|&tv| {
+            let ty = self.env.get_tvar_exact_type(tv)
+                             .or_else(|| self.types().get_tvar_lower_bound(tv))
+                             .unwrap_or_else(|| Ty::new(T::Dynamic(Dyn::User)));
+            (tv, ty)
+        }).collect();
+
+        Ok(f.returns.map(|returns| returns.substitute(&resolved, self.types()).unwrap_or(returns)))
+    }
+
     fn cannot_index(&self, span: Span, tab: &Slot, key: &Slot) -> Result<()> {
         // use a special message when the table is a record and key is a string literal
         match (tab.unlift().get_tables(), key.unlift().as_string()) {
@@ -827,8 +1064,12 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                             (vslot, true)
                         },
 
-                        // the field does not exist and is used as an r-value, return nothing
-                        (None, false) => return Ok(Index::Missing),
+                        // the field does not exist and is used as an r-value,
+                        // try the `__index` metamethod or return nothing
+                        (None, false) => {
+                            return self.check_index_metamethod(&ety0, rvar.clone(), litkey,
+                                                               kty0, expspan);
+                        },
                     };
 
                     vslot.adapt(ety0.flex(), self.types());
@@ -900,6 +1141,141 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         }
     }
 
+    // looks up a missing field in the record through the chain of `__index` metamethods
+    fn check_index_metamethod(&mut self, ety0: &Spanned<Slot>, rvar: RVar, litkey: Key,
+                              kty0: &Spanned<Slot>, expspan: Span) -> Result<Index> {
+        // `__index` may refer to the record itself (e.g. `Proto.__index = Proto`)
+        let mut visited = vec![rvar];
+
+        loop {
+            let last = visited.last().unwrap().clone();
+            let index = match self.get_rvar_metamethod(last, b"__index") {
+                Some(index) => index,
+                None => return Ok(Index::Missing),
+            };
+
+            let indexty = if let Some(ty) = self.env.resolve_exact_type(&index.unlift()) {
+                ty
+            } else {
+                self.env.error(expspan, m::IndexToInexactType { tab: self.display(&index) })
+                        .done()?;
+                return Ok(Index::dummy());
+            };
+
+            if let Some(dyn) = indexty.get_dynamic() {
+                return Ok(Index::Found(Slot::just(Ty::new(T::Dynamic(dyn)))));
+            }
+
+            // `__index` is a function: `__index(table, key)` gives the value
+            if self.env.get_type_bounds(&indexty).1.is_callable() {
+                let mut args = SpannedSlotSeq::from(ety0.clone());
+                args.head.push(kty0.clone());
+                let value = self.call_metamethod(&index.with_loc(expspan), args.unlift())?;
+                return Ok(Index::Found(value));
+            }
+
+            // `__index` is a record: look up its fields, then its own metatable
+            if let Some(&Tables::Fields(ref next)) = indexty.get_tables() {
+                if visited.contains(next) {
+                    return Ok(Index::Missing);
+                }
+
+                let mut vslot = None;
+                let _ = self.env.context().list_rvar_fields(next.clone(), &mut |k, v| {
+                    if *k == litkey {
+                        vslot = Some(v.clone());
+                        Err(())
+                    } else {
+                        Ok(())
+                    }
+                });
+                if let Some(vslot) = vslot {
+                    return Ok(Index::Found(vslot));
+                }
+
+                visited.push(next.clone());
+                continue;
+            }
+
+            // any other type (e.g. class prototypes) is indexed as usual
+            return self.check_index_common(&index.with_loc(ety0), kty0, expspan, false);
+        }
+    }
+
+    // returns the metamethod in the metatable associated to the record, if any.
+    // this is a raw access and does not go through the `__index` metamethod of the metatable.
+    fn get_rvar_metamethod(&mut self, rvar: RVar, name: &[u8]) -> Option<Slot> {
+        let meta = if let Some(meta) = self.env.context().get_rvar_metatable(rvar) {
+            meta
+        } else {
+            return None;
+        };
+
+        let metaty = if let Some(ty) = self.env.resolve_exact_type(&meta.unlift()) {
+            ty
+        } else {
+            return None;
+        };
+
+        if let Some(&Tables::Fields(ref metarvar)) = metaty.get_tables() {
+            let name = Key::from(Str::from(name.to_owned()));
+            let mut method = None;
+            let _ = self.env.context().list_rvar_fields(metarvar.clone(), &mut |k, v| {
+                if *k == name {
+                    method = Some(v.clone());
+                    Err(())
+                } else {
+                    Ok(())
+                }
+            });
+            method
+        } else {
+            None
+        }
+    }
+
+    // returns the metamethod for given type, which should be a record with a metatable
+    fn get_metamethod(&mut self, ty: &Ty, name: &[u8]) -> Option<Slot> {
+        let ty = if let Some(ty) = self.env.resolve_exact_type(ty) {
+            ty
+        } else {
+            return None;
+        };
+
+        if ty.is_dynamic() {
+            return None;
+        }
+        if let Some(&Tables::Fields(ref rvar)) = ty.get_tables() {
+            self.get_rvar_metamethod(rvar.clone(), name)
+        } else {
+            None
+        }
+    }
+
+    // returns true if the type is a LuaJIT 64-bit integer cdata
+    fn is_int64(&self, ty: &Ty) -> bool {
+        if ty.tag() == Some(Tag::Int64) {
+            return true;
+        }
+        self.env.resolve_exact_type(ty).map_or(false, |ty| ty.tag() == Some(Tag::Int64))
+    }
+
+    // calls the metamethod with given arguments and returns the first return value
+    fn call_metamethod(&mut self, method: &Spanned<Slot>, args: SpannedTySeq) -> Result<Slot> {
+        let methodty = method.unlift().clone().with_loc(method);
+        if let Some(dyn) = methodty.get_dynamic() {
+            return Ok(Slot::just(Ty::new(T::Dynamic(dyn))));
+        }
+        if !self.env.get_type_bounds(&methodty).1.is_callable() {
+            self.env.error(&methodty, m::CallToNonFunc { func: self.display(&methodty) })
+                    .done()?;
+            return Ok(Slot::dummy());
+        }
+
+        let Exitable(_, returns) = self.check_callable(&methodty, &args, false)?;
+        Ok(Slot::just(returns.into_first()))
+    }
+
     fn check_rval_index(&mut self, ety: &Spanned<Slot>, kty: &Spanned<Slot>,
                         expspan: Span) -> Result<Slot> {
         match self.check_index_common(ety, kty, expspan, false)? {
@@ -1203,7 +1579,16 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     } else {
                         // variable assignment
                         if let Some(info) = info {
-                            Some(self.env.assign_to_var(nameref, info)?)
+                            let slot = self.env.assign_to_var(nameref, info)?;
+
+                            // narrowed copies are keyed by the variable slot, not the new value
+                            let varslot = self.env.get_var(nameref)
+                                                  .and_then(|def| def.slot.slot().cloned());
+                            if let Some(varslot) = varslot {
+                                self.forget_narrowed(&varslot);
+                            }
+
+                            Some(slot)
                         } else {
                             None
                         }
@@ -1227,6 +1612,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     }
                     if let Some(info) = info {
                         self.assign_to_lval_index(&ety, &kty, &lvalue, &info, specinfo.as_ref())?;
+                        self.forget_narrowed(&lvalue.slot);
                     }
                     // allow lhs to be recorded even when info is missing (for completion)
                     self.context().spanned_slots_mut().insert(lvalue.slot);
@@ -1237,25 +1623,29 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         exprexit.to_stmt(stmtspan, self.env)
     }
 
-    #[cfg(feature = "no_implicit_func_sig")]
     fn error_on_implicit_sig(&mut self, sig: &Sig) -> Result<()> {
-        if sig.args.head.iter().any(|spec| spec.kind.is_none()) {
+        // the strict mode reports each argument instead
+        if self.env.config().no_implicit_func_sig && !self.env.is_strict() &&
+           sig.args.head.iter().any(|spec| spec.kind.is_none()) {
             self.env.error(&sig.args, m::ImplicitSigOnNamedFunc {}).done()?;
         }
         Ok(())
     }
 
-    #[cfg(not(feature = "no_implicit_func_sig"))]
-    fn error_on_implicit_sig(&mut self, _sig: &Sig) -> Result<()> {
-        Ok(())
-    }
-
     /// Type-checks a given chunk (here is same to the top-level block).
     pub fn visit(&mut self, chunk: &'inp Spanned<Block>) -> Result<()> {
         self.visit_block(chunk)?;
         Ok(())
     }
 
+    /// Same to `visit` but returns true if the chunk never finishes normally.
+    ///
+    /// This is required to check the chunk as a module, as in `Env::return_from_module`.
+    pub fn visit_module(&mut self, chunk: &'inp Spanned<Block>) -> Result<bool> {
+        let exit = self.visit_block(chunk)?;
+        Ok(exit >= Exit::Stop)
+    }
+
     fn visit_block(&mut self, block: &'inp Spanned<Block>) -> Result<Exit> {
         // `self.pending_modules` should be kept in sync, even when the checking fails
         self.pending_modules.push(PendingModules::new());
@@ -1319,7 +1709,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 exit = self.visit_stmt(stmt)?;
             }
         }
-        #[cfg(feature = "warn_on_dead_code")] {
+        if self.env.config().warn_on_dead_code {
             if let Some(span) = ignored_stmts {
                 self.env.warn(span, m::DeadCode {}).done()?;
             }
@@ -1346,7 +1736,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             St::Do(ref block) => self.visit_block(block),
 
             St::While(ref cond, ref block) => {
-                let (mut exit, ty) = self.visit_exp_from_stmt(cond, None)?;
+                let Exitable(exit, (cond_, ty)) = self.collect_conds_from_exp(cond)?;
+                let mut exit = exit.to_stmt(cond.span, self.env)?;
                 let boolean = self.check_bool(ty.unspan().unlift());
 
                 // the "normal" exit when the loop body doesn't do anything special
@@ -1357,14 +1748,15 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 };
 
                 // warn if the block has no chance to run
-                #[cfg(feature = "warn_on_dead_code")] {
+                if self.env.config().warn_on_dead_code {
                     if normal_exit.is_none() || exit >= Exit::Break {
                         self.env.warn(block, m::DeadCode {}).done()?;
                     }
                 }
 
                 if let Some(normal_exit) = normal_exit {
-                    exit &= self.visit_block(block)?;
+                    // the body is only executed when the condition is truthy
+                    exit &= self.visit_block_with_cond(block, &cond_)?;
                     exit = exit.loop_boundary(normal_exit);
                 }
                 Ok(exit)
@@ -1393,49 +1785,62 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let mut exit = Exit::Stop; // (C1 & B1) | ... | (C1 & ... & Ck & Bk)
                 let mut condexit = Exit::None; // C1 & ... & Ck
 
+                // each block is checked with its condition being truthy and
+                // all preceding conditions being falsy; they are restored after the statement.
                 let mut ignored_blocks = None; // or Some((first truthy cond span, blocks span))
-                for &Spanned { base: (ref cond, ref block), span } in conds {
-                    // do not update the exit, as blocks after a truthy condition will be ignored
-                    if let Some((_, ref mut blocks_span)) = ignored_blocks {
-                        *blocks_span |= span;
-                        continue;
-                    }
-
-                    let (condexit_, ty) = self.visit_exp_from_stmt(cond, None)?;
-                    condexit &= condexit_;
-                    let boolean = self.check_bool(ty.unspan().unlift());
-                    match boolean {
-                        Bool::Truthy => {
-                            ignored_blocks = Some((cond.span, Span::dummy()));
-                            exit |= condexit & self.visit_block(block)?;
+                let mark = self.narrowed.len();
+                let result = (|| {
+                    for &Spanned { base: (ref cond, ref block), span } in conds {
+                        // do not update the exit, as blocks after a truthy condition will be ignored
+                        if let Some((_, ref mut blocks_span)) = ignored_blocks {
+                            *blocks_span |= span;
+                            continue;
                         }
-                        Bool::Falsy => {
-                            #[cfg(feature = "warn_on_useless_conds")] {
-                                self.env.warn(span, m::IgnoredIfCase {})
-                                        .note(cond, m::IfCaseWithFalsyCond {})
-                                        .done()?;
+
+                        let Exitable(condexit_, (cond_, ty)) = self.collect_conds_from_exp(cond)?;
+                        condexit &= condexit_.to_stmt(cond.span, self.env)?;
+                        let boolean = self.check_bool(ty.unspan().unlift());
+                        match boolean {
+                            Bool::Truthy => {
+                                ignored_blocks = Some((cond.span, Span::dummy()));
+                                exit |= condexit & self.visit_block_with_cond(block, &cond_)?;
+                            }
+                            Bool::Falsy => {
+                                if self.env.config().warn_on_useless_conds {
+                                    self.env.warn(span, m::IgnoredIfCase {})
+                                            .note(cond, m::IfCaseWithFalsyCond {})
+                                            .done()?;
+                                }
+                                exit |= condexit;
+                            }
+                            Bool::Unknown => {
+                                exit |= condexit & self.visit_block_with_cond(block, &cond_)?;
                             }
-                            exit |= condexit;
                         }
-                        Bool::Unknown => {
-                            exit |= condexit & self.visit_block(block)?;
+
+                        if let Some(cond_) = cond_ {
+                            self.narrow_by_cond(&cond_, true);
                         }
                     }
-                }
 
-                if let &Some(ref block) = lastblock {
-                    if let Some((_, ref mut blocks_span)) = ignored_blocks {
-                        *blocks_span |= block.span;
+                    if let &Some(ref block) = lastblock {
+                        if let Some((_, ref mut blocks_span)) = ignored_blocks {
+                            *blocks_span |= block.span;
+                        } else {
+                            exit |= condexit & self.visit_conditional_block(block)?;
+                        }
                     } else {
-                        exit |= condexit & self.visit_block(block)?;
-                    }
-                } else {
-                    if ignored_blocks.is_none() {
-                        exit |= condexit;
+                        if ignored_blocks.is_none() {
+                            exit |= condexit;
+                        }
                     }
-                }
 
-                #[cfg(feature = "warn_on_useless_conds")] {
+                    Ok(())
+                })();
+                self.restore_narrowed(mark);
+                result?;
+
+                if self.env.config().warn_on_useless_conds {
                     if let Some((truthy_span, blocks_span)) = ignored_blocks {
                         if blocks_span.is_dummy() {
                             self.env.warn(truthy_span, m::IfCaseWithTruthyCond {}).done()?;
@@ -1500,7 +1905,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 }
 
                 // warn if the block has no chance to run
-                #[cfg(feature = "warn_on_dead_code")] {
+                if self.env.config().warn_on_dead_code {
                     if exit >= Exit::Break {
                         self.env.warn(block, m::DeadCode {}).done()?;
                     }
@@ -1514,7 +1919,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     scope.register_module_if_needed(&varslot);
                 }
 
-                exit &= scope.visit_block(block)?;
+                exit &= scope.visit_conditional_block(block)?;
                 Ok(exit.loop_boundary(Exit::None))
             }
 
@@ -1577,7 +1982,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 }
 
                 // warn if the block has no chance to run
-                #[cfg(feature = "warn_on_dead_code")] {
+                if self.env.config().warn_on_dead_code {
                     if exit >= Exit::Break {
                         self.env.warn(block, m::DeadCode {}).done()?;
                     }
@@ -1593,7 +1998,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     }
                 }
 
-                exit &= scope.visit_block(block)?;
+                exit &= scope.visit_conditional_block(block)?;
                 Ok(exit.loop_boundary(Exit::None))
             }
 
@@ -1608,6 +2013,10 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     // overwritten by a local function decl (so the NameRef is local
                     // but there is no new sibling scope). it's equivalent to assignment.
                     Some(self.env.assign_to_var(name, info)?)
+                } else if let (&NameRef::Global(..), true) = (&name.base, self.env.is_strict()) {
+                    // in the strict mode globals should have been declared already,
+                    // so this is also an assignment.
+                    Some(self.env.assign_to_var(name, info)?)
                 } else {
                     // otherwise it is a new variable.
                     self.env.add_var(name, None, Some(info))?
@@ -1700,15 +2109,15 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 // collect specified types first (required for hints)
                 let nameinfos = names.iter().map(|namespec| {
                     let info = self.visit_type_spec(namespec)?;
-                    Ok((&namespec.base, info))
+                    Ok((namespec, info))
                 }).collect::<Result<Vec<_>>>()?;
 
                 let hint = SpannedSlotSeq {
-                    head: nameinfos.iter().map(|&(name, ref info)| {
+                    head: nameinfos.iter().map(|&(namespec, ref info)| {
                         if let Some(ref spec) = *info {
                             spec.slot().clone()
                         } else {
-                            Slot::dummy().with_loc(name)
+                            Slot::dummy().with_loc(&namespec.base)
                         }
                     }).collect(),
                     tail: None,
@@ -1716,9 +2125,11 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 };
                 let (exit, infos) = self.visit_explist_from_stmt(exps, Some(hint))?;
 
-                for ((localname, specinfo), info) in nameinfos.into_iter()
-                                                              .zip(infos.into_iter_with_none()) {
+                for ((namespec, specinfo), info) in nameinfos.into_iter()
+                                                             .zip(infos.into_iter_with_none()) {
+                    let localname = &namespec.base;
                     let nameref = NameRef::Local(localname.base.clone()).with_loc(localname);
+                    self.env.record_annotation(&nameref, namespec.kind.is_some());
                     if let Some(varslot) = self.env.add_var(&nameref, specinfo, info)? {
                         self.register_module_if_needed(&varslot);
                     }
@@ -1739,17 +2150,21 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
 
             St::Break => Ok(Exit::Break),
 
+            // the control flow through `goto` is not (yet) tracked,
+            // so they are conservatively treated as if they don't affect the flow
+            St::Goto(_) | St::Label(_) => Ok(Exit::None),
+
             St::KailuaOpen(ref name) => {
                 let opts = self.env.opts().clone();
                 self.env.context().open_library(name.as_ref().map(|n| &n[..]), opts)?;
                 Ok(Exit::None)
             }
 
-            St::KailuaType(scope, ref name, ref kind) => {
+            St::KailuaType(scope, ref name, ref tparams, ref kind) => {
                 // self-redefinition is handled separately, as we cannot distingiush
                 // `--# type local A = <some type> / --# type A = A` from `--# type A = <some type>`
                 if let K::Named(ref name_) = *kind.base {
-                    if name.base == name_.base {
+                    if name.base == name_.base && tparams.is_empty() {
                         match scope {
                             TypeScope::Local => {} // invalid, will error below
                             TypeScope::Global => {
@@ -1764,11 +2179,16 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     }
                 }
 
-                let ty = Ty::from_kind(kind, &mut self.env)?;
+                // type parameters are type variables to be replaced on each use
+                let tparams: Vec<_> = tparams.iter().map(|name| {
+                    (name.clone(), self.types().gen_tvar())
+                }).collect();
+                let ty = Ty::from_kind(kind, &mut TypeParamResolver::new(&mut self.env, &tparams))?;
+                let tparams = tparams.into_iter().map(|(_, tvar)| tvar).collect();
                 match scope {
-                    TypeScope::Local => self.env.define_local_type(name, ty)?,
-                    TypeScope::Global => self.env.define_global_type(name, ty)?,
-                    TypeScope::Exported => self.env.define_and_export_type(name, ty)?,
+                    TypeScope::Local => self.env.define_local_type(name, tparams, ty)?,
+                    TypeScope::Global => self.env.define_global_type(name, tparams, ty)?,
+                    TypeScope::Exported => self.env.define_and_export_type(name, tparams, ty)?,
                 }
                 Ok(Exit::None)
             }
@@ -2009,6 +2429,10 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                        selfparam: Option<(&Spanned<SelfParam>, Slot)>, sig: &Sig,
                        block: &'inp Spanned<Vec<Spanned<Stmt>>>, declspan: Span,
                        hint: Option<Spanned<Slot>>) -> Result<Slot> {
+        if no_check == Some(NoCheck::User) {
+            self.env.record_no_check_func(declspan);
+        }
+
         // if the hint exists and has a functional portion,
         // collect first `sig.args.head.len()` types for missing argument types,
         // and a repeating part of remaining type sequence for a missing variadic argument type.
@@ -2040,12 +2464,21 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             None => (None, None, None),
         };
 
+        // type parameters are visible to the entire signature and body
+        let tparams: Vec<_> = sig.tparams.iter().map(|name| {
+            (name.clone(), self.types().gen_tvar())
+        }).collect();
+
         let vatype = match sig.args.tail {
             None => None,
 
             Some(Varargs { kind: None, .. }) => {
                 // varargs present but types are unspecified
-                if let Some(hint) = hinttail {
+                if self.env.is_strict() {
+                    // the strict mode requires a type even when it can be inferred
+                    self.env.error(declspan, m::StrictVarargsWithoutType {}).done()?;
+                    Some(Ty::dummy())
+                } else if let Some(hint) = hinttail {
                     // use a hint instead ([NO_CHECK] can rely on this hint as well)
                     Some(hint)
                 } else if let Some(no_check) = no_check {
@@ -2059,21 +2492,18 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                         }
                     }
                     return Ok(Slot::dummy());
+                } else if self.env.config().no_implicit_func_sig {
+                    // implicit signature disabled, raise an error and continue
+                    self.env.error(declspan, m::ImplicitVarargsTypeOnAnonymousFunc {}).done()?;
+                    Some(Ty::dummy())
                 } else {
-                    #[cfg(feature = "no_implicit_func_sig")] {
-                        // implicit signature disabled, raise an error and continue
-                        self.env.error(declspan, m::ImplicitVarargsTypeOnAnonymousFunc {}).done()?;
-                        Some(Ty::dummy())
-                    }
-                    #[cfg(not(feature = "no_implicit_func_sig"))] {
-                        // fill a fresh type variable in
-                        Some(Ty::new(T::TVar(self.types().gen_tvar())))
-                    }
+                    // fill a fresh type variable in
+                    Some(Ty::new(T::TVar(self.types().gen_tvar())))
                 }
             },
 
             Some(Varargs { kind: Some(ref k), .. }) => {
-                Some(Ty::from_kind(k, &mut self.env)?)
+                Some(Ty::from_kind(k, &mut TypeParamResolver::new(&mut self.env, &tparams))?)
             },
         };
 
@@ -2087,8 +2517,10 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         let returns = if let Some(ref returns) = sig.returns {
             match *returns {
                 ast::Returns::Never(_) => Returns::Never,
-                ast::Returns::Seq(ref seq) =>
-                    Returns::Explicit(TySeq::from_kind_seq(seq, |kind| kind, &mut self.env)?),
+                ast::Returns::Seq(ref seq) => {
+                    let mut resolv = TypeParamResolver::new(&mut self.env, &tparams);
+                    Returns::Explicit(TySeq::from_kind_seq(seq, |kind| kind, &mut resolv)?)
+                },
             }
         } else if let Some(hint) = hintreturns {
             // use a hint if possible ([NO_CHECK] can rely on this hint as well)
@@ -2111,7 +2543,13 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         let mut argshead = Vec::new();
         let mut argnames = Vec::new();
 
-        let mut scope = self.scoped(Scope::new_function(frame));
+        let mut fnscope = Scope::new_function(frame);
+        for &(ref name, tvar) in &tparams {
+            let ty = Ty::new(T::TVar(tvar)).and_display(DisplayName::Type(name.clone()));
+            fnscope.put_type(name.clone(), ty);
+        }
+
+        let mut scope = self.scoped(fnscope);
         if let Some((selfparam, selfinfo)) = selfparam {
             let ty = selfinfo.unlift().clone();
             let selfid = selfparam.clone().map(|param| param.0);
@@ -2128,9 +2566,14 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
 
             let ty;
             let sty;
-            if let Some(slot) = scope.visit_type_spec(param)? {
+            if scope.env.is_strict() && param.kind.is_none() {
+                // the strict mode requires a type even when it can be inferred
+                scope.env.error(&param.base, m::StrictArgWithoutType {}).done()?;
+                ty = Ty::dummy();
+                sty = Slot::dummy();
+            } else if let Some(slot) = scope.visit_type_spec(param)? {
                 // the type has been specified (can be implicit, but we don't have initializations)
-                #[cfg(feature = "no_implicit_func_sig")] {
+                if scope.env.config().no_implicit_func_sig {
                     // still error for `--: const` or `--: module` when implicit signature disabled
                     if param.kind.is_none() {
                         scope.env.error(&param.base, m::ImplicitArgTypeOnAnonymousFunc {}).done()?;
@@ -2153,21 +2596,20 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     }
                 }
                 return Ok(Slot::dummy());
+            } else if scope.env.config().no_implicit_func_sig {
+                // implicit signature disabled, raise an error and continue
+                scope.env.error(&param.base, m::ImplicitArgTypeOnAnonymousFunc {}).done()?;
+                ty = Ty::dummy();
+                sty = Slot::dummy();
             } else {
-                #[cfg(feature = "no_implicit_func_sig")] {
-                    // implicit signature disabled, raise an error and continue
-                    scope.env.error(&param.base, m::ImplicitArgTypeOnAnonymousFunc {}).done()?;
-                    ty = Ty::dummy();
-                    sty = Slot::dummy();
-                }
-                #[cfg(not(feature = "no_implicit_func_sig"))] {
-                    // fill a fresh type variable in
-                    let argv = scope.types().gen_tvar();
-                    ty = Ty::new(T::TVar(argv));
-                    sty = Slot::new(F::Var, Ty::new(T::TVar(argv)));
-                }
+                // fill a fresh type variable in
+                let argv = scope.types().gen_tvar();
+                ty = Ty::new(T::TVar(argv));
+                sty = Slot::new(F::Var, Ty::new(T::TVar(argv)));
             }
 
+            let paramref = param.base.clone().map(NameRef::Local);
+            scope.env.record_annotation(&paramref, param.kind.is_some());
             let varslot = scope.env.add_local_var_already_set(&param.base, sty.without_loc())?;
             scope.register_module_if_needed(&varslot);
             argshead.push(ty);
@@ -2187,7 +2629,13 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         let args = TySeq { head: argshead, tail: vatype };
 
         if no_check.is_none() {
-            if let Exit::None = scope.visit_block(block)? {
+            // narrowed types do not apply to the function body, which can be called later
+            let narrowed = mem::replace(&mut scope.narrowed, Vec::new());
+            let conditional = mem::replace(&mut scope.conditional, 0);
+            let exit = scope.visit_block(block);
+            scope.narrowed = narrowed;
+            scope.conditional = conditional;
+            if let Exit::None = exit? {
                 // the last statement is an implicit return
                 let span = Span::from(block.span.end()); // conceptually at the end of block
                 scope.visit_return(SpannedSlotSeq::new(span), span)?;
@@ -2198,7 +2646,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             Returns::Implicit(ref ret) | Returns::Explicit(ref ret) => Some(ret.clone()),
             Returns::Never | Returns::None => None,
         };
-        let func = Function { args: args, argnames: argnames, returns: returns };
+        let func = Function { tparams: tparams, args: args, argnames: argnames, returns: returns };
         Ok(Slot::just(Ty::new(T::func(func)).with_tag(tag)))
     }
 
@@ -2212,8 +2660,16 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         };
 
         // construct hints; they are given at the best effort basis
-        let hint = if let Some(&Functions::Simple(ref f)) = functy.get_functions() {
-            let mut args = f.args.clone();
+        // (overloaded functions have no hints since the overload is not yet chosen)
+        let hint = if functy.tag() == Some(Tag::SetMetatable) {
+            None // the table and metatable should retain their own types
+        } else if let Some(&Functions::Simple(ref f)) = functy.get_functions() {
+            // type parameters are not yet known, so they are hinted as `WHATEVER`
+            let whatever: Vec<_> = f.tparams.iter().map(|&(_, tvar)| {
+                (tvar, Ty::new(T::Dynamic(Dyn::User)))
+            }).collect();
+            let mut args = f.args.substitute(&whatever, self.types())
+                                 .unwrap_or_else(|| f.args.clone());
             if selfinfo.is_some() && !args.head.is_empty() {
                 args.head.remove(0); // args do not contain self, so do hints
             }
@@ -2242,6 +2698,28 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         };
 
         if !self.env.get_type_bounds(&functy).1.is_callable() {
+            // `f(...)` is same to `__call(f, ...)` when `f` has the `__call` metamethod
+            if let Some(method) = self.get_metamethod(&functy, b"__call") {
+                if let Some(selfinfo) = selfinfo {
+                    argtys.head.insert(0, selfinfo);
+                }
+                argtys.head.insert(0, Slot::just(functy.base.clone()).with_loc(&functy));
+
+                let methodty = method.unlift().clone().with_loc(&functy);
+                if let Some(dyn) = methodty.get_dynamic() {
+                    return Ok(exit.with(SlotSeq::from(T::Dynamic(dyn))));
+                }
+                if !self.env.get_type_bounds(&methodty).1.is_callable() {
+                    self.env.error(&functy, m::CallToNonFunc { func: self.display(&functy) })
+                            .done()?;
+                    return Ok(exit.with_dummy());
+                }
+
+                let Exitable(retexit, returns) =
+                    self.check_callable(&methodty, &argtys.unlift(), false)?;
+                return Ok(Exitable(cmp::max(exit, retexit), SlotSeq::from_seq(returns)));
+            }
+
             self.env.error(&functy, m::CallToNonFunc { func: self.display(&functy) }).done()?;
             return Ok(exit.with_dummy());
         }
@@ -2289,7 +2767,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
 
                 // non-list arguments have no usable conditions (always evaluate to true)
                 if let Args::List(ref args) = args.base {
-                    if let (Some(cond), _seq) = self.collect_conds_from_exp(&args[0])? {
+                    if let Exitable(_, (Some(cond), _seq)) = self.collect_conds_from_exp(&args[0])? {
                         self.assert_cond(cond, false)?;
                     }
                 }
@@ -2306,7 +2784,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 }
 
                 if let Args::List(ref args) = args.base {
-                    if let (Some(cond), _seq) = self.collect_conds_from_exp(&args[0])? {
+                    if let Exitable(_, (Some(cond), _seq)) = self.collect_conds_from_exp(&args[0])? {
                         self.assert_cond(cond, true)?;
                     }
                 }
@@ -2328,6 +2806,54 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 }
             }
 
+            // setmetatable(table, metatable)
+            Some(Tag::SetMetatable) if selfinfo.is_none() => {
+                if nargs < 1 {
+                    // TODO should display the true name
+                    self.env.error(expspan,
+                                   m::BuiltinGivenLessArgs { name: "setmetatable", nargs: 1 })
+                            .done()?;
+                    return Ok(exit.with_dummy());
+                }
+
+                let Exitable(retexit, _) =
+                    self.check_callable(&functy, &argtys.clone().unlift(), false)?;
+
+                // the table constructor is only visible to this call,
+                // otherwise the call should be always executed to affect the table type
+                let new_table = match args.base {
+                    Args::List(ref ee) => match ee.first().map(|e| &*e.base) {
+                        Some(&Ex::Table(_)) => true,
+                        _ => false,
+                    },
+                    Args::Str(_) => false,
+                    Args::Table(_) => true,
+                };
+
+                let table = argtys.ensure_at(0).clone();
+                let meta = argtys.ensure_at(1).clone();
+                let tablety = if new_table || self.conditional == 0 {
+                    self.env.resolve_exact_type(&table.unlift())
+                } else {
+                    None
+                };
+                if let Some(tablety) = tablety {
+                    if let Some(&Tables::Fields(ref rvar)) = tablety.get_tables() {
+                        // `setmetatable(t, nil)` removes the metatable
+                        let (_, metaflags) = self.env.get_type_bounds(&meta.unlift());
+                        let meta = if metaflags.intersects(!T_NOISY_NIL) {
+                            Some(meta.base)
+                        } else {
+                            None
+                        };
+                        self.types().set_rvar_metatable(rvar.clone(), meta);
+                    }
+                }
+
+                // setmetatable returns the table itself
+                return Ok(Exitable(cmp::max(exit, retexit), SlotSeq::from(table.base)));
+            }
+
             // class([parent])
             Some(Tag::MakeClass(system)) => {
                 if let Some(cid) = self.context().make_class(system, argtys, expspan)? {
@@ -2416,6 +2942,13 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         });
         let mut target = target.unwrap_or_else(|| Target::Fields(false, Vec::new()));
 
+        // the strict mode does not allow implicit records (except for empty tables)
+        if let Target::Fields(false, _) = target {
+            if self.env.is_strict() && !tab.items.is_empty() {
+                self.env.error(tabspan, m::TableLitIsImplicitlyRec {}).done()?;
+            }
+        }
+
         let mut fieldspans = HashMap::new();
 
         let mut add_field = |target: &mut Target, env: &mut Env<R>,
@@ -2640,7 +3173,11 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 } else {
                     Exitable::new(SlotSeq::from(T::Integer))
                 },
-            Ex::Num(_) => Exitable::new(SlotSeq::from(T::Number)),
+            Ex::Num(_) | Ex::Float(_) => Exitable::new(SlotSeq::from(T::Number)),
+            // LuaJIT 64-bit integers only mix with numbers; complex numbers are opaque
+            Ex::CDataNum(_, NumSuffix::Int64) |
+            Ex::CDataNum(_, NumSuffix::UInt64) => Exitable::new(SlotSeq::from(int64_ty())),
+            Ex::CDataNum(_, NumSuffix::Imaginary) => Exitable::new(SlotSeq::from(T::UserData)),
             Ex::Str(ref s) => {
                 let str = Str::from(s[..].to_owned());
                 Exitable::new(SlotSeq::from(T::Str(Cow::Owned(str))))
@@ -2656,7 +3193,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             },
             Ex::Var(ref name) => {
                 if self.env.get_var(name).is_some() {
-                    Exitable::new(SlotSeq::from(self.env.ensure_var(name)?))
+                    let slot = self.env.ensure_var(name)?;
+                    Exitable::new(SlotSeq::from(self.narrowed_slot(slot)))
                 } else {
                     self.env.error(exp, m::NoVar { name: self.env.get_name(name) }).done()?;
                     Exitable::dummy()
@@ -2703,14 +3241,16 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let ty = ty.into_first();
                 let kty = kty.into_first();
                 let exit = exit1.collide(exit2);
-                exit.with(SlotSeq::from(self.check_rval_index(&ty, &kty, exp.span)?))
+                let slot = self.check_rval_index(&ty, &kty, exp.span)?;
+                exit.with(SlotSeq::from(self.narrowed_slot(slot)))
             },
             Ex::IndexName(ref e, ref key) => {
                 let keystr = Str::from(key.base[..].to_owned());
                 let Exitable(exit, ty) = self.visit_exp(e, None)?;
                 let ty = ty.into_first();
                 let kty = Slot::just(Ty::new(T::Str(Cow::Owned(keystr)))).with_loc(key);
-                exit.with(SlotSeq::from(self.check_rval_index(&ty, &kty, exp.span)?))
+                let slot = self.check_rval_index(&ty, &kty, exp.span)?;
+                exit.with(SlotSeq::from(self.narrowed_slot(slot)))
             },
 
             Ex::Un(op, ref e) => {
@@ -2729,6 +3269,13 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 exit.with(SlotSeq::from(info))
             },
 
+            // the rhs of `and` and `or` can be narrowed by the lhs
+            Ex::Bin(_, Spanned { base: BinOp::And, .. }, _) |
+            Ex::Bin(_, Spanned { base: BinOp::Or, .. }, _) => {
+                let Exitable(exit, (_cond, info)) = self.collect_conds_from_exp(exp)?;
+                exit.with(info.unspan())
+            },
+
             Ex::Bin(ref l, op, ref r) => {
                 let Exitable(exit1, lhs) = self.visit_exp(l, None)?;
                 let Exitable(exit2, rhs) = self.visit_exp(r, None)?;
@@ -2850,6 +3397,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
 
             info!("requiring {:?}", modname);
             let opts = self.env.opts().clone();
+            let environment = self.env.context().describe_environment();
+            opts.borrow_mut().module_environment(modname, &environment);
             let chunk = match opts.borrow_mut().require_chunk(modname, self.env) {
                 Ok(chunk) => chunk,
                 Err(_) => {
@@ -2864,12 +3413,24 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 }
             };
 
-            let mut env = Env::new(self.env.context(), opts, chunk.map);
+            self.env.context().add_ignores(&chunk.ignores);
+            let reports_before = self.env.context().num_reports();
+            let strict = chunk.strict || self.env.config().strict;
+            let mut env = Env::new(self.env.context(), opts.clone(), chunk.map);
+            env.set_strict(strict);
             let exit = {
                 let mut sub = Checker::new(&mut env);
                 sub.visit_block(&chunk.block)?
             };
             module = env.return_from_module(&modname, exit >= Exit::Stop, expspan)?;
+
+            // the module can replace the original chunk only if the checking had
+            // no visible effect other than the module itself
+            let reusable = self.env.context().num_reports() == reports_before &&
+                           self.env.context().describe_environment() == environment;
+            opts.borrow_mut().module_checked(modname, module.as_ref(), self.env.types(), reusable);
+        } else {
+            self.env.opts().borrow_mut().module_reused(modname);
         }
 
         Ok(module)
@@ -2885,9 +3446,9 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
     }
 
     fn collect_type_from_exp(&mut self, exp: &'inp Spanned<Exp>)
-            -> Result<(Option<Spanned<Slot>>, SpannedSlotSeq)> {
+            -> Result<Exitable<(Option<Spanned<Slot>>, SpannedSlotSeq)>> {
         if let Ex::FuncCall(ref func, ref args) = *exp.base {
-            let Exitable(_, funcseq) = self.visit_exp(func, None)?;
+            let Exitable(exit1, funcseq) = self.visit_exp(func, None)?;
             let funcspan = funcseq.all_span();
             let funcinfo = funcseq.into_first();
             let funcinfo = funcinfo.unlift();
@@ -2915,74 +3476,55 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             } else {
                 None
             };
-            let Exitable(_, seq) = self.visit_func_call(&funcinfo.clone().with_loc(funcspan),
-                                                        None, args, exp.span)?;
-            Ok((typeofexp, seq.all_with_loc(exp)))
+            let Exitable(exit2, seq) = self.visit_func_call(&funcinfo.clone().with_loc(funcspan),
+                                                            None, args, exp.span)?;
+            Ok(exit1.collide(exit2).with((typeofexp, seq.all_with_loc(exp))))
         } else {
-            let Exitable(_, seq) = self.visit_exp(exp, None)?;
-            Ok((None, seq))
+            let Exitable(exit, seq) = self.visit_exp(exp, None)?;
+            Ok(exit.with((None, seq)))
         }
     }
 
     // similar to visit_exp but also tries to collect Cond
     fn collect_conds_from_exp(&mut self, exp: &'inp Spanned<Exp>)
-            -> Result<(Option<Cond>, SpannedSlotSeq)> {
+            -> Result<Exitable<(Option<Cond>, SpannedSlotSeq)>> {
         debug!("collecting conditions from exp {:?}", *exp);
 
         match *exp.base {
             Ex::Un(Spanned { base: UnOp::Not, .. }, ref e) => {
-                let (cond, seq) = self.collect_conds_from_exp(e)?;
-                let cond = match cond {
-                    Some(Cond::Not(cond)) => match *cond {
-                        Cond::Not(cond) => Some(*cond),
-                        cond => Some(Cond::Not(Box::new(cond))),
-                    },
-                    Some(Cond::Flags(info, flags)) => Some(Cond::Flags(info, !flags)),
-                    Some(cond) => Some(Cond::Not(Box::new(cond))),
-                    None => None,
-                };
+                let Exitable(exit, (cond, seq)) = self.collect_conds_from_exp(e)?;
+                let cond = cond.map(Cond::negate);
                 let info = seq.into_first();
                 let info = self.check_un_op(UnOp::Not, &info, exp.span)?;
-                Ok((cond, SpannedSlotSeq::from(info.with_loc(exp))))
+                Ok(exit.with((cond, SpannedSlotSeq::from(info.with_loc(exp)))))
             }
 
             Ex::Bin(ref l, Spanned { base: BinOp::Eq, .. }, ref r) => {
-                let (lty, linfo) = self.collect_type_from_exp(l)?;
-                let (rty, rinfo) = self.collect_type_from_exp(r)?;
-
-                let linfo = linfo.into_first();
-                let rinfo = rinfo.into_first();
-
-                // detect an expression of the form `type(x) == y`.
-                // it is technically possible to detect `type(x) == type(y)` as well,
-                // but it is not common and results in a very subtle semi-equivalence condition
-                // that we cannot readily handle.
-                let cond = match (lty, rty) {
-                    (Some(ty), None) => {
-                        if let Some(flags) = self.literal_ty_to_flags(&rinfo)? {
-                            Some(Cond::Flags(ty, flags))
-                        } else {
-                            None // the rhs is not a literal, so we don't what it is
-                        }
-                    },
-                    (None, Some(ty)) => {
-                        if let Some(flags) = self.literal_ty_to_flags(&linfo)? {
-                            Some(Cond::Flags(ty, flags))
-                        } else {
-                            None
-                        }
-                    },
-                    (_, _) => None,
-                };
+                let Exitable(exit, cond) = self.collect_conds_from_eq(l, r)?;
+                Ok(exit.with((cond, SpannedSlotSeq::from(T::Boolean.with_loc(exp)))))
+            }
 
-                // TODO when cond is None try to assert the type equivalence;
-                // it is currently not implemented due to bad interaction with sub-literal types
-                Ok((cond, SpannedSlotSeq::from(T::Boolean.with_loc(exp))))
+            Ex::Bin(ref l, Spanned { base: BinOp::Ne, .. }, ref r) => {
+                let Exitable(exit, cond) = self.collect_conds_from_eq(l, r)?;
+                let cond = cond.map(Cond::negate);
+                Ok(exit.with((cond, SpannedSlotSeq::from(T::Boolean.with_loc(exp)))))
             }
 
             Ex::Bin(ref l, Spanned { base: BinOp::And, .. }, ref r) => {
-                let (lcond, lseq) = self.collect_conds_from_exp(l)?;
-                let (rcond, rseq) = self.collect_conds_from_exp(r)?;
+                let Exitable(exit1, (lcond, lseq)) = self.collect_conds_from_exp(l)?;
+
+                // the rhs is only evaluated when the lhs is truthy
+                let mark = self.narrowed.len();
+                self.conditional += 1;
+                if let Some(ref lcond) = lcond {
+                    self.narrow_by_cond(lcond, false);
+                }
+                let rhs = self.collect_conds_from_exp(r).map(|Exitable(exit, (rcond, rseq))| {
+                    Exitable(exit, (rcond.map(|c| self.unnarrow_cond(c)), rseq))
+                });
+                self.conditional -= 1;
+                self.restore_narrowed(mark);
+                let Exitable(exit2, (rcond, rseq)) = rhs?;
 
                 let cond = match (lcond, rcond) {
                     (None, cond) | (cond, None) => cond,
@@ -3003,12 +3545,24 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let linfo = lseq.into_first();
                 let rinfo = rseq.into_first();
                 let info = self.check_bin_op(&linfo, BinOp::And, &rinfo, exp.span)?;
-                Ok((cond, SpannedSlotSeq::from(info.with_loc(exp))))
+                Ok(exit1.collide(exit2).with((cond, SpannedSlotSeq::from(info.with_loc(exp)))))
             }
 
             Ex::Bin(ref l, Spanned { base: BinOp::Or, .. }, ref r) => {
-                let (lcond, lseq) = self.collect_conds_from_exp(l)?;
-                let (rcond, rseq) = self.collect_conds_from_exp(r)?;
+                let Exitable(exit1, (lcond, lseq)) = self.collect_conds_from_exp(l)?;
+
+                // the rhs is only evaluated when the lhs is falsy
+                let mark = self.narrowed.len();
+                self.conditional += 1;
+                if let Some(ref lcond) = lcond {
+                    self.narrow_by_cond(lcond, true);
+                }
+                let rhs = self.collect_conds_from_exp(r).map(|Exitable(exit, (rcond, rseq))| {
+                    Exitable(exit, (rcond.map(|c| self.unnarrow_cond(c)), rseq))
+                });
+                self.conditional -= 1;
+                self.restore_narrowed(mark);
+                let Exitable(exit2, (rcond, rseq)) = rhs?;
 
                 let cond = match (lcond, rcond) {
                     (None, cond) | (cond, None) => cond,
@@ -3029,52 +3583,192 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let linfo = lseq.into_first();
                 let rinfo = rseq.into_first();
                 let info = self.check_bin_op(&linfo, BinOp::Or, &rinfo, exp.span)?;
-                Ok((cond, SpannedSlotSeq::from(info.with_loc(exp))))
+                Ok(exit1.collide(exit2).with((cond, SpannedSlotSeq::from(info.with_loc(exp)))))
             }
 
             _ => {
-                let Exitable(_, seq) = self.visit_exp(exp, None)?;
-                let info = seq.into_first();
+                let Exitable(exit, seq) = self.visit_exp(exp, None)?;
+                let info = seq.clone().into_first();
                 // XXX should detect non-local slots and reject them!
                 // probably we can do that via proper weakening, but who knows.
-                Ok((Some(Cond::Flags(info.clone(), T_TRUTHY)), SpannedSlotSeq::from(info)))
+                // the whole sequence is retained, as an empty sequence should not be truthy
+                Ok(exit.with((Some(Cond::Flags(info, T_TRUTHY)), seq)))
             }
         }
     }
 
-    fn assert_cond(&mut self, cond: Cond, negated: bool) -> Result<()> {
-        debug!("asserting condition {:?} (negated {:?})", cond, negated);
+    // collects Cond from `l == r`
+    fn collect_conds_from_eq(&mut self, l: &'inp Spanned<Exp>,
+                             r: &'inp Spanned<Exp>) -> Result<Exitable<Option<Cond>>> {
+        let Exitable(exit1, (lty, linfo)) = self.collect_type_from_exp(l)?;
+        let Exitable(exit2, (rty, rinfo)) = self.collect_type_from_exp(r)?;
+
+        let linfo = linfo.into_first();
+        let rinfo = rinfo.into_first();
+
+        // detect an expression of the form `type(x) == y`.
+        // it is technically possible to detect `type(x) == type(y)` as well,
+        // but it is not common and results in a very subtle semi-equivalence condition
+        // that we cannot readily handle.
+        let cond = match (lty, rty) {
+            (Some(ty), None) => {
+                if let Some(flags) = self.literal_ty_to_flags(&rinfo)? {
+                    Some(Cond::Flags(ty, flags))
+                } else {
+                    None // the rhs is not a literal, so we don't what it is
+                }
+            },
+            (None, Some(ty)) => {
+                if let Some(flags) = self.literal_ty_to_flags(&linfo)? {
+                    Some(Cond::Flags(ty, flags))
+                } else {
+                    None
+                }
+            },
+
+            // also detect `x == nil` and `nil == x`
+            (None, None) => match (&*l.base, &*r.base) {
+                (_, &Ex::Nil) => Some(Cond::Flags(linfo, T_NOISY_NIL)),
+                (&Ex::Nil, _) => Some(Cond::Flags(rinfo, T_NOISY_NIL)),
+                (_, _) => None,
+            },
+
+            (_, _) => None,
+        };
+
+        // TODO when cond is None try to assert the type equivalence;
+        // it is currently not implemented due to bad interaction with sub-literal types
+        Ok(exit1.collide(exit2).with(cond))
+    }
+
+    // filters slots in the condition, failing when the condition is impossible
+    fn filter_by_cond(&mut self, cond: Cond, negated: bool) -> TypeResult<()> {
+        debug!("filtering by condition {:?} (negated {:?})", cond, negated);
 
         match cond {
             Cond::Flags(info, flags) => {
-                let flags = if negated { !flags } else { flags };
-                // XXX this is temporary, the entire condition assertion should be changed!
-                info.filter_by_flags(flags, self.types()).map_err(|_| kailua_diag::Stop)?;
+                let flags = if negated { T_ALL & !flags } else { flags };
+                info.filter_by_flags(flags, self.types())?;
                 debug!("resulted in {:?}", info);
             }
 
             Cond::And(lcond, rcond) => {
                 if !negated {
-                    self.assert_cond(*lcond, negated)?;
-                    self.assert_cond(*rcond, negated)?;
+                    self.filter_by_cond(*lcond, negated)?;
+                    self.filter_by_cond(*rcond, negated)?;
                 }
             }
 
             Cond::Or(lcond, rcond) => {
                 if negated {
-                    self.assert_cond(*lcond, negated)?;
-                    self.assert_cond(*rcond, negated)?;
+                    self.filter_by_cond(*lcond, negated)?;
+                    self.filter_by_cond(*rcond, negated)?;
                 }
             }
 
             Cond::Not(cond) => {
-                self.assert_cond(*cond, !negated)?;
+                self.filter_by_cond(*cond, !negated)?;
             }
         }
 
         Ok(())
     }
 
+    fn assert_cond(&mut self, cond: Cond, negated: bool) -> Result<()> {
+        // XXX this is temporary, the entire condition assertion should be changed!
+        self.filter_by_cond(cond, negated).map_err(|_| kailua_diag::Stop)
+    }
+
+    // narrows slots in the condition for the region guarded by the condition
+    // (`if`/`elseif`/`while` bodies and the rhs of `and`/`or`), returning a mark for that region.
+    // the caller should call `restore_narrowed` with the mark at the end of that region.
+    //
+    // the slots themselves are not changed; their filtered copies are used when they are read,
+    // so that assignments are still checked against the original types.
+    // the impossible condition narrows nothing (e.g. `type(x) == 'string'` for `x: integer`).
+    fn narrow_by_cond(&mut self, cond: &Cond, negated: bool) -> usize {
+        let mark = self.narrowed.len();
+
+        let cond = {
+            let narrowed = &mut self.narrowed;
+            cond.map_slots(&mut |info| {
+                let orig = unnarrowed_slot(narrowed, &info.base);
+
+                // the same slot can appear multiple times in the condition
+                for &(ref orig_, ref copy) in &narrowed[mark..] {
+                    if let (true, &Some(ref copy)) = (orig_.is_same_as(&orig), copy) {
+                        return copy.clone().with_loc(info);
+                    }
+                }
+
+                let copy = {
+                    let current = narrowed.iter().rev()
+                                          .find(|&&(ref orig_, _)| orig_.is_same_as(&orig));
+                    let current = match current {
+                        Some(&(_, Some(ref copy))) => copy,
+                        _ => &orig,
+                    };
+                    Slot::new(current.flex(), current.unlift().clone())
+                };
+                narrowed.push((orig, Some(copy.clone())));
+                copy.with_loc(info)
+            })
+        };
+
+        if self.filter_by_cond(cond, negated).is_err() {
+            self.narrowed.truncate(mark);
+        }
+        mark
+    }
+
+    fn restore_narrowed(&mut self, mark: usize) {
+        self.narrowed.truncate(mark);
+    }
+
+    // returns the narrowed copy of the slot if any, used for reading variables and fields
+    fn narrowed_slot(&self, slot: Slot) -> Slot {
+        for &(ref orig, ref copy) in self.narrowed.iter().rev() {
+            if orig.is_same_as(&slot) {
+                return copy.clone().unwrap_or(slot);
+            }
+        }
+        slot
+    }
+
+    // the slot has been assigned, so any narrowed copy of it is no longer valid
+    fn forget_narrowed(&mut self, slot: &Slot) {
+        for &mut (ref orig, ref mut copy) in &mut self.narrowed {
+            if orig.is_same_as(slot) {
+                *copy = None;
+            }
+        }
+    }
+
+    // replaces narrowed copies in the condition with the original slots,
+    // so that the condition can be used after the narrowing has been restored
+    fn unnarrow_cond(&self, cond: Cond) -> Cond {
+        cond.map_slots(&mut |info| unnarrowed_slot(&self.narrowed, &info.base).with_loc(info))
+    }
+
+    fn visit_block_with_cond(&mut self, block: &'inp Spanned<Block>,
+                             cond: &Option<Cond>) -> Result<Exit> {
+        let mark = self.narrowed.len();
+        if let Some(ref cond) = *cond {
+            self.narrow_by_cond(cond, false);
+        }
+        let exit = self.visit_conditional_block(block);
+        self.restore_narrowed(mark);
+        exit
+    }
+
+    // visits a block which may not be executed
+    fn visit_conditional_block(&mut self, block: &'inp Spanned<Block>) -> Result<Exit> {
+        self.conditional += 1;
+        let exit = self.visit_block(block);
+        self.conditional -= 1;
+        exit
+    }
+
     fn literal_ty_to_flags(&self, info: &Spanned<Slot>) -> Result<Option<Flags>> {
         if let Some(s) = info.unlift().as_string() {
             let tyname = &s[..];
@@ -128,6 +128,11 @@ impl ClassSystem for DumbClassSystem {
         }
     }
 
+    fn num_definitions(&self) -> usize {
+        let classes = self.classes.read();
+        classes.iter().map(|cls| 1 + cls.class_fields.len() + cls.instance_fields.len()).sum()
+    }
+
     fn fmt_class(&self, cid: ClassId, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result {
         let names = self.class_names.read();
         match (&st.locale[..], &names.get(cid.1 as usize)) {
@@ -197,7 +197,7 @@ impl GiderosClassSystem {
         // now `init` is: function(/* removed self */, ...) -> any
         // fix the return type to make a signature for the `new` method
         let returns = T::Class(Class::Instance(cid));
-        let ctor = Function { args: func.args, argnames: func.argnames,
+        let ctor = Function { tparams: func.tparams, args: func.args, argnames: func.argnames,
                               returns: Some(TySeq::from(returns)) };
         let ctor = Slot::new(F::Const, Ty::new(T::func(ctor)));
 
@@ -484,6 +484,11 @@ impl ClassSystem for GiderosClassSystem {
         Ok(Some((true, slot)))
     }
 
+    fn num_definitions(&self) -> usize {
+        let classes = self.classes.read();
+        classes.iter().map(|cls| 1 + cls.class_fields.len() + cls.instance_fields.len()).sum()
+    }
+
     fn fmt_class(&self, cid: ClassId, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result {
         let names = self.class_names.read();
         match (&st.locale[..], &names.get(cid.1 as usize)) {
@@ -79,6 +79,11 @@ pub trait ClassSystem: Send + Sync + fmt::Debug {
     /// Prints the nominal type name (or an appropriate placeholder if unnamed) to the formatter.
     fn fmt_class(&self, cid: ClassId, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result;
 
+    /// Returns the total number of classes and fields defined so far.
+    ///
+    /// Used to tell if the checking had any effect to the class system.
+    fn num_definitions(&self) -> usize;
+
     /// Iterates over a list of keys and corresponding types available to given nominal type.
     /// The closure can stop the iteration by returning `Err`.
     /// The iteration order is unspecified but all keys will be unique.
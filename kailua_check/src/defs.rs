@@ -5,6 +5,10 @@ use kailua_syntax::{Chunk, Lexer, Nest, Parser};
 pub struct Def {
     pub name: &'static str,
     pub code: &'static [u8],
+
+    /// If set, the code is checked as a module and `require` with this name returns it
+    /// instead of defining any global variables.
+    pub module: Option<&'static str>,
 }
 
 impl Def {
@@ -25,23 +29,43 @@ impl Def {
 
 macro_rules! defs {
     ($($defs:ident, $def:ident = $name:expr, $path:expr;)*) => ($(
-        const $def: Def = Def { name: $name, code: include_bytes!($path) };
+        const $def: Def = Def { name: $name, code: include_bytes!($path), module: None };
         const $defs: &'static [Def] = &[$def];
     )*);
 }
 
 defs! {
-    LUA51_BASE_DEFS,    LUA51_BASE_DEF    = "lua51_base",    "defs/lua51_base.lua";
-    LUA51_PACKAGE_DEFS, LUA51_PACKAGE_DEF = "lua51_package", "defs/lua51_package.lua";
-    LUA51_STRING_DEFS,  LUA51_STRING_DEF  = "lua51_string",  "defs/lua51_string.lua";
-    LUA51_TABLE_DEFS,   LUA51_TABLE_DEF   = "lua51_table",   "defs/lua51_table.lua";
-    LUA51_MATH_DEFS,    LUA51_MATH_DEF    = "lua51_math",    "defs/lua51_math.lua";
-    LUA51_IO_DEFS,      LUA51_IO_DEF      = "lua51_io",      "defs/lua51_io.lua";
-    LUA51_OS_DEFS,      LUA51_OS_DEF      = "lua51_os",      "defs/lua51_os.lua";
-    LUA51_DEBUG_DEFS,   LUA51_DEBUG_DEF   = "lua51_debug",   "defs/lua51_debug.lua";
-    KAILUA_TEST_DEFS,   KAILUA_TEST_DEF   = "kailua_test",   "defs/kailua_test.lua";
+    LUA51_BASE_DEFS,     LUA51_BASE_DEF     = "lua51_base",     "defs/lua51_base.lua";
+    LUA51_PACKAGE_DEFS,  LUA51_PACKAGE_DEF  = "lua51_package",  "defs/lua51_package.lua";
+    LUA51_STRING_DEFS,   LUA51_STRING_DEF   = "lua51_string",   "defs/lua51_string.lua";
+    LUA51_TABLE_DEFS,    LUA51_TABLE_DEF    = "lua51_table",    "defs/lua51_table.lua";
+    LUA51_MATH_DEFS,     LUA51_MATH_DEF     = "lua51_math",     "defs/lua51_math.lua";
+    LUA51_IO_DEFS,       LUA51_IO_DEF       = "lua51_io",       "defs/lua51_io.lua";
+    LUA51_OS_DEFS,       LUA51_OS_DEF       = "lua51_os",       "defs/lua51_os.lua";
+    LUA51_DEBUG_DEFS,    LUA51_DEBUG_DEF    = "lua51_debug",    "defs/lua51_debug.lua";
+    LUA52_BASE_DEFS,     LUA52_BASE_DEF     = "lua52_base",     "defs/lua52_base.lua";
+    LUA52_PACKAGE_DEFS,  LUA52_PACKAGE_DEF  = "lua52_package",  "defs/lua52_package.lua";
+    LUA52_TABLE_DEFS,    LUA52_TABLE_DEF    = "lua52_table",    "defs/lua52_table.lua";
+    LUA52_BIT32_DEFS,    LUA52_BIT32_DEF    = "lua52_bit32",    "defs/lua52_bit32.lua";
+    LUA53_BASE_DEFS,     LUA53_BASE_DEF     = "lua53_base",     "defs/lua53_base.lua";
+    LUA53_STRING_DEFS,   LUA53_STRING_DEF   = "lua53_string",   "defs/lua53_string.lua";
+    LUA53_TABLE_DEFS,    LUA53_TABLE_DEF    = "lua53_table",    "defs/lua53_table.lua";
+    LUA53_MATH_DEFS,     LUA53_MATH_DEF     = "lua53_math",     "defs/lua53_math.lua";
+    LUA53_UTF8_DEFS,     LUA53_UTF8_DEF     = "lua53_utf8",     "defs/lua53_utf8.lua";
+    LUAJIT_BASE_DEFS,    LUAJIT_BASE_DEF    = "luajit_base",    "defs/luajit_base.lua";
+    LUAJIT_PACKAGE_DEFS, LUAJIT_PACKAGE_DEF = "luajit_package", "defs/luajit_package.lua";
+    LUAJIT_MATH_DEFS,    LUAJIT_MATH_DEF    = "luajit_math",    "defs/luajit_math.lua";
+    LUAJIT_BIT_DEFS,     LUAJIT_BIT_DEF     = "luajit_bit",     "defs/luajit_bit.lua";
+    LUAJIT_JIT_DEFS,     LUAJIT_JIT_DEF     = "luajit_jit",     "defs/luajit_jit.lua";
+    KAILUA_TEST_DEFS,    KAILUA_TEST_DEF    = "kailua_test",    "defs/kailua_test.lua";
 }
 
+// `ffi` is not a global variable and should be loaded with `require`
+const LUAJIT_FFI_DEF: Def = Def {
+    name: "luajit_ffi", code: include_bytes!("defs/luajit_ffi.lua"), module: Some("ffi"),
+};
+const LUAJIT_FFI_DEFS: &'static [Def] = &[LUAJIT_FFI_DEF];
+
 const LUA51_DEFS: &'static [Def] = &[
     LUA51_BASE_DEF,
     LUA51_PACKAGE_DEF,
@@ -53,17 +77,78 @@ const LUA51_DEFS: &'static [Def] = &[
     LUA51_DEBUG_DEF,
 ];
 
+// string, math, io, os and debug libraries are mostly compatible with Lua 5.1
+const LUA52_DEFS: &'static [Def] = &[
+    LUA52_BASE_DEF,
+    LUA52_PACKAGE_DEF,
+    LUA51_STRING_DEF,
+    LUA52_TABLE_DEF,
+    LUA51_MATH_DEF,
+    LUA51_IO_DEF,
+    LUA51_OS_DEF,
+    LUA51_DEBUG_DEF,
+    LUA52_BIT32_DEF,
+];
+
+// io, os and debug libraries are mostly compatible with Lua 5.1,
+// the package library is same to Lua 5.2 and `bit32` has been deprecated
+const LUA53_DEFS: &'static [Def] = &[
+    LUA53_BASE_DEF,
+    LUA52_PACKAGE_DEF,
+    LUA53_STRING_DEF,
+    LUA53_TABLE_DEF,
+    LUA53_MATH_DEF,
+    LUA51_IO_DEF,
+    LUA51_OS_DEF,
+    LUA51_DEBUG_DEF,
+    LUA53_UTF8_DEF,
+];
+
+// LuaJIT is mostly compatible with Lua 5.1, but has some extensions from Lua 5.2
+// (basic, package and math libraries) and its own libraries
+const LUAJIT_DEFS: &'static [Def] = &[
+    LUAJIT_BASE_DEF,
+    LUAJIT_PACKAGE_DEF,
+    LUA51_STRING_DEF,
+    LUA51_TABLE_DEF,
+    LUAJIT_MATH_DEF,
+    LUA51_IO_DEF,
+    LUA51_OS_DEF,
+    LUA51_DEBUG_DEF,
+    LUAJIT_BIT_DEF,
+    LUAJIT_JIT_DEF,
+    LUAJIT_FFI_DEF,
+];
+
 pub fn get_defs(name: &str) -> Option<&'static [Def]> {
     match name {
-        "lua51"         => Some(LUA51_DEFS),
-        "lua51_base"    => Some(LUA51_BASE_DEFS),
-        "lua51_package" => Some(LUA51_PACKAGE_DEFS),
-        "lua51_string"  => Some(LUA51_STRING_DEFS),
-        "lua51_table"   => Some(LUA51_TABLE_DEFS),
-        "lua51_math"    => Some(LUA51_MATH_DEFS),
-        "lua51_io"      => Some(LUA51_IO_DEFS),
-        "lua51_os"      => Some(LUA51_OS_DEFS),
-        "lua51_debug"   => Some(LUA51_DEBUG_DEFS),
+        "lua51"          => Some(LUA51_DEFS),
+        "lua51_base"     => Some(LUA51_BASE_DEFS),
+        "lua51_package"  => Some(LUA51_PACKAGE_DEFS),
+        "lua51_string"   => Some(LUA51_STRING_DEFS),
+        "lua51_table"    => Some(LUA51_TABLE_DEFS),
+        "lua51_math"     => Some(LUA51_MATH_DEFS),
+        "lua51_io"       => Some(LUA51_IO_DEFS),
+        "lua51_os"       => Some(LUA51_OS_DEFS),
+        "lua51_debug"    => Some(LUA51_DEBUG_DEFS),
+        "lua52"          => Some(LUA52_DEFS),
+        "lua52_base"     => Some(LUA52_BASE_DEFS),
+        "lua52_package"  => Some(LUA52_PACKAGE_DEFS),
+        "lua52_table"    => Some(LUA52_TABLE_DEFS),
+        "lua52_bit32"    => Some(LUA52_BIT32_DEFS),
+        "lua53"          => Some(LUA53_DEFS),
+        "lua53_base"     => Some(LUA53_BASE_DEFS),
+        "lua53_string"   => Some(LUA53_STRING_DEFS),
+        "lua53_table"    => Some(LUA53_TABLE_DEFS),
+        "lua53_math"     => Some(LUA53_MATH_DEFS),
+        "lua53_utf8"     => Some(LUA53_UTF8_DEFS),
+        "luajit"         => Some(LUAJIT_DEFS),
+        "luajit_base"    => Some(LUAJIT_BASE_DEFS),
+        "luajit_package" => Some(LUAJIT_PACKAGE_DEFS),
+        "luajit_math"    => Some(LUAJIT_MATH_DEFS),
+        "luajit_bit"     => Some(LUAJIT_BIT_DEFS),
+        "luajit_jit"     => Some(LUAJIT_JIT_DEFS),
+        "luajit_ffi"     => Some(LUAJIT_FFI_DEFS),
 
         // only internally used
         "internal kailua_test" => Some(KAILUA_TEST_DEFS),
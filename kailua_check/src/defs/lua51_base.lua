@@ -23,10 +23,9 @@
 --#     function(object: any) --> table
 --#
 --# assume global `ipairs`:
---#     [generic_pairs]
---#     function(t: vector<const WHATEVER>) -->
---#         (function(vector<const WHATEVER>, integer) --> (integer?, any),
---#          vector<const WHATEVER>, integer)
+--#     function<T>(t: vector<const T>) -->
+--#         (function(vector<const T>, integer) --> (integer?, T),
+--#          vector<const T>, integer)
 --#
 --# -- TODO sequence conditional union: (function) | (nil, string)
 --# assume global `load`:
@@ -44,8 +43,12 @@
 --# assume global `next`:
 --#     function(table: table, index: any?) --> (integer, any)
 --#
+--# -- records and `table` still rely on `[generic_pairs]` for key and value types
 --# assume global `pairs`:
---#     [generic_pairs] function(t: table) --> (function(table, any) --> (any?, any), table, any)
+--#     [generic_pairs]
+--#     function<K, V>(t: map<K, const V>) -->
+--#         (function(map<K, const V>, any) --> (K?, V), map<K, const V>, any) &
+--#     function(t: table) --> (function(table, any) --> (any?, any), table, any)
 --#
 --# -- TODO `f` should be once function
 --# -- TODO genericity
@@ -66,13 +69,14 @@
 --#
 --# -- TODO genericity
 --# assume global `select`:
---#     function(index: number|'#', any...) --> (any...)
+--#     function(index: '#', any...) --> integer &
+--#     function(index: number, any...) --> (any...)
 --#
 --# assume global `setfenv`:
 --#     function(f: function|integer?, table: table) --> function
 --#
 --# assume global `setmetatable`:
---#     function(table: table, metatable: any?) --> table
+--#     [setmetatable] function(table: table, metatable: table?) --> table
 --#
 --# assume global `tonumber`:
 --#     function(e: any, base: integer?) --> number
@@ -19,21 +19,16 @@
 --#         `ldexp`: function(m: number, e: integer) --> number;
 --#         `log`: function(x: number) --> number;
 --#         `log10`: function(x: number) --> number;
---#         -- TODO should really be
---#         --      `function(x: integer, integer...) --> integer &
---#         --       function(x: number, number...) --> number`
---#         `max`: function(x: number, number...) --> number;
---#         -- TODO should really be
---#         --      `function(x: integer, integer...) --> integer &
---#         --       function(x: number, number...) --> number`
---#         `min`: function(x: number, number...) --> number;
+--#         `max`: function(x: integer, integer...) --> integer &
+--#                function(x: number, number...) --> number;
+--#         `min`: function(x: integer, integer...) --> integer &
+--#                function(x: number, number...) --> number;
 --#         `modf`: function(x: number) --> (integer, number);
 --#         `pi`: number;
 --#         `pow`: function(x: number, y: number) --> number;
 --#         `rad`: function(x: number) --> number;
---#         -- TODO should really be
---#         --      `function() --> number & function(m: integer, n: integer?) --> integer`
---#         `random`: function(m: integer?, n: integer?) --> number;
+--#         `random`: function() --> number &
+--#                   function(m: integer, n: integer?) --> integer;
 --#         `randomseed`: function(x: integer);
 --#         `sin`: function(x: number) --> number;
 --#         `sinh`: function(x: number) --> number;
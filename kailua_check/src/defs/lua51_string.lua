@@ -6,7 +6,9 @@
 --#         `char`: function(integer...) --> string;
 --#         `dump`: function(`function`: function) --> string;
 --#         -- TODO repeating return types can contain integer when the pattern contains `()`
---#         `find`: function(s: string, pattern: string, init: integer?, plain: boolean?) -->
+--#         `find`: function(s: string, pattern: string, init: integer?, plain: true) -->
+--#                          (integer, integer) &
+--#                 function(s: string, pattern: string, init: integer?, plain: boolean?) -->
 --#                          (integer, integer, string...);
 --#         `format`: function(formatstring: string, any...) --> string;
 --#         `gmatch`: function(s: string, pattern: string) --> function() --> string?;
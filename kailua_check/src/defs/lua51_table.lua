@@ -1,17 +1,15 @@
 -- definitions for Lua 5.1 table library
 
---# -- TODO lacks genericity (yet)
 --# assume global `table`:
 --#     {
 --#         `concat`: function(table: vector<const string|number>, sep: string?,
 --#                            i: integer?, j: integer?) --> string;
---#         -- TODO ah fuck, needs overloading with
---#         --      function(table: vector<WHATEVER>, pos: integer, value: any)
---#         `insert`: function(table: vector<WHATEVER>, value: any);
+--#         `insert`: function<T>(table: vector<T>, value: T) &
+--#                   function<T>(table: vector<T>, pos: integer, value: T);
 --#         `maxn`: function(table: vector<const any>) --> integer;
---#         `remove`: function(table: vector<WHATEVER>, pos: integer?) --> any;
---#         `sort`: function(table: vector<WHATEVER>,
---#                          comp: (function(WHATEVER, WHATEVER) --> boolean)?);
+--#         `remove`: function<T>(table: vector<T>, pos: integer?) --> T?;
+--#         `sort`: function<T>(table: vector<T>,
+--#                             comp: (function(T, T) --> boolean)?);
 --#         ...
 --#     }
 
@@ -0,0 +1,108 @@
+-- definitions for Lua 5.2 basic library
+
+--# -- TODO return generics
+--# assume global `assert`:
+--#     --[[ [assert] ]] function(v: any, message: string?)
+--#
+--# assume global `collectgarbage`:
+--#     function(opt: string?, arg: any?) --> any
+--#
+--# assume global `dofile`:
+--#     [geval] function(filename: string?) --> any
+--#
+--# assume global `error`:
+--#     function(message: string, level: integer?) --> !
+--#
+--# assume global `_ENV`:
+--#     [genv] table
+--#
+--# assume global `_G`:
+--#     [genv] table
+--#
+--# assume global `getmetatable`:
+--#     function(object: any) --> table
+--#
+--# assume global `ipairs`:
+--#     function<T>(t: vector<const T>) -->
+--#         (function(vector<const T>, integer) --> (integer?, T),
+--#          vector<const T>, integer)
+--#
+--# -- TODO sequence conditional union: (function) | (nil, string)
+--# assume global `load`:
+--#     [geval] function(ld: string|(function() --> string?), source: string?,
+--#                      mode: string?, env: table?) --> (function, string)
+--#
+--# -- TODO sequence conditional union: (function) | (nil, string)
+--# assume global `loadfile`:
+--#     [geval] function(filename: string?, mode: string?, env: table?) --> (function, string)
+--#
+--# -- TODO genericity
+--# assume global `next`:
+--#     function(table: table, index: any?) --> (integer, any)
+--#
+--# -- records and `table` still rely on `[generic_pairs]` for key and value types
+--# assume global `pairs`:
+--#     [generic_pairs]
+--#     function<K, V>(t: map<K, const V>) -->
+--#         (function(map<K, const V>, any) --> (K?, V), map<K, const V>, any) &
+--#     function(t: table) --> (function(table, any) --> (any?, any), table, any)
+--#
+--# -- TODO `f` should be once function
+--# -- TODO genericity
+--# assume global `pcall`:
+--#     function(f: function, any...) --> (boolean, any...)
+--#
+--# assume global `print`:
+--#     function(any...)
+--#
+--# assume global `rawequal`:
+--#     function(v1: any, v2: any) --> boolean
+--#
+--# assume global `rawget`:
+--#     function(table: table, index: any) --> any
+--#
+--# assume global `rawlen`:
+--#     function(v: table|string) --> integer
+--#
+--# assume global `rawset`:
+--#     function(table: table, index: any, value: any) --> table
+--#
+--# -- TODO genericity
+--# assume global `select`:
+--#     function(index: '#', any...) --> integer &
+--#     function(index: number, any...) --> (any...)
+--#
+--# assume global `setmetatable`:
+--#     [setmetatable] function(table: table, metatable: table?) --> table
+--#
+--# assume global `tonumber`:
+--#     function(e: any, base: integer?) --> number
+--#
+--# assume global `tostring`:
+--#     function(e: any) --> string
+--#
+--# -- TODO enumerate all the possibility?
+--# assume global `type`:
+--#     [type] function(v: any) --> string
+--#
+--# assume global `_VERSION`:
+--#     string
+--#
+--# -- TODO `f` and `err` should be once function
+--# -- TODO genericity
+--# assume global `xpcall`:
+--#     function(f: function, msgh: function, any...) --> (boolean, any...)
+--#
+--# assume global `coroutine`:
+--#     {
+--#         -- TODO genericity
+--#         `create`: function(f: function) --> thread;
+--#         `resume`: function(co: thread, any...) --> (boolean, any...);
+--#         `running`: function() --> (thread, boolean);
+--#         `status`: function(co: thread) --> string;
+--#         -- TODO genericity
+--#         `wrap`: function(f: function) --> thread;
+--#         `yield`: function(any...) --> (any...);
+--#         ...
+--#     }
+
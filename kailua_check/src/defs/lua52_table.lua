@@ -0,0 +1,18 @@
+-- definitions for Lua 5.2 table library
+
+--# assume global `table`:
+--#     {
+--#         `concat`: function(table: vector<const string|number>, sep: string?,
+--#                            i: integer?, j: integer?) --> string;
+--#         `insert`: function<T>(table: vector<T>, value: T) &
+--#                   function<T>(table: vector<T>, pos: integer, value: T);
+--#         -- TODO should return a table with the field `n`
+--#         `pack`: function(any...) --> vector<any>;
+--#         `remove`: function<T>(table: vector<T>, pos: integer?) --> T?;
+--#         `sort`: function<T>(table: vector<T>,
+--#                             comp: (function(T, T) --> boolean)?);
+--#         -- TODO genericity (tuples cannot be expressed with type parameters)
+--#         `unpack`: function(list: table, i: integer?, j: integer?) --> (any...);
+--#         ...
+--#     }
+
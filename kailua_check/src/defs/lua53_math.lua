@@ -0,0 +1,37 @@
+-- definitions for Lua 5.3 math library
+
+--# assume global `math`:
+--#     {
+--#         `abs`: function(x: integer) --> integer & function(x: number) --> number;
+--#         `acos`: function(x: number) --> number;
+--#         `asin`: function(x: number) --> number;
+--#         `atan`: function(y: number, x: number?) --> number;
+--#         `ceil`: function(x: number) --> integer;
+--#         `cos`: function(x: number) --> number;
+--#         `deg`: function(x: number) --> number;
+--#         `exp`: function(x: number) --> number;
+--#         `floor`: function(x: number) --> integer;
+--#         `fmod`: function(x: number, y: number) --> number;
+--#         `huge`: number;
+--#         `log`: function(x: number, base: number?) --> number;
+--#         `max`: function(x: integer, integer...) --> integer &
+--#                function(x: number, number...) --> number;
+--#         `maxinteger`: integer;
+--#         `min`: function(x: integer, integer...) --> integer &
+--#                function(x: number, number...) --> number;
+--#         `mininteger`: integer;
+--#         `modf`: function(x: number) --> (integer, number);
+--#         `pi`: number;
+--#         `rad`: function(x: number) --> number;
+--#         `random`: function() --> number &
+--#                   function(m: integer, n: integer?) --> integer;
+--#         `randomseed`: function(x: integer);
+--#         `sin`: function(x: number) --> number;
+--#         `sqrt`: function(x: number) --> number;
+--#         `tan`: function(x: number) --> number;
+--#         `tointeger`: function(x: any) --> integer?;
+--#         `type`: function(x: any) --> string?;
+--#         `ult`: function(m: integer, n: integer) --> boolean;
+--#         ...
+--#     }
+
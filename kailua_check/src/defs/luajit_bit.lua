@@ -0,0 +1,19 @@
+-- definitions for LuaJIT 2.x bit library
+
+--# assume global `bit`:
+--#     {
+--#         `arshift`: function(x: number, n: integer) --> integer;
+--#         `band`: function(x: number, number...) --> integer;
+--#         `bnot`: function(x: number) --> integer;
+--#         `bor`: function(x: number, number...) --> integer;
+--#         `bswap`: function(x: number) --> integer;
+--#         `bxor`: function(x: number, number...) --> integer;
+--#         `lshift`: function(x: number, n: integer) --> integer;
+--#         `rol`: function(x: number, n: integer) --> integer;
+--#         `ror`: function(x: number, n: integer) --> integer;
+--#         `rshift`: function(x: number, n: integer) --> integer;
+--#         `tobit`: function(x: number) --> integer;
+--#         `tohex`: function(x: number, n: integer?) --> string;
+--#         ...
+--#     }
+
@@ -0,0 +1,31 @@
+-- definitions for LuaJIT 2.x ffi library
+-- (this is not a global and only available through `require "ffi"`)
+
+-- cdata objects and ctypes are opaque to Kailua and represented as `userdata`.
+local ffi
+--# assume ffi:
+--#     {
+--#         `abi`: function(param: string) --> boolean;
+--#         `alignof`: function(ct: string|userdata) --> integer;
+--#         `arch`: string;
+--#         `C`: WHATEVER;
+--#         `cast`: function(ct: string|userdata, init: any) --> userdata;
+--#         `cdef`: function(def: string);
+--#         `copy`: function(dst: userdata, src: userdata|string, len: integer?);
+--#         `errno`: function(newerr: integer?) --> integer;
+--#         `fill`: function(dst: userdata, len: integer, c: integer?);
+--#         `gc`: function(cdata: userdata, finalizer: function?) --> userdata;
+--#         `istype`: function(ct: string|userdata, obj: any) --> boolean;
+--#         `load`: function(name: string, `global`: boolean?) --> WHATEVER;
+--#         `metatype`: function(ct: string|userdata, metatable: table) --> userdata;
+--#         `new`: function(ct: string|userdata, any...) --> userdata;
+--#         `offsetof`: function(ct: string|userdata,
+--#                              field: string) --> (integer?, integer?, integer?);
+--#         `os`: string;
+--#         `sizeof`: function(ct: string|userdata, nelem: integer?) --> integer?;
+--#         `string`: function(ptr: userdata, len: integer?) --> string;
+--#         `typeof`: function(ct: string|userdata, any...) --> userdata;
+--#         ...
+--#     }
+return ffi
+
@@ -0,0 +1,19 @@
+-- definitions for LuaJIT 2.x jit library
+
+--# assume global `jit`:
+--#     {
+--#         `arch`: string;
+--#         `flush`: function(func: function|boolean?, recursive: boolean?);
+--#         `off`: function(func: function|boolean?, recursive: boolean?);
+--#         `on`: function(func: function|boolean?, recursive: boolean?);
+--#         `opt`: {
+--#             `start`: function(string...);
+--#             ...
+--#         };
+--#         `os`: string;
+--#         `status`: function() --> (boolean, string...);
+--#         `version`: string;
+--#         `version_num`: integer;
+--#         ...
+--#     }
+
@@ -0,0 +1,24 @@
+-- definitions for LuaJIT 2.x package library
+
+--# assume global `module`:
+--#     [become_module] function(name: string, function(table)...)
+--#
+--# assume global `require`:
+--#     [require] function(modname: string) --> any
+--#
+--# assume global `package`:
+--#     {
+--#         `cpath`: [package_cpath] string;
+--#         `loaded`: map<string, table>;
+--#         `loaders`: vector<function(string) --> (function|string)?>;
+--#         `loadlib`: [geval] function(libname: string, funcname: string);
+--#         `path`: [package_path] string;
+--#         `preload`: vector<function(string) --> (function|string)?>;
+--#         -- TODO sequence conditional union: (string) | (nil, string)
+--#         `searchpath`: function(name: string, path: string,
+--#                                sep: string?, rep: string?) --> (string?, string?);
+--#         -- TODO error type not yet supported (should it be a slot?)
+--#         --`seeall`: error "package.seeall is discouraged, use _G instead";
+--#         ...
+--#     }
+
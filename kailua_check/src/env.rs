@@ -8,12 +8,13 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::collections::{hash_map, HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use kailua_env::{self, Span, Spanned, WithLoc, ScopedId, ScopeMap, SpanMap};
 use kailua_diag::{Result, Kind, Report, Reporter, Locale, Localize};
 use kailua_syntax::{Str, Name};
-use kailua_syntax::ast::NameRef;
+use kailua_syntax::ast::{NameRef, Ignore};
 use kailua_types::diag::{TypeReportHint, TypeReportMore};
 use kailua_types::ty::{Displayed, Display, DisplayState, DisplayName};
 use kailua_types::ty::{Ty, TySeq, Nil, T, Slot, SpannedSlotSeq, F, TVar, Lattice, Union, Tag};
@@ -208,6 +209,12 @@ pub struct TypeDef {
     /// The definition span.
     pub span: Span,
 
+    /// Type variables for type parameters, empty if the type is not generic.
+    ///
+    /// A generic type should be always given type arguments,
+    /// which will replace these type variables in `ty`.
+    pub tparams: Vec<TVar>,
+
     /// The type.
     pub ty: Ty,
 }
@@ -246,9 +253,18 @@ impl Scope {
         self.types.get(name)
     }
 
+    pub fn types<'a>(&'a self) -> hash_map::Iter<'a, Name, TypeDef> {
+        self.types.iter()
+    }
+
     // the caller should check for the outermost types first
     pub fn put_type(&mut self, name: Spanned<Name>, ty: Ty) -> bool {
-        self.types.insert(name.base, TypeDef { span: name.span, ty: ty }).is_none()
+        self.put_generic_type(name, Vec::new(), ty)
+    }
+
+    pub fn put_generic_type(&mut self, name: Spanned<Name>, tparams: Vec<TVar>, ty: Ty) -> bool {
+        let def = TypeDef { span: name.span, tparams: tparams, ty: ty };
+        self.types.insert(name.base, def).is_none()
     }
 }
 
@@ -387,16 +403,97 @@ impl ClassProvider for ClassContext {
     }
 }
 
+/// Runtime switches for optional checks.
+///
+/// These can be set per `Context` and apply to every file checked with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckerConfig {
+    /// Warns about statements and loop bodies that can never be executed. Off by default.
+    pub warn_on_dead_code: bool,
+
+    /// Warns about `if` conditions that are always truthy or falsy. Off by default.
+    pub warn_on_useless_conds: bool,
+
+    /// Errors on functions without type annotations for arguments, instead of inferring them.
+    /// On by default.
+    pub no_implicit_func_sig: bool,
+
+    /// Checks every file in the strict mode, as if it had a `--# strict` directive.
+    /// Off by default.
+    pub strict: bool,
+}
+
+impl Default for CheckerConfig {
+    fn default() -> CheckerConfig {
+        CheckerConfig {
+            warn_on_dead_code: false,
+            warn_on_useless_conds: false,
+            no_implicit_func_sig: true,
+            strict: false,
+        }
+    }
+}
+
 /// The global context, which also contains the type context.
 ///
 /// Anything that has to be retained across multiple files should be here.
 /// Due to the presence of a report receiver this is not easily shared or sent across threads;
 /// `Context::into_output` will give a report-free type that is suitable for analysis.
 pub struct Context<R> {
-    report: R,
+    report: IgnoringReport<R>,
+    config: CheckerConfig,
     output: Output,
 }
 
+// a wrapper to the report which drops reports with ignored codes
+struct IgnoringReport<R> {
+    report: R,
+
+    // reports with these codes are ignored, either everywhere or in given regions
+    ignored_codes: HashSet<String>,
+    ignores: Vec<Ignore>,
+
+    // true if the last report other than causes and notes has been ignored,
+    // so that following causes and notes should be ignored as well
+    ignoring: AtomicBool,
+
+    // the number of reports that have not been ignored
+    reported: AtomicUsize,
+}
+
+impl<R: Report> IgnoringReport<R> {
+    fn is_ignored(&self, kind: Kind, span: Span, msg: &Localize) -> bool {
+        match kind {
+            Kind::Fatal => false,
+            Kind::Cause | Kind::Note => self.ignoring.load(Ordering::Relaxed),
+            _ => {
+                let ignored = if let Some(code) = msg.message_code() {
+                    self.ignored_codes.contains(code) ||
+                        self.ignores.iter().any(|ignore| ignore.covers(code, span))
+                } else {
+                    false
+                };
+                self.ignoring.store(ignored, Ordering::Relaxed);
+                ignored
+            }
+        }
+    }
+}
+
+impl<R: Report> Report for IgnoringReport<R> {
+    fn message_locale(&self) -> Locale {
+        self.report.message_locale()
+    }
+
+    fn add_span(&self, k: Kind, s: Span, m: &Localize) -> Result<()> {
+        if self.is_ignored(k, s, m) {
+            return Ok(());
+        }
+        self.reported.fetch_add(1, Ordering::Relaxed);
+        self.report.add_span(k, s, m)
+    }
+}
+
 /// A report-free version of `Context`. Suitable for analysis.
 pub struct Output {
     // name, scope and span information
@@ -404,6 +501,10 @@ pub struct Output {
     scope_maps: Vec<ScopeMap<Name>>,
     spanned_slots: SpanMap<Slot>,
 
+    // annotation information, mainly for statistics
+    annotated_ids: HashMap<Id, bool>, // locals and arguments that can be annotated
+    no_check_funcs: Vec<Span>,
+
     // TODO this might be eventually found useless
     global_scope: Scope,
 
@@ -426,11 +527,20 @@ impl<R: Report> Context<R> {
         let locale = report.message_locale();
         let classes = ClassContext::new();
         let mut ctx = Context {
-            report: report,
+            report: IgnoringReport {
+                report: report,
+                ignored_codes: HashSet::new(),
+                ignores: Vec::new(),
+                ignoring: AtomicBool::new(false),
+                reported: AtomicUsize::new(0),
+            },
+            config: CheckerConfig::default(),
             output: Output {
                 ids: HashMap::new(),
                 scope_maps: Vec::new(),
                 spanned_slots: SpanMap::new(),
+                annotated_ids: HashMap::new(),
+                no_check_funcs: Vec::new(),
                 global_scope: Scope::new(),
                 types: Types::new(locale, Box::new(classes.clone())),
                 opened: HashSet::new(),
@@ -447,7 +557,88 @@ impl<R: Report> Context<R> {
     }
 
     pub fn report(&self) -> &R {
-        &self.report
+        &self.report.report
+    }
+
+    /// Returns the number of reports so far, excluding ignored ones.
+    ///
+    /// Can be used to determine whether a particular file has been checked without any report.
+    pub fn num_reports(&self) -> usize {
+        self.report.reported.load(Ordering::Relaxed)
+    }
+
+    /// Returns a textual summary of the context that a newly checked file can observe:
+    /// the checker configuration, ignored codes, opened libraries, global names and types.
+    ///
+    /// A file checked twice with the same summary should give the same result,
+    /// so this can be used to tell if the previous result is still applicable.
+    pub fn describe_environment(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        let _ = writeln!(out, "config {:?}", self.config);
+
+        let mut codes: Vec<_> = self.report.ignored_codes.iter().collect();
+        codes.sort();
+        let mut opened: Vec<_> = self.opened.iter().collect();
+        opened.sort();
+        let _ = writeln!(out, "ignore {:?}", codes);
+        let _ = writeln!(out, "open {:?}", opened);
+
+        // classes are not always visible via global names, so count them separately
+        {
+            let classes = self.output.classes.inner.read();
+            let defs: Vec<_> = classes.class_systems.iter().map(|&(_, ref system)| {
+                system.num_definitions()
+            }).collect();
+            let _ = writeln!(out, "classes {:?}", defs);
+        }
+
+        // hints are ignored since type names may come from different files
+        let types = &self.types as &TypeContext;
+        let mut names: Vec<_> = self.ids.iter().filter_map(|(id, def)| match *id {
+            Id::Global(ref name) => Some((name, def.slot.slot())),
+            Id::Local(..) => None,
+        }).collect();
+        names.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, slot) in names {
+            match slot {
+                Some(slot) => {
+                    let _ = writeln!(out, "global {:+}: {:-}",
+                                     name, slot.display(types).localized(Locale::dummy()));
+                }
+                None => {
+                    let _ = writeln!(out, "global {:+}", name);
+                }
+            }
+        }
+
+        let mut defs: Vec<_> = self.global_scope.types().collect();
+        defs.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, def) in defs {
+            let _ = writeln!(out, "type {:+}<{}> = {:-}", name, def.tparams.len(),
+                             def.ty.display(types).localized(Locale::dummy()));
+        }
+
+        out
+    }
+
+    pub fn config(&self) -> &CheckerConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: CheckerConfig) {
+        self.config = config;
+    }
+
+    /// Ignores all reports with given message code (e.g. `K0401`).
+    pub fn ignore_code<S: Into<String>>(&mut self, code: S) {
+        self.report.ignored_codes.insert(code.into());
+    }
+
+    /// Registers `--# ignore` regions from the chunk to be checked.
+    pub fn add_ignores(&mut self, ignores: &[Ignore]) {
+        self.report.ignores.extend(ignores.iter().cloned());
     }
 
     pub fn open_library(&mut self, name: Spanned<&[u8]>, opts: Rc<RefCell<Options>>) -> Result<()> {
@@ -457,13 +648,39 @@ impl<R: Report> Context<R> {
                 if self.opened.insert(def.name.to_owned()) {
                     // the built-in code is parsed independently and has no usable span
                     let chunk = def.to_chunk();
-                    let mut env = Env::new(self, opts.clone(), chunk.map);
-                    let mut checker = Checker::new(&mut env);
-                    checker.visit(&chunk.block)?
+                    if let Some(modname) = def.module {
+                        // the module is registered as if it has been `require`d
+                        let span = Span::builtin();
+                        self.mark_module_as_loading(modname.as_bytes(), span);
+                        let mut env = Env::new(self, opts.clone(), chunk.map);
+                        let diverging = Checker::new(&mut env).visit_module(&chunk.block)?;
+                        env.return_from_module(modname.as_bytes(), diverging, span)?;
+                    } else {
+                        let mut env = Env::new(self, opts.clone(), chunk.map);
+                        let mut checker = Checker::new(&mut env);
+                        checker.visit(&chunk.block)?
+                    }
                 }
             }
         } else {
-            self.error(name, m::CannotOpenLibrary {}).done()?;
+            // otherwise the library may have been registered by the user
+            let key = String::from_utf8_lossy(&name.base).into_owned();
+            if self.opened.contains(&key) {
+                return Ok(());
+            }
+            let chunks = match opts.borrow_mut().library_chunks(name, self) {
+                Ok(chunks) => chunks,
+                Err(None) => return self.error(name, m::CannotOpenLibrary {}).done(),
+                Err(Some(stop)) => return Err(stop),
+            };
+            self.opened.insert(key);
+            for chunk in chunks {
+                self.add_ignores(&chunk.ignores);
+                let mut env = Env::new(self, opts.clone(), chunk.map);
+                env.set_strict(chunk.strict);
+                let mut checker = Checker::new(&mut env);
+                checker.visit(&chunk.block)?
+            }
         }
         Ok(())
     }
@@ -569,6 +786,17 @@ impl Output {
         self.ids.iter()
     }
 
+    /// Returns local variables and function arguments which could have been annotated,
+    /// with a flag set to true if they have been actually annotated with a type.
+    pub fn annotated_ids<'a>(&'a self) -> hash_map::Iter<'a, Id, bool> {
+        self.annotated_ids.iter()
+    }
+
+    /// Returns the spans to declarations of all checked functions with `[NO_CHECK]`.
+    pub fn no_check_funcs(&self) -> &[Span] {
+        &self.no_check_funcs
+    }
+
     pub fn get_string_meta(&self) -> Option<Spanned<Slot>> {
         self.string_meta.clone()
     }
@@ -668,6 +896,8 @@ pub struct Env<'ctx, R: 'ctx> {
     scopes: Vec<Scope>,
     // separate from scoped types, `--# type` will set both
     exported_types: HashMap<Name, TypeDef>,
+    // true if the current file should be checked in the strict mode
+    strict: bool,
 }
 
 impl<'ctx, R: Report> Env<'ctx, R> {
@@ -683,9 +913,24 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             // we have local variables even at the global position, so we need at least one Scope
             scopes: vec![Scope::new_function(global_frame)],
             exported_types: HashMap::new(),
+            strict: false,
         }
     }
 
+    /// Turns the strict mode on or off for the current file.
+    ///
+    /// This is off by default, so that built-in libraries are never checked in the strict mode.
+    /// The caller should turn it on when the chunk has a `--# strict` directive or
+    /// the configuration requests so.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Returns true if the current file is checked in the strict mode.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
     pub fn types(&mut self) -> &mut Types {
         &mut self.context.types
     }
@@ -699,6 +944,10 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         &self.opts
     }
 
+    pub fn config(&self) -> &CheckerConfig {
+        &self.context.config
+    }
+
     pub fn scope_map(&self) -> &ScopeMap<Name> {
         &self.context.scope_maps[self.map_index]
     }
@@ -1071,6 +1320,7 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             self.error(nameref, m::CannotRedefineVar { name: name }).done()?;
             return Ok(None);
         }
+        self.check_strict_global(&id)?;
 
         let slot = if let Some(initinfo) = initinfo {
             let specinfo = self.assign_from_spec(&initinfo, specinfo.as_ref())?;
@@ -1097,6 +1347,26 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         }
     }
 
+    /// Records whether a newly added variable has been annotated with a type or not.
+    pub fn record_annotation(&mut self, nameref: &Spanned<NameRef>, annotated: bool) {
+        let id = self.id_from_nameref(nameref);
+        self.context.annotated_ids.insert(id.base, annotated);
+    }
+
+    /// Records the declaration of a function with `[NO_CHECK]`.
+    pub fn record_no_check_func(&mut self, declspan: Span) {
+        self.context.no_check_funcs.push(declspan);
+    }
+
+    // in the strict mode, global variables can be only created by `--# assume global`
+    fn check_strict_global(&self, id: &Spanned<Id>) -> Result<()> {
+        if self.strict && id.is_global() {
+            let name = id.name(&self.context);
+            self.error(id, m::StrictImplicitGlobal { name: name }).done()?;
+        }
+        Ok(())
+    }
+
     /// Adds a local variable with that has been already initialized with the type `info`.
     ///
     /// This is necessary for non-assignment statements that introduce a new variable
@@ -1143,6 +1413,7 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             def.slot = NameSlot::Set(previnfo.clone());
             (previnfo, prevset, needslotassign)
         } else {
+            self.check_strict_global(&id)?;
             let varname = id.name(self.context).clone().with_loc(nameref);
             let info = info.base.clone().set_display(DisplayName::Var(varname));
             self.context.ids.insert(id.base.clone(),
@@ -1222,7 +1493,8 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         self.get_named_local_type(name).or_else(|| self.get_named_global_type(name))
     }
 
-    pub fn define_local_type(&mut self, name: &Spanned<Name>, ty: Ty) -> Result<()> {
+    pub fn define_local_type(&mut self, name: &Spanned<Name>, tparams: Vec<TVar>,
+                             ty: Ty) -> Result<()> {
         if let Some(def) = self.get_named_local_type(name) {
             self.error(name, m::CannotRedefineLocalType { name: &name.base })
                 .note(def.span, m::AlreadyDefinedType {})
@@ -1236,12 +1508,13 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         }
 
         let ty = ty.and_display(DisplayName::Type(name.clone()));
-        let ret = self.current_scope_mut().put_type(name.clone(), ty);
+        let ret = self.current_scope_mut().put_generic_type(name.clone(), tparams, ty);
         assert!(ret, "failed to insert the type");
         Ok(())
     }
 
-    pub fn define_global_type(&mut self, name: &Spanned<Name>, ty: Ty) -> Result<()> {
+    pub fn define_global_type(&mut self, name: &Spanned<Name>, tparams: Vec<TVar>,
+                              ty: Ty) -> Result<()> {
         if let Some(def) = self.get_named_local_type(name) {
             self.error(name, m::CannotRedefineLocalTypeAsGlobal { name: &name.base })
                 .note(def.span, m::AlreadyDefinedType {})
@@ -1255,12 +1528,13 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         }
 
         let ty = ty.and_display(DisplayName::Type(name.clone()));
-        let ret = self.global_scope_mut().put_type(name.clone(), ty);
+        let ret = self.global_scope_mut().put_generic_type(name.clone(), tparams, ty);
         assert!(ret, "failed to insert the type");
         Ok(())
     }
 
-    pub fn define_and_export_type(&mut self, name: &Spanned<Name>, ty: Ty) -> Result<()> {
+    pub fn define_and_export_type(&mut self, name: &Spanned<Name>, tparams: Vec<TVar>,
+                                  ty: Ty) -> Result<()> {
         if let Some(def) = self.get_named_type(name) {
             self.error(name, m::CannotRedefineAndReexportType { name: &name.base })
                 .note(def.span, m::AlreadyDefinedType {})
@@ -1271,7 +1545,7 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         // insert to the exported types (distinct from scoped types)
         let defspan = match self.exported_types.entry(name.base.clone()) {
             hash_map::Entry::Vacant(e) => {
-                e.insert(TypeDef { ty: ty.clone(), span: name.span });
+                e.insert(TypeDef { span: name.span, tparams: tparams.clone(), ty: ty.clone() });
                 None
             },
             hash_map::Entry::Occupied(e) => Some(e.get().span),
@@ -1288,14 +1562,14 @@ impl<'ctx, R: Report> Env<'ctx, R> {
 
         // insert a locally scoped type
         let ty = ty.and_display(DisplayName::Type(name.clone()));
-        let ret = self.current_scope_mut().put_type(name.clone(), ty);
+        let ret = self.current_scope_mut().put_generic_type(name.clone(), tparams, ty);
         assert!(ret, "failed to insert the type");
         Ok(())
     }
 
     pub fn redefine_global_type(&mut self, name: &Spanned<Name>, tyspan: Span) -> Result<()> {
-        let ty = if let Some(def) = self.get_named_local_type(name) {
-            def.ty.clone()
+        let (tparams, ty) = if let Some(def) = self.get_named_local_type(name) {
+            (def.tparams.clone(), def.ty.clone())
         } else if let Some(def) = self.get_named_global_type(name) {
             self.error(name, m::CannotRedefineGlobalType { name: &name.base })
                 .note(def.span, m::AlreadyDefinedType {})
@@ -1303,17 +1577,17 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             return Ok(());
         } else {
             self.error(tyspan, m::NoType { name: &name.base }).done()?;
-            Ty::dummy()
+            (Vec::new(), Ty::dummy())
         };
 
-        let ret = self.global_scope_mut().put_type(name.clone(), ty);
+        let ret = self.global_scope_mut().put_generic_type(name.clone(), tparams, ty);
         assert!(ret, "failed to insert the type");
         Ok(())
     }
 
     pub fn reexport_local_type(&mut self, name: &Spanned<Name>, tyspan: Span) -> Result<()> {
-        let ty = if let Some(def) = self.get_named_local_type(name) {
-            def.ty.clone()
+        let (tparams, ty) = if let Some(def) = self.get_named_local_type(name) {
+            (def.tparams.clone(), def.ty.clone())
         } else if let Some(def) = self.get_named_global_type(name) {
             self.error(name, m::CannotRedefineGlobalType { name: &name.base })
                 .note(def.span, m::AlreadyDefinedType {})
@@ -1321,13 +1595,13 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             return Ok(());
         } else {
             self.error(tyspan, m::NoType { name: &name.base }).done()?;
-            Ty::dummy()
+            (Vec::new(), Ty::dummy())
         };
 
         // insert to the exported types
         let defspan = match self.exported_types.entry(name.base.clone()) {
             hash_map::Entry::Vacant(e) => {
-                e.insert(TypeDef { ty: ty.clone(), span: name.span });
+                e.insert(TypeDef { span: name.span, tparams: tparams, ty: ty });
                 None
             },
             hash_map::Entry::Occupied(e) => Some(e.get().span),
@@ -1352,7 +1626,8 @@ impl<'ctx, R: Report> Env<'ctx, R> {
                 return Ok(());
             }
 
-            let ret = self.current_scope_mut().put_type(name.with_loc(def.span), def.ty);
+            let ret = self.current_scope_mut().put_generic_type(name.with_loc(def.span),
+                                                                def.tparams, def.ty);
             assert!(ret, "failed to insert the type");
         }
 
@@ -1409,6 +1684,13 @@ impl<'ctx, R: Report> TypeResolver for Env<'ctx, R> {
 
     fn ty_from_name(&self, name: &Spanned<Name>) -> Result<Ty> {
         if let Some(def) = self.get_named_type(name) {
+            if !def.tparams.is_empty() {
+                self.error(name, m::WrongTypeArgsArity { name: &name.base,
+                                                         nparams: def.tparams.len() })
+                    .note(def.span, m::AlreadyDefinedType {})
+                    .done()?;
+                return Ok(Ty::dummy());
+            }
             Ok(def.ty.clone())
         } else {
             self.error(name, m::NoType { name: &name.base }).done()?;
@@ -1416,6 +1698,32 @@ impl<'ctx, R: Report> TypeResolver for Env<'ctx, R> {
         }
     }
 
+    fn ty_from_generic_name(&mut self, name: &Spanned<Name>, args: &[Ty]) -> Result<Ty> {
+        let def = if let Some(def) = self.get_named_type(name) {
+            def.clone()
+        } else {
+            self.error(name, m::NoType { name: &name.base }).done()?;
+            return Ok(Ty::dummy());
+        };
+
+        if def.tparams.is_empty() {
+            self.error(name, m::NonGenericTypeWithArgs { name: &name.base })
+                .note(def.span, m::AlreadyDefinedType {})
+                .done()?;
+            return Ok(Ty::dummy());
+        }
+        if def.tparams.len() != args.len() {
+            self.error(name, m::WrongTypeArgsArity { name: &name.base,
+                                                     nparams: def.tparams.len() })
+                .note(def.span, m::AlreadyDefinedType {})
+                .done()?;
+            return Ok(Ty::dummy());
+        }
+
+        let tvars: Vec<_> = def.tparams.iter().cloned().zip(args.iter().cloned()).collect();
+        Ok(def.ty.substitute(&tvars, self.context_mut()).unwrap_or(def.ty))
+    }
+
     fn class_system_from_name(&self, name: &Spanned<Name>) -> Result<Option<ClassSystemId>> {
         if let Some(csid) = self.context.classes.read().class_system_names.get(name) {
             Ok(Some(csid.base))
@@ -1424,6 +1732,13 @@ impl<'ctx, R: Report> TypeResolver for Env<'ctx, R> {
             Ok(None)
         }
     }
+
+    fn check_dynamic(&mut self, span: Span) -> Result<()> {
+        if self.strict {
+            self.error(span, m::StrictDynamicType {}).done()?;
+        }
+        Ok(())
+    }
 }
 
 #[test]
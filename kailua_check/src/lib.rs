@@ -41,6 +41,8 @@ pub mod env;
 mod defs;
 mod class_system;
 mod check;
+pub mod stub;
+pub mod stats;
 
 /// Options to populate the execution environment before checking.
 ///
@@ -67,7 +69,10 @@ pub fn check_from_chunk<R: Report>(
     chunk: kailua_syntax::Chunk,
     opts: Rc<RefCell<options::Options>>
 ) -> kailua_diag::Result<()> {
+    context.add_ignores(&chunk.ignores);
+    let strict = chunk.strict || context.config().strict;
     let mut env = env::Env::new(context, opts, chunk.map);
+    env.set_strict(strict);
     let mut checker = Checker::new(&mut env);
     checker.visit(&chunk.block)
 }
@@ -84,7 +89,10 @@ pub fn check_from_chunk_with_preloading<R: Report>(
         context.open_library(name.as_ref().map(|n| &n[..]), opts.clone())?;
     }
 
+    context.add_ignores(&chunk.ignores);
+    let strict = chunk.strict || context.config().strict;
     let mut env = env::Env::new(context, opts, chunk.map);
+    env.set_strict(strict);
     let mut checker = Checker::new(&mut env);
 
     // preload `require`s into the checker
@@ -95,3 +103,34 @@ pub fn check_from_chunk_with_preloading<R: Report>(
     checker.visit(&chunk.block)
 }
 
+
+/// Same to `check_from_chunk_with_preloading` but checks the chunk as a module named `modname`.
+///
+/// The resulting module is recorded to the context (so that later `require`s can reuse it)
+/// and also returned. Returns `Ok(None)` when the module cannot be made from the chunk
+/// (e.g. it returns an inexact type), which should have been reported.
+pub fn check_module_from_chunk_with_preloading<R: Report>(
+    context: &mut env::Context<R>,
+    chunk: kailua_syntax::Chunk,
+    modname: Spanned<&[u8]>,
+    opts: Rc<RefCell<options::Options>>,
+    preload: &Preload
+) -> kailua_diag::Result<Option<env::Module>> {
+    for name in &preload.open {
+        context.open_library(name.as_ref().map(|n| &n[..]), opts.clone())?;
+    }
+
+    context.add_ignores(&chunk.ignores);
+    context.mark_module_as_loading(&modname, modname.span);
+    let strict = chunk.strict || context.config().strict;
+    let mut env = env::Env::new(context, opts, chunk.map);
+    env.set_strict(strict);
+    let diverging = {
+        let mut checker = Checker::new(&mut env);
+        for name in &preload.require {
+            checker.require(name.as_ref().map(|n| &n[..]), name.span)?;
+        }
+        checker.visit_module(&chunk.block)?
+    };
+    env.return_from_module(&modname, diverging, modname.span)
+}
@@ -1,12 +1,17 @@
 //! The user-configurable portion of the type checker.
 
 use std::str;
+use std::fs::{self, File};
+use std::io::Read;
 use std::ascii::AsciiExt;
 use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use std::collections::HashMap;
 
 use kailua_env::{Spanned, WithLoc};
 use kailua_diag::{Report, Stop};
 use kailua_syntax::Chunk;
+use kailua_types::ty::TypeContext;
+use env::Module;
 
 /// Options for customizing the type checker.
 ///
@@ -38,6 +43,41 @@ pub trait Options {
                      _report: &Report) -> Result<Chunk, Option<Stop>> {
         Err(None)
     }
+
+    /// Called when `--# open` is used with a name that is not a built-in library.
+    ///
+    /// Should return chunks defining the library in order,
+    /// which are checked exactly like built-in definitions.
+    /// Errors by default; the checker will use its own error message.
+    fn library_chunks(&mut self, _name: Spanned<&[u8]>,
+                      _report: &Report) -> Result<Vec<Chunk>, Option<Stop>> {
+        Err(None)
+    }
+
+    /// Called before `require_chunk` with a summary of the context the module will see.
+    /// See `Context::describe_environment` for the details.
+    ///
+    /// Does nothing by default.
+    fn module_environment(&mut self, _path: Spanned<&[u8]>, _environment: &str) {
+    }
+
+    /// Called when the chunk from `require_chunk` has been checked.
+    ///
+    /// `module` is `None` when the checking has been stopped.
+    /// `reusable` is true when the module (and every module it requires) has been checked
+    /// without any report and without changing the environment,
+    /// so the module can be replaced with its result in the same environment.
+    ///
+    /// Does nothing by default.
+    fn module_checked(&mut self, _path: Spanned<&[u8]>, _module: Option<&Module>,
+                      _types: &TypeContext, _reusable: bool) {
+    }
+
+    /// Called when `require` is called for the module that has been already loaded.
+    ///
+    /// Does nothing by default.
+    fn module_reused(&mut self, _path: Spanned<&[u8]>) {
+    }
 }
 
 /// Checker options that are tailored to loading from the file system.
@@ -51,6 +91,31 @@ pub trait FsSource {
     fn chunk_from_path(&self, resolved_path: Spanned<&Path>,
                        report: &Report) -> Result<Option<Chunk>, Option<Stop>>;
 
+    /// Should try to read the raw contents of a given fully resolved path.
+    ///
+    /// Used to tell if the file has been changed. `None` means that the path doesn't exist
+    /// or the contents are not fixed (e.g. the file is being edited).
+    /// Reads the file from the file system by default.
+    fn data_from_path(&self, resolved_path: &Path) -> Option<Vec<u8>> {
+        let mut data = Vec::new();
+        File::open(resolved_path).and_then(|mut f| f.read_to_end(&mut data)).ok().map(|_| data)
+    }
+
+    /// Should try to list files in a given fully resolved directory path.
+    ///
+    /// Used to load every definition file in the library directory.
+    /// `None` means that the path is not a directory.
+    /// Reads the directory from the file system by default.
+    fn files_in_dir(&self, resolved_path: &Path) -> Option<Vec<PathBuf>> {
+        match fs::read_dir(resolved_path) {
+            Ok(entries) => {
+                Some(entries.filter_map(|e| e.ok()).map(|e| e.path())
+                            .filter(|path| path.is_file()).collect())
+            }
+            Err(_) => None,
+        }
+    }
+
     /// Should try to parse a byte string containing a relative path to a path buffer.
     ///
     /// Used to delegate the encoding decision to the user.
@@ -72,6 +137,7 @@ pub struct FsOptions<S> {
     root: PathBuf,
     package_path: Vec<Vec<u8>>,
     package_cpath: Vec<Vec<u8>>,
+    libraries: HashMap<Vec<u8>, Vec<PathBuf>>,
 }
 
 impl<S: FsSource> FsOptions<S> {
@@ -83,9 +149,18 @@ impl<S: FsSource> FsOptions<S> {
             // by default, local files only
             package_path: vec![b"?.lua".to_vec()],
             package_cpath: vec![],
+            libraries: HashMap::new(),
         }
     }
 
+    /// Registers a library to be opened with `--# open`.
+    ///
+    /// Each path (relative to the root) can be either a definition file or a directory,
+    /// in which case every `.kailua` file in it is loaded in the order of file names.
+    pub fn add_library(&mut self, name: Vec<u8>, paths: Vec<PathBuf>) {
+        self.libraries.insert(name, paths);
+    }
+
     fn search_file(&self, path: Spanned<&[u8]>, search_paths: &[Vec<u8>], suffix: &[u8],
                    report: &Report) -> Result<Option<Chunk>, Option<Stop>> {
         for template in search_paths {
@@ -154,5 +229,34 @@ impl<S: FsSource> Options for FsOptions<S> {
 
         Err(None)
     }
+
+    fn library_chunks(&mut self, name: Spanned<&[u8]>,
+                      report: &Report) -> Result<Vec<Chunk>, Option<Stop>> {
+        let paths = match self.libraries.get(&name.base[..]) {
+            Some(paths) => paths,
+            None => return Err(None),
+        };
+
+        let mut chunks = Vec::new();
+        for path in paths {
+            let resolved_path = self.root.join(path);
+            let files = match self.source.files_in_dir(&resolved_path) {
+                Some(mut files) => {
+                    files.retain(|path| path.extension().map_or(false, |ext| ext == "kailua"));
+                    files.sort();
+                    files
+                }
+                None => vec![resolved_path],
+            };
+            for file in files {
+                trace!("trying to load {:?} for the library {:?}", file, name);
+                match self.source.chunk_from_path((&*file).with_loc(name), report)? {
+                    Some(chunk) => chunks.push(chunk),
+                    None => return Err(None),
+                }
+            }
+        }
+        Ok(chunks)
+    }
 }
 
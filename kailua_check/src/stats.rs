@@ -0,0 +1,165 @@
+//! Type coverage statistics from checked outputs.
+//!
+//! The statistics are collected per source unit (i.e. per file),
+//! so that the progress of typing can be tracked for each file and in total.
+//! Expressions are counted from the spanned slots recorded by the checker,
+//! and local variables and arguments are counted from the recorded annotations.
+//! Built-in definitions are never counted.
+
+use std::ops;
+use std::collections::HashMap;
+
+use kailua_env::{Span, Unit};
+use kailua_types::ty::{Dyn, T};
+
+use env::Output;
+
+/// Type coverage statistics for a single file or a group of files.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of expressions with a known type.
+    pub exprs: usize,
+
+    /// The number of expressions inferred as `WHATEVER`.
+    pub whatever_exprs: usize,
+
+    /// The number of expressions inferred as `any`.
+    pub any_exprs: usize,
+
+    /// The number of expressions with an erroneous type (`Dyn::Oops`).
+    pub oops_exprs: usize,
+
+    /// The number of functions with `[NO_CHECK]`.
+    pub no_check_funcs: usize,
+
+    /// The number of local variables and arguments.
+    pub names: usize,
+
+    /// The number of local variables and arguments annotated with a type.
+    pub annotated_names: usize,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats::default()
+    }
+
+    /// Returns the ratio of expressions with a type other than `WHATEVER`, `any` and
+    /// erroneous types, or `None` if there is no expression.
+    pub fn typed_exprs_ratio(&self) -> Option<f64> {
+        if self.exprs > 0 {
+            let untyped = self.whatever_exprs + self.any_exprs + self.oops_exprs;
+            Some((self.exprs - untyped) as f64 / self.exprs as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the ratio of annotated local variables and arguments,
+    /// or `None` if there is no such name.
+    pub fn annotated_names_ratio(&self) -> Option<f64> {
+        if self.names > 0 {
+            Some(self.annotated_names as f64 / self.names as f64)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> ops::AddAssign<&'a Stats> for Stats {
+    fn add_assign(&mut self, rhs: &'a Stats) {
+        self.exprs += rhs.exprs;
+        self.whatever_exprs += rhs.whatever_exprs;
+        self.any_exprs += rhs.any_exprs;
+        self.oops_exprs += rhs.oops_exprs;
+        self.no_check_funcs += rhs.no_check_funcs;
+        self.names += rhs.names;
+        self.annotated_names += rhs.annotated_names;
+    }
+}
+
+// returns the statistics for the unit containing given span, unless it is built-in
+fn entry(stats: &mut HashMap<Unit, Stats>, span: Span) -> Option<&mut Stats> {
+    if span.is_source_dependent() {
+        Some(stats.entry(span.unit()).or_insert_with(Stats::new))
+    } else {
+        None
+    }
+}
+
+/// Collects the statistics for each source unit from the checker output.
+pub fn stats_by_unit(output: &Output) -> HashMap<Unit, Stats> {
+    let mut stats = HashMap::new();
+
+    for slot in output.spanned_slots() {
+        if let Some(stats) = entry(&mut stats, slot.span) {
+            stats.exprs += 1;
+            match **slot.unlift() {
+                T::Dynamic(Dyn::User) => stats.whatever_exprs += 1,
+                T::Dynamic(Dyn::Oops) => stats.oops_exprs += 1,
+                T::All => stats.any_exprs += 1,
+                _ => {}
+            }
+        }
+    }
+
+    for &span in output.no_check_funcs() {
+        if let Some(stats) = entry(&mut stats, span) {
+            stats.no_check_funcs += 1;
+        }
+    }
+
+    for (id, &annotated) in output.annotated_ids() {
+        let span = output.get(id).map_or(Span::dummy(), |def| def.span);
+        if let Some(stats) = entry(&mut stats, span) {
+            stats.names += 1;
+            if annotated {
+                stats.annotated_names += 1;
+            }
+        }
+    }
+
+    stats
+}
+
+#[test]
+fn test_stats() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use kailua_env::{Source, SourceFile};
+    use kailua_diag::NoReport;
+    use kailua_syntax::parse_chunk;
+    use env::Context;
+    use options::Options;
+
+    let mut source = Source::new();
+    let span = source.add(SourceFile::from_u8("test.lua".to_string(), b"\
+        local a = 1\n\
+        local b --: WHATEVER\n\
+        local c = b\n\
+        local function f(x, --: integer\n\
+                         y) --: string\n\
+            return x\n\
+        end\n\
+        --v [NO_CHECK] function(x: integer) --> integer\n\
+        local function g(x)\n\
+            return x\n\
+        end\n".to_vec()));
+    let chunk = parse_chunk(&source, span, &NoReport).unwrap();
+
+    struct NoOptions;
+    impl Options for NoOptions {}
+
+    let mut context = Context::new(NoReport);
+    let opts = Rc::new(RefCell::new(NoOptions));
+    ::check_from_chunk(&mut context, chunk, opts).unwrap();
+
+    let stats = stats_by_unit(&context.into_output());
+    assert_eq!(stats.len(), 1);
+    let stats = &stats[&span.unit()];
+    assert_eq!(stats.no_check_funcs, 1);
+    // a, b, c, x and y in f, x in g (where b and all arguments are annotated)
+    assert_eq!((stats.names, stats.annotated_names), (6, 4));
+    assert_eq!(stats.whatever_exprs, 1); // b in `local c = b`
+    assert_eq!(stats.oops_exprs, 0);
+}
@@ -0,0 +1,411 @@
+//! Generation of `.kailua` declaration files from checked modules.
+//!
+//! `FsOptions` reads `<module>.kailua` in place of `<module>.lua` if any,
+//! so a declaration file can replace the module without checking it again.
+//! The file consists of `--# type` declarations for exported types and
+//! a single `--# assume` declaration for the returned value.
+//!
+//! Not every type can be written back to the source code (e.g. recursive records),
+//! so each declared type is parsed again and replaced with `WHATEVER` if it fails;
+//! a comment is left for each such type. The `self` argument of a method is often
+//! inferred to a type variable which cannot be written. The record type of the module
+//! would be recursive in that case, so the receiver is written as the module record
+//! without such methods (e.g. `{name: string, ...}`).
+//! The exact stub, used for caching checked modules, gives up instead.
+
+use std::fmt::Write;
+use std::collections::BTreeMap;
+
+use kailua_env::{Source, SourceFile};
+use kailua_diag::{Locale, NoReport};
+use kailua_diag::report::TrackMaxKind;
+use kailua_syntax::parse_chunk;
+use kailua_types::ty::{Display, TypeContext, T, Ty, Tables, Functions, Key, Slot, RVar};
+
+use env::Module;
+
+// the name of the local variable holding the module value
+const MODULE_VAR: &'static str = "M";
+
+// returns true if given declaration (e.g. `--# type T = <text>`) is valid
+fn is_valid_decl(decl: String) -> bool {
+    let mut source = Source::new();
+    let span = source.add(SourceFile::from_u8("<stub>".to_string(), decl.into_bytes()));
+    let report = TrackMaxKind::new(NoReport);
+    parse_chunk(&source, span, &report).is_ok() && report.can_continue()
+}
+
+// `{:0}` ignores the type name at the top level, so that `--# type T = T` cannot happen.
+// `{:-0}` additionally ignores every type name, so that the text does not depend on
+// type names that may not be visible from the stub.
+fn display_text<X: Display>(x: &X, types: &TypeContext, exact: bool) -> String {
+    let displayed = x.display(types);
+    let localized = displayed.localized(Locale::dummy());
+    if exact { format!("{:-0}", localized) } else { format!("{:0}", localized) }
+}
+
+// returns `None` if the type cannot be written back.
+fn type_text<X: Display>(x: &X, types: &TypeContext, exact: bool) -> Option<String> {
+    let text = display_text(x, types, exact);
+    if is_valid_decl(format!("--# type T = {}\n", text)) { Some(text) } else { None }
+}
+
+// same to `type_text` but for record fields, which may have a modifier like `const`.
+fn field_text<X: Display>(x: &X, types: &TypeContext, exact: bool) -> Option<String> {
+    let text = display_text(x, types, exact);
+    if is_valid_decl(format!("--# type T = {{x: {}}}\n", text)) { Some(text) } else { None }
+}
+
+// `self` in `function M:method(...)` is a fresh type variable unless it can be inferred,
+// and it remains unresolved after checking. returns the same method without `self`,
+// which should be written with `method_text`.
+fn without_unresolved_self(ty: &Ty, types: &TypeContext) -> Option<Ty> {
+    let ty = types.resolve_exact_type(ty)?;
+    let func = match *ty {
+        T::Functions(ref funcs) => match **funcs {
+            Functions::Simple(ref func) => func,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    match func.argnames.first() {
+        Some(&Some(ref name)) if &name[..] == b"self" => {}
+        _ => return None,
+    }
+    match func.args.head.first().and_then(|ty| ty.get_tvar()) {
+        Some(tvar) if types.get_tvar_exact_type(tvar).is_none() => {}
+        _ => return None,
+    }
+
+    let mut func = func.clone();
+    func.args.head.remove(0);
+    func.argnames.remove(0);
+    Some(Ty::new(T::func(func)).union_nil(ty.nil()).with_tag(ty.tag()))
+}
+
+// inserts `self: <recv>` to the text of the method without `self`.
+// returns `None` if the result cannot be written back.
+fn method_text(text: &str, recv: &str) -> Option<String> {
+    let func = text.find("function")?;
+    let args = func + text[func..].find('(')? + 1;
+    let sep = if text[args..].starts_with(')') { "" } else { ", " };
+    let text = format!("{}self: {}{}{}", &text[..args], recv, sep, &text[args..]);
+    if is_valid_decl(format!("--# type T = {}\n", text)) { Some(text) } else { None }
+}
+
+/// Returns the contents of the declaration file for given module.
+pub fn module_to_stub(module: &Module, types: &TypeContext) -> String {
+    write_stub(module, types, false).expect("write_stub should not fail when not exact")
+}
+
+/// Same to `module_to_stub` but returns `None` when the stub would be different from
+/// the original module in any way, e.g. when a type cannot be written back or is generic.
+///
+/// Types are always written without type names, so the result is suitable for caching.
+pub fn module_to_exact_stub(module: &Module, types: &TypeContext) -> Option<String> {
+    write_stub(module, types, true)
+}
+
+fn write_stub(module: &Module, types: &TypeContext, exact: bool) -> Option<String> {
+    let mut out = String::new();
+
+    // exported types are sorted by their names for the stable output
+    let exported: BTreeMap<_, _> = module.exported_types.iter().collect();
+    for (name, def) in exported {
+        if !def.tparams.is_empty() {
+            if exact {
+                return None;
+            }
+            // type parameters are not tracked after the definition
+            let _ = writeln!(out, "-- generic type {:+} cannot be declared", name);
+            continue;
+        }
+        let text = match type_text(&def.ty, types, exact) {
+            Some(text) => text,
+            None if exact => return None,
+            None => {
+                let _ = writeln!(out, "-- type {:+} cannot be written and is declared as WHATEVER",
+                                 name);
+                "WHATEVER".to_string()
+            }
+        };
+        let _ = writeln!(out, "--# type {:+} = {}", name, text);
+    }
+    if !module.exported_types.is_empty() {
+        out.push('\n');
+    }
+
+    let returns = match module.returns {
+        Some(ref returns) => returns,
+        None => {
+            if exact {
+                return None;
+            }
+            out.push_str("-- the module never returns\n");
+            return Some(out);
+        }
+    };
+
+    let returns = returns.unlift();
+    let ty = types.resolve_exact_type(&returns).unwrap_or_else(|| returns.clone());
+    let rvar = match *ty {
+        T::Tables(ref tables) => match **tables {
+            Tables::Fields(ref rvar) => Some(rvar.clone()),
+            _ => None,
+        },
+        _ => None,
+    };
+    match rvar {
+        // records are written one field per line for the readability
+        Some(rvar) => {
+            // the metatable cannot be written back
+            if exact && types.get_rvar_metatable(rvar.clone()).is_some() {
+                return None;
+            }
+
+            let mut fields: Vec<(Key, Slot)> = Vec::new();
+            let lastrvar = types.list_rvar_fields(rvar, &mut |k, v| {
+                fields.push((k.clone(), v.clone()));
+                Ok(())
+            });
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+
+            // methods with unresolved `self` need the receiver type,
+            // which is made of every other field and known only after the first pass
+            let mut texts = Vec::new();
+            let mut recv = String::from("{");
+            for (key, slot) in fields {
+                let text = field_text(&slot, types, exact);
+                let mut method = None;
+                if let Some(ref text) = text {
+                    let _ = write!(recv, "{}: {}, ", key, text);
+                } else if !exact {
+                    method = without_unresolved_self(&slot.unlift(), types).and_then(|ty| {
+                        type_text(&ty, types, exact)
+                    });
+                }
+                texts.push((key, text, method));
+            }
+            recv.push_str("...}");
+
+            let mut lines = String::new();
+            for (key, text, method) in texts {
+                let text = text.or_else(|| method.and_then(|text| method_text(&text, &recv)));
+                let text = match text {
+                    Some(text) => text,
+                    None if exact => return None,
+                    None => {
+                        // comments cannot appear in the middle of the declaration
+                        let _ = writeln!(out, "-- field {} cannot be written \
+                                               and is declared as WHATEVER", key);
+                        "WHATEVER".to_string()
+                    }
+                };
+                let _ = writeln!(lines, "--#     {}: {};", key, text);
+            }
+
+            // requirers can add new fields to the record unless it has been closed
+            if lastrvar != Ok(RVar::empty()) {
+                lines.push_str("--#     ...\n");
+            }
+
+            let _ = writeln!(out, "--# assume {}: {{", MODULE_VAR);
+            out.push_str(&lines);
+            out.push_str("--# }\n");
+        }
+
+        None => {
+            let text = match type_text(&ty, types, exact) {
+                Some(text) => text,
+                None if exact => return None,
+                None => {
+                    out.push_str("-- the module cannot be written and is declared as WHATEVER\n");
+                    "WHATEVER".to_string()
+                }
+            };
+            let _ = writeln!(out, "--# assume {}: {}", MODULE_VAR, text);
+        }
+    }
+    let _ = writeln!(out, "return {}", MODULE_VAR);
+
+    Some(out)
+}
+
+#[test]
+fn test_module_to_stub() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use kailua_env::{Spanned, WithLoc};
+    use kailua_diag::{Kind, Report, Stop, CollectedReport};
+    use kailua_syntax::Chunk;
+    use env::Context;
+    use options::Options;
+
+    fn parse(source: &mut Source, name: &str, code: &str, report: &Report) -> Chunk {
+        let span = source.add(SourceFile::from_u8(name.to_string(), code.as_bytes().to_owned()));
+        parse_chunk(source, span, report).unwrap()
+    }
+
+    let mut source = Source::new();
+    let report = CollectedReport::new(Locale::dummy());
+    let chunk = parse(&mut source, "mod.lua", "\
+        --# type Point = {x: integer, y: integer}\n\
+        --# type local Hidden = string\n\
+        local M = {}\n\
+        M.origin = {x = 0, y = 0} --: Point\n\
+        M.name = 'mod' --: string\n\
+        --v function(p: Point, q: Point) --> number\n\
+        function M.dist(p, q)\n\
+            return 0\n\
+        end\n\
+        return M\n", &report);
+
+    struct NoOptions;
+    impl Options for NoOptions {}
+
+    let mut context = Context::new(report);
+    let opts = Rc::new(RefCell::new(NoOptions));
+    let preload = ::Preload::default();
+    let module = ::check_module_from_chunk_with_preloading(
+        &mut context, chunk, (&b"mod"[..]).without_loc(), opts, &preload
+    ).unwrap().unwrap();
+
+    let output = context.into_output();
+    let stub = module_to_stub(&module, output.types());
+    assert_eq!(stub, "\
+        --# type Point = {x: integer, y: integer}\n\
+        \n\
+        --# assume M: {\n\
+        --#     dist: function(p: Point, q: Point) --> number;\n\
+        --#     name: string;\n\
+        --#     origin: Point;\n\
+        --#     ...\n\
+        --# }\n\
+        return M\n");
+
+    // the exact stub never refers to type names
+    assert_eq!(module_to_exact_stub(&module, output.types()).unwrap(), "\
+        --# type Point = {x: integer, y: integer}\n\
+        \n\
+        --# assume M: {\n\
+        --#     dist: function(p: {x: integer, y: integer}, \
+                               q: {x: integer, y: integer}) --> number;\n\
+        --#     name: string;\n\
+        --#     origin: {x: integer, y: integer};\n\
+        --#     ...\n\
+        --# }\n\
+        return M\n");
+
+    // the stub should be usable in place of the original module
+    struct StubOptions { chunk: Option<Chunk> }
+    impl Options for StubOptions {
+        fn require_chunk(&mut self, _path: Spanned<&[u8]>,
+                         _report: &Report) -> Result<Chunk, Option<Stop>> {
+            self.chunk.take().ok_or(None)
+        }
+    }
+
+    let report = Rc::new(CollectedReport::new(Locale::dummy()));
+    let stubchunk = parse(&mut source, "mod.lua.kailua", &stub, &report);
+    let mainchunk = parse(&mut source, "main.lua", "\
+        --# open lua51\n\
+        local mod = require 'mod'\n\
+        local p = mod.origin --: Point\n\
+        local d = mod.dist(p, p) --: number\n\
+        mod.new = 1\n", &report);
+    let mut context = Context::new(report.clone());
+    let opts = Rc::new(RefCell::new(StubOptions { chunk: Some(stubchunk) }));
+    ::check_from_chunk(&mut context, mainchunk, opts).unwrap();
+    drop(context);
+    let reports = Rc::try_unwrap(report).ok().unwrap().into_reports();
+    assert!(reports.is_empty(), "{:?}", reports);
+
+    // `self` in methods cannot be written exactly, but other arguments can.
+    // the receiver is written as the module record without methods
+    let report = CollectedReport::new(Locale::dummy());
+    let chunk = parse(&mut source, "meth.lua", "\
+        local M = {}\n\
+        M.count = 0 --: integer\n\
+        --v method(n: integer) --> string\n\
+        function M:rep(n)\n\
+            return ('x'):rep(n)\n\
+        end\n\
+        return M\n", &report);
+
+    let mut context = Context::new(report);
+    let opts = Rc::new(RefCell::new(NoOptions));
+    let module = ::check_module_from_chunk_with_preloading(
+        &mut context, chunk, (&b"meth"[..]).without_loc(), opts, &preload
+    ).unwrap().unwrap();
+
+    let output = context.into_output();
+    let stub = module_to_stub(&module, output.types());
+    assert_eq!(stub, "\
+        --# assume M: {\n\
+        --#     count: integer;\n\
+        --#     rep: function(self: {count: integer, ...}, n: integer) --> string;\n\
+        --#     ...\n\
+        --# }\n\
+        return M\n");
+    assert_eq!(module_to_exact_stub(&module, output.types()), None);
+
+    // the stub should be valid in the strict mode, and reject non-module receivers
+    let report = Rc::new(CollectedReport::new(Locale::dummy()));
+    let stubchunk = parse(&mut source, "meth.lua.kailua", &format!("--# strict\n{}", stub),
+                          &report);
+    let mainchunk = parse(&mut source, "main.lua", "\
+        --# open lua51\n\
+        local meth = require 'meth'\n\
+        local s = meth:rep(3) --: string\n\
+        local t = meth.rep(42, 3)\n", &report);
+    let mut context = Context::new(report.clone());
+    let opts = Rc::new(RefCell::new(StubOptions { chunk: Some(stubchunk) }));
+    ::check_from_chunk(&mut context, mainchunk, opts).unwrap();
+    drop(context);
+    let reports = Rc::try_unwrap(report).ok().unwrap().into_reports();
+    let errors: Vec<_> = reports.iter().filter(|r| r.0 == Kind::Error).collect();
+    assert_eq!(errors.len(), 1, "{:?}", reports);
+    assert!(errors[0].2.contains("cannot be called"), "{:?}", reports);
+
+    // constant fields are kept constant, and the closed record is kept closed
+    let report = CollectedReport::new(Locale::dummy());
+    let chunk = parse(&mut source, "konst.lua", "\
+        local M = {}\n\
+        M.c = 5 --: const integer\n\
+        --# assume M: {c: const integer}\n\
+        return M\n", &report);
+
+    let mut context = Context::new(report);
+    let opts = Rc::new(RefCell::new(NoOptions));
+    let module = ::check_module_from_chunk_with_preloading(
+        &mut context, chunk, (&b"konst"[..]).without_loc(), opts, &preload
+    ).unwrap().unwrap();
+
+    let output = context.into_output();
+    let stub = module_to_stub(&module, output.types());
+    assert_eq!(stub, "\
+        --# assume M: {\n\
+        --#     c: const integer;\n\
+        --# }\n\
+        return M\n");
+    assert_eq!(module_to_exact_stub(&module, output.types()).as_ref(), Some(&stub));
+
+    let report = Rc::new(CollectedReport::new(Locale::dummy()));
+    let stubchunk = parse(&mut source, "konst.lua.kailua", &stub, &report);
+    let mainchunk = parse(&mut source, "main.lua", "\
+        --# open lua51\n\
+        local konst = require 'konst'\n\
+        local c = konst.c --: integer\n\
+        konst.c = 6\n\
+        konst.new = 1\n", &report);
+    let mut context = Context::new(report.clone());
+    let opts = Rc::new(RefCell::new(StubOptions { chunk: Some(stubchunk) }));
+    ::check_from_chunk(&mut context, mainchunk, opts).unwrap();
+    drop(context);
+    let reports = Rc::try_unwrap(report).ok().unwrap().into_reports();
+    let errors: Vec<_> = reports.iter().filter(|r| r.0 == Kind::Error)
+                                .map(|r| &r.2[..]).collect();
+    assert_eq!(errors, ["Cannot assign `6` into `const integer`",
+                        "Missing key \"new\" in `{c: const integer}`"], "{:?}", reports);
+}
@@ -0,0 +1,187 @@
+--8<-- generic-func-identity
+--# assume id: function<T>(x: T) --> T
+local a = id(42) --: integer
+local b = id('string') --: string
+--! ok
+
+--8<-- generic-func-identity-mismatch
+--# assume id: function<T>(x: T) --> T
+local a = id('string') --: integer
+--@^ Error: Cannot assign `string` into `integer`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- generic-func-fresh-per-call
+--# assume first: function<T>(x: vector<T>) --> T
+local p = {1, 2, 3} --: vector<integer>
+local q = {'a', 'b'} --: vector<string>
+local a = first(p) + 1
+local b = first(q) .. 'c'
+--! ok
+
+--8<-- generic-func-two-literals
+--# assume max: function<T>(a: T, b: T) --> T
+local a = max(3, 4) + 5
+--! ok
+
+--8<-- generic-func-disjoint-args
+--# assume max: function<T>(a: T, b: T) --> T
+local a = max(3, 'four')
+--@^ Error: The type `function<T>(a: T, b: T) --> T` cannot be called
+--@^^ Cause: Second function argument `string` is not a subtype of `T`
+--@^^^ Note: The other type originates here
+--@ Cause: `string` is not a subtype of `integer`
+--! error
+
+--8<-- generic-func-unresolved
+--# assume make: function<T>() --> T
+local a = make()
+local b = a + 1 -- T is unresolved and becomes WHATEVER
+--! ok
+
+--8<-- generic-func-multiple-params
+--# assume swap: function<T, U>(a: T, b: U) --> (U, T)
+local a, b = swap(1, 'x')
+local c = a .. 'y'
+local d = b * 2
+--! ok
+
+--8<-- generic-func-callback
+--# assume transform: function<T, U>(t: vector<T>, f: function(T) --> U) --> vector<U>
+--# assume tostr: function(x: integer) --> string
+local p = {1, 2, 3} --: vector<integer>
+local q = transform(p, tostr)
+local r = q[1] .. 'x'
+--! ok
+
+--8<-- generic-func-assign-generic
+--# assume id: function<U>(x: U) --> U
+local f = id --: function<T>(x: T) --> T
+local a = f('string') .. 'x'
+--! ok
+
+--8<-- generic-func-assign-non-generic
+--# assume inc: function(x: integer) --> integer
+local f = inc --: function<T>(x: T) --> T
+--@^ Error: Cannot assign `function(x: integer) --> integer` into `function<T>(x: T) --> T`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- generic-func-display
+--# assume f: function<T>(x: vector<T>) --> T
+--v function(x: integer)
+local function g(x) end
+g(f)
+--@^ Error: The type `function(x: integer) --> ()` cannot be called
+--@^^ Cause: First function argument `function<T>(x: vector<T>) --> T` is not a subtype of `integer`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- generic-func-decl
+--v function<T>(t: vector<T>) --> T?
+local function last(t)
+    return t[#t]
+end
+local p = {1, 2, 3} --: vector<integer>
+local a = last(p) --: integer?
+--! ok
+
+--8<-- generic-func-decl-body-annotation
+--v function<T>(t: vector<T>) --> vector<T>
+local function copy(t)
+    local u = {} --: vector<T>
+    for i = 1, #t do
+        u[i] = t[i]
+    end
+    return u
+end
+local p = {'a', 'b'} --: vector<string>
+local q = copy(p)
+local r = q[1] .. 'c'
+--! ok
+
+--8<-- generic-func-decl-param-not-visible-outside
+--v function<T>(x: T) --> T
+local function id(x) return x end
+local a = 42 --: T
+--@^ Error: Type `T` is not defined
+--! error
+
+--8<-- generic-type-alias
+--# type Pair<T> = {first: T, second: T}
+local p = {first = 1, second = 2} --: Pair<integer>
+local q = {first = 'a', second = 'b'} --: Pair<string>
+local a = p.first + 1
+local b = q.second .. 'c'
+--! ok
+
+--8<-- generic-type-alias-mismatch
+--# type Pair<T> = {first: T, second: T}
+local p = {first = 1, second = 'two'} --: Pair<integer>
+--@^ Error: Cannot assign `{first: 1, second: "two", ...}` into `{first: integer, second: integer}`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- generic-type-alias-nested
+--# type List<T> = vector<T>
+--# type Table<K, V> = map<K, List<V>>
+local t = {} --: Table<string, integer>
+t.a = {1, 2}
+local b = t.a[1] + 1
+--! ok
+
+--8<-- generic-type-alias-no-args
+--# type List<T> = vector<T>
+local t = {} --: List
+--@^ Error: Type `List` needs 1 type argument(s)
+--@1 Note: The type was originally defined here
+--! error
+
+--8<-- generic-type-alias-wrong-arity
+--# type List<T> = vector<T>
+local t = {} --: List<integer, string>
+--@^ Error: Type `List` needs 1 type argument(s)
+--@1 Note: The type was originally defined here
+--! error
+
+--8<-- generic-type-alias-non-generic
+--# type Foo = vector<integer>
+local t = {} --: Foo<integer>
+--@^ Error: Type `Foo` is not generic and cannot have type arguments
+--@1 Note: The type was originally defined here
+--! error
+
+--8<-- generic-type-alias-param-with-args
+--# type Foo<T> = T<integer>
+--@^ Error: The type parameter `T` cannot have type arguments
+--! error
+
+--8<-- generic-type-alias-global
+--# type global Box<T> = {value: T}
+--# assume b: Box<string>
+local s = b.value .. 'x'
+--! ok
+
+--8<-- generic-type-alias-in-func
+--# type List<T> = vector<T>
+--# assume push: function<T>(t: List<T>, v: T)
+local t = {} --: List<integer>
+push(t, 1)
+push(t, 'x')
+--@^ Error: The type `function<T>(t: vector<T>, v: T) --> ()` cannot be called
+--@^^ Cause: Second function argument `string` is not a subtype of `T`
+--@^^^ Note: The other type originates here
+--@ Cause: `string` is not a subtype of `integer`
+--! error
+
+--8<-- generic-table-sort
+--# open lua51
+local t = {3, 1, 2} --: vector<integer>
+table.sort(t, function(a, b) return a > b end)
+--! ok
+
+--8<-- generic-table-remove
+--# open lua51
+local t = {'a', 'b'} --: vector<string>
+local s = table.remove(t) --: string?
+--! ok
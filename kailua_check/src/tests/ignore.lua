@@ -0,0 +1,75 @@
+--8<-- ignore-next-line
+--# ignore-next-line K0401
+local a = x
+local b = y
+--@^ Error: Global or local variable `y` is not defined
+--! error
+
+--8<-- ignore-next-line-blank
+--# ignore-next-line K0401
+
+local a = x
+--@^ Error: Global or local variable `x` is not defined
+--! error
+
+--8<-- ignore-next-line-other-code
+--# ignore-next-line K0420
+local a = x
+--@^ Error: Global or local variable `x` is not defined
+--! error
+
+--8<-- ignore-before-func-spec
+--# ignore K0401
+--v function(a: integer) --> integer
+local function f(a)
+    return a + x
+end
+--! ok
+
+--8<-- ignore-rest
+local a = x
+--@^ Error: Global or local variable `x` is not defined
+--# ignore K0401
+local b = y
+local function f()
+    return z
+end
+--! error
+
+--8<-- ignore-rest-in-block
+do
+    --# ignore K0401
+    local a = x
+end
+local b = y
+--! ok
+
+--8<-- ignore-multiple-codes
+--# ignore K0401, K0420
+local a = x
+local b = 1 + {}
+--! ok
+
+--8<-- ignore-causes-and-notes
+--# ignore-next-line K0420
+local a = 1 + {}
+local b = 1 + {}
+--@^ Error: Cannot apply + operator to `1` and `{...}`
+--@^^ Cause: `{...}` is not a subtype of `number`
+--! error
+
+--8<-- ignore-no-code
+--# ignore
+--@^-< Error: Expected a name, got a newline
+--! ok
+
+--8<-- ignore-next-line-no-code
+--# ignore-next-line
+--@^-< Error: Expected a name, got a newline
+--! ok
+
+--8<-- ignore-next-line-typo
+--# ignore-next-lines K0401
+--@^ Error: Expected `line`, got a name
+--! ok
+
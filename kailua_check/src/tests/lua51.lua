@@ -149,10 +149,9 @@ end
 --# open lua51
 --# assume p: map<integer, string>
 for x, y in ipairs(p) do
-    --@^ Error: The type `[generic_pairs] function(t: vector<const WHATEVER>) --> (function(vector<const WHATEVER>, integer) --> (integer?, any), vector<const WHATEVER>, integer)` cannot be called
-    --@^^ Cause: First function argument `map<integer, string>` is not a subtype of `vector<const WHATEVER>`
+    --@^ Error: The type `function<T>(t: vector<const T>) --> (function(vector<const T>, integer) --> (integer?, T), vector<const T>, integer)` cannot be called
+    --@^^ Cause: First function argument `map<integer, string>` is not a subtype of `vector<const T>`
     --@^^^ Note: The other type originates here
-    -- XXX WHATEVER is temporary
 end
 --! error
 
@@ -160,10 +159,9 @@ end
 --# open lua51
 --# assume p: table
 for x, y in ipairs(p) do
-    --@^ Error: The type `[generic_pairs] function(t: vector<const WHATEVER>) --> (function(vector<const WHATEVER>, integer) --> (integer?, any), vector<const WHATEVER>, integer)` cannot be called
-    --@^^ Cause: First function argument `table` is not a subtype of `vector<const WHATEVER>`
+    --@^ Error: The type `function<T>(t: vector<const T>) --> (function(vector<const T>, integer) --> (integer?, T), vector<const T>, integer)` cannot be called
+    --@^^ Cause: First function argument `table` is not a subtype of `vector<const T>`
     --@^^^ Note: The other type originates here
-    -- XXX WHATEVER is temporary
 end
 --! error
 
@@ -171,10 +169,9 @@ end
 --# open lua51
 --# assume p: string
 for x, y in ipairs(p) do
-    --@^ Error: The type `[generic_pairs] function(t: vector<const WHATEVER>) --> (function(vector<const WHATEVER>, integer) --> (integer?, any), vector<const WHATEVER>, integer)` cannot be called
-    --@^^ Cause: First function argument `string` is not a subtype of `vector<const WHATEVER>`
+    --@^ Error: The type `function<T>(t: vector<const T>) --> (function(vector<const T>, integer) --> (integer?, T), vector<const T>, integer)` cannot be called
+    --@^^ Cause: First function argument `string` is not a subtype of `vector<const T>`
     --@^^^ Note: The other type originates here
-    -- XXX WHATEVER is temporary
 end
 --! error
 
@@ -292,9 +289,13 @@ end
 --# open lua51
 --# assume p: string
 for x, y in pairs(p) do
-    --@^ Error: The type `[generic_pairs] function(t: table) --> (function(table, any) --> (any?, any), table, any)` cannot be called
-    --@^^ Cause: First function argument `string` is not a subtype of `table`
-    --@^^^ Note: The other type originates here
+    --@^ Error: None of the overloads in the type `[generic_pairs] function<K, V>(t: map<K, const V>) --> (function(map<K, const V>, any) --> (K?, V), map<K, const V>, any) & function(t: table) --> (function(table, any) --> (any?, any), table, any)` can be called with given arguments
+    --@^^ Note: The overload `function<K, V>(t: map<K, const V>) --> (function(map<K, const V>, any) --> (K?, V), map<K, const V>, any)` does not match
+    --@^^^ Cause: First function argument `string` is not a subtype of `map<K, const V>`
+    --@^^^^ Note: The other type originates here
+    --@^^^^^ Note: The overload `function(t: table) --> (function(table, any) --> (any?, any), table, any)` does not match
+    --@^^^^^^ Cause: First function argument `string` is not a subtype of `table`
+    --@^^^^^^^ Note: The other type originates here
 end
 --! error
 
@@ -397,13 +398,28 @@ table.insert(x, 42)
 table.insert(x, 54)
 --! ok
 
--->8-- lua51-table-insert-2
+--8<-- lua51-table-insert-2
 --# open lua51
 local x = {} --: vector<integer>
 table.insert(x, 42)
-table.insert(x, 'not an integer') -- should error, but current definition is rather weak
+table.insert(x, 'not an integer')
+--@^ Error: None of the overloads in the type `function<T>(table: vector<T>, value: T) --> () & function<T>(table: vector<T>, pos: integer, value: T) --> ()` can be called with given arguments
+--@^^ Note: The overload `function<T>(table: vector<T>, value: T) --> ()` does not match
+--@^^^ Cause: Second function argument `string` is not a subtype of `T`
+--@^^^^ Note: The other type originates here
+--@ Cause: `string` is not a subtype of `integer`
+--@^^^^^^ Note: The overload `function<T>(table: vector<T>, pos: integer, value: T) --> ()` does not match
+--@^^^^^^^ Cause: Second function argument `"not an integer"` is not a subtype of `integer`
+--@^^^^^^^^ Note: The other type originates here
 --! error
 
+--8<-- lua51-table-insert-3
+--# open lua51
+local x = {} --: vector<string>
+table.insert(x, 1, 'foo')
+table.insert(x, 'bar')
+--! ok
+
 --8<-- lua51-table-maxn
 --# open lua51
 local x = {1, 2, 3} --: vector<integer>
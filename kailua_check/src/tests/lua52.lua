@@ -0,0 +1,76 @@
+-- Tests specific to Lua 5.2 language and library support in the Kailua type checker.
+
+--8<-- lua52-print
+--# open lua52
+print('hello')
+--! ok
+
+--8<-- lua52-duplicate-open
+--# open lua52
+--# open lua52
+print('hello')
+--! ok
+
+--8<-- lua52-table-pack-unpack
+--# open lua52
+local t = table.pack(1, 2, 3)
+print(table.unpack(t))
+--! ok
+
+--8<-- lua52-no-unpack
+--# open lua52
+print(unpack({1, 2, 3})) --@< Error: Global or local variable `unpack` is not defined
+--! error
+
+--8<-- lua52-no-setfenv
+--# open lua52
+setfenv(1, {}) --@< Error: Global or local variable `setfenv` is not defined
+--! error
+
+--8<-- lua52-no-getfenv
+--# open lua52
+local env = getfenv(1) --@< Error: Global or local variable `getfenv` is not defined
+--! error
+
+--8<-- lua52-bit32
+--# open lua52
+local x = bit32.band(0xff, 0x0f) + bit32.lshift(1, 4) --: integer
+--! ok
+
+--8<-- lua52-bit32-bad-arg
+--# open lua52
+local x = bit32.bnot('foo')
+--@^ Error: The type `function(x: integer) --> integer` cannot be called
+--@^^ Cause: First function argument `"foo"` is not a subtype of `integer`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- lua52-rawlen
+--# open lua52
+local n = rawlen({1, 2, 3}) --: integer
+--! ok
+
+--8<-- lua52-xpcall-args
+--# open lua52
+local ok = xpcall(print, print, 1, 2) --: boolean
+--! ok
+
+--8<-- lua52-env
+--# open lua52
+print(_ENV)
+--! ok
+
+--8<-- lua52-goto-continue
+--# open lua52
+for i = 1, 10 do
+    if i % 2 == 0 then goto continue end
+    print(i)
+    ::continue::
+end
+--! ok
+
+--8<-- lua51-goto-is-name
+--# open lua51
+local goto = 42 --@< Warning: The use of a keyword `goto` is discouraged as it was a name in Lua 5.1 but it became a keyword since Lua 5.2
+--! ok
+
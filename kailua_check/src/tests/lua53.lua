@@ -0,0 +1,108 @@
+-- Tests specific to Lua 5.3 language and library support in the Kailua type checker.
+
+--8<-- lua53-print
+--# open lua53
+print('hello')
+--! ok
+
+--8<-- lua53-bitwise-integer
+--# open lua53
+--# assume a: integer
+--# assume b: integer
+local x = a & b | a ~ b << 2 >> 1 --: integer
+local y = ~a --: integer
+--! ok
+
+--8<-- lua53-bitwise-number
+--# open lua53
+--# assume a: number
+local x = a & 1
+--@^ Error: Cannot apply & operator to `number` and `1`
+--@^^ Cause: `number` is not a subtype of `integer`
+--! error
+
+--8<-- lua53-unary-bnot-number
+--# open lua53
+--# assume a: number
+local x = ~a --@< Error: Cannot apply ~ operator to `number`
+             --@^ Cause: `number` is not a subtype of `integer`
+--! error
+
+--8<-- lua53-unary-bnot-string
+--# open lua53
+local x = ~'foo' --@< Error: Cannot apply ~ operator to `"foo"`
+                 --@^ Cause: `"foo"` is not a subtype of `integer`
+--! error
+
+--8<-- lua53-idiv-integer
+--# open lua53
+--# assume a: integer
+--# assume b: integer
+local x = a // b --: integer
+--! ok
+
+--8<-- lua53-idiv-number
+--# open lua53
+--# assume a: integer
+--# assume b: number
+local x = a // b --: integer --@< Error: Cannot assign `number` into `integer`
+                             --@^ Note: The other type originates here
+--! error
+
+--8<-- lua53-div-integer
+--# open lua53
+--# assume a: integer
+--# assume b: integer
+local x = a / b --: integer --@< Error: Cannot assign `number` into `integer`
+                            --@^ Note: The other type originates here
+--! error
+
+--8<-- lua53-float-literal
+--# open lua53
+local a = 7 // 2 --: integer
+local b = 7.0 --: number
+local c = 7.0 // 2 --: integer --@< Error: Cannot assign `number` into `integer`
+                               --@^ Note: The other type originates here
+local d = 1e3 --: integer --@< Error: Cannot assign `number` into `integer`
+                          --@^ Note: The other type originates here
+--! error
+
+--8<-- lua51-float-literal
+--# open lua51
+local a = 7.0 --: integer
+local b = 1e3 --: integer
+--! ok
+
+--8<-- lua53-math-tointeger
+--# open lua53
+local x = math.tointeger(3.0) --: integer?
+local y = math.type(x) --: string?
+local z = math.maxinteger --: integer
+--! ok
+
+--8<-- lua53-string-pack
+--# open lua53
+local s = string.pack('i4', 42) --: string
+local n = string.packsize('i4') --: integer
+--! ok
+
+--8<-- lua53-utf8
+--# open lua53
+local n = utf8.len('hello') --: integer?
+local s = utf8.char(72, 105) --: string
+for p, c in utf8.codes('hello') do
+    local d = c --: integer
+end
+--! ok
+
+--8<-- lua53-no-bit32
+--# open lua53
+local x = bit32.band(1, 2) --@< Error: Global or local variable `bit32` is not defined
+--! error
+
+--8<-- lua53-goto
+--# open lua53
+goto foo
+::foo::
+--! ok
+
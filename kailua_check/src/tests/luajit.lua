@@ -0,0 +1,136 @@
+-- Tests specific to LuaJIT language and library support in the Kailua type checker.
+
+--8<-- luajit-print
+--# open luajit
+print('hello')
+--! ok
+
+--8<-- luajit-unpack
+--# open luajit
+print(unpack({1, 2, 3}))
+--! ok
+
+--8<-- luajit-load-string
+--# open luajit
+local f = load('return 42', 'chunk', 't')
+--! ok
+
+--8<-- luajit-xpcall-args
+--# open luajit
+local ok = xpcall(print, print, 1, 2) --: boolean
+--! ok
+
+--8<-- luajit-math-log-base
+--# open luajit
+local x = math.log(8, 2) --: number
+--! ok
+
+--8<-- luajit-package-searchpath
+--# open luajit
+local path = package.searchpath('foo', package.path)
+--! ok
+
+--8<-- luajit-no-bit32
+--# open luajit
+local x = bit32.band(0xff, 0x0f) --@< Error: Global or local variable `bit32` is not defined
+--! error
+
+--8<-- luajit-bit
+--# open luajit
+local x = bit.band(0xff, 0x0f) + bit.lshift(1, 4) --: integer
+local s = bit.tohex(x) --: string
+--! ok
+
+--8<-- luajit-bit-bad-arg
+--# open luajit
+local x = bit.bnot('foo')
+--@^ Error: The type `function(x: number) --> integer` cannot be called
+--@^^ Cause: First function argument `"foo"` is not a subtype of `number`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- luajit-jit
+--# open luajit
+jit.off()
+jit.opt.start('hotloop=10')
+local v = jit.version --: string
+--! ok
+
+--8<-- luajit-ffi-not-global
+--# open luajit
+ffi.cdef [[ int printf(const char *fmt, ...); ]]
+--@^ Error: Global or local variable `ffi` is not defined
+--! error
+
+--8<-- luajit-ffi
+--# open luajit
+local ffi = require 'ffi'
+ffi.cdef [[ int printf(const char *fmt, ...); ]]
+ffi.C.printf('hello %s!', 'world')
+local buf = ffi.new('uint8_t[?]', 16) --: userdata
+local p = ffi.cast('uint8_t*', buf) --: userdata
+local n = ffi.sizeof(buf) --: integer?
+--! ok
+
+--8<-- luajit-ffi-new-not-string
+--# open luajit
+local ffi = require 'ffi'
+local n = string.len(ffi.new('int'))
+--@^ Error: The type `function(s: string) --> integer` cannot be called
+--@^^ Cause: First function argument `userdata` is not a subtype of `string`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- luajit-cdata-number
+--# open luajit
+local x = 42LL --: [int64] userdata
+local y = 0x10ULL --: [int64] userdata
+local z = 12i --: userdata
+--! ok
+
+--8<-- luajit-cdata-number-arith
+--# open luajit
+local x = 42LL + 1 --: [int64] userdata
+local y = 0x10ULL * x --: [int64] userdata
+local z = -(x / 2) --: [int64] userdata
+local b = x < y and 1 <= x and x == 42 --: boolean
+--! ok
+
+--8<-- luajit-cdata-number-not-number
+--# open luajit
+local n = math.floor(1LL)
+--@^ Error: The type `function(x: number) --> integer` cannot be called
+--@^^ Cause: First function argument `[int64] userdata` is not a subtype of `number`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- luajit-cdata-number-not-key
+--# open luajit
+local t = {} --: vector<string>
+local s = t[1LL]
+--@^ Error: Cannot index an array `vector<string>` with a non-integral key `[int64] userdata`
+--! error
+
+--8<-- luajit-cdata-number-not-string
+--# open luajit
+local s = string.len(42LL)
+--@^ Error: The type `function(s: string) --> integer` cannot be called
+--@^^ Cause: First function argument `[int64] userdata` is not a subtype of `string`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- luajit-cdata-complex
+--# open luajit
+local z = 12i < 1
+--@^ Error: Cannot apply < operator to `userdata` and `1`
+--! error
+
+--8<-- luajit-goto
+--# open luajit
+for i = 1, 10 do
+    if i % 2 == 0 then goto continue end
+    print(i)
+    ::continue::
+end
+--! ok
+
@@ -0,0 +1,239 @@
+--8<-- metatable-returns-table
+--# open lua51
+local t = setmetatable({x = 1}, {})
+local a = t.x + 1 --: integer
+--! ok
+
+--8<-- metatable-index-table
+--# open lua51
+local Proto = {}
+--v function() --> string
+function Proto.hello() return 'hi' end
+local obj = setmetatable({}, {__index = Proto})
+local s = obj.hello() .. '!' --: string
+--! ok
+
+--8<-- metatable-index-table-own-field
+--# open lua51
+local Proto = {x = 'string'}
+local obj = setmetatable({x = 42}, {__index = Proto})
+local a = obj.x + 1 --: integer
+--! ok
+
+--8<-- metatable-index-missing
+--# open lua51
+local Proto = {x = 42}
+local obj = setmetatable({}, {__index = Proto})
+local a = obj.y
+--@^ Error: Missing key "y" in `{...}`
+--! error
+
+--8<-- metatable-index-self
+--# open lua51
+local Proto = {x = 42}
+Proto.__index = Proto
+local obj = setmetatable({}, Proto)
+local a = obj.x + 1 --: integer
+local b = obj.y
+--@^ Error: Missing key "y" in `{...}`
+--! error
+
+--8<-- metatable-index-chain
+--# open lua51
+local Base = {x = 42}
+local Derived = setmetatable({y = 'string'}, {__index = Base})
+local obj = setmetatable({}, {__index = Derived})
+local a = obj.x + 1 --: integer
+local b = obj.y .. 'x' --: string
+--! ok
+
+--8<-- metatable-index-func
+--# open lua51
+--v function(t: table, k: string) --> integer
+local function index(t, k) return 42 end
+local obj = setmetatable({}, {__index = index})
+local a = obj.anything + 1 --: integer
+--! ok
+
+--8<-- metatable-index-func-wrong-key
+--# open lua51
+--v function(t: table, k: string) --> integer
+local function index(t, k) return 42 end
+local obj = setmetatable({}, {__index = index})
+local a = obj[1]
+--@^ Error: The type `function(t: table, k: string) --> integer` cannot be called
+--@^^ Cause: Second function argument `1` is not a subtype of `string`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- metatable-no-index-for-lval
+--# open lua51
+local Proto = {x = 42}
+local obj = setmetatable({}, {__index = Proto})
+obj.x = 'string'
+local a = obj.x .. 'x' --: string
+--! ok
+
+--8<-- metatable-remove
+--# open lua51
+local Proto = {x = 42}
+local obj = setmetatable({}, {__index = Proto})
+setmetatable(obj, nil)
+local a = obj.x
+--@^ Error: Missing key "x" in `{...}`
+--! error
+
+--8<-- metatable-conditional
+--# open lua51
+--# assume cond: boolean
+local Proto = {x = 42}
+local obj = {}
+if cond then
+    setmetatable(obj, {__index = Proto})
+end
+local a = obj.x
+--@^ Error: Missing key "x" in `{...}`
+--! error
+
+--8<-- metatable-conditional-new-table
+--# open lua51
+--# assume cond: boolean
+local Proto = {x = 42}
+if cond then
+    local obj = setmetatable({}, {__index = Proto})
+    local a = obj.x + 1 --: integer
+end
+--! ok
+
+--8<-- metatable-in-function
+--# open lua51
+local Proto = {x = 42}
+local function new()
+    local obj = {}
+    setmetatable(obj, {__index = Proto})
+    return obj
+end
+local a = new().x + 1 --: integer
+--! ok
+
+--8<-- metatable-non-table
+--# open lua51
+local obj = setmetatable({}, 42)
+--@^ Error: The type `[setmetatable] function(table: table, metatable: table?) --> table` cannot be called
+--@^^ Cause: Second function argument `42` is not a subtype of `table?`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- metatable-add
+--# open lua51
+local V = {}
+--v function(a: table, b: table) --> integer
+function V.__add(a, b) return 42 end
+local p = setmetatable({}, V)
+local q = setmetatable({}, V)
+local r = p + q --: integer
+--! ok
+
+--8<-- metatable-add-rhs
+--# open lua51
+local V = {}
+--v function(a: integer, b: table) --> string
+function V.__add(a, b) return 'added' end
+local p = setmetatable({}, V)
+local r = 1 + p --: string
+--! ok
+
+--8<-- metatable-add-wrong-operand
+--# open lua51
+local V = {}
+--v function(a: table, b: table) --> integer
+function V.__add(a, b) return 42 end
+local p = setmetatable({}, V)
+local r = p + 'string'
+--@^ Error: The type `function(a: table, b: table) --> integer` cannot be called
+--@^^ Cause: Second function argument `"string"` is not a subtype of `table`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- metatable-no-add
+--# open lua51
+local p = setmetatable({}, {})
+local r = p + 1
+--@^ Error: Cannot apply + operator to `{...}` and `1`
+--@^^ Cause: `{...}` is not a subtype of `number`
+--! error
+
+--8<-- metatable-concat
+--# open lua51
+local V = {}
+--v function(a: table, b: string) --> string
+function V.__concat(a, b) return b end
+local p = setmetatable({}, V)
+local r = p .. 'x' --: string
+--! ok
+
+--8<-- metatable-compare
+--# open lua51
+local V = {}
+--v function(a: table, b: table) --> boolean
+function V.__lt(a, b) return true end
+--v function(a: table, b: table) --> boolean
+function V.__le(a, b) return true end
+--v function(a: table, b: table) --> boolean
+function V.__eq(a, b) return true end
+local p = setmetatable({}, V)
+local q = setmetatable({}, V)
+local r1 = p < q --: boolean
+local r2 = p >= q --: boolean
+local r3 = p == q --: boolean
+--! ok
+
+--8<-- metatable-eq-non-table
+--# open lua51
+local V = {}
+--v function(a: {x: number}, b: {x: number}) --> boolean
+function V.__eq(a, b) return a.x == b.x end
+local v = setmetatable({x = 1}, V)
+local r1 = v ~= 'foo' --: boolean
+local r2 = 42 == v --: boolean
+--! ok
+
+--8<-- metatable-unary
+--# open lua51
+local V = {}
+--v function(a: table) --> string
+function V.__unm(a) return 'negated' end
+--v function(a: table) --> string
+function V.__len(a) return 'length' end
+local p = setmetatable({}, V)
+local r1 = -p .. 'x' --: string
+local r2 = #p .. 'x' --: string
+--! ok
+
+--8<-- metatable-call
+--# open lua51
+local V = {}
+--v function(self: table, x: integer) --> string
+function V.__call(self, x) return 'called' end
+local p = setmetatable({}, V)
+local r = p(42) .. 'x' --: string
+--! ok
+
+--8<-- metatable-call-wrong-args
+--# open lua51
+local V = {}
+--v function(self: table, x: integer) --> string
+function V.__call(self, x) return 'called' end
+local p = setmetatable({}, V)
+local r = p('string')
+--@^ Error: The type `function(self: table, x: integer) --> string` cannot be called
+--@^^ Cause: Second function argument `"string"` is not a subtype of `integer`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- metatable-no-call
+--# open lua51
+local p = setmetatable({}, {})
+local r = p()
+--@^ Error: Tried to call a non-function `{...}`
+--! error
@@ -0,0 +1,215 @@
+--8<-- narrowing-if-not-nil
+--# assume x: integer?
+if x ~= nil then
+    local y = x --: integer!
+end
+--! ok
+
+--8<-- narrowing-if-not-nil-reversed
+--# assume x: integer?
+if nil ~= x then
+    local y = x --: integer!
+end
+--! ok
+
+--8<-- narrowing-if-nil-else
+--# assume x: integer?
+if x == nil then
+    local y = x --: nil
+else
+    local y = x --: integer!
+end
+--! ok
+
+--8<-- narrowing-if-truthy
+--# assume x: integer?
+if x then
+    local y = x --: integer!
+end
+--! ok
+
+--8<-- narrowing-if-not-truthy
+--# assume x: integer?
+if not x then
+    local y = x + 1
+    --@^ Error: Cannot apply + operator to `nil` and `1`
+    --@^^ Cause: `nil` is not a subtype of `number`
+else
+    local y = x --: integer!
+end
+--! error
+
+--8<-- narrowing-if-restored
+--# assume x: integer?
+if x ~= nil then
+    local y = x --: integer!
+end
+local z = x --: integer!
+--@^ Error: Cannot assign `integer?` into `integer!`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- narrowing-if-type-string
+--# open lua51
+--# assume v: string|integer
+if type(v) == 'string' then
+    local s = v .. 'x' --: string
+else
+    local n = v + 1 --: integer
+end
+--! ok
+
+--8<-- narrowing-if-type-restored
+--# open lua51
+--# assume v: string|integer
+if type(v) == 'string' then
+    local s = v .. 'x'
+end
+local n = v + 1
+--@^ Error: Cannot apply + operator to `(integer|string)` and `1`
+--@^^ Cause: `(integer|string)` is not a subtype of `number`
+--@ Cause: `string` is not a subtype of `number`
+--! error
+
+--8<-- narrowing-if-type-not-equal
+--# open lua51
+--# assume v: string|integer
+if type(v) ~= 'string' then
+    local n = v + 1 --: integer
+end
+--! ok
+
+--8<-- narrowing-elseif
+--# open lua51
+--# assume v: string|integer|boolean
+if type(v) == 'string' then
+    local s = v .. 'x' --: string
+elseif type(v) == 'number' then
+    local n = v + 1 --: integer
+else
+    local b = v --: boolean
+end
+--! ok
+
+--8<-- narrowing-elseif-nil
+--# assume x: integer?
+--# assume y: string?
+if x == nil then
+elseif y == nil then
+    local a = x + 1 --: integer
+else
+    local a = x + 1 --: integer
+    local b = y .. 'x' --: string
+end
+--! ok
+
+--8<-- narrowing-and
+--# assume x: integer?
+local y = x ~= nil and x + 1
+--! ok
+
+--8<-- narrowing-and-type
+--# open lua51
+--# assume v: string|integer
+local y = type(v) == 'string' and v .. 'x'
+--! ok
+
+--8<-- narrowing-or
+--# assume x: integer?
+local y = x == nil or x + 1
+--! ok
+
+--8<-- narrowing-and-restored
+--# assume x: integer?
+local y = x ~= nil and x + 1
+local z = x + 1
+--@^ Error: Cannot apply + operator to `integer?` and `1`
+--@^^ Cause: `integer?` is not a subtype of `number`
+--! error
+
+--8<-- narrowing-and-in-if
+--# assume x: integer?
+--# assume y: string?
+if x ~= nil and y ~= nil then
+    local a = x + 1 --: integer
+    local b = y .. 'x' --: string
+end
+--! ok
+
+--8<-- narrowing-while
+--# assume x: integer?
+while x ~= nil do
+    local y = x + 1 --: integer
+end
+--! ok
+
+--8<-- narrowing-impossible
+--# open lua51
+--# assume v: string
+if type(v) == 'number' then
+    local n = v --: string
+end
+--! ok
+
+--8<-- narrowing-if-nil-assign
+local x --: integer?
+if x == nil then
+    x = 3
+end
+--! ok
+
+--8<-- narrowing-if-nil-assign-field
+local opts = {} --: {a: integer?}
+if opts.a == nil then
+    opts.a = 1
+end
+--! ok
+
+--8<-- narrowing-if-not-nil-assign-checked
+local x --: integer?
+if x ~= nil then
+    x = 'foo'
+    --@^ Error: Cannot assign `"foo"` into `integer?`
+    --@^^ Note: The other type originates here
+end
+--! error
+
+--8<-- narrowing-if-nil-assign-then-read
+local x --: integer?
+if x == nil then
+    x = 3
+    local y = x --: integer?
+end
+--! ok
+
+--8<-- narrowing-if-not-nil-reassign
+--# assume f: function() --> integer?
+local x = f()
+if x ~= nil then
+    x = f()
+    local y = x + 1
+    --@^ Error: Cannot apply + operator to `integer?` and `1`
+end
+--! error
+
+--8<-- narrowing-if-not-nil-assign-nil
+--# assume f: function() --> integer?
+local x = f()
+if x ~= nil then
+    x = nil
+    local y = x + 1
+    --@^ Error: Cannot apply + operator to `integer?` and `1`
+end
+--! error
+
+--8<-- narrowing-not-in-nested-function
+local x --: integer?
+if x ~= nil then
+    -- `f` can be called after `x` has been changed
+    local function f()
+        local y = x --: integer!
+        --@^ Error: Cannot assign `integer?` into `integer!`
+        --@^^ Note: The other type originates here
+    end
+end
+--! error
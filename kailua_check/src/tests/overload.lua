@@ -0,0 +1,105 @@
+--8<-- overload-first-match
+--# assume f: function(x: integer) --> integer & function(x: string) --> string
+local a = f(42) --: integer
+local b = f('foo') --: string
+--! ok
+
+--8<-- overload-first-match-order
+--# assume f: function(x: number) --> number & function(x: integer) --> integer
+local a = f(42) --: integer
+--@^ Error: Cannot assign `number` into `integer`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- overload-arity
+--# assume f: function() --> string & function(x: integer) --> integer
+local a = f() .. 'x'
+local b = f(1) + 2
+--! ok
+
+--8<-- overload-no-match
+--# assume f: function(x: integer) & function(x: string)
+f(true)
+--@^ Error: None of the overloads in the type `function(x: integer) --> () & function(x: string) --> ()` can be called with given arguments
+--@^^ Note: The overload `function(x: integer) --> ()` does not match
+--@^^^ Cause: First function argument `true` is not a subtype of `integer`
+--@^^^^ Note: The other type originates here
+--@^^^^^ Note: The overload `function(x: string) --> ()` does not match
+--@^^^^^^ Cause: First function argument `true` is not a subtype of `string`
+--@^^^^^^^ Note: The other type originates here
+--! error
+
+--8<-- overload-diverging
+--# assume f: function(x: integer) --> ! & function(x: string) --> integer
+local a = f('x') + 1
+f(1)
+local b = 2 -- should not be checked
+--! ok
+
+--8<-- overload-generic
+--# assume f: function<T>(x: vector<T>) --> T & function(x: string) --> integer
+local p = {true, false} --: vector<boolean>
+local a = f(p) --: boolean
+local b = f('x') --: integer
+--! ok
+
+--8<-- overload-rollback-tvar -- feature:!no_implicit_func_sig
+--# assume f: function(x: integer, y: integer) --> integer &
+--#           function(x: string, y: string) --> string
+local function g(a)
+    -- the first overload binds `a` to `integer` but fails on the second argument
+    return f(a, 'x') .. 'y'
+end
+--! ok
+
+--8<-- overload-method
+--# assume t: {f: function(self: table, x: integer) --> integer &
+--#                function(self: table, x: string) --> string}
+local a = t:f(1) + 2
+local b = t:f('x') .. 'y'
+--! ok
+
+--8<-- overload-display
+--# assume f: function(x: integer) & function(x: string)
+local a = f --: integer
+--@^ Error: Cannot assign `function(x: integer) --> () & function(x: string) --> ()` into `integer`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- overload-sub-simple
+--# assume f: function(x: integer) --> integer & function(x: string) --> string
+local g = f --: function(string) --> string
+local h = f --: function(integer) --> integer
+--! ok
+
+--8<-- overload-sub-simple-mismatch
+--# assume f: function(x: integer) --> integer & function(x: string) --> string
+local g = f --: function(boolean) --> string
+--@^ Error: Cannot assign `function(x: integer) --> integer & function(x: string) --> string` into `function(boolean) --> string`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- overload-sub-overloaded
+--# assume f: function(x: integer) --> integer & function(x: string) --> string
+local g = f --: function(string) --> string & function(integer) --> integer
+--! ok
+
+--8<-- overload-lua51-math
+--# open lua51
+local a = math.max(1, 2, 3) --: integer
+local b = math.max(1, 2.5) --: number
+local c = math.random() --: number
+local d = math.random(6) --: integer
+--! ok
+
+--8<-- overload-lua51-select
+--# open lua51
+local n = select('#', 1, 2, 3) --: integer
+local a = select(2, 'a', 'b')
+--! ok
+
+--8<-- overload-lua51-io-read
+--# open lua51
+local n = io.read('*n') --: number
+local s = io.read('*l') --: string
+--! ok
@@ -0,0 +1,142 @@
+--8<-- strict-global
+--# strict
+x = 42
+--@^ Error: A global variable `x` should be declared with `--# assume global` before the creation in the strict mode
+--! error
+
+--8<-- strict-global-assumed
+--# strict
+--# assume global x: integer
+x = 42
+--! ok
+
+--8<-- strict-global-func
+--# strict
+--v function(a: integer) --> integer
+function f(a)
+--@^ Error: A global variable `f` should be declared with `--# assume global` before the creation in the strict mode
+    return a
+end
+--! error
+
+--8<-- strict-global-func-assumed
+--# strict
+--# assume global f: function(integer) --> integer
+--v function(a: integer) --> integer
+function f(a)
+    return a
+end
+local x = f(42) --: integer
+--! ok
+
+--8<-- strict-global-existing
+--# open lua51
+--# strict
+print('hello')
+--! ok
+
+--8<-- no-strict-global
+x = 42
+--! ok
+
+--8<-- strict-local
+--# strict
+local x = 42
+local y --: string
+--! ok
+
+--8<-- strict-func-arg
+--# strict
+local function f(a, --: integer
+                 b)
+--@^ Error: Every function argument should have a type specified in the strict mode
+    return a
+end
+--! error
+
+--8<-- strict-func-varargs
+--# strict
+local f = function(...) end
+--@^ Error: Variadic arguments should have a type specified in the strict mode
+--! error
+
+--8<-- strict-func-typed
+--# strict
+--v function(a: integer, ...: string) --> integer
+local function f(a, ...)
+    return a
+end
+local g = function(a, --: integer
+                   b) --: integer
+    return a + b
+end
+--! ok
+
+--8<-- strict-func-arg-hint
+--# strict
+--# assume run: function(function(integer))
+run(function(a) end)
+--@^ Error: Every function argument should have a type specified in the strict mode
+--! error
+
+--8<-- no-strict-func-arg-hint
+--# assume run: function(function(integer))
+run(function(a) end)
+--! ok
+
+--8<-- strict-whatever
+--# strict
+local x = 42 --: WHATEVER
+--@^ Error: `WHATEVER` type cannot be used in the strict mode
+--! error
+
+--8<-- strict-whatever-nested
+--# strict
+--# type T = {a: integer, b: vector<WHATEVER>}
+--@^ Error: `WHATEVER` type cannot be used in the strict mode
+--! error
+
+--8<-- strict-whatever-in-library
+--# strict
+--# open lua51
+local x = string.format('%d', 42) --: string
+--! ok
+
+--8<-- strict-implicit-rec
+--# strict
+local t = {a = 1, b = 2}
+--@^ Error: The type of this table was unknown so is assumed to be a record; please specify its type
+--! error
+
+--8<-- strict-explicit-rec
+--# strict
+local t = {a = 1, b = 2} --: {a: integer, b: integer}
+local u = {} -- empty tables are fine
+local v = {1, 2, 3} --: vector<integer>
+--! ok
+
+--8<-- strict-directive-in-block
+local function f()
+    --# strict
+end
+x = 42
+--@^ Error: A global variable `x` should be declared with `--# assume global` before the creation in the strict mode
+--! error
+
+--8<-- strict-config -- feature:strict
+x = 42
+--@^ Error: A global variable `x` should be declared with `--# assume global` before the creation in the strict mode
+--! error
+
+--8<-- strict-per-file
+--# open lua51
+local a = require 'a'
+x = 42
+
+--& a
+--# strict
+y = 42
+--@^ Error: A global variable `y` should be declared with `--# assume global` before the creation in the strict mode
+return 'a'
+
+--! error
@@ -1821,7 +1821,7 @@ local q = p * 3
 --! error
 
 --8<-- invalid-open
---# open `internal kailua_dummy` --@< Error: Cannot find the built-in library name given to `--# open` directive
+--# open `internal kailua_dummy` --@< Error: Cannot find the library name given to `--# open` directive
 --! error
 
 --8<-- duplicate-open
@@ -1829,6 +1829,48 @@ local q = p * 3
 --# open `internal kailua_test`
 --! ok
 
+--8<-- open-user-library
+--# open mylib
+local x = foo + 1 --: integer
+
+--& mylib.kailua
+--# assume global foo: integer
+
+--! ok
+
+--8<-- open-user-library-with-builtin
+--# open mylib
+local x = foo(bar) --: integer
+
+--& mylib.kailua
+--# open lua51
+--# assume global foo: function(string) --> integer
+--# assume global bar: string
+local n = string.len(bar)
+
+--! ok
+
+--8<-- open-user-library-error
+--# open mylib
+
+--& mylib.kailua
+--# assume global foo: integer
+local x = foo .. {} --@< Error: Cannot apply .. operator to `integer` and `{...}`
+                    --@^ Cause: `{...}` is not a subtype of `(number|string)`
+
+--! error
+
+--8<-- duplicate-open-user-library
+--# open mylib
+--# open mylib
+
+--& mylib.kailua
+-- this should be reported only once
+local x = 1 .. {} --@< Error: Cannot apply .. operator to `1` and `{...}`
+                  --@^ Cause: `{...}` is not a subtype of `(number|string)`
+
+--! error
+
 --8<-- assign-identical
 --# assume x: WHATEVER
 x = x
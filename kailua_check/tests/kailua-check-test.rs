@@ -12,7 +12,7 @@ use std::str;
 use std::usize;
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use clap::{App, Arg, ArgMatches};
 use kailua_env::{Source, Span, Spanned};
 use kailua_diag::{Stop, Locale, Report, Reporter, TrackMaxKind};
@@ -20,7 +20,7 @@ use kailua_syntax::{Chunk, parse_chunk};
 use kailua_types::ty::{TypeContext, Display};
 use kailua_check::check_from_chunk;
 use kailua_check::options::Options;
-use kailua_check::env::Context;
+use kailua_check::env::{Context, CheckerConfig};
 
 struct Testing {
     note_spanned_infos: bool,
@@ -47,7 +47,7 @@ impl kailua_test::Testing for Testing {
     }
 
     fn run(&self, source: Rc<RefCell<Source>>, span: Span, filespans: &HashMap<String, Span>,
-           report: Rc<Report>) -> String {
+           features: &HashSet<String>, report: Rc<Report>) -> String {
         let chunk = match parse_chunk(&source.borrow(), span, &*report) {
             Ok(chunk) => chunk,
             Err(_) => return format!("parse error"),
@@ -65,11 +65,26 @@ impl kailua_test::Testing for Testing {
                 let span = *self.filespans.get(path).ok_or(None)?;
                 parse_chunk(&self.source.borrow(), span, report).map_err(|_| None)
             }
+
+            // `--# open NAME` loads a file named `NAME.kailua` if any
+            fn library_chunks(&mut self, name: Spanned<&[u8]>,
+                              report: &Report) -> Result<Vec<Chunk>, Option<Stop>> {
+                let name = str::from_utf8(&name).map_err(|_| None)?;
+                let span = *self.filespans.get(&format!("{}.kailua", name)).ok_or(None)?;
+                let chunk = parse_chunk(&self.source.borrow(), span, report).map_err(|_| None)?;
+                Ok(vec![chunk])
+            }
         }
 
         let report = Rc::new(TrackMaxKind::new(report));
         let opts = Rc::new(RefCell::new(Opts { source: source, filespans: filespans.clone() }));
         let mut context = Context::new(report.clone());
+        context.set_config(CheckerConfig {
+            warn_on_dead_code: features.contains("warn_on_dead_code"),
+            warn_on_useless_conds: features.contains("warn_on_useless_conds"),
+            no_implicit_func_sig: features.contains("no_implicit_func_sig"),
+            strict: features.contains("strict"),
+        });
         let ret = check_from_chunk(&mut context, chunk, opts);
 
         // spanned information is available even on error
@@ -106,10 +121,12 @@ impl kailua_test::Testing for Testing {
 
 fn main() {
     env_logger::init().unwrap();
+    let config = CheckerConfig::default();
     kailua_test::Tester::new("kailua-check-test", Testing::new())
-        .feature("no_implicit_func_sig", cfg!(feature = "no_implicit_func_sig"))
-        .feature("warn_on_useless_conds", cfg!(feature = "warn_on_useless_conds"))
-        .feature("warn_on_dead_code", cfg!(feature = "warn_on_dead_code"))
+        .configurable_feature("no_implicit_func_sig", config.no_implicit_func_sig)
+        .configurable_feature("warn_on_useless_conds", config.warn_on_useless_conds)
+        .configurable_feature("warn_on_dead_code", config.warn_on_dead_code)
+        .configurable_feature("strict", config.strict)
         .scan("src/tests")
         .done();
 }
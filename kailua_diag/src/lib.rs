@@ -6,13 +6,16 @@ extern crate parse_generics_shim;
 #[macro_use] extern crate log;
 #[cfg(windows)] extern crate winapi;
 #[cfg(windows)] extern crate kernel32;
+#[macro_use] extern crate serde_json;
 extern crate kailua_env;
 
 pub use message::{Locale, Localize, Localized};
 pub use report::{Kind, Stop, Result, Report, Reporter};
-pub use report::{ConsoleReport, CollectedReport, NoReport, TrackMaxKind};
+pub use report::{ConsoleReport, CollectedReport, NoReport, TrackMaxKind, DedupReport};
+pub use structured::StructuredReport;
 
 pub mod message;
 pub mod report;
+pub mod structured;
 mod dummy_term;
 
@@ -108,12 +108,24 @@ fn test_locale_names() {
 /// Any type that can be formatted into a localized text.
 pub trait Localize: fmt::Debug {
     fn fmt_localized(&self, f: &mut fmt::Formatter, locale: Locale) -> fmt::Result;
+
+    /// Returns a stable code (e.g. `K0401`) for this message, if any.
+    ///
+    /// The code does not depend on the locale and can be used to identify
+    /// a particular kind of messages, for example to ignore them.
+    fn message_code(&self) -> Option<&'static str> {
+        None
+    }
 }
 
 impl<'a> Localize for &'a Localize {
     fn fmt_localized(&self, f: &mut fmt::Formatter, locale: Locale) -> fmt::Result {
         (**self).fmt_localized(f, locale)
     }
+
+    fn message_code(&self) -> Option<&'static str> {
+        (**self).message_code()
+    }
 }
 
 impl<T: fmt::Display + fmt::Debug> Localize for T {
@@ -160,6 +172,7 @@ macro_rules! define_msg_internal {
     (
         $name:ident
         ($($prefix:tt)*)
+        ($code:expr)
         {
             constr: [ $($constr:tt)* ],
             params: [ $($params:tt)* ],
@@ -189,6 +202,10 @@ macro_rules! define_msg_internal {
                         $($locale => $format),*;
                         ($($(, $fname = $crate::Localized::new(&self.$fname, locale))*)*))
                 }
+
+                fn message_code(&self) -> Option<&'static str> {
+                    $code
+                }
             }
         }
     );
@@ -209,6 +226,18 @@ macro_rules! define_msg_internal {
 /// (But note that the constructor itself is a struct, so `StructName {}` is required
 /// even when there are no message parameters.)
 ///
+/// The message can be optionally prefixed with a stable code in brackets,
+/// which is returned by `Localize::message_code`:
+///
+/// ```rust,ignore
+/// define_msg! { [K0001] pub StructName:
+///     _ => "The default string",
+/// }
+/// ```
+///
+/// Codes are shown to the user and can be used to ignore particular messages,
+/// so they should never change or be reused once assigned.
+///
 /// # Dependencies
 ///
 /// This macro internally depends a `parse_generics_shim` crate,
@@ -226,10 +255,26 @@ macro_rules! define_msg_internal {
 /// ```
 #[macro_export]
 macro_rules! define_msg {
+    ([$code:ident] $(#[$meta:meta])* pub $name:ident $($t:tt)*) => (
+        parse_generics_shim! {
+            { constr, params, ltimes, tnames },
+            then define_msg_internal!($name ($(#[$meta])* pub) (Some(stringify!($code)))),
+            $($t)*
+        }
+    );
+
+    ([$code:ident] $(#[$meta:meta])* $name:ident $($t:tt)*) => (
+        parse_generics_shim! {
+            { constr, params, ltimes, tnames },
+            then define_msg_internal!($name ($(#[$meta])*) (Some(stringify!($code)))),
+            $($t)*
+        }
+    );
+
     ($(#[$meta:meta])* pub $name:ident $($t:tt)*) => (
         parse_generics_shim! {
             { constr, params, ltimes, tnames },
-            then define_msg_internal!($name ($(#[$meta])* pub)),
+            then define_msg_internal!($name ($(#[$meta])* pub) (None)),
             $($t)*
         }
     );
@@ -237,7 +282,7 @@ macro_rules! define_msg {
     ($(#[$meta:meta])* $name:ident $($t:tt)*) => (
         parse_generics_shim! {
             { constr, params, ltimes, tnames },
-            then define_msg_internal!($name ($(#[$meta])*)),
+            then define_msg_internal!($name ($(#[$meta])*) (None)),
             $($t)*
         }
     );
@@ -7,6 +7,7 @@ use std::result;
 use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::collections::HashSet;
 use unicode_width::UnicodeWidthChar;
 use kailua_env::{Source, SourceSlice, Span, Pos};
 
@@ -380,6 +381,9 @@ impl Report for ConsoleReport {
         let _ = term.fg(bright);
         let _ = write!(term, "{:?}", kind);
         let _ = term.fg(dim);
+        if let Some(code) = msg.message_code() {
+            let _ = write!(term, " {}", code);
+        }
         let _ = write!(term, "] ");
         let _ = term.fg(color::BRIGHT_WHITE);
         let _ = write!(term, "{}", Localized::new(msg, self.locale));
@@ -582,6 +586,10 @@ impl<R: Report> TrackMaxKind<R> {
         self.maxkind.get() < Some(Kind::Error)
     }
 
+    pub fn get_ref(&self) -> &R {
+        &self.report
+    }
+
     pub fn into_inner(self) -> R {
         self.report
     }
@@ -602,3 +610,77 @@ impl<R: Report> Report for TrackMaxKind<R> {
     }
 }
 
+/// A wrapper for `Report` implementations that drops duplicate reports.
+///
+/// A report is a duplicate if a report with the same kind, span and localized message
+/// has been received before. Causes and notes following the duplicate are dropped as well.
+/// This is useful when the same file can be checked multiple times.
+pub struct DedupReport<R: Report> {
+    report: R,
+    seen: RefCell<HashSet<(Kind, Span, String)>>,
+    dropping: Cell<bool>,
+    duplicates: Cell<usize>,
+}
+
+impl<R: Report> DedupReport<R> {
+    pub fn new(report: R) -> DedupReport<R> {
+        DedupReport {
+            report: report,
+            seen: RefCell::new(HashSet::new()),
+            dropping: Cell::new(false),
+            duplicates: Cell::new(0),
+        }
+    }
+
+    /// Returns the number of dropped reports, excluding causes and notes.
+    pub fn num_duplicates(&self) -> usize {
+        self.duplicates.get()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.report
+    }
+}
+
+impl<R: Report> Report for DedupReport<R> {
+    fn message_locale(&self) -> Locale {
+        self.report.message_locale()
+    }
+
+    fn add_span(&self, kind: Kind, span: Span, msg: &Localize) -> Result<()> {
+        match kind {
+            Kind::Cause | Kind::Note => {
+                if self.dropping.get() {
+                    return Ok(());
+                }
+            }
+            _ => {
+                let text = Localized::new(msg, self.report.message_locale()).to_string();
+                let duplicate = !self.seen.borrow_mut().insert((kind, span, text));
+                self.dropping.set(duplicate);
+                if duplicate {
+                    self.duplicates.set(self.duplicates.get() + 1);
+                    return if kind == Kind::Fatal { Err(Stop) } else { Ok(()) };
+                }
+            }
+        }
+        self.report.add_span(kind, span, msg)
+    }
+}
+
+#[test]
+fn test_dedup_report() {
+    let span = Span::dummy();
+    let report = DedupReport::new(CollectedReport::new(Locale::dummy()));
+    report.add_span(Kind::Error, span, &"foo").unwrap();
+    report.add_span(Kind::Note, span, &"bar").unwrap();
+    report.add_span(Kind::Error, span, &"foo").unwrap();
+    report.add_span(Kind::Note, span, &"bar").unwrap();
+    report.add_span(Kind::Warning, span, &"foo").unwrap();
+    assert_eq!(report.num_duplicates(), 1);
+    assert_eq!(report.into_inner().into_reports(), vec![
+        (Kind::Error, span, "foo".to_string()),
+        (Kind::Note, span, "bar".to_string()),
+        (Kind::Warning, span, "foo".to_string()),
+    ]);
+}
@@ -0,0 +1,355 @@
+//! Machine-readable diagnostics.
+
+use std::io::{self, Write};
+use std::str;
+use std::char;
+use std::cmp;
+use std::cell::RefCell;
+use std::rc::Rc;
+use serde_json::{self, Value};
+use kailua_env::{Source, SourceSlice, Pos, Span};
+
+use message::{Locale, Localize, Localized};
+use report::{Kind, Stop, Result, Report};
+
+/// The output format for `StructuredReport`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// Line-delimited JSON, one JSON object for each root report.
+    Json,
+
+    /// A single [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/) log.
+    Sarif,
+}
+
+impl Format {
+    /// Parses a format name (`json` or `sarif`).
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "json" => Some(Format::Json),
+            "sarif" => Some(Format::Sarif),
+            _ => None,
+        }
+    }
+}
+
+fn kind_name(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Note => "note",
+        Kind::Info => "info",
+        Kind::Cause => "cause",
+        Kind::Warning => "warning",
+        Kind::Error => "error",
+        Kind::Fatal => "fatal",
+    }
+}
+
+/// A resolved location of the report.
+///
+/// Both line and column numbers start from 1 and columns are counted in Unicode code points
+/// (or bytes if the line is not in UTF-8). The end position is exclusive.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Location {
+    pub path: String,
+    pub begin_line: usize,
+    pub begin_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+/// A single report with a resolved location and a localized message.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Entry {
+    pub kind: Kind,
+    pub code: Option<&'static str>,
+    pub location: Option<Location>,
+    pub message: String,
+}
+
+/// A root report (fatal error, error, warning or info) with following causes and notes.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    pub entry: Entry,
+    pub related: Vec<Entry>,
+}
+
+/// An implementation of `Report` that collects reports and
+/// writes them in the machine-readable format later.
+///
+/// Causes and notes are grouped into the preceding root report.
+pub struct StructuredReport {
+    source: Rc<RefCell<Source>>,
+    locale: Locale,
+    diags: RefCell<Vec<Diagnostic>>,
+}
+
+impl StructuredReport {
+    pub fn new(source: Rc<RefCell<Source>>, locale: Locale) -> StructuredReport {
+        StructuredReport { source: source, locale: locale, diags: RefCell::new(Vec::new()) }
+    }
+
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diags.into_inner()
+    }
+
+    fn resolve_pos(&self, pos: Pos) -> Option<(String, usize, usize)> {
+        let source = self.source.borrow();
+        let file = match source.get_file(pos.unit()) {
+            Some(file) => file,
+            None => return None,
+        };
+        let (line, linespan) = match file.line_from_pos(pos) {
+            Some(line) => line,
+            None => return None,
+        };
+
+        // column number starts from 0 here
+        let off = pos.to_usize() - linespan.begin().to_usize();
+        let column = match source.slice_from_span(linespan) {
+            Some(SourceSlice::U8(s)) => {
+                let off = cmp::min(off, s.len());
+                if let Ok(s) = str::from_utf8(s) {
+                    s.char_indices().take_while(|&(i, _)| i < off).count()
+                } else {
+                    off
+                }
+            }
+            Some(SourceSlice::U16(s)) => {
+                let off = cmp::min(off, s.len());
+                char::decode_utf16(s[..off].iter().cloned()).count()
+            }
+            None => return None,
+        };
+
+        Some((file.path().to_owned(), line + 1, column + 1))
+    }
+
+    fn resolve_span(&self, span: Span) -> Option<Location> {
+        if span.is_dummy() {
+            return None;
+        }
+
+        let (path, begin_line, begin_column) = match self.resolve_pos(span.begin()) {
+            Some(pos) => pos,
+            None => return None,
+        };
+        let (_, end_line, end_column) = match self.resolve_pos(span.end()) {
+            Some(pos) => pos,
+            None => return None,
+        };
+        Some(Location {
+            path: path,
+            begin_line: begin_line,
+            begin_column: begin_column,
+            end_line: end_line,
+            end_column: end_column,
+        })
+    }
+
+    /// Writes collected reports in given format.
+    pub fn write_to<W: Write>(&self, format: Format, w: &mut W) -> io::Result<()> {
+        let diags = self.diags.borrow();
+        match format {
+            Format::Json => {
+                for diag in diags.iter() {
+                    serde_json::to_writer(&mut *w, &diag_to_json(diag))?;
+                    writeln!(w, "")?;
+                }
+            }
+            Format::Sarif => {
+                serde_json::to_writer_pretty(&mut *w, &diags_to_sarif(&diags))?;
+                writeln!(w, "")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Report for StructuredReport {
+    fn message_locale(&self) -> Locale {
+        self.locale
+    }
+
+    fn add_span(&self, kind: Kind, span: Span, msg: &Localize) -> Result<()> {
+        let entry = Entry {
+            kind: kind,
+            code: msg.message_code(),
+            location: self.resolve_span(span),
+            message: Localized::new(msg, self.locale).to_string(),
+        };
+
+        let mut diags = self.diags.borrow_mut();
+        match kind {
+            Kind::Cause | Kind::Note if !diags.is_empty() => {
+                diags.last_mut().unwrap().related.push(entry);
+            }
+            _ => {
+                diags.push(Diagnostic { entry: entry, related: Vec::new() });
+            }
+        }
+
+        if kind == Kind::Fatal { Err(Stop) } else { Ok(()) }
+    }
+}
+
+fn entry_to_json(entry: &Entry) -> Value {
+    let mut obj = serde_json::Map::new();
+    obj.insert("kind".to_owned(), Value::from(kind_name(entry.kind)));
+    obj.insert("code".to_owned(), entry.code.map_or(Value::Null, Value::from));
+    obj.insert("message".to_owned(), Value::from(&entry.message[..]));
+    if let Some(ref loc) = entry.location {
+        obj.insert("file".to_owned(), Value::from(&loc.path[..]));
+        obj.insert("line".to_owned(), Value::from(loc.begin_line));
+        obj.insert("column".to_owned(), Value::from(loc.begin_column));
+        obj.insert("end_line".to_owned(), Value::from(loc.end_line));
+        obj.insert("end_column".to_owned(), Value::from(loc.end_column));
+    } else {
+        obj.insert("file".to_owned(), Value::Null);
+    }
+    Value::Object(obj)
+}
+
+fn diag_to_json(diag: &Diagnostic) -> Value {
+    let mut value = entry_to_json(&diag.entry);
+    let related = diag.related.iter().map(entry_to_json).collect();
+    value.as_object_mut().unwrap().insert("related".to_owned(), Value::Array(related));
+    value
+}
+
+fn sarif_level(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Fatal | Kind::Error => "error",
+        Kind::Warning => "warning",
+        Kind::Info | Kind::Cause | Kind::Note => "note",
+    }
+}
+
+fn sarif_uri(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+fn sarif_physical_location(loc: &Location) -> Value {
+    json!({
+        "artifactLocation": { "uri": sarif_uri(&loc.path) },
+        "region": {
+            "startLine": loc.begin_line,
+            "startColumn": loc.begin_column,
+            "endLine": loc.end_line,
+            "endColumn": loc.end_column,
+        },
+    })
+}
+
+fn diags_to_sarif(diags: &[Diagnostic]) -> Value {
+    let results: Vec<_> = diags.iter().map(|diag| {
+        let mut result = json!({
+            "level": sarif_level(diag.entry.kind),
+            "message": { "text": diag.entry.message },
+        });
+
+        let obj = result.as_object_mut().unwrap();
+        if let Some(code) = diag.entry.code {
+            obj.insert("ruleId".to_owned(), Value::from(code));
+        }
+        if let Some(ref loc) = diag.entry.location {
+            obj.insert("locations".to_owned(),
+                       json!([{ "physicalLocation": sarif_physical_location(loc) }]));
+        }
+
+        // causes and notes are kept in order; the kind is retained in the message
+        let related: Vec<_> = diag.related.iter().enumerate().map(|(i, entry)| {
+            let mut related = json!({
+                "id": i,
+                "message": { "text": format!("[{:?}] {}", entry.kind, entry.message) },
+            });
+            if let Some(ref loc) = entry.location {
+                related.as_object_mut().unwrap()
+                       .insert("physicalLocation".to_owned(), sarif_physical_location(loc));
+            }
+            related
+        }).collect();
+        if !related.is_empty() {
+            obj.insert("relatedLocations".to_owned(), Value::Array(related));
+        }
+
+        result
+    }).collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "kailua",
+                    "informationUri": "https://github.com/devcat-studio/kailua/",
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    })
+}
+
+#[test]
+fn test_structured_report() {
+    use std::fmt;
+    use kailua_env::SourceFile;
+    use report::Reporter;
+
+    #[derive(Debug)]
+    struct Root;
+
+    impl Localize for Root {
+        fn fmt_localized(&self, f: &mut fmt::Formatter, _locale: Locale) -> fmt::Result {
+            write!(f, "root")
+        }
+
+        fn message_code(&self) -> Option<&'static str> {
+            Some("K0000")
+        }
+    }
+
+    let source = Rc::new(RefCell::new(Source::new()));
+    let span = source.borrow_mut().add(SourceFile::from_u8("a.lua".to_owned(),
+                                                           b"local a = 1\nlocal \xea\xb0\x80 = 2\n"
+                                                           .to_vec()));
+    let poses: Vec<Pos> = source.borrow().iter_from_span(span).unwrap()
+                                .map(|d| d.span.begin()).collect();
+    let pos = |off: usize| poses[off];
+    let report = StructuredReport::new(source.clone(), Locale::dummy());
+    report.error(Span::new(pos(6), pos(7)), Root)
+          .cause(Span::new(pos(18), pos(21)), "cause")
+          .note(Span::dummy(), "note")
+          .done().unwrap();
+    report.warn(Span::new(pos(4), pos(16)), "second").done().unwrap();
+
+    let mut json = Vec::new();
+    report.write_to(Format::Json, &mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    let lines: Vec<Value> = json.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(lines, vec![
+        json!({"kind": "error", "code": "K0000", "message": "root", "file": "a.lua",
+               "line": 1, "column": 7, "end_line": 1, "end_column": 8,
+               "related": [
+                   {"kind": "cause", "code": null, "message": "cause", "file": "a.lua",
+                    "line": 2, "column": 7, "end_line": 2, "end_column": 8},
+                   {"kind": "note", "code": null, "message": "note", "file": null},
+               ]}),
+        json!({"kind": "warning", "code": null, "message": "second", "file": "a.lua",
+               "line": 1, "column": 5, "end_line": 2, "end_column": 5, "related": []}),
+    ]);
+
+    let mut sarif = Vec::new();
+    report.write_to(Format::Sarif, &mut sarif).unwrap();
+    let sarif: Value = serde_json::from_slice(&sarif).unwrap();
+    assert_eq!(sarif["version"], json!("2.1.0"));
+    let results = &sarif["runs"][0]["results"];
+    assert_eq!(results[0]["level"], json!("error"));
+    assert_eq!(results[0]["ruleId"], json!("K0000"));
+    assert_eq!(results[0]["locations"][0]["physicalLocation"]["region"],
+               json!({"startLine": 1, "startColumn": 7, "endLine": 1, "endColumn": 8}));
+    assert_eq!(results[0]["relatedLocations"][0]["message"]["text"], json!("[Cause] cause"));
+    assert_eq!(results[0]["relatedLocations"][1].get("physicalLocation"), None);
+    assert_eq!(results[1]["level"], json!("warning"));
+}
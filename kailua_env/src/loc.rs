@@ -1,6 +1,7 @@
 //! Location types and a location-bundled container.
 
 use std::ops;
+use std::u32;
 use std::cmp;
 use std::fmt;
 use std::borrow::Borrow;
@@ -106,6 +107,18 @@ impl Pos {
     pub fn to_usize(&self) -> usize {
         self.pos as usize
     }
+
+    /// Returns a position moved by given (possibly negative) offset in the same unit.
+    /// The source-independent position is returned as is.
+    pub fn shift(&self, delta: isize) -> Pos {
+        if self.is_source_dependent() {
+            let pos = self.pos as i64 + delta as i64;
+            assert!(pos >= 0 && pos <= u32::MAX as i64, "Pos::shift went out of bounds");
+            Pos { unit: self.unit, pos: pos as u32 }
+        } else {
+            *self
+        }
+    }
 }
 
 /// In the debugging output the position is denoted `@_` or <code>@<i>unit</i>/<i>off</i></code>.
@@ -271,6 +271,27 @@ impl<Name: Clone + Hash + Eq + fmt::Debug> ScopeMap<Name> {
         }
     }
 
+    pub fn scope_span(&self, scope: Scope) -> Span {
+        assert!((scope.scope as usize) < self.scopes.len());
+        self.scopes[scope.scope as usize].span
+    }
+
+    /// Updates spans of all scopes with given function, which receives the scope and
+    /// its current span (possibly dummy) and returns a new span.
+    /// Returning a dummy span removes the scope from the location-to-scope mapping,
+    /// while names in that scope are kept.
+    pub fn map_spans<F: FnMut(Scope, Span) -> Span>(&mut self, mut f: F) {
+        let mut spans = SpanMap::new();
+        for (i, item) in self.scopes.iter_mut().enumerate().skip(1) {
+            let scope = Scope { scope: i as u32 };
+            item.span = f(scope, item.span);
+            if item.span.is_source_dependent() {
+                spans.insert(Spanned { span: item.span, base: scope });
+            }
+        }
+        self.spans = spans;
+    }
+
     pub fn parent_scope(&self, scope: Scope) -> Option<Scope> {
         assert!((scope.scope as usize) < self.scopes.len());
         let parent = self.scopes[scope.scope as usize].parent;
@@ -457,3 +478,35 @@ fn test_scope_map_span() {
     assert_eq!(m.scope_from_pos(pos_from_u32(unit2, 30)), Some(c));
 }
 
+#[test]
+fn test_scope_map_map_spans() {
+    use loc::{unit_from_u32, pos_from_u32, span_from_u32, WithLoc};
+
+    let unit = unit_from_u32(1);
+    let pos = |pos| pos_from_u32(unit, pos);
+    let span = |begin, end| span_from_u32(unit, begin, end);
+
+    let mut m = ScopeMap::<()>::new();
+    let g = m.generate_root(); m.set_span(g.with_loc(span(0, 100)));
+    let a = m.generate(g); m.set_span(a.with_loc(span(10, 20)));
+    let b = m.generate(g); m.set_span(b.with_loc(span(30, 40)));
+
+    // remove `a` and move `b` and the end of `g` by 5
+    m.map_spans(|scope, s| {
+        if scope == a {
+            Span::dummy()
+        } else if s.end().to_usize() >= 30 {
+            Span::new(if s.begin().to_usize() >= 30 { s.begin().shift(5) } else { s.begin() },
+                      s.end().shift(5))
+        } else {
+            s
+        }
+    });
+    assert_eq!(m.scope_span(g), span(0, 105));
+    assert_eq!(m.scope_span(a), Span::dummy());
+    assert_eq!(m.scope_span(b), span(35, 45));
+    assert_eq!(m.scope_from_pos(pos(15)), Some(g));
+    assert_eq!(m.scope_from_pos(pos(32)), Some(g));
+    assert_eq!(m.scope_from_pos(pos(40)), Some(b));
+    assert_eq!(m.scope_from_pos(pos(105)), Some(g));
+}
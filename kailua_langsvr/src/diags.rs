@@ -4,6 +4,7 @@ use std::hash;
 use std::sync::Arc;
 use std::collections::HashSet;
 use parking_lot::{Mutex, MutexGuard};
+use serde_json::Value;
 
 use kailua_env::{Pos, Span, Source, SourceFile, SourceSlice};
 use kailua_diag::{self, Kind, Report, Locale, Localize, Localized};
@@ -57,6 +58,47 @@ pub fn translate_span_without_path(span: Span, file: &SourceFile) -> Option<Rang
     })
 }
 
+/// The inverse of `translate_span_without_path` for the document text as sent by VS Code.
+/// Returns the byte offset to `text` for given position.
+///
+/// Lines are separated with `\r`, `\n` or `\r\n` as `SourceFile` does.
+/// The position past the end of line (or the end of text) is clamped as the protocol requires,
+/// and the position splitting a surrogate pair is moved to the beginning of that pair.
+pub fn translate_position(pos: &Position, text: &str) -> usize {
+    let bytes = text.as_bytes();
+
+    // the line 0 starts after the BOM as in `SourceFile`
+    let mut off = if text.starts_with("\u{feff}") { 3 } else { 0 };
+
+    // skip given number of lines
+    let mut line = 0;
+    while line < pos.line {
+        match bytes[off..].iter().position(|&b| b == b'\r' || b == b'\n') {
+            Some(i) => {
+                off += i + 1;
+                if bytes[off - 1] == b'\r' && bytes.get(off) == Some(&b'\n') {
+                    off += 1;
+                }
+                line += 1;
+            }
+            None => return text.len(),
+        }
+    }
+
+    // VS Code expects the u16 offset (i.e. as JavaScript .charAt expects)
+    let mut nu16s = 0;
+    for (i, c) in text[off..].char_indices() {
+        if c == '\r' || c == '\n' {
+            return off + i;
+        }
+        nu16s += c.len_utf16() as u64;
+        if nu16s > pos.character {
+            return off + i;
+        }
+    }
+    text.len()
+}
+
 pub fn translate_span(span: Span, source: &Source) -> Option<(String, Range)> {
     source.get_file(span.unit()).and_then(|file| {
         translate_span_without_path(span, file).map(|range| (file.path().to_owned(), range))
@@ -162,6 +204,7 @@ impl<F> Report for ReportTreeReport<F>
     }
 
     fn add_span(&self, kind: Kind, span: Span, msg: &Localize) -> kailua_diag::Result<()> {
+        let code = msg.message_code();
         let msg = Localized::new(msg, self.inner.locale).to_string();
 
         // TODO span should be translated _after_ deciding whether to put a new diagnostic,
@@ -200,7 +243,8 @@ impl<F> Report for ReportTreeReport<F>
                 // otherwise report normally
                 collected.push((path, Diagnostic {
                     range: range, severity: Some(severity),
-                    code: None, source: None, message: msg,
+                    code: code.map(|code| Value::String(code.to_owned())),
+                    source: None, message: msg,
                 }));
             }
         }
@@ -272,3 +316,25 @@ impl Iterator for ReportTrees {
     }
 }
 
+
+#[test]
+fn test_translate_position() {
+    let pos = |line, character| Position { line: line, character: character };
+
+    let text = "ab\r\ncd\re\u{e9}\u{1f600}f\n";
+    assert_eq!(translate_position(&pos(0, 0), text), 0);
+    assert_eq!(translate_position(&pos(0, 2), text), 2);
+    assert_eq!(translate_position(&pos(0, 9), text), 2); // clamped to the end of line
+    assert_eq!(translate_position(&pos(1, 1), text), 5);
+    assert_eq!(translate_position(&pos(2, 1), text), 8);
+    assert_eq!(translate_position(&pos(2, 2), text), 10); // after U+00E9 (2 bytes, 1 u16)
+    assert_eq!(translate_position(&pos(2, 3), text), 10); // inside U+1F600 (4 bytes, 2 u16s)
+    assert_eq!(translate_position(&pos(2, 4), text), 14);
+    assert_eq!(translate_position(&pos(2, 5), text), 15);
+    assert_eq!(translate_position(&pos(3, 0), text), 16);
+    assert_eq!(translate_position(&pos(4, 0), text), 16); // clamped to the end of text
+
+    let text = "\u{feff}x\ny";
+    assert_eq!(translate_position(&pos(0, 0), text), 3);
+    assert_eq!(translate_position(&pos(1, 1), text), 6);
+}
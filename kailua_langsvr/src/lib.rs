@@ -49,28 +49,58 @@ use workspace::{Workspace, WorkspaceFile};
 // initialization options supposed to be sent from the extension
 struct InitOptions {
     default_locale: kailua_diag::Locale,
+    // can be overridden by the configuration file
+    default_checker: kailua_check::env::CheckerConfig,
 }
 
 impl Default for InitOptions {
     fn default() -> InitOptions {
-        InitOptions { default_locale: kailua_diag::Locale::from("en") }
+        InitOptions {
+            default_locale: kailua_diag::Locale::from("en"),
+            default_checker: kailua_check::env::CheckerConfig::default(),
+        }
     }
 }
 
 fn parse_init_options(opts: Option<serde_json::Value>) -> InitOptions {
     #[derive(Deserialize)]
     struct Options {
-        default_locale: String,
+        default_locale: Option<String>,
+        checker: Option<Checker>,
+    }
+
+    #[derive(Deserialize)]
+    struct Checker {
+        warn_on_dead_code: Option<bool>,
+        warn_on_useless_conds: Option<bool>,
+        no_implicit_func_sig: Option<bool>,
+        strict: Option<bool>,
     }
 
+    let mut initopts = InitOptions::default();
     if let Some(opts) = opts {
         if let Ok(opts) = serde_json::from_value::<Options>(opts) {
-            if let Some(locale) = kailua_diag::Locale::new(&opts.default_locale) {
-                return InitOptions { default_locale: locale };
+            if let Some(locale) = opts.default_locale.and_then(|l| kailua_diag::Locale::new(&l)) {
+                initopts.default_locale = locale;
+            }
+            if let Some(checker) = opts.checker {
+                let config = &mut initopts.default_checker;
+                if let Some(value) = checker.warn_on_dead_code {
+                    config.warn_on_dead_code = value;
+                }
+                if let Some(value) = checker.warn_on_useless_conds {
+                    config.warn_on_useless_conds = value;
+                }
+                if let Some(value) = checker.no_implicit_func_sig {
+                    config.no_implicit_func_sig = value;
+                }
+                if let Some(value) = checker.strict {
+                    config.strict = value;
+                }
             }
         }
     }
-    InitOptions::default()
+    initopts
 }
 
 // both show the message (more visible but will be dismissed after a few seconds)
@@ -136,7 +166,8 @@ fn initialize_workspace(server: &Server) -> Workspace {
                     let pool = Arc::new(CpuPool::new(nworkers));
 
                     let mut workspace = Workspace::new(PathBuf::from(dir), pool.clone(),
-                                                       initopts.default_locale);
+                                                       initopts.default_locale,
+                                                       initopts.default_checker);
 
                     // try to read the config...
                     let config_read = workspace.read_config();
@@ -151,13 +182,13 @@ fn initialize_workspace(server: &Server) -> Workspace {
                             /*
                             textDocumentSync: Some(TextDocumentSyncOptions {
                                 openClose: true,
-                                change: TextDocumentSyncKind::Full,
+                                change: TextDocumentSyncKind::Incremental,
                                 willSave: false,
                                 willSaveWaitUntil: false,
                                 save: Some(SaveOptions { includeText: false }),
                             }),
                             */
-                            textDocumentSync: TextDocumentSyncKind::Full,
+                            textDocumentSync: TextDocumentSyncKind::Incremental,
                             completionProvider: Some(CompletionOptions {
                                 resolveProvider: false,
                                 triggerCharacters:
@@ -171,7 +202,23 @@ fn initialize_workspace(server: &Server) -> Workspace {
                                 triggerCharacters: vec!["(".to_string(), ",".to_string()],
                             }),
                             definitionProvider: true,
+                            referencesProvider: true,
+                            documentHighlightProvider: true,
+                            documentSymbolProvider: true,
+                            workspaceSymbolProvider: true,
+                            documentFormattingProvider: true,
+                            documentRangeFormattingProvider: true,
                             renameProvider: true,
+                            codeActionProvider: true,
+                            executeCommandProvider: Some(ExecuteCommandOptions {
+                                commands: vec![APPLY_EDIT_COMMAND.to_string()],
+                            }),
+                            inlayHintProvider: true,
+                            semanticTokensProvider: Some(SemanticTokensOptions {
+                                legend: ops::semantic::legend(),
+                                range: true,
+                                full: true,
+                            }),
                             ..Default::default()
                         },
                     });
@@ -404,11 +451,8 @@ fn main_loop(server: Server, workspace: Arc<RwLock<Workspace>>) {
                         WorkspaceError("file does not exist for changes")
                     }));
 
-                    let mut e = Ok(());
-                    for change in params.contentChanges {
-                        e = e.or(file.apply_change(params.textDocument.version, change));
-                    }
-                    try_or_notify!(e);
+                    try_or_notify!(file.apply_changes(params.textDocument.version,
+                                                      params.contentChanges));
 
                     on_file_changed(&file, server.clone(), &pool);
                 }
@@ -481,6 +525,75 @@ fn main_loop(server: Server, workspace: Arc<RwLock<Workspace>>) {
                 definition(server.clone(), workspace.clone(), id, file, token, &params.position);
             }
 
+            Received::Request(id, Request::FindReferences(params)) => {
+                let token = CancelToken::new();
+                cancel_tokens.insert(id.clone(), token.clone());
+
+                let uri = &params.textDocument.uri;
+                let file = try_or_notify!(workspace.read().file(uri).ok_or_else(|| {
+                    WorkspaceError("file does not exist for finding references")
+                }));
+
+                references(server.clone(), workspace.clone(), id, file, token,
+                           &params.position, params.context.includeDeclaration);
+            }
+
+            Received::Request(id, Request::DocumentHighlight(params)) => {
+                let token = CancelToken::new();
+                cancel_tokens.insert(id.clone(), token.clone());
+
+                let uri = &params.textDocument.uri;
+                let file = try_or_notify!(workspace.read().file(uri).ok_or_else(|| {
+                    WorkspaceError("file does not exist for document highlights")
+                }));
+
+                highlight(server.clone(), workspace.clone(), id, file, token, &params.position);
+            }
+
+            Received::Request(id, Request::DocumentSymbol(params)) => {
+                let token = CancelToken::new();
+                cancel_tokens.insert(id.clone(), token.clone());
+
+                let uri = &params.textDocument.uri;
+                let file = try_or_notify!(workspace.read().file(uri).ok_or_else(|| {
+                    WorkspaceError("file does not exist for document symbols")
+                }));
+
+                document_symbols(server.clone(), workspace.clone(), id, file, token);
+            }
+
+            Received::Request(id, Request::WorkspaceSymbol(params)) => {
+                let token = CancelToken::new();
+                cancel_tokens.insert(id.clone(), token.clone());
+
+                workspace_symbols(server.clone(), workspace.clone(), id, token, params.query);
+            }
+
+            Received::Request(id, Request::DocumentFormatting(params)) => {
+                let token = CancelToken::new();
+                cancel_tokens.insert(id.clone(), token.clone());
+
+                let uri = &params.textDocument.uri;
+                let file = try_or_notify!(workspace.read().file(uri).ok_or_else(|| {
+                    WorkspaceError("file does not exist for formatting")
+                }));
+
+                format(server.clone(), workspace.clone(), id, file, token, &params.options, None);
+            }
+
+            Received::Request(id, Request::DocumentRangeFormatting(params)) => {
+                let token = CancelToken::new();
+                cancel_tokens.insert(id.clone(), token.clone());
+
+                let uri = &params.textDocument.uri;
+                let file = try_or_notify!(workspace.read().file(uri).ok_or_else(|| {
+                    WorkspaceError("file does not exist for formatting")
+                }));
+
+                format(server.clone(), workspace.clone(), id, file, token,
+                       &params.options, Some(&params.range));
+            }
+
             Received::Request(id, Request::Rename(params)) => {
                 let token = CancelToken::new();
                 cancel_tokens.insert(id.clone(), token.clone());
@@ -494,6 +607,60 @@ fn main_loop(server: Server, workspace: Arc<RwLock<Workspace>>) {
                        &params.position, params.newName);
             }
 
+            Received::Request(id, Request::CodeAction(params)) => {
+                let token = CancelToken::new();
+                cancel_tokens.insert(id.clone(), token.clone());
+
+                let uri = &params.textDocument.uri;
+                let file = try_or_notify!(workspace.read().file(uri).ok_or_else(|| {
+                    WorkspaceError("file does not exist for code actions")
+                }));
+
+                code_actions(server.clone(), workspace.clone(), id, file, token,
+                             uri.clone(), params.range, params.context.diagnostics);
+            }
+
+            Received::Request(id, Request::ExecuteCommand(params)) => {
+                execute_command(server.clone(), workspace.clone(), id, params);
+            }
+
+            Received::Request(id, Request::InlayHint(params)) => {
+                let token = CancelToken::new();
+                cancel_tokens.insert(id.clone(), token.clone());
+
+                let uri = &params.textDocument.uri;
+                let file = try_or_notify!(workspace.read().file(uri).ok_or_else(|| {
+                    WorkspaceError("file does not exist for inlay hints")
+                }));
+
+                inlay_hints(server.clone(), workspace.clone(), id, file, token, params.range);
+            }
+
+            Received::Request(id, Request::SemanticTokensFull(params)) => {
+                let token = CancelToken::new();
+                cancel_tokens.insert(id.clone(), token.clone());
+
+                let uri = &params.textDocument.uri;
+                let file = try_or_notify!(workspace.read().file(uri).ok_or_else(|| {
+                    WorkspaceError("file does not exist for semantic tokens")
+                }));
+
+                semantic_tokens(server.clone(), workspace.clone(), id, file, token, None);
+            }
+
+            Received::Request(id, Request::SemanticTokensRange(params)) => {
+                let token = CancelToken::new();
+                cancel_tokens.insert(id.clone(), token.clone());
+
+                let uri = &params.textDocument.uri;
+                let file = try_or_notify!(workspace.read().file(uri).ok_or_else(|| {
+                    WorkspaceError("file does not exist for semantic tokens")
+                }));
+
+                semantic_tokens(server.clone(), workspace.clone(), id, file, token,
+                                Some(params.range));
+            }
+
             _ => {}
         }
     }
@@ -731,7 +898,7 @@ fn definition(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::I
         let workspace = spare_workspace.clone();
         let ws = workspace.read();
         match class {
-            Some(definition::Class::Var(_, NameRef::Local(scoped_id))) => {
+            Some(definition::Class::Var(NameRef::Local(scoped_id))) => {
                 if let Some(span) = definition::local_var_definition(&chunk.0, &scoped_id) {
                     send_spans(server, id, &[span], &ws.source());
                 } else {
@@ -740,7 +907,7 @@ fn definition(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::I
                 future::ok(()).boxed()
             },
 
-            Some(definition::Class::Var(_, NameRef::Global(name))) => {
+            Some(definition::Class::Var(NameRef::Global(name))) => {
                 let spare_cancel_token = cancel_token.clone();
 
                 // should wait for all chunks being parsed
@@ -764,7 +931,8 @@ fn definition(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::I
             },
 
             // XXX PossiblyRequire depends on package.path/cpath, which can change in runtime
-            Some(definition::Class::PossiblyRequire(_, _, _)) | None => {
+            Some(definition::Class::PossiblyRequire) |
+            Some(definition::Class::Field(_, _)) | None => {
                 send_spans(server, id, &[], &ws.source());
                 future::ok(()).boxed()
             },
@@ -831,13 +999,13 @@ fn rename(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
         let workspace = spare_workspace.clone();
         let ws = workspace.read();
         match class {
-            Some(definition::Class::Var(_, NameRef::Local(scoped_id))) => {
+            Some(definition::Class::Var(NameRef::Local(scoped_id))) => {
                 let spans = definition::local_var_uses(&tokens.0, &chunk.0, &scoped_id);
                 send_spans(server, id, &spans, new_name, &ws.source());
                 future::ok(()).boxed()
             },
 
-            Some(definition::Class::Var(_, NameRef::Global(name))) => {
+            Some(definition::Class::Var(NameRef::Global(name))) => {
                 let spare_cancel_token = cancel_token.clone();
 
                 // should wait for all chunks being parsed
@@ -880,6 +1048,516 @@ fn rename(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
     workspace.read().pool().spawn(fut).forget();
 }
 
+fn references(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
+              file: WorkspaceFile, cancel_token: CancelToken,
+              position: &protocol::Position, include_declaration: bool) {
+    use std::path::Path;
+    use futures::{future, stream, Stream};
+    use url::Url;
+    use kailua_env::{Span, Source};
+    use kailua_syntax::ast::NameRef;
+    use ops::definition;
+    use protocol::*;
+
+    let tokens_fut = file.ensure_tokens().map_err(|e| e.as_ref().map(|_| ()));
+    let chunk_fut = file.ensure_chunk().map_err(|e| e.as_ref().map(|_| ()));
+    let pos_fut = file.translate_position(position);
+
+    let spare_workspace = workspace.clone();
+    let fut = tokens_fut.join(chunk_fut).join(pos_fut).and_then(move |((tokens, chunk), pos)| {
+        if let Err(e) = cancel_token.keep_going() {
+            return future::err(e).boxed();
+        }
+
+        let class = definition::classify(&tokens.0, &chunk.0, pos);
+        debug!("references: {:?} {:#?}", class, pos);
+
+        fn send_spans(server: Server, id: protocol::Id, spans: &[Span], source: &Source) {
+            debug!("reference spans: {:#?}", spans);
+            let locs: Vec<_> = spans.iter().filter_map(|&span| {
+                diags::translate_span(span, source).and_then(|(path, range)| {
+                    Url::from_file_path(Path::new(&path)).ok().map(|url| {
+                        Location { uri: url.to_string(), range: range }
+                    })
+                })
+            }).collect();
+            let _ = server.send_ok(id, locs);
+        }
+
+        let workspace = spare_workspace.clone();
+        let ws = workspace.read();
+        match class {
+            Some(definition::Class::Var(NameRef::Local(scoped_id))) => {
+                let mut spans = definition::local_var_uses(&tokens.0, &chunk.0, &scoped_id);
+                if !include_declaration {
+                    if let Some(def) = definition::local_var_definition(&chunk.0, &scoped_id) {
+                        spans.retain(|&span| span != def);
+                    }
+                }
+                send_spans(server, id, &spans, &ws.source());
+                future::ok(()).boxed()
+            },
+
+            Some(definition::Class::Var(NameRef::Global(name))) => {
+                let spare_cancel_token = cancel_token.clone();
+
+                // should wait for all chunks being parsed
+                let tokens_and_chunk_futs: Vec<_> = ws.files().values().map(|f| {
+                    Ok(f.ensure_tokens().join(f.ensure_chunk()))
+                }).collect();
+                let tokens_and_chunk_stream =
+                    stream::iter(tokens_and_chunk_futs.into_iter()).and_then(|fut| fut);
+                let spans_fut = tokens_and_chunk_stream.filter_map(move |tokens_and_chunk| {
+                    if spare_cancel_token.is_canceled() {
+                        None
+                    } else {
+                        let (tokens, chunk) = tokens_and_chunk;
+                        let mut spans = definition::global_var_uses(&tokens.0, &chunk.0, &name);
+                        if !include_declaration {
+                            if let Some(def) = definition::global_var_definition(&chunk.0, &name) {
+                                spans.retain(|&span| span != def);
+                            }
+                        }
+                        Some(spans)
+                    }
+                }).fold(Vec::new(), |mut a, b| { a.extend_from_slice(&b); Ok(a) });
+
+                spans_fut.map_err(|e| e.as_ref().map(|_| ())).and_then(move |spans| {
+                    cancel_token.keep_going()?;
+                    let ws = spare_workspace.read();
+                    send_spans(server, id, &spans, &ws.source());
+                    Ok(())
+                }).boxed()
+            },
+
+            // fields do not have a single declaration, so `include_declaration` is not used
+            Some(definition::Class::Field(sep_idx, name)) => {
+                // uses can be in any file, including modules which would be loaded
+                // from the module cache without function bodies
+                let outputs_fut = if let Ok(outputs_fut) = ws.ensure_combined_full_check_outputs() {
+                    outputs_fut.map(|(outputs, _diags)| outputs).boxed()
+                } else {
+                    // checking couldn't be started, `checking_loop` will notify the incident
+                    send_spans(server, id, &[], &ws.source());
+                    return future::ok(()).boxed();
+                };
+
+                // should wait for all files being tokenized
+                let tokens_futs: Vec<_> = ws.files().values().map(|f| Ok(f.ensure_tokens())).collect();
+                let all_tokens_fut = stream::iter(tokens_futs.into_iter()).and_then(|fut| fut)
+                                                                          .collect()
+                                                                          .map_err(|e| {
+                    e.as_ref().map(|_| ())
+                });
+
+                outputs_fut.join(all_tokens_fut).and_then(move |(outputs, all_tokens)| {
+                    cancel_token.keep_going()?;
+
+                    let slots = definition::field_slots(&tokens.0, sep_idx, &name, &outputs);
+                    let mut spans = Vec::new();
+                    for tokens in &all_tokens {
+                        spans.extend(definition::field_uses(&tokens.0, &name, &slots, &outputs));
+                    }
+
+                    let ws = spare_workspace.read();
+                    send_spans(server, id, &spans, &ws.source());
+                    Ok(())
+                }).boxed()
+            },
+
+            Some(definition::Class::PossiblyRequire) | None => {
+                send_spans(server, id, &[], &ws.source());
+                future::ok(()).boxed()
+            },
+        }
+    });
+
+    workspace.read().pool().spawn(fut).forget();
+}
+
+fn highlight(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
+             file: WorkspaceFile, cancel_token: CancelToken, position: &protocol::Position) {
+    use std::collections::HashSet;
+    use futures::future;
+    use kailua_env::{Span, Source};
+    use kailua_syntax::ast::NameRef;
+    use ops::definition;
+    use protocol::*;
+
+    let tokens_fut = file.ensure_tokens().map_err(|e| e.as_ref().map(|_| ()));
+    let chunk_fut = file.ensure_chunk().map_err(|e| e.as_ref().map(|_| ()));
+    let pos_fut = file.translate_position(position);
+
+    let spare_workspace = workspace.clone();
+    let fut = tokens_fut.join(chunk_fut).join(pos_fut).and_then(move |((tokens, chunk), pos)| {
+        if let Err(e) = cancel_token.keep_going() {
+            return future::err(e).boxed();
+        }
+
+        let class = definition::classify(&tokens.0, &chunk.0, pos);
+        debug!("highlight: {:?} {:#?}", class, pos);
+
+        fn send_spans(server: Server, id: protocol::Id, spans: &[Span],
+                      writes: &HashSet<Span>, source: &Source) {
+            debug!("highlight spans: {:#?}", spans);
+            let highlights: Vec<_> = spans.iter().filter_map(|&span| {
+                diags::translate_span(span, source).map(|(_path, range)| {
+                    let kind = if writes.contains(&span) {
+                        DocumentHighlightKind::Write
+                    } else {
+                        DocumentHighlightKind::Read
+                    };
+                    DocumentHighlight { range: range, kind: kind }
+                })
+            }).collect();
+            let _ = server.send_ok(id, highlights);
+        }
+
+        // highlights are limited to the current file
+        let writes = definition::write_spans(&chunk.0);
+
+        let workspace = spare_workspace.clone();
+        let ws = workspace.read();
+        match class {
+            Some(definition::Class::Var(NameRef::Local(scoped_id))) => {
+                let spans = definition::local_var_uses(&tokens.0, &chunk.0, &scoped_id);
+                send_spans(server, id, &spans, &writes, &ws.source());
+                future::ok(()).boxed()
+            },
+
+            Some(definition::Class::Var(NameRef::Global(name))) => {
+                let spans = definition::global_var_uses(&tokens.0, &chunk.0, &name);
+                send_spans(server, id, &spans, &writes, &ws.source());
+                future::ok(()).boxed()
+            },
+
+            Some(definition::Class::Field(sep_idx, name)) => {
+                let outputs = ws.last_valid_check_outputs();
+                let spans = if !outputs.is_empty() {
+                    let slots = definition::field_slots(&tokens.0, sep_idx, &name, &outputs);
+                    Some(definition::field_uses(&tokens.0, &name, &slots, &outputs))
+                } else {
+                    None
+                };
+
+                if let Some(spans) = spans {
+                    send_spans(server, id, &spans, &writes, &ws.source());
+                    future::ok(()).boxed()
+                } else if let Ok(outputs_fut) = ws.ensure_combined_check_outputs() {
+                    outputs_fut.and_then(move |(outputs, _diags)| {
+                        cancel_token.keep_going()?;
+
+                        let slots = definition::field_slots(&tokens.0, sep_idx, &name, &outputs);
+                        let spans = definition::field_uses(&tokens.0, &name, &slots, &outputs);
+                        let ws = spare_workspace.read();
+                        send_spans(server, id, &spans, &writes, &ws.source());
+                        Ok(())
+                    }).boxed()
+                } else {
+                    future::ok(()).boxed()
+                }
+            },
+
+            Some(definition::Class::PossiblyRequire) | None => {
+                send_spans(server, id, &[], &writes, &ws.source());
+                future::ok(()).boxed()
+            },
+        }
+    });
+
+    workspace.read().pool().spawn(fut).forget();
+}
+
+fn send_symbols(server: Server, id: protocol::Id, symbols: &[ops::symbols::Symbol],
+                source: &kailua_env::Source) {
+    use std::path::Path;
+    use url::Url;
+    use protocol::*;
+
+    debug!("symbols: {:?}", symbols.iter().map(|sym| &sym.name).collect::<Vec<_>>());
+    let infos: Vec<_> = symbols.iter().filter_map(|sym| {
+        diags::translate_span(sym.span, source).and_then(|(path, range)| {
+            Url::from_file_path(Path::new(&path)).ok().map(|url| {
+                SymbolInformation {
+                    name: sym.name.clone(),
+                    kind: sym.kind,
+                    location: Location { uri: url.to_string(), range: range },
+                    containerName: sym.container.clone(),
+                }
+            })
+        })
+    }).collect();
+    let _ = server.send_ok(id, infos);
+}
+
+fn document_symbols(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
+                    file: WorkspaceFile, cancel_token: CancelToken) {
+    let spare_workspace = workspace.clone();
+    let fut = file.ensure_chunk().map_err(|e| e.as_ref().map(|_| ())).and_then(move |chunk| {
+        cancel_token.keep_going()?;
+
+        let symbols = ops::symbols::document_symbols(&chunk.0);
+        let ws = spare_workspace.read();
+        send_symbols(server, id, &symbols, &ws.source());
+        Ok(())
+    });
+
+    workspace.read().pool().spawn(fut).forget();
+}
+
+fn workspace_symbols(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
+                     cancel_token: CancelToken, query: String) {
+    use futures::{stream, Stream};
+
+    let spare_cancel_token = cancel_token.clone();
+
+    // should wait for all chunks being parsed
+    let chunk_futs: Vec<_> =
+        workspace.read().files().values().map(|f| Ok(f.ensure_chunk())).collect();
+    let chunk_stream = stream::iter(chunk_futs.into_iter()).and_then(|fut| fut);
+    let symbols_fut = chunk_stream.filter_map(move |chunk| {
+        if spare_cancel_token.is_canceled() {
+            None
+        } else {
+            Some(ops::symbols::workspace_symbols(&chunk.0, &query))
+        }
+    }).fold(Vec::new(), |mut a, b| { a.extend_from_slice(&b); Ok(a) });
+
+    let spare_workspace = workspace.clone();
+    let fut = symbols_fut.map_err(|e| e.as_ref().map(|_| ())).and_then(move |symbols| {
+        cancel_token.keep_going()?;
+        let ws = spare_workspace.read();
+        send_symbols(server, id, &symbols, &ws.source());
+        Ok(())
+    });
+
+    workspace.read().pool().spawn(fut).forget();
+}
+
+fn format(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
+          file: WorkspaceFile, cancel_token: CancelToken,
+          options: &protocol::FormattingOptions, range: Option<&protocol::Range>) {
+    use futures::future;
+    use kailua_env::{Span, SourceSlice};
+    use kailua_syntax::format::format_chunk;
+    use protocol::*;
+
+    let tokens_fut = file.ensure_tokens().map_err(|e| e.as_ref().map(|_| ()));
+    let chunk_fut = file.ensure_chunk().map_err(|e| e.as_ref().map(|_| ()));
+    let range_fut = if let Some(range) = range {
+        let begin_fut = file.translate_position(&range.start);
+        let end_fut = file.translate_position(&range.end);
+        begin_fut.join(end_fut).map(|(begin, end)| Some(Span::new(begin, end))).boxed()
+    } else {
+        future::ok(None).boxed()
+    };
+
+    let options = options.clone();
+    let spare_workspace = workspace.clone();
+    let fut = tokens_fut.join(chunk_fut).join(range_fut).and_then(move |((tokens, chunk), range)| {
+        cancel_token.keep_going()?;
+
+        let ws = spare_workspace.read();
+
+        // formatting a broken code is not very useful and may even be harmful
+        let has_errors = tokens.1.diagnostics().chain(chunk.1.diagnostics()).any(|(_, diag)| {
+            diag.severity == Some(DiagnosticSeverity::Error)
+        });
+        if has_errors {
+            let _ = server.send_notify(
+                Method::ShowMessage,
+                ShowMessageParams {
+                    type_: MessageType::Warning,
+                    message: ws.localize(&message::CannotFormat {}).to_string(),
+                },
+            );
+            let _ = server.send_ok(id, Vec::<TextEdit>::new());
+            return Ok(());
+        }
+
+        let source = ws.source();
+        let options = ops::formatting::format_options(&options, range);
+        let formatted = format_chunk(&source, &tokens.0, &chunk.0, &options);
+        let original = tokens.0.last().and_then(|tok| source.get_file(tok.tok.span.unit()));
+
+        let edits = match (original, formatted) {
+            (Some(original), Some(formatted)) => {
+                let original = match original.data() {
+                    SourceSlice::U8(s) => String::from_utf8_lossy(s).into_owned(),
+                    SourceSlice::U16(s) => String::from_utf16_lossy(s),
+                };
+                let formatted = String::from_utf8_lossy(&formatted);
+                ops::formatting::text_edits(&original, &formatted)
+            }
+            _ => Vec::new(),
+        };
+        let _ = server.send_ok(id, edits);
+        Ok(())
+    });
+
+    workspace.read().pool().spawn(fut).forget();
+}
+
+// a command that applies a serialized `WorkspaceEdit` in the first argument.
+// the client cannot apply edits from code actions by itself, so it asks the server to do so.
+const APPLY_EDIT_COMMAND: &'static str = "kailua.applyEdit";
+
+fn code_actions(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
+                file: WorkspaceFile, cancel_token: CancelToken, uri: String,
+                range: protocol::Range, diagnostics: Vec<protocol::Diagnostic>) {
+    use std::collections::BTreeMap;
+    use futures::future;
+    use kailua_check::env::Output;
+    use protocol::*;
+
+    let tokens_fut = file.ensure_tokens().map_err(|e| e.as_ref().map(|_| ()));
+    let chunk_fut = file.ensure_chunk().map_err(|e| e.as_ref().map(|_| ()));
+
+    let spare_workspace = workspace.clone();
+    let fut = tokens_fut.join(chunk_fut).and_then(move |(tokens, chunk)| {
+        if let Err(e) = cancel_token.keep_going() {
+            return future::err(e).boxed();
+        }
+
+        let send_actions = move |outputs: &[Arc<Output>], workspace: &Workspace| {
+            let source = workspace.source();
+            let actions = ops::actions::actions(&tokens.0, &chunk.0, outputs, &source,
+                                                &range, &diagnostics, |s| workspace.localize(s));
+            let file = tokens.0.last().and_then(|tok| source.get_file(tok.tok.span.unit()));
+
+            let commands: Vec<_> = file.map_or(Vec::new(), |file| {
+                actions.into_iter().filter_map(|action| {
+                    let mut changes = BTreeMap::new();
+                    changes.insert(uri.clone(), action.text_edits(file));
+                    let edit = WorkspaceEdit { changes: changes, documentChanges: Vec::new() };
+                    serde_json::to_string(&edit).ok().map(|edit| {
+                        Command {
+                            title: action.title,
+                            command: APPLY_EDIT_COMMAND.to_string(),
+                            arguments: vec![edit],
+                        }
+                    })
+                }).collect()
+            });
+            let _ = server.send_ok(id, commands);
+        };
+
+        // most actions depend on types, but some of them can be done without them
+        let workspace = spare_workspace.clone();
+        let outputs = workspace.read().last_valid_check_outputs();
+        if !outputs.is_empty() {
+            send_actions(&outputs, &workspace.read());
+            future::ok(()).boxed()
+        } else if let Ok(outputs_fut) = workspace.read().ensure_combined_check_outputs() {
+            outputs_fut.map_err(|e| e.as_ref().map(|_| ())).and_then(move |(outputs, _)| {
+                cancel_token.keep_going()?;
+                send_actions(&outputs, &spare_workspace.read());
+                Ok(())
+            }).boxed()
+        } else {
+            send_actions(&[], &workspace.read());
+            future::ok(()).boxed()
+        }
+    });
+
+    workspace.read().pool().spawn(fut).forget();
+}
+
+fn execute_command(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
+                   params: protocol::ExecuteCommandParams) {
+    use protocol::*;
+
+    if params.command != APPLY_EDIT_COMMAND {
+        let _ = server.send_err(Some(id), error_codes::INVALID_PARAMS, "unknown command", ());
+        return;
+    }
+
+    let edit = params.arguments.get(0).and_then(|arg| arg.as_str()).and_then(|arg| {
+        serde_json::from_str::<WorkspaceEdit>(arg).ok()
+    });
+    let edit = if let Some(edit) = edit {
+        edit
+    } else {
+        let _ = server.send_err(Some(id), error_codes::INVALID_PARAMS, "invalid edit", ());
+        return;
+    };
+
+    match server.send_req(Method::ApplyEdit, ApplyWorkspaceEditParams { edit: edit }) {
+        Ok(fut) => {
+            let fut = fut.then(|res| {
+                if let Err(e) = res {
+                    warn!("failed to apply an edit: {:?}", e);
+                }
+                Ok::<(), ()>(())
+            });
+            workspace.read().pool().spawn(fut).forget();
+            let _ = server.send_ok(id, ());
+        }
+        Err(e) => {
+            let _ = server.send_err(Some(id), error_codes::INTERNAL_ERROR, e.to_string(), ());
+        }
+    }
+}
+
+fn inlay_hints(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
+               file: WorkspaceFile, cancel_token: CancelToken, range: protocol::Range) {
+    use futures::future;
+
+    let tokens_fut = file.ensure_tokens().map_err(|e| e.as_ref().map(|_| ()));
+    let chunk_fut = file.ensure_chunk().map_err(|e| e.as_ref().map(|_| ()));
+
+    let spare_workspace = workspace.clone();
+    let fut = tokens_fut.join(chunk_fut).and_then(move |(tokens, chunk)| {
+        if let Err(e) = cancel_token.keep_going() {
+            return future::err(e).boxed();
+        }
+
+        let workspace = spare_workspace.clone();
+        let outputs = workspace.read().last_valid_check_outputs();
+        if !outputs.is_empty() {
+            let ws = workspace.read();
+            let hints = ops::inlay::hints(&tokens.0, &chunk.0, &outputs, &ws.source(), &range,
+                                          |s| ws.localize(s));
+            let _ = server.send_ok(id, hints);
+            future::ok(()).boxed()
+        } else if let Ok(outputs_fut) = workspace.read().ensure_combined_check_outputs() {
+            outputs_fut.map_err(|e| e.as_ref().map(|_| ())).and_then(move |(outputs, _)| {
+                cancel_token.keep_going()?;
+
+                let ws = spare_workspace.read();
+                let hints = ops::inlay::hints(&tokens.0, &chunk.0, &outputs, &ws.source(),
+                                              &range, |s| ws.localize(s));
+                let _ = server.send_ok(id, hints);
+                Ok(())
+            }).boxed()
+        } else {
+            let _ = server.send_ok(id, Vec::<protocol::InlayHint>::new());
+            future::ok(()).boxed()
+        }
+    });
+
+    workspace.read().pool().spawn(fut).forget();
+}
+
+fn semantic_tokens(server: Server, workspace: Arc<RwLock<Workspace>>, id: protocol::Id,
+                   file: WorkspaceFile, cancel_token: CancelToken,
+                   range: Option<protocol::Range>) {
+    let tokens_fut = file.ensure_tokens().map_err(|e| e.as_ref().map(|_| ()));
+    let chunk_fut = file.ensure_chunk().map_err(|e| e.as_ref().map(|_| ()));
+
+    let spare_workspace = workspace.clone();
+    let fut = tokens_fut.join(chunk_fut).and_then(move |(tokens, chunk)| {
+        cancel_token.keep_going()?;
+
+        let ws = spare_workspace.read();
+        let data = ops::semantic::tokens(&tokens.0, &chunk.0, &ws.source(), range.as_ref());
+        let _ = server.send_ok(id, protocol::SemanticTokens { resultId: None, data: data });
+        Ok(())
+    });
+
+    workspace.read().pool().spawn(fut).forget();
+}
+
 /// The connection target of the language server.
 ///
 /// In any case the stderr remains unused, so it is reserved for debugging outputs.
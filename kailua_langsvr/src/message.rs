@@ -2,33 +2,33 @@ use std::error::Error;
 
 // general notifications
 
-define_msg! { pub CannotReadConfig:
+define_msg! { [K0003] pub CannotReadConfig:
     "ko" => "프로젝트에서 `kailua.json`이나 `.vscode/kailua.json`을 읽을 수 없습니다. \
              이번 세션에서 타입 체크가 비활성화됩니다.",
     _    => "Cannot read `kailua.json` or `.vscode/kailua.json` in the project; \
              type checking is disabled for this session.",
 }
 
-define_msg! { pub NoStartPath:
+define_msg! { [K0004] pub NoStartPath:
     "ko" => "`kailua.json`에 시작 경로가 지정되어 있지 않습니다. \
              이번 세션에서 타입 체크가 비활성화됩니다.",
     _    => "There is no start path specified in `kailua.json`; \
              type checking is disabled for this session.",
 }
 
-define_msg! { pub CannotRename:
+define_msg! { [K0005] pub CannotRename:
     "ko" => "이 이름은 고칠 수 없습니다.",
     _    => "You cannot rename this name.",
 }
 
 // reports generated by language server
 
-define_msg! { pub RestartRequired:
+define_msg! { [K0006] pub RestartRequired:
     "ko" => "`kailua.json`에 문제가 있습니다. 고친 뒤 세션을 재시작해 주십시오",
     _    => "`kailua.json` has an issue; please fix it and restart the session",
 }
 
-define_msg! { pub CannotOpenStartPath<'a> { error: &'a Error }:
+define_msg! { [K0007] pub CannotOpenStartPath<'a> { error: &'a Error }:
     "ko" => "시작 경로를 열 수 없습니다. (이유: {error})",
     _    => "Couldn't open a start path. (Cause: {error})",
 }
@@ -38,3 +38,35 @@ define_msg! { pub OmittedSelfLabel:
     _    => "<omitted>",
 }
 
+
+define_msg! { [K0009] pub CannotFormat:
+    "ko" => "문법 오류가 있는 파일은 정리할 수 없습니다.",
+    _    => "You cannot format a file with syntax errors.",
+}
+
+// code action titles, which are not reports and have no message codes
+
+define_msg! { pub OpenLua51Library:
+    "ko" => "`--# open lua51`을 추가합니다",
+    _    => "Add `--# open lua51`",
+}
+
+define_msg! { pub MakeLocal<'a> { names: &'a str }:
+    "ko" => "{names}을(를) 지역 변수로 만듭니다",
+    _    => "Make {names} local",
+}
+
+define_msg! { pub AddTypeAnnotation<'a> { ty: &'a str }:
+    "ko" => "`--: {ty}` 타입 주석을 추가합니다",
+    _    => "Add a type annotation `--: {ty}`",
+}
+
+define_msg! { pub AddNilCheck<'a> { name: &'a str }:
+    "ko" => "`{name}`이(가) `nil`이 아닌지 검사합니다",
+    _    => "Check if `{name}` is not `nil`",
+}
+
+define_msg! { pub AddFuncSig:
+    "ko" => "`--v` 함수 서명을 추가합니다",
+    _    => "Add a `--v` function signature",
+}
@@ -0,0 +1,508 @@
+// code actions: quick fixes for some diagnostics and missing annotations.
+// each action is a list of edits on the current file, which gets sent back to the client
+// as a command (the client is expected to execute it, then the server applies the edits).
+//
+// all actions are computed from the tokens, the parsed chunk and the checker outputs,
+// and none of them requires an additional checking.
+
+use std::fmt::Write;
+use std::sync::Arc;
+use std::collections::HashSet;
+
+use kailua_env::{Pos, Span, Spanned, Source, SourceFile, SourceSlice};
+use kailua_diag::{Localize, Localized};
+use kailua_syntax::Name;
+use kailua_syntax::lex::{Tok, Punct, NestedToken};
+use kailua_syntax::ast::{NameRef, Var, Chunk, Stmt, St, MM};
+use kailua_types::ty::{Display, TypeContext, Ty, Slot, Functions, Nil};
+use kailua_check::env::Output;
+
+use diags;
+use message as m;
+use protocol::{Position, Range, Diagnostic, TextEdit};
+use super::visit::{Visitor, walk_stmt};
+
+#[derive(Clone, Debug)]
+pub struct Action {
+    pub title: String,
+    // an empty span means the insertion
+    pub edits: Vec<(Span, String)>,
+}
+
+impl Action {
+    pub fn text_edits(&self, file: &SourceFile) -> Vec<TextEdit> {
+        self.edits.iter().filter_map(|&(span, ref text)| {
+            diags::translate_span_without_path(span, file).map(|mut range| {
+                // `translate_span_without_path` never returns an empty range
+                if span.begin() == span.end() {
+                    range.end = range.start.clone();
+                }
+                TextEdit { range: range, newText: text.clone() }
+            })
+        }).collect()
+    }
+}
+
+// global names that would be defined by `--# open lua51`
+const LUA51_GLOBALS: &'static [&'static str] = &[
+    "_G", "_VERSION", "assert", "collectgarbage", "coroutine", "debug", "dofile", "error",
+    "getfenv", "getmetatable", "io", "ipairs", "load", "loadfile", "loadstring", "math",
+    "module", "next", "os", "package", "pairs", "pcall", "print", "rawequal", "rawget",
+    "rawset", "require", "select", "setfenv", "setmetatable", "string", "table", "tonumber",
+    "tostring", "type", "unpack", "xpcall",
+];
+
+// diagnostics that can be possibly caused by a nilable operand
+const NIL_SENSITIVE_CODES: &'static [&'static str] = &[
+    "K0419", "K0420", "K0421", "K0422", "K0423", // operators
+    "K0424", "K0426", // function calls
+    "K0441", "K0449", // indexing
+    "K0452", // assignment
+];
+
+fn position_key(pos: &Position) -> (u64, u64) {
+    (pos.line, pos.character)
+}
+
+fn overlaps(a: &Range, b: &Range) -> bool {
+    position_key(&a.start) <= position_key(&b.end) &&
+    position_key(&b.start) <= position_key(&a.end)
+}
+
+fn slice_to_string(slice: SourceSlice) -> String {
+    match slice {
+        SourceSlice::U8(s) => String::from_utf8_lossy(s).into_owned(),
+        SourceSlice::U16(s) => String::from_utf16_lossy(s),
+    }
+}
+
+fn name_to_string(name: &Name) -> String {
+    String::from_utf8_lossy(name).into_owned()
+}
+
+// types that cannot be written back (e.g. `<error>`) are displayed with angle brackets.
+// type arguments also use angle brackets, but they always follow a name.
+fn is_valid_type_text(s: &str) -> bool {
+    let mut prev = ' ';
+    for c in s.chars() {
+        if c == '<' && !(prev.is_alphanumeric() || prev == '_') {
+            return false;
+        }
+        prev = c;
+    }
+    !s.is_empty()
+}
+
+// implicit literal types are widened like the checker does for new variables
+fn type_to_text<F>(ty: &Ty, types: &TypeContext, localize: &mut F) -> Option<String>
+    where F: for<'b> FnMut(&'b Localize) -> Localized<'b, Localize>
+{
+    let ty = ty.clone().coerce();
+    let text = format!("{}", localize(&ty.display(types)));
+    if is_valid_type_text(&text) { Some(text) } else { None }
+}
+
+struct Context<'a> {
+    tokens: &'a [NestedToken],
+    chunk: &'a Chunk,
+    outputs: &'a [Arc<Output>],
+    source: &'a Source,
+    file: &'a SourceFile,
+    range: &'a Range,
+}
+
+impl<'a> Context<'a> {
+    fn span_to_range(&self, span: Span) -> Option<Range> {
+        diags::translate_span_without_path(span, self.file)
+    }
+
+    fn is_in_range(&self, span: Span) -> bool {
+        self.span_to_range(span).map_or(false, |range| overlaps(&range, self.range))
+    }
+
+    fn line_of(&self, pos: Pos) -> Option<(usize, Span)> {
+        self.file.line_from_pos(pos)
+    }
+
+    // returns the beginning of the line and its leading whitespaces
+    fn line_indent(&self, pos: Pos) -> Option<(Pos, String)> {
+        let (_, span) = self.line_of(pos)?;
+        let line = slice_to_string(self.source.slice_from_span(span)?);
+        let indent = line.chars().take_while(|&c| c == ' ' || c == '\t').collect();
+        Some((span.begin(), indent))
+    }
+
+    fn text(&self, span: Span) -> Option<String> {
+        self.source.slice_from_span(span).map(slice_to_string)
+    }
+
+    // true if there is nothing but whitespaces after `pos` in the same line
+    fn is_end_of_line(&self, pos: Pos) -> bool {
+        let next = self.tokens.iter().find(|tok| tok.tok.span.begin() >= pos);
+        match next {
+            Some(tok) => match tok.tok.base {
+                Tok::EOF => true,
+                _ => self.line_of(tok.tok.span.begin()).map(|(l, _)| l) !=
+                     self.line_of(pos).map(|(l, _)| l),
+            },
+            None => true,
+        }
+    }
+
+    // the smallest exactly matching slot with a given predicate among the outputs
+    fn find_slot_type<F>(&self, span: Span, mut f: F) -> Option<String>
+        where F: FnMut(&Output, &Slot) -> Option<String>
+    {
+        for output in self.outputs {
+            let slots = output.spanned_slots().adjacencies(span);
+            for slot in slots.filter(|slot| slot.span == span) {
+                if let Some(s) = f(output, &slot.base) {
+                    return Some(s);
+                }
+            }
+        }
+        None
+    }
+}
+
+// collects all statements in the pre-order, including those in function bodies
+struct StmtCollector<'a> {
+    stmts: Vec<&'a Spanned<Stmt>>,
+}
+
+impl<'a> Visitor<'a> for StmtCollector<'a> {
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        self.stmts.push(stmt);
+        walk_stmt(self, stmt);
+    }
+}
+
+// `--# open lua51` for undefined standard library names
+fn open_lua51<F>(ctx: &Context, diags: &[Diagnostic], localize: &mut F, actions: &mut Vec<Action>)
+    where F: for<'b> FnMut(&'b Localize) -> Localized<'b, Localize>
+{
+    let needed = diags.iter().filter(|diag| {
+        diag.code.as_ref().and_then(|code| code.as_str()) == Some("K0401")
+    }).any(|diag| {
+        ctx.tokens.iter().any(|tok| {
+            match tok.tok.base {
+                Tok::Name(ref name) => {
+                    LUA51_GLOBALS.contains(&&*name_to_string(name)) &&
+                    ctx.span_to_range(tok.tok.span).map_or(false, |r| overlaps(&r, &diag.range))
+                }
+                _ => false,
+            }
+        })
+    });
+
+    if needed {
+        let begin = Span::from(ctx.file.span().begin());
+        actions.push(Action {
+            title: localize(&m::OpenLua51Library {}).to_string(),
+            edits: vec![(begin, "--# open lua51\n".to_owned())],
+        });
+    }
+}
+
+// `local` for the first assignment to global names
+fn make_local<F>(ctx: &Context, stmts: &[&Spanned<Stmt>],
+                 localize: &mut F, actions: &mut Vec<Action>)
+    where F: for<'b> FnMut(&'b Localize) -> Localized<'b, Localize>
+{
+    for stmt in stmts {
+        if let St::Assign(ref vars, Some(_)) = *stmt.base {
+            if !ctx.is_in_range(stmt.span) {
+                continue;
+            }
+
+            let names: Option<Vec<String>> = vars.base.iter().map(|var| {
+                match var.base.base {
+                    Var::Name(ref nameref) => match nameref.base {
+                        NameRef::Global(ref name)
+                                if ctx.chunk.global_scope.get(name) == Some(&nameref.span) => {
+                            Some(format!("`{}`", name_to_string(name)))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }).collect();
+
+            if let Some(names) = names {
+                let names = names.join(", ");
+                actions.push(Action {
+                    title: localize(&m::MakeLocal { names: &names }).to_string(),
+                    edits: vec![(Span::from(stmt.span.begin()), "local ".to_owned())],
+                });
+            }
+        }
+    }
+}
+
+// `--: type` for a local variable without any annotation
+fn annotate_local<F>(ctx: &Context, stmts: &[&Spanned<Stmt>],
+                     localize: &mut F, actions: &mut Vec<Action>)
+    where F: for<'b> FnMut(&'b Localize) -> Localized<'b, Localize>
+{
+    for stmt in stmts {
+        if let St::Local(ref names, ref exps, _) = *stmt.base {
+            if names.len() != 1 || exps.is_empty() || !ctx.is_in_range(stmt.span) {
+                continue;
+            }
+            let name = &names[0];
+            if name.kind.is_some() || name.modf != MM::None {
+                continue;
+            }
+            if !ctx.is_end_of_line(stmt.span.end()) {
+                continue;
+            }
+
+            let ty = ctx.find_slot_type(exps[0].span, |output, slot| {
+                type_to_text(&slot.unlift(), output.types(), &mut *localize)
+            });
+
+            if let Some(ty) = ty {
+                actions.push(Action {
+                    title: localize(&m::AddTypeAnnotation { ty: &ty }).to_string(),
+                    edits: vec![(Span::from(stmt.span.end()), format!(" --: {}", ty))],
+                });
+            }
+        }
+    }
+}
+
+// `if x ~= nil then ... end` around a statement which uses a nilable variable
+fn check_nil<F>(ctx: &Context, stmts: &[&Spanned<Stmt>], diags: &[Diagnostic],
+                localize: &mut F, actions: &mut Vec<Action>)
+    where F: for<'b> FnMut(&'b Localize) -> Localized<'b, Localize>
+{
+    let mut seen = HashSet::new();
+
+    for diag in diags {
+        let code = diag.code.as_ref().and_then(|code| code.as_str());
+        if !code.map_or(false, |code| NIL_SENSITIVE_CODES.contains(&code)) {
+            continue;
+        }
+
+        for (i, tok) in ctx.tokens.iter().enumerate() {
+            let name = match tok.tok.base {
+                Tok::Name(ref name) => name_to_string(name),
+                _ => continue,
+            };
+            if !ctx.span_to_range(tok.tok.span).map_or(false, |r| overlaps(&r, &diag.range)) {
+                continue;
+            }
+
+            // skip field names
+            if i > 0 {
+                match ctx.tokens[i-1].tok.base {
+                    Tok::Punct(Punct::Dot) | Tok::Punct(Punct::Colon) => continue,
+                    _ => {}
+                }
+            }
+
+            let nilable = ctx.find_slot_type(tok.tok.span, |_, slot| {
+                if slot.nil() == Nil::Noisy { Some(String::new()) } else { None }
+            }).is_some();
+            if !nilable {
+                continue;
+            }
+
+            // only wrap a statement that doesn't introduce names or affect the control flow
+            let stmt = stmts.iter().filter(|stmt| {
+                stmt.span.begin() <= tok.tok.span.begin() && tok.tok.span.end() <= stmt.span.end()
+            }).min_by_key(|stmt| stmt.span.len());
+            let stmt = match stmt {
+                Some(stmt) => match *stmt.base {
+                    St::Void(_) | St::Assign(..) => stmt,
+                    _ => continue,
+                },
+                None => continue,
+            };
+
+            if !seen.insert((stmt.span, name.clone())) {
+                continue;
+            }
+
+            let (indent, text) = match (ctx.line_indent(stmt.span.begin()), ctx.text(stmt.span)) {
+                (Some((_, indent)), Some(text)) => (indent, text),
+                (_, _) => continue,
+            };
+
+            let mut replaced = String::new();
+            let _ = write!(replaced, "if {} ~= nil then\n{}    ", name, indent);
+            replaced.push_str(&text.replace("\n", "\n    "));
+            let _ = write!(replaced, "\n{}end", indent);
+
+            actions.push(Action {
+                title: localize(&m::AddNilCheck { name: &name }).to_string(),
+                edits: vec![(stmt.span, replaced)],
+            });
+        }
+    }
+}
+
+// `--v function(...)` from the inferred type of the function declaration
+fn add_func_sig<F>(ctx: &Context, stmts: &[&Spanned<Stmt>],
+                   localize: &mut F, actions: &mut Vec<Action>)
+    where F: for<'b> FnMut(&'b Localize) -> Localized<'b, Localize>
+{
+    for stmt in stmts {
+        if let St::FuncDecl(ref name, ref sig, ..) = *stmt.base {
+            // only the function header counts
+            if !ctx.is_in_range(Span::new(stmt.span.begin(), sig.args.span.end())) {
+                continue;
+            }
+
+            let annotated = !sig.attrs.is_empty() || !sig.tparams.is_empty() ||
+                            sig.returns.is_some() ||
+                            sig.args.head.iter().any(|arg| arg.kind.is_some()) ||
+                            sig.args.tail.as_ref().map_or(false, |va| va.kind.is_some());
+            if annotated {
+                continue;
+            }
+
+            let funcsig = ctx.outputs.iter().filter_map(|output| {
+                let def = output.all().map(|(_, def)| def).find(|def| def.span == name.span)?;
+                let types = output.types() as &TypeContext;
+                let ty = types.resolve_exact_type(&def.slot.slot()?.unlift())?;
+                let func = match ty.get_functions() {
+                    Some(&Functions::Simple(ref func)) => func,
+                    _ => return None,
+                };
+
+                // types that cannot be inferred (e.g. missing argument types) are left as `any`
+                let mut type_text = |ty: &Ty| {
+                    type_to_text(ty, types, &mut *localize).unwrap_or_else(|| "any".to_owned())
+                };
+
+                let mut text = "function(".to_owned();
+                let mut first = true;
+                for (arg, ty) in sig.args.head.iter().zip(func.args.head.iter()) {
+                    if first { first = false; } else { text.push_str(", "); }
+                    let argname = name_to_string(arg.base.base.name(&ctx.chunk.map));
+                    let _ = write!(text, "{}: {}", argname, type_text(ty));
+                }
+                if sig.args.tail.is_some() {
+                    if !first { text.push_str(", "); }
+                    let ty = func.args.tail.as_ref().map_or("any".to_owned(), |ty| type_text(ty));
+                    let _ = write!(text, "...: {}", ty);
+                }
+                text.push(')');
+
+                match func.returns {
+                    Some(ref returns) => match (returns.head.len(), returns.tail.as_ref()) {
+                        (0, None) => {}
+                        (1, None) => {
+                            let _ = write!(text, " --> {}", type_text(&returns.head[0]));
+                        }
+                        (_, tail) => {
+                            let mut types: Vec<_> = returns.head.iter().map(&mut type_text).collect();
+                            if let Some(tail) = tail {
+                                types.push(format!("{}...", type_text(tail)));
+                            }
+                            let _ = write!(text, " --> ({})", types.join(", "));
+                        }
+                    },
+                    None => text.push_str(" --> !"),
+                }
+                Some(text)
+            }).next();
+
+            let (linebegin, indent) = match (funcsig.as_ref(), ctx.line_indent(stmt.span.begin())) {
+                (Some(_), Some(line)) => line,
+                (_, _) => continue,
+            };
+
+            actions.push(Action {
+                title: localize(&m::AddFuncSig {}).to_string(),
+                edits: vec![(Span::from(linebegin),
+                             format!("{}--v {}\n", indent, funcsig.unwrap()))],
+            });
+        }
+    }
+}
+
+// `diags` should be diagnostics for the current file
+pub fn actions<F>(tokens: &[NestedToken], chunk: &Chunk, outputs: &[Arc<Output>],
+                  source: &Source, range: &Range, diags: &[Diagnostic],
+                  mut localize: F) -> Vec<Action>
+    where F: for<'b> FnMut(&'b Localize) -> Localized<'b, Localize>
+{
+    let file = match tokens.last().and_then(|tok| source.get_file(tok.tok.span.unit())) {
+        Some(file) => file,
+        None => return Vec::new(),
+    };
+
+    let ctx = Context {
+        tokens: tokens, chunk: chunk, outputs: outputs, source: source, file: file, range: range,
+    };
+
+    let mut collector = StmtCollector { stmts: Vec::new() };
+    collector.visit_block(&chunk.block);
+    let stmts = collector.stmts;
+
+    let diags: Vec<_> = diags.iter().filter(|diag| overlaps(&diag.range, range)).cloned().collect();
+
+    let mut actions = Vec::new();
+    open_lua51(&ctx, &diags, &mut localize, &mut actions);
+    check_nil(&ctx, &stmts, &diags, &mut localize, &mut actions);
+    make_local(&ctx, &stmts, &mut localize, &mut actions);
+    annotate_local(&ctx, &stmts, &mut localize, &mut actions);
+    add_func_sig(&ctx, &stmts, &mut localize, &mut actions);
+    actions
+}
+
+#[test]
+fn test_actions() {
+    use serde_json::Value;
+    use kailua_diag::{Locale, CollectedReport};
+    use ops::definition::{parse_for_test, check_for_test};
+
+    let code = "local a = 42\n\
+                function f(n)\n\
+                \x20   b = n\n\
+                \x20   return b\n\
+                end\n\
+                --# assume z: integer?\n\
+                a = z + 1\n\
+                print(a)\n";
+    let (source, span, tokens, chunk) = parse_for_test(code.as_bytes());
+    let outputs = check_for_test(&chunk, CollectedReport::new(Locale::dummy()));
+
+    let range = |l1, c1, l2, c2| Range {
+        start: Position { line: l1, character: c1 },
+        end: Position { line: l2, character: c2 },
+    };
+    let diag = |code: &str, range| Diagnostic {
+        range: range, severity: None, code: Some(Value::String(code.to_owned())),
+        source: None, message: String::new(),
+    };
+    let diags = vec![diag("K0420", range(6, 4, 6, 9)), diag("K0401", range(7, 0, 7, 5))];
+
+    let actions = actions(&tokens, &chunk, &outputs, &source, &range(0, 0, 8, 0), &diags,
+                          |msg| Localized::new(msg, Locale::from("en")));
+    let results: Vec<_> = actions.into_iter().map(|action| {
+        // apply edits in the reverse order
+        let mut code = code.to_owned();
+        let mut edits = action.edits.clone();
+        edits.sort_by_key(|&(span, _)| span.begin());
+        for (editspan, text) in edits.into_iter().rev() {
+            let begin = editspan.begin().to_usize() - span.begin().to_usize();
+            let end = editspan.end().to_usize() - span.begin().to_usize();
+            code = format!("{}{}{}", &code[..begin], text, &code[end..]);
+        }
+        (action.title, code)
+    }).collect();
+    let expected = vec![
+        ("Add `--# open lua51`", format!("--# open lua51\n{}", code)),
+        ("Check if `z` is not `nil`",
+         code.replace("a = z + 1\n", "if z ~= nil then\n    a = z + 1\nend\n")),
+        ("Make `b` local", code.replace("b = n", "local b = n")),
+        ("Add a type annotation `--: integer`", code.replace("42\n", "42 --: integer\n")),
+        ("Add a `--v` function signature",
+         code.replace("function f", "--v function(n: any) --> any\nfunction f")),
+    ];
+    let expected: Vec<_> = expected.into_iter().map(|(t, c)| (t.to_owned(), c)).collect();
+    assert_eq!(results, expected);
+}
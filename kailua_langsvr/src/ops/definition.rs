@@ -4,26 +4,40 @@
 //
 // rename: determine a variable for the token (shared with go to definition)
 // and go through the current file or all open files for the matching token
+//
+// references and highlights: same to rename, but fields are also resolved
+// by comparing field slots from checker outputs. writes are determined from the AST.
+
+use std::collections::HashSet;
+use std::sync::Arc;
 
-use kailua_env::{Pos, Span, ScopedId};
+use kailua_env::{Pos, Span, Spanned, ScopedId};
+#[cfg(test)] use kailua_env::Source;
 use kailua_syntax::{Str, Name};
 use kailua_syntax::lex::{Tok, Punct, NestedToken};
-use kailua_syntax::ast::{NameRef, TokenAux, LocalNameKind, Chunk};
+use kailua_syntax::ast::{NameRef, TokenAux, LocalNameKind, Chunk, Block, Stmt, St, Var, Sig};
+use kailua_types::ty::{Key, Slot};
+use kailua_check::env::Output;
 
-use super::last_non_comment;
+use super::{get_prefix_expr_slot, last_non_comment};
+use super::visit::{Visitor, walk_stmt, walk_func};
 
 #[derive(Clone, Debug)]
 pub enum Class {
     // the cursor is at (or by) a name token resolving to given variable.
-    Var(usize, NameRef),
+    Var(NameRef),
 
     // the cursor is at (or by) a string token preceded by a `(` or an identifier,
     // so that it is possibly a function call to `require` and further checking is required.
-    PossiblyRequire(usize /*end of prefix expr*/, usize /*str*/, Str),
+    PossiblyRequire,
+
+    // the cursor is at (or by) a name token preceded by a `.` or `:`,
+    // so that it is possibly a field and checker outputs are required to resolve it.
+    Field(usize /*`.` or `:`*/, Name),
 }
 
 fn is_name_or_str(tok: &NestedToken) -> bool {
-    match tok.tok.base { Tok::Str(_) | Tok::Name(_) => true, _ => false }
+    match tok.tok.base { Tok::Str(..) | Tok::Name(_) => true, _ => false }
 }
 
 pub fn classify(tokens: &[NestedToken], chunk: &Chunk, pos: Pos) -> Option<Class> {
@@ -54,15 +68,11 @@ pub fn classify(tokens: &[NestedToken], chunk: &Chunk, pos: Pos) -> Option<Class
 
     if let Some(idx) = name_idx {
         match tokens[idx].tok.base {
-            Tok::Str(ref s) => {
-                if let Some((pidx, ptok)) = last_non_comment(&tokens[..idx]) {
+            Tok::Str(..) => {
+                if let Some((_, ptok)) = last_non_comment(&tokens[..idx]) {
                     match ptok.tok.base {
-                        // NAME STR, the prefix expr ends after NAME
-                        Tok::Name(_) =>
-                            Some(Class::PossiblyRequire(pidx + 1, idx, s.clone())),
-                        // `(` STR, the prefix expr ends before `(`
-                        Tok::Punct(Punct::LParen) =>
-                            Some(Class::PossiblyRequire(pidx, idx, s.clone())),
+                        // NAME STR or `(` STR
+                        Tok::Name(_) | Tok::Punct(Punct::LParen) => Some(Class::PossiblyRequire),
                         // otherwise it's just a string
                         _ => None,
                     }
@@ -74,7 +84,17 @@ pub fn classify(tokens: &[NestedToken], chunk: &Chunk, pos: Pos) -> Option<Class
             Tok::Name(ref name) => {
                 // auxiliary info has a resolution
                 match chunk.token_aux[idx] {
-                    TokenAux::None => None,
+                    TokenAux::None => {
+                        match last_non_comment(&tokens[..idx]) {
+                            Some((sidx, &NestedToken { tok: Spanned {
+                                base: Tok::Punct(Punct::Dot), ..
+                            }, .. })) |
+                            Some((sidx, &NestedToken { tok: Spanned {
+                                base: Tok::Punct(Punct::Colon), ..
+                            }, .. })) => Some(Class::Field(sidx, name.clone())),
+                            _ => None,
+                        }
+                    },
                     TokenAux::LocalVarName(ref id) => {
                         // we need to resolve the actual name if the name has been assumed!
                         let nameref = match chunk.local_names.get(id).map(|def| &def.kind) {
@@ -84,10 +104,10 @@ pub fn classify(tokens: &[NestedToken], chunk: &Chunk, pos: Pos) -> Option<Class
                                 NameRef::Global(name.clone()),
                             _ => NameRef::Local(id.clone()),
                         };
-                        Some(Class::Var(idx, nameref))
+                        Some(Class::Var(nameref))
                     },
                     TokenAux::GlobalVarName => {
-                        Some(Class::Var(idx, NameRef::Global(name.clone())))
+                        Some(Class::Var(NameRef::Global(name.clone())))
                     },
                 }
             },
@@ -156,3 +176,216 @@ pub fn global_var_uses(tokens: &[NestedToken], chunk: &Chunk, name: &Name) -> Ve
     spans
 }
 
+
+fn is_same_slot(lhs: &Slot, rhs: &Slot) -> bool {
+    &*lhs.unlift() as *const _ == &*rhs.unlift() as *const _
+}
+
+// resolves the field following the separator at `sep_idx`, if the prefix expression is a table
+fn field_slot(tokens: &[NestedToken], sep_idx: usize, name: &Name, output: &Output) -> Option<Slot> {
+    let end = if let Some((_, tok)) = last_non_comment(&tokens[..sep_idx]) {
+        tok.tok.span.end()
+    } else {
+        return None;
+    };
+
+    let slot = get_prefix_expr_slot(end, output)?;
+    let fields = output.get_available_fields(&slot.unlift())?;
+    let key = Key::from(Str::from(&name[..]));
+    fields.get(&key).cloned()
+}
+
+// there can be multiple outputs, so the field can be resolved to multiple slots
+pub fn field_slots(tokens: &[NestedToken], sep_idx: usize, name: &Name,
+                   outputs: &[Arc<Output>]) -> Vec<Slot> {
+    outputs.iter().filter_map(|output| field_slot(tokens, sep_idx, name, output)).collect()
+}
+
+// this should be called for _each_ tokens in the workspace
+pub fn field_uses(tokens: &[NestedToken], name: &Name, slots: &[Slot],
+                  outputs: &[Arc<Output>]) -> Vec<Span> {
+    let mut spans = Vec::new();
+    if slots.is_empty() {
+        return spans;
+    }
+
+    for (idx, tok) in tokens.iter().enumerate() {
+        match tok.tok.base {
+            Tok::Name(ref tok_name) if tok_name[..] == name[..] => {}
+            _ => continue,
+        }
+
+        let sep_idx = match last_non_comment(&tokens[..idx]) {
+            Some((sidx, sep)) => match sep.tok.base {
+                Tok::Punct(Punct::Dot) | Tok::Punct(Punct::Colon) => sidx,
+                _ => continue,
+            },
+            None => continue,
+        };
+
+        let found = outputs.iter().any(|output| {
+            field_slot(tokens, sep_idx, name, output).map_or(false, |slot| {
+                slots.iter().any(|target| is_same_slot(&slot, target))
+            })
+        });
+        if found {
+            spans.push(tok.tok.span);
+        }
+    }
+
+    spans
+}
+
+// collects spans of names which are being declared or assigned, including fields.
+// fields in table constructors are not included, as they are not resolved to slots.
+pub fn write_spans(chunk: &Chunk) -> HashSet<Span> {
+    let mut collector = WriteCollector { spans: HashSet::new() };
+    collector.visit_block(&chunk.block);
+    collector.spans
+}
+
+struct WriteCollector {
+    spans: HashSet<Span>,
+}
+
+impl<'a> Visitor<'a> for WriteCollector {
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        match *stmt.base {
+            St::Assign(ref vars, _) => {
+                for var in &vars.base {
+                    match var.base.base {
+                        Var::Name(ref name) => {
+                            self.spans.insert(name.span);
+                        }
+                        Var::IndexName(_, ref name) => {
+                            self.spans.insert(name.span);
+                        }
+                        Var::Index(..) => {}
+                    }
+                }
+            }
+
+            St::For(ref name, ..) => {
+                self.spans.insert(name.span);
+            }
+
+            St::ForIn(ref names, ..) => {
+                for name in &names.base {
+                    self.spans.insert(name.span);
+                }
+            }
+
+            St::FuncDecl(ref name, ..) => {
+                self.spans.insert(name.span);
+            }
+
+            St::MethodDecl(ref names, ..) => {
+                // only the last name is assigned
+                let (ref name, ref fields) = names.base;
+                self.spans.insert(fields.last().map_or(name.span, |field| field.span));
+            }
+
+            St::Local(ref names, ..) => {
+                for name in &names.base {
+                    self.spans.insert(name.base.span);
+                }
+            }
+
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_func(&mut self, sig: &'a Sig, body: &'a Spanned<Block>) {
+        for arg in &sig.args.head {
+            self.spans.insert(arg.base.span);
+        }
+        walk_func(self, sig, body);
+    }
+}
+
+#[cfg(test)]
+pub fn parse_for_test(code: &[u8]) -> (Source, Span, Vec<NestedToken>, Chunk) {
+    use kailua_env::SourceFile;
+    use kailua_diag::NoReport;
+    use kailua_syntax::lex::{Lexer, Nest};
+    use kailua_syntax::Parser;
+
+    let mut source = Source::new();
+    let span = source.add(SourceFile::from_u8("dummy".to_string(), code.to_owned()));
+
+    let mut tokens = Vec::new();
+    {
+        let mut iter = source.iter_from_span(span).unwrap();
+        let mut lexer = Lexer::new(&mut iter, &NoReport);
+        let mut nest = Nest::new(&mut lexer);
+        while let Some(tok) = nest.next() {
+            tokens.push(tok);
+        }
+    }
+    let chunk = Parser::new(&mut tokens.clone().into_iter(), &NoReport).into_chunk().unwrap();
+    (source, span, tokens, chunk)
+}
+
+#[cfg(test)]
+pub fn check_for_test<R: ::kailua_diag::Report>(chunk: &Chunk, report: R) -> Vec<Arc<Output>> {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use kailua_check::env::Context;
+    use kailua_check::options::Options;
+
+    struct NoOptions;
+    impl Options for NoOptions {}
+
+    let mut context = Context::new(report);
+    ::kailua_check::check_from_chunk(&mut context, chunk.clone(),
+                                     Rc::new(RefCell::new(NoOptions))).unwrap();
+    vec![Arc::new(context.into_output())]
+}
+
+#[cfg(test)]
+fn offsets_for_test(spans: &[Span], base: Span) -> Vec<usize> {
+    let mut offsets: Vec<_> = spans.iter().map(|span| {
+        span.begin().to_usize() - base.begin().to_usize()
+    }).collect();
+    offsets.sort();
+    offsets
+}
+
+#[test]
+fn test_write_spans() {
+    let (_source, span, tokens, chunk) = parse_for_test(b"local a, b = 1\n\
+                                                          a = b + a\n\
+                                                          function f(x) a.y = x; return a.y end\n");
+
+    let writes: Vec<_> = write_spans(&chunk).into_iter().collect();
+    assert_eq!(offsets_for_test(&writes, span),
+               vec![6 /*a*/, 9 /*b*/, 15 /*a*/, 34 /*f*/, 36 /*x*/, 41 /*y*/]);
+
+    // `a.y` is classified as a field
+    let pos = tokens[tokens.len() - 3].tok.span.begin();
+    match classify(&tokens, &chunk, pos) {
+        Some(Class::Field(_, ref name)) => assert_eq!(&name[..], b"y"),
+        class => panic!("unexpected classification {:?}", class),
+    }
+}
+
+#[test]
+fn test_field_uses() {
+    use kailua_diag::NoReport;
+
+    let (_source, span, tokens, chunk) = parse_for_test(b"local a = {x = 1, y = 2}\n\
+                                                          a.y = 2\n\
+                                                          local b = {y = 3}\n\
+                                                          b.y = 3\n\
+                                                          local c = a.y + b.y\n");
+    let outputs = check_for_test(&chunk, NoReport);
+
+    // the first `y` in `a.y`
+    let name = Name::from(&b"y"[..]);
+    let sep_idx = tokens.iter().position(|tok| tok.tok.base == Tok::Punct(Punct::Dot)).unwrap();
+    let slots = field_slots(&tokens, sep_idx, &name, &outputs);
+    assert_eq!(slots.len(), 1);
+    assert_eq!(offsets_for_test(&field_uses(&tokens, &name, &slots, &outputs), span),
+               vec![27 /*a.y*/, 71 /*a.y*/]);
+}
@@ -0,0 +1,80 @@
+// formatting: the actual formatting is done by kailua_syntax::format,
+// this module converts options and the formatted text into a minimal set of edits.
+
+use serde_json::Value;
+
+use kailua_env::Span;
+use kailua_syntax::format::FormatOptions;
+
+use protocol::{Position, Range, TextEdit, FormattingOptions};
+
+pub fn format_options(options: &FormattingOptions, range: Option<Span>) -> FormatOptions {
+    let tab_size = options.get("tabSize").and_then(Value::as_u64).unwrap_or(4);
+    let insert_spaces = options.get("insertSpaces").and_then(Value::as_bool).unwrap_or(true);
+    let indent = if insert_spaces {
+        " ".repeat(tab_size as usize)
+    } else {
+        "\t".to_owned()
+    };
+    FormatOptions { indent: indent, range: range }
+}
+
+// lines with their terminators
+fn lines(s: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let bytes = s.as_bytes();
+    for (i, &c) in bytes.iter().enumerate() {
+        if c == b'\n' || (c == b'\r' && bytes.get(i + 1) != Some(&b'\n')) {
+            lines.push(&s[start..i+1]);
+            start = i + 1;
+        }
+    }
+    if start < s.len() {
+        lines.push(&s[start..]);
+    }
+    lines
+}
+
+// returns at most one edit replacing differing lines
+pub fn text_edits(original: &str, formatted: &str) -> Vec<TextEdit> {
+    let orig = lines(original);
+    let new = lines(formatted);
+
+    let prefix = orig.iter().zip(&new).take_while(|&(a, b)| a == b).count();
+    if prefix == orig.len() && prefix == new.len() {
+        return Vec::new();
+    }
+    let maxsuffix = orig.len().min(new.len()) - prefix;
+    let suffix = orig.iter().rev().zip(new.iter().rev())
+                     .take(maxsuffix).take_while(|&(a, b)| a == b).count();
+
+    let orig_end = orig.len() - suffix;
+    let end = match orig.last() {
+        // the last line without a newline cannot be addressed with the next line
+        Some(last) if orig_end == orig.len() && !last.ends_with('\n') && !last.ends_with('\r') => {
+            let ch = last.encode_utf16().count();
+            Position { line: (orig.len() - 1) as u64, character: ch as u64 }
+        }
+        _ => Position { line: orig_end as u64, character: 0 },
+    };
+
+    vec![TextEdit {
+        range: Range { start: Position { line: prefix as u64, character: 0 }, end: end },
+        newText: new[prefix..new.len()-suffix].concat(),
+    }]
+}
+
+#[test]
+fn test_text_edits() {
+    fn edits(original: &str, formatted: &str) -> Vec<(u64, u64, u64, u64, String)> {
+        text_edits(original, formatted).into_iter().map(|edit| {
+            let Range { start, end } = edit.range;
+            (start.line, start.character, end.line, end.character, edit.newText)
+        }).collect()
+    }
+
+    assert_eq!(edits("a\nb\nc\n", "a\nb\nc\n"), vec![]);
+    assert_eq!(edits("a\n b\nc\n", "a\nb\nc\n"), vec![(1, 0, 2, 0, "b\n".to_owned())]);
+    assert_eq!(edits("a\n\n\nb", "a\n\nb\n"), vec![(2, 0, 3, 1, "b\n".to_owned())]);
+}
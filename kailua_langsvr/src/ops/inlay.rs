@@ -0,0 +1,267 @@
+// inlay hints: show inferred types where the annotation is missing.
+// the sites are collected from the parsed chunk and the types are read from checker outputs:
+//
+// - unannotated local names (`local`, `for ... in`) and function parameters get `: T`
+//   from the slots of their definitions;
+// - functions without return types get `--> T` after the parameter list,
+//   from the slot of the function declaration or the function expression.
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::collections::HashMap;
+
+use kailua_env::{Span, Spanned, Source, SourceFile};
+use kailua_diag::{Localize, Localized};
+use kailua_syntax::lex::{Tok, Punct, NestedToken};
+use kailua_syntax::ast::{Chunk, Stmt, St, Exp, Ex, Sig};
+use kailua_types::ty::{Display, TypeContext, Ty, T, Dyn, Functions};
+use kailua_check::env::Output;
+
+use diags;
+use protocol::{Position, Range, InlayHint, InlayHintKind};
+use super::visit::{Visitor, walk_stmt, walk_exp};
+
+#[derive(Clone, Debug)]
+enum Site {
+    // the name span of unannotated local variable or parameter
+    Name(Span),
+    // the argument span of a function without return types,
+    // and the name span (for declarations) or the span of the function expression
+    Returns(Span, Span),
+}
+
+#[derive(Default)]
+struct Collector {
+    sites: Vec<Site>,
+}
+
+impl Collector {
+    fn collect_from_args(&mut self, sig: &Sig) {
+        for arg in &sig.args.head {
+            if arg.kind.is_none() {
+                self.sites.push(Site::Name(arg.base.span));
+            }
+        }
+    }
+
+    fn collect_from_sig(&mut self, sig: &Sig, func: Span) {
+        self.collect_from_args(sig);
+        if sig.returns.is_none() {
+            self.sites.push(Site::Returns(sig.args.span, func));
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for Collector {
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        match *stmt.base {
+            St::ForIn(ref names, ..) => {
+                for name in &names.base {
+                    self.sites.push(Site::Name(name.span));
+                }
+            }
+
+            St::FuncDecl(ref name, ref sig, ..) => self.collect_from_sig(sig, name.span),
+
+            // the method type is not recorded anywhere, so only parameters are hinted
+            St::MethodDecl(_, _, ref sig, ..) => self.collect_from_args(sig),
+
+            St::Local(ref names, ref exps, _) => {
+                for (i, name) in names.base.iter().enumerate() {
+                    // function expressions have their own hints
+                    let is_func = exps.base.get(i).map_or(false, |exp| {
+                        if let Ex::Func(..) = *exp.base { true } else { false }
+                    });
+                    if name.kind.is_none() && !is_func {
+                        self.sites.push(Site::Name(name.base.span));
+                    }
+                }
+            }
+
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_exp(&mut self, exp: &'a Spanned<Exp>) {
+        if let Ex::Func(ref sig, ..) = *exp.base {
+            self.collect_from_sig(sig, exp.span);
+        }
+        walk_exp(self, exp);
+    }
+}
+
+fn is_error(ty: &Ty) -> bool {
+    match **ty {
+        T::Dynamic(Dyn::Oops) => true,
+        _ => false,
+    }
+}
+
+// the hint goes to the end of given span
+fn hint_position(span: Span, file: &SourceFile) -> Option<Position> {
+    diags::translate_span_without_path(span, file).map(|range| range.end)
+}
+
+fn in_range(pos: &Position, range: &Range) -> bool {
+    let pos = (pos.line, pos.character);
+    (range.start.line, range.start.character) <= pos && pos <= (range.end.line, range.end.character)
+}
+
+pub fn hints<F>(tokens: &[NestedToken], chunk: &Chunk, outputs: &[Arc<Output>],
+                source: &Source, range: &Range, mut localize: F) -> Vec<InlayHint>
+    where F: for<'a> FnMut(&'a Localize) -> Localized<'a, Localize>
+{
+    let file = match tokens.last().and_then(|tok| source.get_file(tok.tok.span.unit())) {
+        Some(file) => file,
+        None => return Vec::new(),
+    };
+
+    let mut collector = Collector::default();
+    collector.visit_block(&chunk.block);
+
+    // names in the current file are mapped to their definitions; the first output wins
+    let unit = file.span().unit();
+    let mut defs = HashMap::new();
+    for output in outputs {
+        for (_, def) in output.all() {
+            if def.span.unit() != unit || defs.contains_key(&def.span) {
+                continue;
+            }
+            if let Some(slot) = def.slot.slot() {
+                defs.insert(def.span, (output, slot.unlift().clone()));
+            }
+        }
+    }
+
+    let mut hints = Vec::new();
+    for site in collector.sites {
+        match site {
+            Site::Name(span) => {
+                let (output, ty) = match defs.get(&span) {
+                    Some(&(output, ref ty)) if !is_error(ty) => (output, ty),
+                    _ => continue,
+                };
+                let position = match hint_position(span, file) {
+                    Some(pos) => pos,
+                    None => continue,
+                };
+                if !in_range(&position, range) {
+                    continue;
+                }
+
+                let types = output.types() as &TypeContext;
+                hints.push(InlayHint {
+                    position: position,
+                    label: format!(": {:0.1}", localize(&ty.display(types))),
+                    kind: Some(InlayHintKind::Type),
+                    paddingLeft: false,
+                    paddingRight: false,
+                });
+            }
+
+            Site::Returns(args, func) => {
+                // the hint goes right after the closing parenthesis,
+                // which is searched from the first token after the arguments
+                let first = tokens.binary_search_by(|tok| {
+                    if tok.tok.span.begin() < args.end() { Ordering::Less } else { Ordering::Greater }
+                }).unwrap_or_else(|i| i);
+                let rparen = tokens[first..].iter().find(|tok| {
+                    tok.tok.base == Tok::Punct(Punct::RParen)
+                });
+                let position = match rparen.and_then(|tok| hint_position(tok.tok.span, file)) {
+                    Some(pos) => pos,
+                    None => continue,
+                };
+                if !in_range(&position, range) {
+                    continue;
+                }
+
+                // declarations are recorded as names, function expressions as slots
+                let functy = defs.get(&func).cloned().or_else(|| {
+                    outputs.iter().filter_map(|output| {
+                        output.spanned_slots().adjacencies(func).find(|slot| slot.span == func)
+                              .map(|slot| (output, slot.base.unlift().clone()))
+                    }).next()
+                });
+                let (output, functy) = match functy {
+                    Some(functy) => functy,
+                    None => continue,
+                };
+
+                let types = output.types() as &TypeContext;
+                let functy = match types.resolve_exact_type(&functy) {
+                    Some(ty) => ty,
+                    None => continue,
+                };
+                let returns = match functy.get_functions() {
+                    Some(&Functions::Simple(ref func)) => func.returns.clone(),
+                    _ => continue,
+                };
+
+                let label = match returns {
+                    Some(ref returns) => match (returns.head.len(), returns.tail.is_some()) {
+                        (0, false) => continue,
+                        (1, false) => {
+                            if is_error(&returns.head[0]) {
+                                continue;
+                            }
+                            format!("--> {:0.1}", localize(&returns.head[0].display(types)))
+                        }
+                        (_, _) => format!("--> {:0.1}", localize(&returns.display(types))),
+                    },
+                    None => format!("--> !"),
+                };
+                hints.push(InlayHint {
+                    position: position,
+                    label: label,
+                    kind: Some(InlayHintKind::Type),
+                    paddingLeft: true,
+                    paddingRight: false,
+                });
+            }
+        }
+    }
+    hints
+}
+
+#[test]
+fn test_hints() {
+    use kailua_diag::{Locale, CollectedReport};
+    use ops::definition::{parse_for_test, check_for_test};
+
+    let (source, _span, tokens, chunk) = parse_for_test(b"--# open lua51\n\
+                                                         local a, b = 42, 'x'\n\
+                                                         local function f(x) --: string\n\
+                                                         \x20   return #x, x\n\
+                                                         end\n\
+                                                         local g = function(y)\n\
+                                                         \x20   return y + 1\n\
+                                                         end\n\
+                                                         local c = g(3)\n\
+                                                         for i, v in ipairs({true}) do end\n");
+    let outputs = check_for_test(&chunk, CollectedReport::new(Locale::dummy()));
+
+    let hints_in_lines = |start, end| {
+        let range = Range {
+            start: Position { line: start, character: 0 },
+            end: Position { line: end, character: 0 },
+        };
+        let hints = hints(&tokens, &chunk, &outputs, &source, &range,
+                          |msg| Localized::new(msg, Locale::from("en")));
+        hints.into_iter().map(|hint| {
+            (hint.position.line, hint.position.character, hint.label)
+        }).collect::<Vec<_>>()
+    };
+
+    assert_eq!(hints_in_lines(0, 11), vec![
+        (1, 7, ": integer".to_string()),
+        (1, 10, ": string".to_string()),
+        (2, 19, "--> (integer, string)".to_string()),
+        (5, 21, "--> integer".to_string()),
+        (8, 7, ": integer".to_string()),
+        (9, 5, ": integer".to_string()),
+        (9, 8, ": WHATEVER".to_string()),
+    ]);
+    assert_eq!(hints_in_lines(8, 9), vec![(8, 7, ": integer".to_string())]);
+}
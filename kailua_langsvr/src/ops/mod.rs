@@ -6,7 +6,13 @@ use kailua_check::env::Output;
 pub mod completion;
 pub mod hover;
 pub mod signature;
-pub mod definition; // also contains rename
+pub mod definition; // also contains rename, references and highlights
+pub mod symbols;
+pub mod formatting;
+pub mod actions;
+pub mod inlay;
+pub mod semantic;
+mod visit;
 
 // common routines
 
@@ -0,0 +1,290 @@
+// semantic tokens: classify names by what they refer to.
+// this only depends on the parsed chunk (and not on checker outputs), so it is always available:
+//
+// - names with `TokenAux::LocalVarName` are local variables or parameters,
+//   optionally marked as constant (`--: const`) or upvalue (used from the nested function);
+// - names with `TokenAux::GlobalVarName` are global variables;
+// - other names in the type position of meta comments (`--:`, `--v`, `-->` and
+//   `--# type`/`--# assume`) are types, or classes when declared by `--# assume class`.
+
+use std::collections::HashSet;
+
+use kailua_env::{Span, Spanned, Source, ScopedId};
+use kailua_syntax::lex::{Tok, Punct, Keyword, NestedToken};
+use kailua_syntax::ast::{Chunk, Block, Stmt, St, Sig, NameRef};
+use kailua_syntax::ast::{TokenAux, LocalNameKind, MM, M};
+
+use diags;
+use protocol::{Range, SemanticTokensLegend};
+use super::visit::{Visitor, walk_stmt, walk_func};
+
+// the order should match with the constants below
+const TOKEN_TYPES: &'static [&'static str] = &["variable", "parameter", "type", "class"];
+const TOKEN_MODIFIERS: &'static [&'static str] = &["declaration", "readonly", "global", "upvalue"];
+
+const TYPE_VARIABLE: u32 = 0;
+const TYPE_PARAMETER: u32 = 1;
+const TYPE_TYPE: u32 = 2;
+const TYPE_CLASS: u32 = 3;
+
+const MOD_DECLARATION: u32 = 1 << 0;
+const MOD_READONLY: u32 = 1 << 1;
+const MOD_GLOBAL: u32 = 1 << 2;
+const MOD_UPVALUE: u32 = 1 << 3;
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        tokenTypes: TOKEN_TYPES.iter().map(|s| s.to_string()).collect(),
+        tokenModifiers: TOKEN_MODIFIERS.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    params: HashSet<ScopedId>,
+    consts: HashSet<ScopedId>,
+    // spans of function bodies (including parameters) for finding upvalues
+    funcs: Vec<Span>,
+    // spans of class names being declared
+    class_decls: HashSet<Span>,
+}
+
+impl<'a> Visitor<'a> for Collector {
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        match *stmt.base {
+            St::Local(ref names, ..) => {
+                for name in &names.base {
+                    if name.modf == MM::Const {
+                        self.consts.insert(name.base.base.clone());
+                    }
+                }
+            }
+
+            St::KailuaAssume(ref name, M::Const, _, _) => {
+                if let NameRef::Local(ref id) = name.base.after {
+                    self.consts.insert(id.clone());
+                }
+            }
+
+            St::KailuaAssumeClass(_, ref name, _, _) => {
+                self.class_decls.insert(name.span);
+            }
+
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_func(&mut self, sig: &'a Sig, body: &'a Spanned<Block>) {
+        for arg in &sig.args.head {
+            self.params.insert(arg.base.base.clone());
+        }
+        self.funcs.push(Span::new(sig.args.span.begin(), body.span.end()));
+        walk_func(self, sig, body);
+    }
+}
+
+impl Collector {
+    // the innermost function body containing given span, if any
+    fn enclosing_func(&self, span: Span) -> Option<Span> {
+        self.funcs.iter().filter(|func| func.contains(span.begin()))
+                  .max_by_key(|func| func.begin()).cloned()
+    }
+}
+
+fn contains_span(outer: Span, inner: Span) -> bool {
+    outer.begin() <= inner.begin() && inner.end() <= outer.end()
+}
+
+// returns (token index, token type, token modifiers) for each classified token
+fn classify(tokens: &[NestedToken], chunk: &Chunk) -> Vec<(usize, u32, u32)> {
+    let mut collector = Collector::default();
+    collector.visit_block(&chunk.block);
+
+    // class names declared in this chunk; used to distinguish classes from other types
+    let mut class_names = HashSet::new();
+    for tok in tokens {
+        if let Tok::Name(ref name) = tok.tok.base {
+            if collector.class_decls.iter().any(|&decl| contains_span(decl, tok.tok.span)) {
+                class_names.insert(name.clone());
+            }
+        }
+    }
+
+    let is_punct = |i: Option<usize>, p: Punct| {
+        i.and_then(|i| tokens.get(i)).map_or(false, |tok| tok.tok.base == Tok::Punct(p))
+    };
+
+    let mut classified = Vec::new();
+    let mut in_meta = false;
+    let mut has_types = false; // false for meta comments without types (`--# open` etc.)
+    for (i, tok) in tokens.iter().enumerate() {
+        let name = match tok.tok.base {
+            Tok::Punct(Punct::DashDashColon) |
+            Tok::Punct(Punct::DashDashV) |
+            Tok::Punct(Punct::DashDashGt) => {
+                in_meta = true;
+                has_types = true;
+                continue;
+            }
+            Tok::Punct(Punct::DashDashHash) => {
+                in_meta = true;
+                has_types = match tokens.get(i + 1).map(|tok| &tok.tok.base) {
+                    Some(&Tok::Keyword(Keyword::Type)) | Some(&Tok::Keyword(Keyword::Assume)) => true,
+                    _ => false,
+                };
+                continue;
+            }
+            Tok::Punct(Punct::Newline) => {
+                in_meta = false;
+                continue;
+            }
+            Tok::Name(ref name) => name,
+            _ => continue,
+        };
+
+        let span = tok.tok.span;
+        let is_class = collector.class_decls.iter().any(|&decl| contains_span(decl, span));
+        match chunk.token_aux.get(i) {
+            Some(&TokenAux::LocalVarName(ref id)) => {
+                let local = match chunk.local_names.get(id) {
+                    Some(local) => local,
+                    None => continue,
+                };
+
+                let toktype = if is_class {
+                    TYPE_CLASS
+                } else if collector.params.contains(id) || local.kind == LocalNameKind::ImplicitSelf {
+                    TYPE_PARAMETER
+                } else {
+                    TYPE_VARIABLE
+                };
+
+                let mut modifiers = 0;
+                if local.def_span == span {
+                    modifiers |= MOD_DECLARATION;
+                }
+                if collector.consts.contains(id) {
+                    modifiers |= MOD_READONLY;
+                }
+                if collector.enclosing_func(span) != collector.enclosing_func(local.def_span) {
+                    modifiers |= MOD_UPVALUE;
+                }
+                classified.push((i, toktype, modifiers));
+            }
+
+            Some(&TokenAux::GlobalVarName) => {
+                let toktype = if is_class { TYPE_CLASS } else { TYPE_VARIABLE };
+                classified.push((i, toktype, MOD_GLOBAL));
+            }
+
+            _ => {
+                if !in_meta || !has_types {
+                    continue;
+                }
+
+                // skip field names (`.name`, `{name: ...}`), argument names (`(name: ...)`),
+                // attributes (`[NO_CHECK]`) and class systems (`class(system)`)
+                let prev = if i > 0 { Some(i - 1) } else { None };
+                if is_punct(prev, Punct::Dot) || is_punct(Some(i + 1), Punct::Colon) ||
+                   is_punct(prev, Punct::LBracket) {
+                    continue;
+                }
+                if is_punct(prev, Punct::LParen) && i >= 2 &&
+                   tokens[i - 2].tok.base == Tok::Keyword(Keyword::Class) {
+                    continue;
+                }
+
+                let toktype = if class_names.contains(name) { TYPE_CLASS } else { TYPE_TYPE };
+                classified.push((i, toktype, 0));
+            }
+        }
+    }
+
+    classified
+}
+
+// encodes tokens in the relative format described by the protocol
+pub fn tokens(tokens: &[NestedToken], chunk: &Chunk, source: &Source,
+              range: Option<&Range>) -> Vec<u32> {
+    let file = match tokens.last().and_then(|tok| source.get_file(tok.tok.span.unit())) {
+        Some(file) => file,
+        None => return Vec::new(),
+    };
+
+    let mut data = Vec::new();
+    let mut last = (0, 0);
+    for (i, toktype, modifiers) in classify(tokens, chunk) {
+        let tokrange = match diags::translate_span_without_path(tokens[i].tok.span, file) {
+            Some(tokrange) => tokrange,
+            None => continue,
+        };
+        if tokrange.start.line != tokrange.end.line {
+            continue;
+        }
+        if let Some(range) = range {
+            let start = (tokrange.start.line, tokrange.start.character);
+            let end = (tokrange.end.line, tokrange.end.character);
+            if end < (range.start.line, range.start.character) ||
+               (range.end.line, range.end.character) < start {
+                continue;
+            }
+        }
+
+        let (line, character) = (tokrange.start.line as u32, tokrange.start.character as u32);
+        let delta_line = line - last.0;
+        let delta_start = if delta_line == 0 { character - last.1 } else { character };
+        data.push(delta_line);
+        data.push(delta_start);
+        data.push(tokrange.end.character as u32 - character);
+        data.push(toktype);
+        data.push(modifiers);
+        last = (line, character);
+    }
+    data
+}
+
+#[test]
+fn test_tokens() {
+    use ops::definition::parse_for_test;
+
+    let (source, _span, tokens, chunk) = parse_for_test(b"--# assume global class Hello\n\
+                                                         local x = 1 --: const\n\
+                                                         function f(a) --> Hello\n\
+                                                         \x20   return function() return a + x + y end\n\
+                                                         end\n\
+                                                         --# type Point = {x: integer, y: Hello}\n\
+                                                         --# open lua51\n");
+
+    let data = self::tokens(&tokens, &chunk, &source, None);
+    assert_eq!(data.len() % 5, 0);
+    let decoded = data.chunks(5).scan((0, 0), |last, tok| {
+        let line = last.0 + tok[0];
+        let character = if tok[0] == 0 { last.1 + tok[1] } else { tok[1] };
+        *last = (line, character);
+        Some((line, character, tok[2], TOKEN_TYPES[tok[3] as usize], tok[4]))
+    }).collect::<Vec<_>>();
+
+    assert_eq!(decoded, vec![
+        (0, 24, 5, "class", MOD_GLOBAL),
+        (1, 6, 1, "variable", MOD_DECLARATION | MOD_READONLY),
+        (2, 9, 1, "variable", MOD_GLOBAL),
+        (2, 11, 1, "parameter", MOD_DECLARATION),
+        (2, 18, 5, "class", 0),
+        (3, 29, 1, "parameter", MOD_UPVALUE),
+        (3, 33, 1, "variable", MOD_READONLY | MOD_UPVALUE),
+        (3, 37, 1, "variable", MOD_GLOBAL),
+        (5, 9, 5, "type", 0),
+        (5, 21, 7, "type", 0),
+        (5, 33, 5, "class", 0),
+    ]);
+
+    // only tokens intersecting with the range are returned (relative to the first one)
+    let range = Range {
+        start: ::protocol::Position { line: 2, character: 0 },
+        end: ::protocol::Position { line: 2, character: 12 },
+    };
+    let data = self::tokens(&tokens, &chunk, &source, Some(&range));
+    assert_eq!(data, vec![2, 9, 1, TYPE_VARIABLE, MOD_GLOBAL,
+                          0, 2, 1, TYPE_PARAMETER, MOD_DECLARATION]);
+}
@@ -7,7 +7,7 @@ use std::collections::HashSet;
 use kailua_env::Pos;
 use kailua_diag::{Localize, Localized};
 use kailua_syntax::lex::{Tok, Punct, NestedToken};
-use kailua_types::ty::{TypeContext, Display, Nil, Functions, Function};
+use kailua_types::ty::{TypeContext, Display, Nil, Function};
 use kailua_check::env::Output;
 
 use protocol::*;
@@ -59,8 +59,8 @@ fn enclosing_func_call(tokens: &[NestedToken], pos: Pos) -> Option<(usize, usize
     // a special case for `<func> "str"`, which has no nesting changes
     let ptok = if idx > 1 { tokens.get(idx - 2) } else { None };
     match (ptok.map(|tok| &tok.tok.base), &last_tok.tok.base) {
-        (Some(&Tok::Name(_)), &Tok::Str(_)) |
-        (Some(&Tok::Punct(Punct::RParen)), &Tok::Str(_)) => return Some((idx - 1, 0)),
+        (Some(&Tok::Name(_)), &Tok::Str(..)) |
+        (Some(&Tok::Punct(Punct::RParen)), &Tok::Str(..)) => return Some((idx - 1, 0)),
         (_, _) => {}
     }
 
@@ -168,40 +168,42 @@ pub fn help<F>(tokens: &[NestedToken], loc: &Loc, outputs: &[Arc<Output>],
                 return Some(empty_signature());
             }
 
-            let func = if let Some(&Functions::Simple(ref func)) = ty.get_functions() {
-                func
-            } else {
+            // overloaded functions give multiple signatures in the order of overloads
+            let funcs = ty.get_functions().map_or(Vec::new(), |funcs| funcs.candidates());
+            if funcs.is_empty() {
                 return Some(empty_signature());
-            };
-
-            let (label, params) = format_signature(func, is_method, output, &mut localize);
-            let paramlist: Vec<_> = params.iter().map(|param| param.label.clone()).collect();
-            if !seen.insert((label.clone(), paramlist)) {
-                continue;
             }
 
-            let param_idx_for_sig = if loc.arg_idx < params.len() {
-                Some(loc.arg_idx as u32)
-            } else if func.args.tail.is_some() {
-                // clamp to the number of parameters listed,
-                // mapping the last arguments to the variadic position
-                assert!(!params.is_empty());
-                Some((params.len() - 1) as u32)
-            } else {
-                // otherwise it's an excess parameter and should not be highlighted
-                None
-            };
+            for func in funcs {
+                let (label, params) = format_signature(func, is_method, output, &mut localize);
+                let paramlist: Vec<_> = params.iter().map(|param| param.label.clone()).collect();
+                if !seen.insert((label.clone(), paramlist)) {
+                    continue;
+                }
 
-            if param_idx_for_sig.is_some() {
-                param_idx = param_idx.or(param_idx_for_sig);
-                active_sig = active_sig.or(Some(signatures.len()));
-            }
+                let param_idx_for_sig = if loc.arg_idx < params.len() {
+                    Some(loc.arg_idx as u32)
+                } else if func.args.tail.is_some() {
+                    // clamp to the number of parameters listed,
+                    // mapping the last arguments to the variadic position
+                    assert!(!params.is_empty());
+                    Some((params.len() - 1) as u32)
+                } else {
+                    // otherwise it's an excess parameter and should not be highlighted
+                    None
+                };
+
+                if param_idx_for_sig.is_some() {
+                    param_idx = param_idx.or(param_idx_for_sig);
+                    active_sig = active_sig.or(Some(signatures.len()));
+                }
 
-            signatures.push(SignatureInformation {
-                label: label,
-                documentation: None,
-                parameters: params,
-            });
+                signatures.push(SignatureInformation {
+                    label: label,
+                    documentation: None,
+                    parameters: params,
+                });
+            }
         }
     }
 
@@ -0,0 +1,187 @@
+// document symbols: collect declarations from the parsed chunk, which is available without checking.
+// functions, methods, types and classes are collected from every block except function bodies;
+// local variables are only collected from the top-level block.
+//
+// workspace symbols: same to document symbols but for all chunks, filtered by the query
+
+use kailua_env::{Span, Spanned};
+use kailua_syntax::Name;
+use kailua_syntax::ast::{NameRef, Chunk, Block, Stmt, St, Exp, Sig, MM};
+
+use protocol::SymbolKind;
+use super::visit::{Visitor, walk_block, walk_stmt};
+
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub span: Span,
+    pub container: Option<String>,
+}
+
+fn name_to_string(name: &Name) -> String {
+    String::from_utf8_lossy(name).into_owned()
+}
+
+fn nameref_to_string(nameref: &NameRef, chunk: &Chunk) -> String {
+    match *nameref {
+        NameRef::Local(ref scoped_id) => name_to_string(scoped_id.name(&chunk.map)),
+        NameRef::Global(ref name) => name_to_string(name),
+    }
+}
+
+struct Collector<'a> {
+    chunk: &'a Chunk,
+    // the number of enclosing blocks, 1 for the top-level block
+    depth: usize,
+    symbols: Vec<Symbol>,
+}
+
+impl<'a> Visitor<'a> for Collector<'a> {
+    fn visit_block(&mut self, block: &'a Spanned<Block>) {
+        self.depth += 1;
+        walk_block(self, block);
+        self.depth -= 1;
+    }
+
+    // declarations in function bodies are not collected,
+    // and expressions can contain declarations only in function bodies
+    fn visit_func(&mut self, _sig: &'a Sig, _body: &'a Spanned<Block>) {}
+    fn visit_exp(&mut self, _exp: &'a Spanned<Exp>) {}
+
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        let chunk = self.chunk;
+        let symbols = &mut self.symbols;
+        match *stmt.base {
+            St::FuncDecl(ref name, ..) => {
+                symbols.push(Symbol {
+                    name: nameref_to_string(&name.base, chunk),
+                    kind: SymbolKind::Function,
+                    span: stmt.span,
+                    container: None,
+                });
+            }
+
+            St::MethodDecl(ref names, ref selfparam, ..) => {
+                let (ref base, ref fields) = names.base;
+                if let Some((last, fields)) = fields.split_last() {
+                    // `a.b.c:d` is a method `d` in the container `a.b.c`
+                    let mut container = nameref_to_string(&base.base, chunk);
+                    for field in fields {
+                        container.push('.');
+                        container.push_str(&name_to_string(&field.base));
+                    }
+
+                    symbols.push(Symbol {
+                        name: name_to_string(&last.base),
+                        kind: if selfparam.is_some() {
+                            SymbolKind::Method
+                        } else {
+                            SymbolKind::Function
+                        },
+                        span: stmt.span,
+                        container: Some(container),
+                    });
+                }
+            }
+
+            St::Local(ref names, ..) if self.depth == 1 => {
+                for name in &names.base {
+                    symbols.push(Symbol {
+                        name: name_to_string(name.base.base.name(&chunk.map)),
+                        kind: if name.modf == MM::Const {
+                            SymbolKind::Constant
+                        } else {
+                            SymbolKind::Variable
+                        },
+                        span: name.base.span,
+                        container: None,
+                    });
+                }
+            }
+
+            St::KailuaType(_, ref name, ..) => {
+                symbols.push(Symbol {
+                    name: name_to_string(&name.base),
+                    kind: SymbolKind::Interface,
+                    span: name.span,
+                    container: None,
+                });
+            }
+
+            St::KailuaAssumeClass(_, ref name, ..) => {
+                symbols.push(Symbol {
+                    name: nameref_to_string(&name.base.after, chunk),
+                    kind: SymbolKind::Class,
+                    span: name.span,
+                    container: None,
+                });
+            }
+
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+}
+
+pub fn document_symbols(chunk: &Chunk) -> Vec<Symbol> {
+    let mut collector = Collector { chunk: chunk, depth: 0, symbols: Vec::new() };
+    collector.visit_block(&chunk.block);
+    collector.symbols
+}
+
+// case-insensitive subsequence match, e.g. `fb` or `fOoBa` matches `FooBar`.
+// an empty query matches everything.
+fn matches_query(name: &str, query: &str) -> bool {
+    let mut name = name.chars().flat_map(|c| c.to_lowercase());
+    query.chars().flat_map(|c| c.to_lowercase()).all(|q| name.any(|c| c == q))
+}
+
+// this should be called for _each_ chunk in the workspace
+pub fn workspace_symbols(chunk: &Chunk, query: &str) -> Vec<Symbol> {
+    let mut symbols = document_symbols(chunk);
+    symbols.retain(|sym| matches_query(&sym.name, query));
+    symbols
+}
+
+#[test]
+fn test_matches_query() {
+    assert!(matches_query("FooBar", ""));
+    assert!(matches_query("FooBar", "foobar"));
+    assert!(matches_query("FooBar", "fb"));
+    assert!(matches_query("FooBar", "OBA"));
+    assert!(!matches_query("FooBar", "bf"));
+    assert!(!matches_query("FooBar", "foobarr"));
+}
+
+#[test]
+fn test_document_symbols() {
+    use kailua_env::{Source, SourceFile};
+    use kailua_diag::NoReport;
+    use kailua_syntax::parse_chunk;
+
+    let mut source = Source::new();
+    let code = b"local a, b --: const\n\
+                 function f() local c end\n\
+                 if a then local function g() end end\n\
+                 --# assume global class Foo\n\
+                 function Foo.bar.new() end\n\
+                 function Foo:quux() end\n\
+                 --# type T = integer\n";
+    let span = source.add(SourceFile::from_u8("dummy".to_string(), code[..].to_owned()));
+    let chunk = parse_chunk(&source, span, &NoReport).unwrap();
+
+    let symbols: Vec<_> = document_symbols(&chunk).into_iter().map(|sym| {
+        (sym.name, sym.kind, sym.container)
+    }).collect();
+    assert_eq!(symbols, vec![
+        ("a".to_string(), SymbolKind::Variable, None),
+        ("b".to_string(), SymbolKind::Constant, None),
+        ("f".to_string(), SymbolKind::Function, None),
+        ("g".to_string(), SymbolKind::Function, None),
+        ("Foo".to_string(), SymbolKind::Class, None),
+        ("new".to_string(), SymbolKind::Function, Some("Foo.bar".to_string())),
+        ("quux".to_string(), SymbolKind::Method, Some("Foo".to_string())),
+        ("T".to_string(), SymbolKind::Interface, None),
+    ]);
+}
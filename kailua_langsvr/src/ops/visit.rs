@@ -0,0 +1,168 @@
+// a read-only AST visitor shared by operations collecting informations from the parsed chunk.
+//
+// every `visit_*` method defaults to the corresponding `walk_*` function, which visits
+// children in the source order. an implementation overrides what it is interested in
+// and calls the `walk_*` function to continue (or does not, to skip the children).
+// the walkers match exhaustively, so a new AST node should be handled here once.
+
+use kailua_env::Spanned;
+use kailua_syntax::ast::{Var, Block, Stmt, St, Exp, Ex, Sig, Args, Table};
+
+pub trait Visitor<'a>: Sized {
+    fn visit_block(&mut self, block: &'a Spanned<Block>) {
+        walk_block(self, block);
+    }
+
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_exp(&mut self, exp: &'a Spanned<Exp>) {
+        walk_exp(self, exp);
+    }
+
+    // the signature and body of function declarations, method declarations
+    // and function expressions
+    fn visit_func(&mut self, sig: &'a Sig, body: &'a Spanned<Block>) {
+        walk_func(self, sig, body);
+    }
+}
+
+pub fn walk_block<'a, V: Visitor<'a>>(v: &mut V, block: &'a Spanned<Block>) {
+    for stmt in &block.base {
+        v.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_func<'a, V: Visitor<'a>>(v: &mut V, _sig: &'a Sig, body: &'a Spanned<Block>) {
+    // signatures do not contain any expression
+    v.visit_block(body);
+}
+
+pub fn walk_stmt<'a, V: Visitor<'a>>(v: &mut V, stmt: &'a Spanned<Stmt>) {
+    match *stmt.base {
+        St::Oops | St::Break | St::Goto(_) | St::Label(_) => {}
+
+        St::Void(ref exp) => v.visit_exp(exp),
+
+        St::Assign(ref vars, ref exps) => {
+            for var in &vars.base {
+                walk_var(v, &var.base);
+            }
+            if let Some(ref exps) = *exps {
+                walk_exps(v, &exps.base);
+            }
+        }
+
+        St::Do(ref block) => v.visit_block(block),
+
+        St::While(ref cond, ref block) => {
+            v.visit_exp(cond);
+            v.visit_block(block);
+        }
+
+        St::Repeat(ref block, ref cond) => {
+            v.visit_block(block);
+            v.visit_exp(cond);
+        }
+
+        St::If(ref conds, ref lastblock) => {
+            for cond in conds {
+                let (ref cond, ref block) = cond.base;
+                v.visit_exp(cond);
+                v.visit_block(block);
+            }
+            if let Some(ref block) = *lastblock {
+                v.visit_block(block);
+            }
+        }
+
+        St::For(_, ref start, ref end, ref step, _, ref block) => {
+            v.visit_exp(start);
+            v.visit_exp(end);
+            if let Some(ref step) = *step {
+                v.visit_exp(step);
+            }
+            v.visit_block(block);
+        }
+
+        St::ForIn(_, ref exps, _, ref block) => {
+            walk_exps(v, &exps.base);
+            v.visit_block(block);
+        }
+
+        St::FuncDecl(_, ref sig, _, ref block, _) |
+        St::MethodDecl(_, _, ref sig, _, ref block) => v.visit_func(sig, block),
+
+        St::Local(_, ref exps, _) => walk_exps(v, &exps.base),
+
+        St::Return(ref exps) => walk_exps(v, &exps.base),
+
+        // Kailua statements only contain names and types
+        St::KailuaOpen(_) | St::KailuaType(..) | St::KailuaAssume(..) |
+        St::KailuaAssumeField(..) | St::KailuaAssumeMethod(..) |
+        St::KailuaClassSystem(_) | St::KailuaAssumeClass(..) => {}
+    }
+}
+
+pub fn walk_exp<'a, V: Visitor<'a>>(v: &mut V, exp: &'a Spanned<Exp>) {
+    match *exp.base {
+        Ex::Oops | Ex::Nil | Ex::False | Ex::True |
+        Ex::Num(_) | Ex::Float(_) | Ex::CDataNum(..) | Ex::Str(_) | Ex::Varargs | Ex::Var(_) => {}
+
+        Ex::Func(ref sig, _, ref block) => v.visit_func(sig, block),
+
+        Ex::Table(ref table) => walk_table(v, table),
+
+        Ex::Exp(ref exp) | Ex::Un(_, ref exp) | Ex::IndexName(ref exp, _) => v.visit_exp(exp),
+
+        Ex::FuncCall(ref func, ref args) => {
+            v.visit_exp(func);
+            walk_args(v, &args.base);
+        }
+
+        Ex::MethodCall(ref recv, ref args) => {
+            v.visit_exp(&recv.base.0);
+            walk_args(v, &args.base);
+        }
+
+        Ex::Index(ref lhs, ref rhs) | Ex::Bin(ref lhs, _, ref rhs) => {
+            v.visit_exp(lhs);
+            v.visit_exp(rhs);
+        }
+    }
+}
+
+fn walk_exps<'a, V: Visitor<'a>>(v: &mut V, exps: &'a [Spanned<Exp>]) {
+    for exp in exps {
+        v.visit_exp(exp);
+    }
+}
+
+fn walk_var<'a, V: Visitor<'a>>(v: &mut V, var: &'a Var) {
+    match *var {
+        Var::Name(_) => {}
+        Var::Index(ref exp, ref key) => {
+            v.visit_exp(exp);
+            v.visit_exp(key);
+        }
+        Var::IndexName(ref exp, _) => v.visit_exp(exp),
+    }
+}
+
+fn walk_table<'a, V: Visitor<'a>>(v: &mut V, table: &'a Table) {
+    for &(ref key, ref value) in &table.items {
+        if let Some(ref key) = *key {
+            v.visit_exp(key);
+        }
+        v.visit_exp(value);
+    }
+}
+
+fn walk_args<'a, V: Visitor<'a>>(v: &mut V, args: &'a Args) {
+    match *args {
+        Args::List(ref exps) => walk_exps(v, exps),
+        Args::Str(_) => {}
+        Args::Table(ref table) => walk_table(v, table),
+    }
+}
@@ -1,9 +1,11 @@
 use std::mem;
+use std::cmp;
 use std::fmt;
-use std::io;
+use std::io::{self, Read};
+use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::ffi::OsStr;
-use std::collections::{hash_map, HashMap};
+use std::collections::{hash_map, HashMap, HashSet};
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -17,10 +19,11 @@ use walkdir::WalkDir;
 use kailua_env::{Unit, Pos, Span, Spanned, Source, SourceFile, SourceSlice};
 use kailua_diag::{self, Stop, Report, Locale, Localize, Localized};
 use kailua_syntax::{Lexer, Nest, NestedToken, Parser, Chunk};
+use kailua_syntax::reparse;
 use kailua_check;
 use kailua_check::options::FsSource;
-use kailua_check::env::{Context, Output};
-use kailua_workspace::{self, WorkspaceOptions};
+use kailua_check::env::{Context, Output, CheckerConfig};
+use kailua_workspace::{self, WorkspaceOptions, DirCacheStore, MemoryCacheStore};
 
 use fmtutils::Ellipsis;
 use diags::{self, ReportTree};
@@ -181,6 +184,9 @@ struct WorkspaceFileInner {
     chunk: Option<ReportFuture<Arc<Chunk>>>,
 
     last_chunk: Option<Arc<Chunk>>,
+
+    // the last tokens and chunk parsed without any report, used for the incremental reparsing
+    reparse_base: Option<(Arc<Vec<NestedToken>>, Arc<Chunk>)>,
 }
 
 type Inner = Arc<RwLock<WorkspaceFileInner>>;
@@ -207,6 +213,7 @@ impl fmt::Debug for WorkspaceFile {
          .field("tokens", &inner.tokens.as_ref().map(|_| Ellipsis))
          .field("chunk", &inner.chunk.as_ref().map(|_| Ellipsis))
          .field("last_chunk", &inner.last_chunk.as_ref().map(|_| Ellipsis))
+         .field("reparse_base", &inner.reparse_base.as_ref().map(|_| Ellipsis))
          .finish()
     }
 }
@@ -228,6 +235,7 @@ impl WorkspaceFile {
                 tokens: None,
                 chunk: None,
                 last_chunk: None,
+                reparse_base: None,
             })),
         }
     }
@@ -242,8 +250,8 @@ impl WorkspaceFile {
         inner.tokens = None;
         inner.chunk = None;
 
-        // also signal the workspace to cancel jobs
-        inner.workspace.write().cancel();
+        // also signal the workspace to cancel jobs depending on this file
+        inner.workspace.write().cancel_for_path(&inner.path);
     }
 
     #[allow(dead_code)]
@@ -371,7 +379,7 @@ impl WorkspaceFile {
             let tokens_fut = Self::ensure_tokens_with_inner(spare_inner.clone(), inner);
 
             let fut = tokens_fut.map_err(|e| (*e).clone()).and_then(move |tokens_ret| {
-                let tokens = (*tokens_ret.0).clone();
+                let tokens = tokens_ret.0.clone();
                 let parent_diags = tokens_ret.1.clone();
 
                 let mut inner = spare_inner.write();
@@ -380,20 +388,40 @@ impl WorkspaceFile {
                 let diags = ReportTree::new(inner.message_locale, None);
                 diags.add_parent(parent_diags);
 
+                // if the change is contained in a function body, only that body is parsed
+                let reparsed = match inner.reparse_base {
+                    Some((ref prev_tokens, ref prev_chunk)) =>
+                        reparse::reparse_chunk(prev_tokens, prev_chunk, &tokens),
+                    None => None,
+                };
+
                 // in this future source access is only needed for reporting
-                let chunk = {
+                let chunk = if let Some(chunk) = reparsed {
+                    Ok(chunk)
+                } else {
                     let report = diags.report(|span| {
                         diags::translate_span(span, &inner.source.read())
                     });
-                    parse_to_chunk(tokens, &report)
+                    parse_to_chunk((*tokens).clone(), &report).map(|mut chunk| {
+                        chunk.resolve_ignores(&inner.source.read());
+                        chunk
+                    })
                 };
                 match chunk {
                     Ok(chunk) => {
                         let chunk = Arc::new(chunk);
                         inner.last_chunk = Some(chunk.clone());
+                        inner.reparse_base = if diags.diagnostics().next().is_none() {
+                            Some((tokens, chunk.clone()))
+                        } else {
+                            None
+                        };
                         Ok((chunk, diags))
                     },
-                    Err(_) => Err(From::from(diags)),
+                    Err(_) => {
+                        inner.reparse_base = None;
+                        Err(From::from(diags))
+                    },
                 }
             });
 
@@ -430,13 +458,12 @@ impl WorkspaceFile {
         }).boxed()
     }
 
-    pub fn apply_change(&mut self, version: u64,
-                        event: protocol::TextDocumentContentChangeEvent) -> WorkspaceResult<()> {
-        // TODO, there are several ambiguities with offsets?
-        if event.range.is_some() || event.rangeLength.is_some() {
-            return Err(WorkspaceError("incremental edits not yet supported"));
-        }
-
+    /// Applies all changes from a single `textDocument/didChange` notification in order.
+    /// Changes without a range replace the entire document.
+    pub fn apply_changes(&mut self, version: u64,
+                         events: Vec<protocol::TextDocumentContentChangeEvent>)
+        -> WorkspaceResult<()>
+    {
         self.update_document(move |doc| {
             if let Some(mut doc) = doc {
                 if doc.last_version >= version {
@@ -444,7 +471,23 @@ impl WorkspaceFile {
                 }
 
                 doc.last_version = version;
-                doc.last_text = event.text;
+                for event in events {
+                    if let Some(range) = event.range {
+                        // `rangeLength` is redundant (and deprecated), so it is ignored
+                        let start = diags::translate_position(&range.start, &doc.last_text);
+                        let end = diags::translate_position(&range.end, &doc.last_text);
+                        let end = cmp::max(start, end);
+
+                        let mut text = String::with_capacity(doc.last_text.len() -
+                                                             (end - start) + event.text.len());
+                        text.push_str(&doc.last_text[..start]);
+                        text.push_str(&event.text);
+                        text.push_str(&doc.last_text[end..]);
+                        doc.last_text = text;
+                    } else {
+                        doc.last_text = event.text;
+                    }
+                }
                 Ok(Some(doc))
             } else {
                 Err(WorkspaceError("change notification with non-existent or non-open file"))
@@ -495,6 +538,19 @@ struct WorkspaceShared {
 
     check_outputs: Vec<Option<ReportFuture<Arc<Output>>>>,
     last_check_outputs: Vec<Option<Arc<Output>>>,
+
+    // paths read by each completed check in `check_outputs`, used to keep unaffected outputs
+    check_deps: Vec<Option<HashSet<PathBuf>>>,
+
+    // same to `check_outputs` and `check_deps` but without reusing `module_cache`.
+    // cached modules are loaded from declarations without function bodies,
+    // so requests looking for spans in other files should use these outputs instead.
+    full_check_outputs: Vec<Option<ReportFuture<Arc<Output>>>>,
+    full_check_deps: Vec<Option<HashSet<PathBuf>>>,
+
+    // results of modules checked so far, reused across checks unless they have been changed.
+    // they are also written to the disk when the workspace enables the cache.
+    module_cache: MemoryCacheStore,
 }
 
 type Shared = Arc<RwLock<WorkspaceShared>>;
@@ -514,18 +570,47 @@ impl fmt::Debug for WorkspaceShared {
          .field("cancel_token", &self.cancel_token)
          .field("check_outputs", &DummyOptionList(&self.check_outputs))
          .field("last_check_outputs", &DummyOptionList(&self.last_check_outputs))
+         .field("full_check_outputs", &DummyOptionList(&self.full_check_outputs))
          .finish()
     }
 }
 
 impl WorkspaceShared {
+    fn check_outputs_mut(&mut self, use_cache: bool)
+        -> &mut Vec<Option<ReportFuture<Arc<Output>>>>
+    {
+        if use_cache { &mut self.check_outputs } else { &mut self.full_check_outputs }
+    }
+
     fn cancel(&mut self) {
         self.cancel_token.cancel();
         self.cancel_token = CancelToken::new();
 
-        for output in &mut self.check_outputs {
+        for output in self.check_outputs.iter_mut().chain(self.full_check_outputs.iter_mut()) {
             *output = None;
         }
+        for deps in self.check_deps.iter_mut().chain(self.full_check_deps.iter_mut()) {
+            *deps = None;
+        }
+    }
+
+    // same to `cancel` but keeps completed checks that have never read given path
+    fn cancel_for_path(&mut self, path: &Path) {
+        self.cancel_token.cancel();
+        self.cancel_token = CancelToken::new();
+
+        let outputs = self.check_outputs.iter_mut().chain(self.full_check_outputs.iter_mut());
+        let deps = self.check_deps.iter_mut().chain(self.full_check_deps.iter_mut());
+        for (output, deps) in outputs.zip(deps) {
+            let affected = deps.as_ref().map_or(true, |deps| deps.contains(path));
+            if affected {
+                *output = None;
+                *deps = None;
+            }
+        }
+
+        // cached modules that have read the path are no longer used
+        self.module_cache.evict(path);
     }
 }
 
@@ -539,6 +624,9 @@ struct WorkspaceFsSourceInner {
 
     message_locale: Locale,
     root_report: ReportTree,
+
+    module_cache: MemoryCacheStore,
+    read_paths: HashSet<PathBuf>, // every path that has been tried to read
 }
 
 #[derive(Clone)]
@@ -552,6 +640,7 @@ impl FsSource for WorkspaceFsSource {
         let mut fssource = self.inner.borrow_mut();
 
         fssource.cancel_token.keep_going::<()>().map_err(|_| Stop)?;
+        fssource.read_paths.insert(path.base.to_owned());
 
         // try to use the client-maintained text as a source code
         let files = fssource.files.clone();
@@ -580,11 +669,16 @@ impl FsSource for WorkspaceFsSource {
         }
 
         // try to read the file (and finally raise an error if it can't be read)
+        // or the cached module result which has a virtual path
 
-        let sourcefile = match SourceFile::from_file(path.base) {
-            Ok(f) => f,
-            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
-            Err(_) => return Err(None),
+        let sourcefile = if let Some(data) = fssource.module_cache.get(path.base) {
+            SourceFile::from_u8(path.display().to_string(), data.into_bytes())
+        } else {
+            match SourceFile::from_file(path.base) {
+                Ok(f) => f,
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+                Err(_) => return Err(None),
+            }
         };
         let span = fssource.source.write().add(sourcefile);
         fssource.temp_units.push(span.unit());
@@ -596,7 +690,10 @@ impl FsSource for WorkspaceFsSource {
             let source = fssource.source.read();
             let report = diags.report(|span| diags::translate_span(span, &source));
             let tokens = collect_tokens(&source, span, &report);
-            parse_to_chunk(tokens, &report)
+            parse_to_chunk(tokens, &report).map(|mut chunk| {
+                chunk.resolve_ignores(&source);
+                chunk
+            })
         };
         match chunk {
             Ok(chunk) => {
@@ -606,6 +703,21 @@ impl FsSource for WorkspaceFsSource {
             Err(Stop) => Err(Some(Stop)), // we have already reported parsing errors
         }
     }
+
+    fn data_from_path(&self, path: &Path) -> Option<Vec<u8>> {
+        let mut fssource = self.inner.borrow_mut();
+        fssource.read_paths.insert(path.to_owned());
+
+        // the client-maintained text can change at any time, so it should not be cached
+        if let Some(file) = fssource.files.read().get(path) {
+            if file.inner.read().document.is_some() {
+                return None;
+            }
+        }
+
+        let mut data = Vec::new();
+        File::open(path).and_then(|mut f| f.read_to_end(&mut data)).ok().map(|_| data)
+    }
 }
 
 pub struct Workspace {
@@ -634,7 +746,11 @@ impl fmt::Debug for Workspace {
 }
 
 impl Workspace {
-    pub fn new(base_dir: PathBuf, pool: Arc<CpuPool>, default_locale: Locale) -> Workspace {
+    pub fn new(base_dir: PathBuf, pool: Arc<CpuPool>, default_locale: Locale,
+               default_checker: CheckerConfig) -> Workspace {
+        let mut config = kailua_workspace::Config::from_base_dir(base_dir.clone());
+        config.checker = default_checker;
+
         Workspace {
             message_locale: default_locale,
             pool: pool,
@@ -642,9 +758,13 @@ impl Workspace {
             source: Arc::new(RwLock::new(Source::new())),
             shared: Arc::new(RwLock::new(WorkspaceShared {
                 cancel_token: CancelToken::new(),
-                base: WorkspaceBase::Config(kailua_workspace::Config::from_base_dir(base_dir)),
+                base: WorkspaceBase::Config(config),
                 check_outputs: Vec::new(),
                 last_check_outputs: Vec::new(),
+                check_deps: Vec::new(),
+                full_check_outputs: Vec::new(),
+                full_check_deps: Vec::new(),
+                module_cache: MemoryCacheStore::new(base_dir.join(kailua_workspace::CACHE_DIR)),
             })),
         }
     }
@@ -687,6 +807,9 @@ impl Workspace {
             shared.base = WorkspaceBase::Workspace(ws);
             shared.check_outputs.resize(noutputs, None);
             shared.last_check_outputs.resize(noutputs, None);
+            shared.check_deps.resize(noutputs, None);
+            shared.full_check_outputs.resize(noutputs, None);
+            shared.full_check_deps.resize(noutputs, None);
         }
         true
     }
@@ -773,6 +896,8 @@ impl Workspace {
 
     pub fn on_file_created(&self, uri: &str) -> Option<WorkspaceFile> {
         if let Ok(path) = uri_to_path(uri) {
+            // a new file can change the resolution of `require`
+            self.shared.write().cancel_for_path(&path);
             let file = self.ensure_file(&path);
             let _ = file.ensure_chunk();
             Some(file)
@@ -811,7 +936,8 @@ impl Workspace {
     }
 
     fn build_future_for_check_output(
-        &self, index: usize, start_path: &Path, spare_shared: Shared, shared: &mut SharedWrite
+        &self, index: usize, start_path: &Path, use_cache: bool,
+        spare_shared: Shared, shared: &mut SharedWrite
     ) -> ReportFuture<Arc<Output>> {
         let start_chunk_fut = self.ensure_file(start_path).ensure_chunk();
 
@@ -819,6 +945,7 @@ impl Workspace {
         let files = self.files.clone();
         let source = self.source.clone();
         let cancel_token = shared.cancel_token.clone();
+        let module_cache = shared.module_cache.clone();
         let message_locale = self.message_locale;
 
         let fut = start_chunk_fut.map_err(|e| (*e).clone()).and_then(move |chunk_ret| {
@@ -841,6 +968,8 @@ impl Workspace {
                     temp_files: HashMap::new(),
                     message_locale: message_locale,
                     root_report: diags.clone(),
+                    module_cache: module_cache.clone(),
+                    read_paths: HashSet::new(),
                 })),
             };
 
@@ -852,7 +981,18 @@ impl Workspace {
                     return Err(From::from(diags));
                 },
                 WorkspaceBase::Workspace(ref ws) => {
-                    let opts = WorkspaceOptions::new(fssource.clone(), &start_path, ws);
+                    let mut opts = WorkspaceOptions::new(fssource.clone(), &start_path, ws);
+                    // modules are always cached in the memory so that they can be evicted
+                    // as soon as files change, and persist only when the workspace enables it
+                    if use_cache {
+                        let store = match ws.cache_dir() {
+                            Some(dir) => module_cache.with_backing_store(DirCacheStore::new(dir)),
+                            None => module_cache,
+                        };
+                        opts.set_cache_store(Box::new(store));
+                    } else {
+                        opts.disable_cache();
+                    }
                     (Rc::new(RefCell::new(opts)), ws.preload().clone())
                 },
             };
@@ -863,6 +1003,12 @@ impl Workspace {
                 let mut context = Context::new(diags.report(|span| {
                     diags::translate_span(span, &source.read())
                 }));
+                if let WorkspaceBase::Workspace(ref ws) = spare_shared.read().base {
+                    context.set_config(ws.checker_config().clone());
+                    for code in ws.ignored_codes() {
+                        context.ignore_code(&code[..]);
+                    }
+                }
                 let ok = kailua_check::check_from_chunk_with_preloading(&mut context, start_chunk,
                                                                         opts, &preload).is_ok();
                 (ok, context.into_output())
@@ -885,7 +1031,18 @@ impl Workspace {
 
             if ok {
                 let output = Arc::new(output);
-                spare_shared.write().last_check_outputs[index] = Some(output.clone());
+                let mut deps = fssource.read_paths;
+                deps.insert(start_path);
+
+                // the check can be canceled right before locking the shared
+                let mut shared = spare_shared.write();
+                cancel_token.keep_going()?;
+                if use_cache {
+                    shared.last_check_outputs[index] = Some(output.clone());
+                    shared.check_deps[index] = Some(deps);
+                } else {
+                    shared.full_check_deps[index] = Some(deps);
+                }
                 Ok((output, diags))
             } else {
                 Err(From::from(diags))
@@ -896,6 +1053,12 @@ impl Workspace {
     }
 
     pub fn ensure_check_outputs(&self) -> WorkspaceResult<Vec<ReportFuture<Arc<Output>>>> {
+        self.ensure_check_outputs_with(true)
+    }
+
+    fn ensure_check_outputs_with(&self, use_cache: bool)
+        -> WorkspaceResult<Vec<ReportFuture<Arc<Output>>>>
+    {
         let spare_shared = self.shared.clone();
         let mut shared = self.shared.write();
 
@@ -905,17 +1068,19 @@ impl Workspace {
             },
             WorkspaceBase::Workspace(ref ws) => ws.start_paths().to_owned(),
         };
-        assert_eq!(shared.check_outputs.len(), start_paths.len());
+
+        assert_eq!(shared.check_outputs_mut(use_cache).len(), start_paths.len());
 
         for (i, path) in start_paths.iter().enumerate() {
-            if shared.check_outputs[i].is_none() {
-                let fut = self.build_future_for_check_output(i, path, spare_shared.clone(),
-                                                             &mut shared);
-                shared.check_outputs[i] = Some(fut);
+            if shared.check_outputs_mut(use_cache)[i].is_none() {
+                let fut = self.build_future_for_check_output(i, path, use_cache,
+                                                             spare_shared.clone(), &mut shared);
+                shared.check_outputs_mut(use_cache)[i] = Some(fut);
             }
         }
 
-        Ok(shared.check_outputs.iter().map(|fut| fut.as_ref().unwrap().clone()).collect())
+        let outputs = shared.check_outputs_mut(use_cache);
+        Ok(outputs.iter().map(|fut| fut.as_ref().unwrap().clone()).collect())
     }
 
     // this is similar to `ensure_check_outputs`, but produces a single future
@@ -925,7 +1090,21 @@ impl Workspace {
         -> WorkspaceResult<BoxFuture<(Vec<Arc<Output>>, ReportTree), CancelError<()>>>
     {
         let output_futs = self.ensure_check_outputs()?;
+        Ok(self.combine_check_outputs(output_futs))
+    }
 
+    // same to `ensure_combined_check_outputs` but no modules are loaded from the module cache,
+    // so that the outputs have every span from the source code, at the expense of time.
+    pub fn ensure_combined_full_check_outputs(&self)
+        -> WorkspaceResult<BoxFuture<(Vec<Arc<Output>>, ReportTree), CancelError<()>>>
+    {
+        let output_futs = self.ensure_check_outputs_with(false)?;
+        Ok(self.combine_check_outputs(output_futs))
+    }
+
+    fn combine_check_outputs(&self, output_futs: Vec<ReportFuture<Arc<Output>>>)
+        -> BoxFuture<(Vec<Arc<Output>>, ReportTree), CancelError<()>>
+    {
         // checking can result in the fatal error (Err) only when cancellation is requested.
         // so we only need to return Ok when every checking results in Ok,
         // avoiding the difficulty to combine incomplete reports when one of them fails.
@@ -933,7 +1112,7 @@ impl Workspace {
         let outputs_fut = output_stream.collect();
 
         let message_locale = self.message_locale;
-        Ok(outputs_fut.map_err(|_| CancelError::Error(())).map(move |ret| {
+        outputs_fut.map_err(|_| CancelError::Error(())).map(move |ret| {
             let mut outputs = Vec::new();
             let diags = ReportTree::new(message_locale, None);
             for e in ret.into_iter() {
@@ -941,7 +1120,7 @@ impl Workspace {
                 diags.add_parent(e.1.clone());
             }
             (outputs, diags)
-        }).boxed())
+        }).boxed()
     }
 
     #[allow(dead_code)]
@@ -954,3 +1133,99 @@ impl Workspace {
     }
 }
 
+
+#[cfg(test)]
+fn test_recheck_with(cache: bool) {
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    let base_dir = env::temp_dir().join(format!("kailua-langsvr-test-{}-{}",
+                                                ::std::process::id(), cache));
+    let _ = fs::remove_dir_all(&base_dir);
+    fs::create_dir_all(&base_dir).unwrap();
+
+    let write = |name: &str, data: &str| {
+        File::create(base_dir.join(name)).unwrap().write_all(data.as_bytes()).unwrap();
+    };
+    write("kailua.json", &format!("{{\"start_path\": \"main.lua\", \"cache\": {}}}", cache));
+    write("main.lua", "\
+        --# open lua51\n\
+        local b = require 'b'\n\
+        b.new = 1\n\
+        local s = b.s --: string\n");
+    write("b.lua", "\
+        local M = {}\n\
+        M.s = 'x'\n\
+        return M\n");
+
+    let pool = Arc::new(CpuPool::new(1));
+    let mut ws = Workspace::new(base_dir.clone(), pool, Locale::dummy(), CheckerConfig::default());
+    assert!(ws.read_config());
+
+    // returns sorted diagnostics from either the (possibly) cached or the full check
+    let diagnostics = |ws: &Workspace, full: bool| {
+        let fut = if full {
+            ws.ensure_combined_full_check_outputs()
+        } else {
+            ws.ensure_combined_check_outputs()
+        };
+        let (_, root) = fut.unwrap().wait().ok().unwrap();
+        let mut diags = Vec::new();
+        for tree in root.trees() {
+            for (path, diag) in tree.diagnostics() {
+                let name = Path::new(&path).file_name().unwrap().to_string_lossy().into_owned();
+                diags.push(format!("{}: {}", name, diag.message));
+            }
+        }
+        diags.sort();
+        diags
+    };
+
+    // returns true if the last (possibly) cached check has read any module from the cache
+    let reused = |ws: &Workspace| {
+        let cache_dir = base_dir.join(kailua_workspace::CACHE_DIR);
+        let shared = ws.shared.read();
+        shared.check_deps[0].as_ref().unwrap().iter().any(|path| path.starts_with(&cache_dir))
+    };
+
+    assert_eq!(diagnostics(&ws, false), Vec::<String>::new());
+    assert!(!reused(&ws));
+    assert_eq!(diagnostics(&ws, true), Vec::<String>::new());
+
+    // the unaffected module `b` should be reused by the recheck regardless of the cache option,
+    // which only affects whether it is also written to the disk
+    write("main.lua", "\
+        --# open lua51\n\
+        local b = require 'b'\n\
+        b.new = 2\n\
+        local s = b.s --: string\n");
+    let uri = Url::from_file_path(base_dir.join("main.lua")).unwrap().to_string();
+    ws.on_file_changed(&uri);
+    assert_eq!(diagnostics(&ws, false), Vec::<String>::new());
+    assert!(reused(&ws));
+    assert_eq!(base_dir.join(kailua_workspace::CACHE_DIR).exists(), cache);
+
+    // the requirer should be checked against the changed leaf module
+    write("b.lua", "\
+        local M = {}\n\
+        M.s = 42\n\
+        return M\n");
+    let uri = Url::from_file_path(base_dir.join("b.lua")).unwrap().to_string();
+    ws.on_file_changed(&uri);
+    let diags = diagnostics(&ws, false);
+    assert!(!diags.is_empty());
+    assert_eq!(diags, diagnostics(&ws, true));
+
+    let _ = fs::remove_dir_all(&base_dir);
+}
+
+#[test]
+fn test_recheck() {
+    test_recheck_with(false);
+}
+
+#[test]
+fn test_recheck_with_cache() {
+    test_recheck_with(true);
+}
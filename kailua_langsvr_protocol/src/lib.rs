@@ -479,6 +479,9 @@ define_methods! {
     Rename "textDocument/rename" = 0x11a,
     ExecuteCommand "workspace/executeCommand" = 0x303,
     ApplyEdit "workspace/applyEdit" = 0x304,
+    InlayHint "textDocument/inlayHint" = 0x400,
+    SemanticTokensFull "textDocument/semanticTokens/full" = 0x401,
+    SemanticTokensRange "textDocument/semanticTokens/range" = 0x402,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -515,6 +518,9 @@ pub enum Request {
     DocumentLink(DocumentLinkParams),
     DocumentLinkResolve(DocumentLink),
     Rename(RenameParams),
+    InlayHint(InlayHintParams),
+    SemanticTokensFull(SemanticTokensParams),
+    SemanticTokensRange(SemanticTokensRangeParams),
 }
 
 impl Request {
@@ -562,6 +568,9 @@ impl Request {
             Ok(M::DocumentLink) => parse(msg, R::DocumentLink),
             Ok(M::DocumentLinkResolve) => parse(msg, R::DocumentLinkResolve),
             Ok(M::Rename) => parse(msg, R::Rename),
+            Ok(M::InlayHint) => parse(msg, R::InlayHint),
+            Ok(M::SemanticTokensFull) => parse(msg, R::SemanticTokensFull),
+            Ok(M::SemanticTokensRange) => parse(msg, R::SemanticTokensRange),
 
             _ => (msg.id, Err(MessageError::MethodNotFound(msg.method))),
         }
@@ -936,6 +945,8 @@ interface! {
         pub renameProvider?: bool,
         pub documentLinkProvider?: Option<DocumentLinkOptions>,
         pub executeCommandProvider?: Option<ExecuteCommandOptions>,
+        pub inlayHintProvider?: bool,
+        pub semanticTokensProvider?: Option<SemanticTokensOptions>,
     }
 }
 
@@ -1349,6 +1360,59 @@ interface! {
     }
 }
 
+// inlay hints
+
+interface! {
+    pub struct InlayHintParams {
+        pub textDocument: TextDocumentIdentifier,
+        pub range: Range,
+    }
+
+    pub struct InlayHint {
+        pub position: Position,
+        pub label: String,
+        pub kind?: Option<InlayHintKind>,
+        pub paddingLeft?: bool,
+        pub paddingRight?: bool,
+    }
+}
+
+enum_number! {
+    pub enum InlayHintKind {
+        Type = 1,
+        Parameter = 2,
+    }
+}
+
+// semantic tokens
+
+interface! {
+    pub struct SemanticTokensLegend {
+        pub tokenTypes: Vec<String>,
+        pub tokenModifiers: Vec<String>,
+    }
+
+    pub struct SemanticTokensOptions {
+        pub legend: SemanticTokensLegend,
+        pub range?: bool,
+        pub full?: bool,
+    }
+
+    pub struct SemanticTokensParams {
+        pub textDocument: TextDocumentIdentifier,
+    }
+
+    pub struct SemanticTokensRangeParams {
+        pub textDocument: TextDocumentIdentifier,
+        pub range: Range,
+    }
+
+    pub struct SemanticTokens {
+        pub resultId?: Option<String>,
+        pub data: Vec<u32>,
+    }
+}
+
 // workspace edits
 
 interface! {
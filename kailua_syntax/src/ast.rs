@@ -8,9 +8,10 @@
 use std::fmt;
 use std::cell::Cell;
 use std::collections::HashMap;
-use kailua_env::{Span, Spanned, Scope, ScopedId, ScopeMap};
+use kailua_env::{Span, Spanned, Scope, ScopedId, ScopeMap, Source};
 
 use string::{Str, Name};
+use lex::NumSuffix;
 
 // a helper type for printing commas
 struct Comma(Cell<bool>);
@@ -28,6 +29,19 @@ impl fmt::Display for Comma {
     }
 }
 
+// a helper function for printing type parameters (prints nothing if empty)
+fn fmt_tparams(tparams: &[Spanned<Name>], f: &mut fmt::Formatter) -> fmt::Result {
+    if !tparams.is_empty() {
+        write!(f, "<")?;
+        let comma = Comma::new();
+        for tparam in tparams {
+            write!(f, "{}{:?}", comma, tparam)?;
+        }
+        write!(f, ">")?;
+    }
+    Ok(())
+}
+
 /// A resolved reference to the name, either local or global.
 //
 // why don't we use scoped ids everywhere? scoped ids are bound to the scope map,
@@ -259,6 +273,9 @@ pub struct Sig {
     /// A list of attributes.
     pub attrs: Vec<Spanned<Attr>>,
 
+    /// A list of type parameters (`function<T, U>(...)`), empty if not generic.
+    pub tparams: Vec<Spanned<Name>>,
+
     /// A list of arguments (resolved to scoped identifiers) and associated types if any.
     pub args: Spanned<Seq<TypeSpec<Spanned<ScopedId>>, Varargs>>,
 
@@ -271,6 +288,7 @@ impl fmt::Debug for Sig {
         for attr in &self.attrs {
             write!(f, "{:?} ", attr)?;
         }
+        fmt_tparams(&self.tparams, f)?;
         write!(f, "[")?;
         let comma = Comma::new();
         for namespec in &self.args.head {
@@ -369,6 +387,14 @@ pub enum Ex {
     /// A number literal.
     Num(f64),
 
+    /// A number literal which is always a float.
+    ///
+    /// Only used in Lua 5.3 for numbers with a fraction or an exponent (e.g. `7.0`).
+    Float(f64),
+
+    /// A LuaJIT cdata number literal.
+    CDataNum(f64, NumSuffix),
+
     /// A string literal.
     ///
     /// A difference between `"string"` and `[[string]]` is not recorded.
@@ -423,6 +449,8 @@ impl fmt::Debug for Ex {
             Ex::False => write!(f, "false"),
             Ex::True => write!(f, "true"),
             Ex::Num(v) => write!(f, "{:?}", v),
+            Ex::Float(v) => write!(f, "{:?}", v),
+            Ex::CDataNum(v, suffix) => write!(f, "{}{}", v, suffix.as_str()),
             Ex::Str(ref s) => write!(f, "{:?}", *s),
             Ex::Varargs => write!(f, "..."),
             Ex::Func(ref p, bs, ref b) => write!(f, "Func({:?}, {:?}{:?})", *p, bs, *b),
@@ -453,6 +481,8 @@ pub enum UnOp {
     Not,
     /// `#`.
     Len,
+    /// `~`. [5.3+]
+    BNot,
 }
 
 impl UnOp {
@@ -461,6 +491,7 @@ impl UnOp {
             UnOp::Neg => "-",
             UnOp::Not => "not",
             UnOp::Len => "#",
+            UnOp::BNot => "~",
         }
     }
 }
@@ -476,12 +507,24 @@ pub enum BinOp {
     Mul,
     /// `/`.
     Div,
+    /// `//`. [5.3+]
+    IDiv,
     /// `^`.
     Pow,
     /// `%`.
     Mod,
     /// `..`.
     Cat,
+    /// `&`. [5.3+]
+    BAnd,
+    /// `|`. [5.3+]
+    BOr,
+    /// `~`. [5.3+]
+    BXor,
+    /// `<<`. [5.3+]
+    Shl,
+    /// `>>`. [5.3+]
+    Shr,
     /// `<`.
     Lt,
     /// `<=`.
@@ -507,9 +550,15 @@ impl BinOp {
             BinOp::Sub => "-",
             BinOp::Mul => "*",
             BinOp::Div => "/",
+            BinOp::IDiv => "//",
             BinOp::Pow => "^",
             BinOp::Mod => "%",
             BinOp::Cat => "..",
+            BinOp::BAnd => "&",
+            BinOp::BOr => "|",
+            BinOp::BXor => "~",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
             BinOp::Lt  => "<",
             BinOp::Le  => "<=",
             BinOp::Gt  => ">",
@@ -609,11 +658,17 @@ pub enum St {
     /// `break`.
     Break,
 
+    /// `goto name`. [5.2+]
+    Goto(Spanned<Name>),
+
+    /// `::name::`. [5.2+]
+    Label(Spanned<Name>),
+
     /// `--# open name`.
     KailuaOpen(Spanned<Name>),
 
-    /// `--# type [scope] name = type`.
-    KailuaType(TypeScope, Spanned<Name>, Spanned<Kind>),
+    /// `--# type [scope] name[<param, ...>] = type`.
+    KailuaType(TypeScope, Spanned<Name>, Vec<Spanned<Name>>, Spanned<Kind>),
 
     /// `--# assume [global] name: type`.
     ///
@@ -687,10 +742,15 @@ impl fmt::Debug for St {
             St::Local(ref ii, ref ee, is) => write!(f, "Local({:?}, {:?}){:?}", ii, ee, is),
             St::Return(ref ee) => write!(f, "Return({:?})", ee),
             St::Break => write!(f, "Break"),
+            St::Goto(ref i) => write!(f, "Goto({:?})", i),
+            St::Label(ref i) => write!(f, "Label({:?})", i),
 
             St::KailuaOpen(ref lib) => write!(f, "KailuaOpen({:?})", lib),
-            St::KailuaType(scope, ref t, ref k) =>
-                write!(f, "KailuaType({:?}, {:?}, {:?})", scope, t, k),
+            St::KailuaType(scope, ref t, ref params, ref k) => {
+                write!(f, "KailuaType({:?}, {:?}", scope, t)?;
+                fmt_tparams(params, f)?;
+                write!(f, ", {:?})", k)
+            },
             St::KailuaAssume(ref i, m, ref k, is) => {
                 write!(f, "KailuaAssume({:?}, {:?}, {:?})", i, m, k)?;
                 if let Some(is) = is { write!(f, "{:?}", is)?; }
@@ -791,6 +851,12 @@ impl fmt::Debug for SlotKind {
 /// A function type for Kailua.
 #[derive(Clone, PartialEq)]
 pub struct FuncKind {
+    /// A list of type parameters (`function<T, U>(...)`), empty if not generic.
+    ///
+    /// Each parameter is in scope for the argument and return types,
+    /// and gets instantiated to a fresh type per each call.
+    pub tparams: Vec<Spanned<Name>>,
+
     /// A list of argument types with optional names.
     ///
     /// The name is purely for description and has no effect in the type.
@@ -804,6 +870,7 @@ pub struct FuncKind {
 
 impl fmt::Debug for FuncKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_tparams(&self.tparams, f)?;
         write!(f, "(")?;
         let comma = Comma::new();
         for &(ref name, ref arg) in &self.args.head {
@@ -889,6 +956,11 @@ pub enum K {
     /// `function(...) -> ...`.
     Func(Spanned<FuncKind>),
 
+    /// `function(...) --> ... & function(...) --> ... & ...`.
+    ///
+    /// Always contains at least two function types.
+    Overloaded(Vec<Spanned<FuncKind>>),
+
     /// `thread`.
     Thread,
 
@@ -898,6 +970,9 @@ pub enum K {
     /// A named type.
     Named(Spanned<Name>),
 
+    /// A generic type alias applied to type arguments, `Name<T, U, ...>`.
+    Generic(Spanned<Name>, Vec<Spanned<Kind>>),
+
     /// `T?`.
     ///
     /// The checker distinguishes a plain type and a "nilable" type,
@@ -941,6 +1016,14 @@ impl fmt::Debug for K {
             K::Map(ref k, ref v)  => write!(f, "Map({:?}, {:?})", *k, *v),
             K::Function           => write!(f, "Function"),
             K::Named(ref name)    => write!(f, "{:?}", *name),
+            K::Generic(ref name, ref args) => {
+                write!(f, "{:?}<", *name)?;
+                let comma = Comma::new();
+                for arg in args {
+                    write!(f, "{}{:?}", comma, arg)?;
+                }
+                write!(f, ">")
+            },
             K::WithNil(ref k)     => write!(f, "{:?}?", *k),
             K::WithoutNil(ref k)  => write!(f, "{:?}!", *k),
             K::Thread             => write!(f, "Thread"),
@@ -962,6 +1045,7 @@ impl fmt::Debug for K {
             },
             K::Tuple(ref fields) => write!(f, "Tuple({:?})", *fields),
             K::Func(ref func) => write!(f, "Func({:?})", *func),
+            K::Overloaded(ref funcs) => write!(f, "Overloaded({:?})", *funcs),
             K::Union(ref kinds) => write!(f, "Union({:?})", *kinds),
             K::Attr(ref k, ref a) => write!(f, "{:?} {:?}", a, k),
         }
@@ -1016,6 +1100,42 @@ pub enum TokenAux {
     GlobalVarName,
 }
 
+/// The extent of `Ignore`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IgnoreScope {
+    /// `--# ignore`, from the directive to the end of the chunk.
+    Rest,
+
+    /// `--# ignore-next-line`, only applies to the line next to the directive.
+    NextLine,
+}
+
+/// A region where the reports with given codes are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ignore {
+    /// Codes of messages to be ignored.
+    pub codes: Vec<Spanned<Name>>,
+
+    /// The extent of this directive.
+    pub scope: IgnoreScope,
+
+    /// The region where reports are ignored.
+    ///
+    /// The parser does not know about lines, so for `IgnoreScope::NextLine`
+    /// this is initially set to the directive itself.
+    /// `Chunk::resolve_ignores` should be called to set it to the next line.
+    pub span: Span,
+}
+
+impl Ignore {
+    /// Returns true if a report with given code and span should be ignored.
+    pub fn covers(&self, code: &str, span: Span) -> bool {
+        !span.is_dummy() && span.unit() == self.span.unit() &&
+            self.span.begin() <= span.begin() && span.begin() < self.span.end() &&
+            self.codes.iter().any(|c| *c.base == *code.as_bytes())
+    }
+}
+
 /// The parsed chunk, representing a single source file with associated side informations.
 #[derive(Clone)]
 pub struct Chunk {
@@ -1035,5 +1155,34 @@ pub struct Chunk {
 
     /// Auxiliary informations for each input token (including `Tok::EOF`), in the order.
     pub token_aux: Vec<TokenAux>,
+
+    /// Regions where particular reports are ignored, in the order of directives.
+    pub ignores: Vec<Ignore>,
+
+    /// True if the chunk has a `--# strict` directive anywhere.
+    pub strict: bool,
+}
+
+impl Chunk {
+    /// Updates the regions for `--# ignore-next-line` directives from the source.
+    ///
+    /// `kailua_syntax::parse_chunk` does this automatically.
+    pub fn resolve_ignores(&mut self, source: &Source) {
+        for ignore in &mut self.ignores {
+            if ignore.scope != IgnoreScope::NextLine {
+                continue;
+            }
+            let file = match source.get_file(ignore.span.unit()) {
+                Some(file) => file,
+                None => continue,
+            };
+            // the directive can span multiple lines, so we look for the line after its end
+            if let Some((line, _)) = file.line_from_pos(ignore.span.end()) {
+                if let Some(nextspan) = file.line_spans().nth(line + 1) {
+                    ignore.span = nextspan;
+                }
+            }
+        }
+    }
 }
 
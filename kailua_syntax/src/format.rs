@@ -0,0 +1,573 @@
+//! Source code formatter.
+//!
+//! The formatter works on the token stream, which preserves comments and meta comments,
+//! and uses the parsed chunk to tell where each statement starts.
+//! Tokens are never added, removed or reordered; only whitespace between them is changed:
+//!
+//! * Each line is indented according to the nesting of blocks and brackets.
+//!   A line continuing the statement from the previous line is indented once more.
+//!
+//! * Spaces between tokens in the same line are normalized.
+//!
+//! * Consecutive blank lines are collapsed into one and trailing whitespace is removed.
+//!
+//! Comments and meta comments (`--#`, `--:`, `--v` and `-->`) are kept verbatim.
+//! The formatted code is lexed again and compared to the original tokens,
+//! so the formatter gives up instead of changing the meaning of the code.
+
+use std::collections::HashSet;
+
+use kailua_env::{Pos, Span, Source, SourceFile, SourceSlice};
+use kailua_diag::{self, Report, NoReport, TrackMaxKind};
+
+use lex::{Lexer, Nest, NestedToken, Tok, Punct, Keyword};
+use ast::{Chunk, Block, St, Ex, Exp, Var, Args};
+use parser::Parser;
+
+/// Options for the formatter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// A string inserted for each level of indentation. Defaults to four spaces.
+    pub indent: String,
+
+    /// If set, only the whitespace before tokens starting in this span is changed.
+    ///
+    /// The indentation is still calculated from the entire file.
+    pub range: Option<Span>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions { indent: "    ".to_owned(), range: None }
+    }
+}
+
+// a unit of the formatting. comments and meta comments are never changed.
+#[derive(Copy, Clone, Debug)]
+enum Item<'a> {
+    Code(&'a Tok),
+    Comment,
+    Meta,
+}
+
+fn is_meta_start(tok: &Tok) -> bool {
+    match *tok {
+        Tok::Punct(Punct::DashDashHash) |
+        Tok::Punct(Punct::DashDashColon) |
+        Tok::Punct(Punct::DashDashGt) |
+        Tok::Punct(Punct::DashDashV) => true,
+        _ => false,
+    }
+}
+
+// tokens which open an indented region, closed by `is_closer` tokens
+fn is_opener(tok: &Tok) -> bool {
+    match *tok {
+        Tok::Keyword(Keyword::Function) |
+        Tok::Keyword(Keyword::Do) |
+        Tok::Keyword(Keyword::Then) |
+        Tok::Keyword(Keyword::Else) |
+        Tok::Keyword(Keyword::Repeat) => true,
+        Tok::Punct(Punct::LParen) |
+        Tok::Punct(Punct::LBrace) |
+        Tok::Punct(Punct::LBracket) => true,
+        _ => false,
+    }
+}
+
+fn is_closer(tok: &Tok) -> bool {
+    match *tok {
+        Tok::Keyword(Keyword::End) |
+        Tok::Keyword(Keyword::Else) |
+        Tok::Keyword(Keyword::Elseif) |
+        Tok::Keyword(Keyword::Until) => true,
+        Tok::Punct(Punct::RParen) |
+        Tok::Punct(Punct::RBrace) |
+        Tok::Punct(Punct::RBracket) => true,
+        _ => false,
+    }
+}
+
+fn is_bracket(tok: &Tok) -> bool {
+    match *tok {
+        Tok::Punct(Punct::LParen) | Tok::Punct(Punct::LBrace) | Tok::Punct(Punct::LBracket) => true,
+        _ => false,
+    }
+}
+
+// tokens after which `-` and `~` are binary operators
+fn is_operand_end(tok: &Tok) -> bool {
+    match *tok {
+        Tok::Name(_) | Tok::Num(..) | Tok::CDataNum(..) | Tok::Str(..) => true,
+        Tok::Punct(Punct::RParen) |
+        Tok::Punct(Punct::RBracket) |
+        Tok::Punct(Punct::RBrace) |
+        Tok::Punct(Punct::DotDotDot) => true,
+        Tok::Keyword(Keyword::Nil) |
+        Tok::Keyword(Keyword::True) |
+        Tok::Keyword(Keyword::False) |
+        Tok::Keyword(Keyword::End) => true,
+        _ => false,
+    }
+}
+
+// tokens after which `(`, `[`, a string or a table constructor may start a call or an index
+fn is_callee_end(tok: &Tok) -> bool {
+    match *tok {
+        Tok::Name(_) | Tok::Punct(Punct::RParen) | Tok::Punct(Punct::RBracket) => true,
+        _ => false,
+    }
+}
+
+// `prev_unary` is true when `prev` is an unary operator;
+// `in_label` is true when `prev` is `::` opening a label.
+fn needs_space(prev: Item, cur: Item, cur_text: &[u8],
+               prev_unary: bool, in_label: bool, had_space: bool) -> bool {
+    let (prev, cur) = match (prev, cur) {
+        (Item::Code(prev), Item::Code(cur)) => (prev, cur),
+        _ => return true,
+    };
+
+    match (prev, cur) {
+        // `- -x` should not become a comment
+        (&Tok::Punct(Punct::Dash), &Tok::Punct(Punct::Dash)) => true,
+        // `a[ [[x]] ]` should not become a long string
+        (&Tok::Punct(Punct::LBracket), &Tok::Str(..)) if cur_text.starts_with(b"[") => true,
+
+        (_, &Tok::Punct(Punct::Comma)) |
+        (_, &Tok::Punct(Punct::Semicolon)) |
+        (_, &Tok::Punct(Punct::RParen)) |
+        (_, &Tok::Punct(Punct::RBracket)) => false,
+        (&Tok::Punct(Punct::LParen), _) |
+        (&Tok::Punct(Punct::LBracket), _) => false,
+
+        // `{}`, `{a}` and `{ a }` are all allowed
+        (&Tok::Punct(Punct::LBrace), &Tok::Punct(Punct::RBrace)) => false,
+        (&Tok::Punct(Punct::LBrace), _) |
+        (_, &Tok::Punct(Punct::RBrace)) => had_space,
+
+        (&Tok::Punct(Punct::Dot), _) | (_, &Tok::Punct(Punct::Dot)) |
+        (&Tok::Punct(Punct::Colon), _) | (_, &Tok::Punct(Punct::Colon)) => false,
+
+        // `::label::`
+        (&Tok::Punct(Punct::ColonColon), _) if in_label => false,
+        (_, &Tok::Punct(Punct::ColonColon)) if in_label => false,
+
+        (&Tok::Punct(Punct::Comma), _) | (&Tok::Punct(Punct::Semicolon), _) => true,
+
+        (&Tok::Punct(Punct::Hash), _) => false,
+        (&Tok::Punct(Punct::Dash), _) | (&Tok::Punct(Punct::Tilde), _) if prev_unary => false,
+
+        (&Tok::Keyword(Keyword::Function), &Tok::Punct(Punct::LParen)) => false,
+        (prev, &Tok::Punct(Punct::LParen)) |
+        (prev, &Tok::Punct(Punct::LBracket)) => !is_callee_end(prev),
+
+        // `f"string"` and `f{table}`
+        (prev, &Tok::Str(..)) |
+        (prev, &Tok::Punct(Punct::LBrace)) if is_callee_end(prev) => had_space,
+
+        _ => true,
+    }
+}
+
+fn slice_to_bytes(slice: Option<SourceSlice>) -> Vec<u8> {
+    match slice {
+        Some(SourceSlice::U8(s)) => s.to_owned(),
+        Some(SourceSlice::U16(s)) => String::from_utf16_lossy(s).into_bytes(),
+        None => Vec::new(),
+    }
+}
+
+fn count_newlines(s: &[u8]) -> usize {
+    // `\r\n`, `\n` and a lone `\r` are all newlines
+    let mut count = 0;
+    for (i, &c) in s.iter().enumerate() {
+        if c == b'\n' || (c == b'\r' && s.get(i + 1) != Some(&b'\n')) {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn trim_end(s: &[u8]) -> &[u8] {
+    let len = s.iter().rposition(|&c| c != b' ' && c != b'\t').map_or(0, |i| i + 1);
+    &s[..len]
+}
+
+fn collect_stmt_starts(block: &Block, starts: &mut HashSet<Pos>) {
+    for stmt in block {
+        starts.insert(stmt.span.begin());
+
+        match *stmt.base {
+            St::Void(ref e) => collect_stmt_starts_from_exp(e, starts),
+            St::Assign(ref lhs, ref rhs) => {
+                for var in &lhs.base {
+                    match var.base.base {
+                        Var::Name(_) => {}
+                        Var::Index(ref e, ref key) => {
+                            collect_stmt_starts_from_exp(e, starts);
+                            collect_stmt_starts_from_exp(key, starts);
+                        }
+                        Var::IndexName(ref e, _) => collect_stmt_starts_from_exp(e, starts),
+                    }
+                }
+                if let Some(ref rhs) = *rhs {
+                    for e in &rhs.base { collect_stmt_starts_from_exp(e, starts); }
+                }
+            }
+            St::Do(ref block) => collect_stmt_starts(block, starts),
+            St::While(ref cond, ref block) | St::Repeat(ref block, ref cond) => {
+                collect_stmt_starts_from_exp(cond, starts);
+                collect_stmt_starts(block, starts);
+            }
+            St::If(ref conds, ref lastblock) => {
+                for cond in conds {
+                    collect_stmt_starts_from_exp(&cond.base.0, starts);
+                    collect_stmt_starts(&cond.base.1, starts);
+                }
+                if let Some(ref block) = *lastblock {
+                    collect_stmt_starts(block, starts);
+                }
+            }
+            St::For(_, ref start, ref end, ref step, _, ref block) => {
+                collect_stmt_starts_from_exp(start, starts);
+                collect_stmt_starts_from_exp(end, starts);
+                if let Some(ref step) = *step { collect_stmt_starts_from_exp(step, starts); }
+                collect_stmt_starts(block, starts);
+            }
+            St::ForIn(_, ref exps, _, ref block) => {
+                for e in &exps.base { collect_stmt_starts_from_exp(e, starts); }
+                collect_stmt_starts(block, starts);
+            }
+            St::FuncDecl(_, _, _, ref block, _) |
+            St::MethodDecl(_, _, _, _, ref block) => collect_stmt_starts(block, starts),
+            St::Local(_, ref exps, _) | St::Return(ref exps) => {
+                for e in &exps.base { collect_stmt_starts_from_exp(e, starts); }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_stmt_starts_from_exp(exp: &Exp, starts: &mut HashSet<Pos>) {
+    match **exp {
+        Ex::Func(_, _, ref block) => collect_stmt_starts(block, starts),
+        Ex::Table(ref table) => {
+            for &(ref k, ref v) in &table.items {
+                if let Some(ref k) = *k { collect_stmt_starts_from_exp(k, starts); }
+                collect_stmt_starts_from_exp(v, starts);
+            }
+        }
+        Ex::Exp(ref e) | Ex::IndexName(ref e, _) | Ex::Un(_, ref e) => {
+            collect_stmt_starts_from_exp(e, starts);
+        }
+        Ex::FuncCall(ref f, ref args) => {
+            collect_stmt_starts_from_exp(f, starts);
+            collect_stmt_starts_from_args(args, starts);
+        }
+        Ex::MethodCall(ref f, ref args) => {
+            collect_stmt_starts_from_exp(&(f.base.0), starts);
+            collect_stmt_starts_from_args(args, starts);
+        }
+        Ex::Index(ref e, ref key) | Ex::Bin(ref e, _, ref key) => {
+            collect_stmt_starts_from_exp(e, starts);
+            collect_stmt_starts_from_exp(key, starts);
+        }
+        _ => {}
+    }
+}
+
+fn collect_stmt_starts_from_args(args: &Args, starts: &mut HashSet<Pos>) {
+    match *args {
+        Args::List(ref exps) => {
+            for e in exps { collect_stmt_starts_from_exp(e, starts); }
+        }
+        Args::Str(_) => {}
+        Args::Table(ref table) => {
+            for &(ref k, ref v) in &table.items {
+                if let Some(ref k) = *k { collect_stmt_starts_from_exp(k, starts); }
+                collect_stmt_starts_from_exp(v, starts);
+            }
+        }
+    }
+}
+
+/// Formats a chunk from the tokens and the chunk parsed from them.
+///
+/// Returns `None` when the formatted code would not have the same tokens as the original,
+/// which is possible when the tokens have errors.
+pub fn format_chunk(source: &Source, tokens: &[NestedToken], chunk: &Chunk,
+                    options: &FormatOptions) -> Option<Vec<u8>> {
+    let file = match tokens.last().and_then(|tok| source.get_file(tok.tok.span.unit())) {
+        Some(file) => file,
+        None => return None,
+    };
+    let text = |span: Span| slice_to_bytes(source.slice_from_span(span));
+
+    // the first newline determines the newline for the entire output
+    let newline: &[u8] = {
+        let data = text(file.span());
+        match data.iter().position(|&c| c == b'\n' || c == b'\r') {
+            Some(i) if data[i] == b'\r' && data.get(i + 1) == Some(&b'\n') => b"\r\n",
+            Some(i) if data[i] == b'\r' => b"\r",
+            _ => b"\n",
+        }
+    };
+
+    // group meta comments into single items, up to (but excluding) the newline token
+    let mut items = Vec::new();
+    let mut eof = file.span().end();
+    let mut i = 0;
+    while i < tokens.len() {
+        let tok = &tokens[i].tok;
+        if is_meta_start(&tok.base) {
+            let begin = tok.span.begin();
+            while i < tokens.len() && tokens[i].tok.base != Tok::Punct(Punct::Newline) {
+                i += 1;
+            }
+            let end = tokens.get(i).map_or(eof, |tok| tok.tok.span.begin());
+            items.push((Item::Meta, Span::new(begin, end)));
+        } else {
+            match tok.base {
+                Tok::EOF => { eof = tok.span.begin(); break; }
+                Tok::Comment => items.push((Item::Comment, tok.span)),
+                ref tok_ => items.push((Item::Code(tok_), tok.span)),
+            }
+        }
+        i += 1;
+    }
+
+    let mut stmt_starts = HashSet::new();
+    collect_stmt_starts(&chunk.block.base, &mut stmt_starts);
+
+    let in_range = |pos: Pos| {
+        options.range.map_or(true, |range| {
+            range.unit() != pos.unit() || (range.begin() <= pos && pos <= range.end())
+        })
+    };
+
+    let mut out = Vec::new();
+    // openers with the base indentation of their lines
+    let mut stack: Vec<(&Tok, usize)> = Vec::new();
+    let mut prev: Option<Item> = None;
+    let mut last_code: Option<&Tok> = None;
+    let mut last_was_code = false; // ignoring comments; used to detect continued lines
+    let mut prev_unary = false;
+    let mut in_label = false;
+    let mut line_base = 0;
+    let mut prev_end = file.span().begin();
+
+    for &(item, span) in &items {
+        let gap = text(Span::new(prev_end, span.begin()));
+        let newlines = count_newlines(&gap);
+        let itemtext = text(span);
+        let formatting = in_range(span.begin());
+
+        if prev.is_none() || newlines > 0 {
+            let (base, indent) = match item {
+                Item::Code(tok) if is_closer(tok) => {
+                    let base = stack.last().map_or(0, |&(_, base)| base);
+                    (base, base)
+                }
+                _ => {
+                    let base = stack.last().map_or(0, |&(_, base)| base + 1);
+                    let continued = match item {
+                        Item::Code(_) => {
+                            last_was_code && !stmt_starts.contains(&span.begin()) &&
+                                !stack.last().map_or(false, |&(tok, _)| is_bracket(tok))
+                        }
+                        _ => false,
+                    };
+                    (base, if continued { base + 1 } else { base })
+                }
+            };
+            line_base = base;
+
+            if formatting {
+                if prev.is_some() {
+                    for _ in 0..newlines.min(2) {
+                        out.extend_from_slice(newline);
+                    }
+                }
+                for _ in 0..indent {
+                    out.extend_from_slice(options.indent.as_bytes());
+                }
+            } else {
+                out.extend_from_slice(&gap);
+            }
+        } else if formatting {
+            if needs_space(prev.unwrap(), item, &itemtext, prev_unary, in_label, !gap.is_empty()) {
+                out.push(b' ');
+            }
+        } else {
+            out.extend_from_slice(&gap);
+        }
+
+        match item {
+            Item::Comment | Item::Meta if formatting => out.extend_from_slice(trim_end(&itemtext)),
+            _ => out.extend_from_slice(&itemtext),
+        }
+
+        match item {
+            Item::Code(tok) => {
+                prev_unary = match *tok {
+                    Tok::Punct(Punct::Dash) | Tok::Punct(Punct::Tilde) |
+                    Tok::Punct(Punct::Hash) => !last_code.map_or(false, is_operand_end),
+                    _ => false,
+                };
+                if *tok == Tok::Punct(Punct::ColonColon) {
+                    in_label = !in_label;
+                }
+                if is_closer(tok) {
+                    stack.pop();
+                }
+                if is_opener(tok) {
+                    stack.push((tok, line_base));
+                }
+                last_code = Some(tok);
+                last_was_code = true;
+            }
+            Item::Meta => {
+                last_was_code = false;
+            }
+            Item::Comment => {}
+        }
+
+        prev = Some(item);
+        prev_end = span.end();
+    }
+
+    let gap = text(Span::new(prev_end, eof));
+    if in_range(eof) {
+        if !out.is_empty() {
+            out.extend_from_slice(newline);
+        }
+    } else {
+        out.extend_from_slice(&gap);
+    }
+    let trailing = text(Span::new(eof, file.span().end()));
+    out.extend_from_slice(&trailing);
+
+    // make sure that the formatted code has the same tokens
+    let mut formatted = Source::new();
+    let span = formatted.add(SourceFile::from_u8(file.path().to_owned(), out.clone()));
+    let mut iter = formatted.iter_from_span(span).unwrap();
+    let same = {
+        let lexer = Lexer::new(&mut iter, &NoReport);
+        let mut newtoks = lexer.map(|tok| tok.base);
+        tokens.iter().all(|tok| newtoks.next().as_ref() == Some(&tok.tok.base)) &&
+            newtoks.next().is_none()
+    };
+    if same { Some(out) } else { None }
+}
+
+/// An one-off function to format a chunk from a given span in the `Source`.
+///
+/// Any parsing error stops the formatting; `Ok(None)` is returned when
+/// the formatter failed to preserve the tokens, which is a bug.
+pub fn format_span(source: &Source, span: Span, options: &FormatOptions,
+                   report: &Report) -> kailua_diag::Result<Option<Vec<u8>>> {
+    use kailua_diag::Stop;
+
+    let report = TrackMaxKind::new(report);
+    let tokens = if let Some(mut iter) = source.iter_from_span(span) {
+        let mut lexer = Lexer::new(&mut iter, &report);
+        let nest = Nest::new(&mut lexer);
+        nest.collect::<Vec<_>>()
+    } else {
+        use kailua_diag::Reporter;
+        use message as m;
+        return report.fatal(span, m::NoFileForSpan {}).done();
+    };
+
+    let chunk = {
+        let mut iter = tokens.clone().into_iter();
+        Parser::new(&mut iter, &report).into_chunk()?
+    };
+    if !report.can_continue() {
+        return Err(Stop);
+    }
+
+    Ok(format_chunk(source, &tokens, &chunk, options))
+}
+
+#[cfg(test)]
+fn format_for_test(code: &[u8], options: &FormatOptions) -> Option<String> {
+    let mut source = Source::new();
+    let span = source.add(SourceFile::from_u8("dummy".to_owned(), code.to_owned()));
+    format_span(&source, span, options, &NoReport).unwrap()
+        .map(|out| String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn test_format_indent() {
+    let code = b"local function f(a,b)\n\
+                 if a then\n\
+                 return {\n\
+                 x=1,\n\
+                 y = function() return b end,\n\
+                 }\n\
+                 elseif b then return nil\n\
+                 else\n\
+                 g(function()\n\
+                 print( 'hi' )\n\
+                 end)\n\
+                 end\n\
+                 end\n";
+    assert_eq!(format_for_test(code, &FormatOptions::default()).unwrap(),
+               "local function f(a, b)\n\
+               \x20   if a then\n\
+               \x20       return {\n\
+               \x20           x = 1,\n\
+               \x20           y = function() return b end,\n\
+               \x20       }\n\
+               \x20   elseif b then return nil\n\
+               \x20   else\n\
+               \x20       g(function()\n\
+               \x20           print('hi')\n\
+               \x20       end)\n\
+               \x20   end\n\
+               end\n");
+}
+
+#[test]
+fn test_format_spaces() {
+    let code = b"local a=-1 -  - 2 ..#t\nlocal b = not a or t [1] . x:y ( ) {}\n\
+                 f { 1,2 } f{1} f\"x\" f 'y' ; f(...)\n";
+    assert_eq!(format_for_test(code, &FormatOptions::default()).unwrap(),
+               "local a = -1 - -2 .. #t\nlocal b = not a or t[1].x:y() {}\n\
+                f { 1, 2 } f{1} f\"x\" f 'y'; f(...)\n");
+}
+
+#[test]
+fn test_format_comments_and_lines() {
+    let code = b"\n\n--# type T = integer   \nlocal x = 1  --: T\n\n\n\n\
+                 local y = x +\n2 -- note\n--[[ long\n   comment ]]   \n\
+                 --v function(a: T)\nfunction g(a) end";
+    assert_eq!(format_for_test(code, &FormatOptions::default()).unwrap(),
+               "--# type T = integer\nlocal x = 1 --: T\n\n\
+                local y = x +\n    2 -- note\n--[[ long\n   comment ]]\n\
+                --v function(a: T)\nfunction g(a) end\n");
+}
+
+#[test]
+fn test_format_range() {
+    use kailua_env::Spanned;
+
+    let code = b"local a=1\nlocal b=2\nlocal c=3\n";
+    let mut source = Source::new();
+    let span = source.add(SourceFile::from_u8("dummy".to_owned(), code[..].to_owned()));
+    let poses: Vec<Pos> = source.iter_from_span(span).unwrap()
+                                .map(|d: Spanned<_>| d.span.begin()).collect();
+    let options = FormatOptions { range: Some(Span::new(poses[10], poses[19])),
+                                  ..FormatOptions::default() };
+    let out = format_span(&source, span, &options, &NoReport).unwrap().unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "local a=1\nlocal b = 2\nlocal c=3\n");
+}
+
+#[test]
+fn test_format_with_errors() {
+    let mut source = Source::new();
+    let span = source.add(SourceFile::from_u8("dummy".to_owned(), b"local = 1".to_vec()));
+    assert!(format_span(&source, span, &FormatOptions::default(), &NoReport).is_err());
+}
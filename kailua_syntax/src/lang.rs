@@ -1,7 +1,7 @@
 //! Source language description.
 //!
-//! Right now Kailua only supports the entirety of Lua 5.1 and its own extension syntax,
-//! but these types may allow other versions of Lua in the future.
+//! Right now Kailua supports the entirety of Lua 5.1, 5.2 and 5.3, LuaJIT 2.x
+//! and its own extension syntax, but these types may allow other versions of Lua in the future.
 
 use std::fmt;
 use kailua_diag::{Locale, Localize, Localized};
@@ -72,6 +72,9 @@ impl Localize for Language {
 }
 
 /// Lua version.
+///
+/// The ordering is not meaningful for LuaJIT, which lies between Lua 5.1 and 5.2;
+/// use the feature methods like `Lua::has_goto` instead.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Lua {
     /// Lua 5.1.
@@ -82,6 +85,9 @@ pub enum Lua {
 
     /// Lua 5.3.
     Lua53 = 0x53,
+
+    /// LuaJIT 2.x, which is Lua 5.1 with a part of Lua 5.2 extensions and cdata literals.
+    LuaJIT = 0x20,
 }
 
 impl Lua {
@@ -90,6 +96,18 @@ impl Lua {
             0x51 => Some(Lua::Lua51),
             0x52 => Some(Lua::Lua52),
             0x53 => Some(Lua::Lua53),
+            0x20 => Some(Lua::LuaJIT),
+            _ => None,
+        }
+    }
+
+    /// Returns the Lua version set by `--# open` with given library name, if any.
+    pub fn from_library_name(name: &[u8]) -> Option<Lua> {
+        match name {
+            b"lua51" => Some(Lua::Lua51),
+            b"lua52" => Some(Lua::Lua52),
+            b"lua53" => Some(Lua::Lua53),
+            b"luajit" => Some(Lua::LuaJIT),
             _ => None,
         }
     }
@@ -99,8 +117,49 @@ impl Lua {
             Lua::Lua51 => "Lua 5.1",
             Lua::Lua52 => "Lua 5.2",
             Lua::Lua53 => "Lua 5.3",
+            Lua::LuaJIT => "LuaJIT 2.x",
         }
     }
+
+    /// Returns true if `goto` and labels are available.
+    pub fn has_goto(&self) -> bool {
+        *self != Lua::Lua51
+    }
+
+    /// Returns true if `\x` and `\z` escape sequences are available in strings.
+    pub fn has_new_escapes(&self) -> bool {
+        *self != Lua::Lua51
+    }
+
+    /// Returns true if global names are resolved through `_ENV`.
+    pub fn has_env(&self) -> bool {
+        *self == Lua::Lua52 || *self == Lua::Lua53
+    }
+
+    /// Returns true if `break` should be the last statement in the block.
+    pub fn has_last_break(&self) -> bool {
+        *self == Lua::Lua51 || *self == Lua::LuaJIT
+    }
+
+    /// Returns true if variadic functions define an implicit `arg` local variable.
+    pub fn has_legacy_arg(&self) -> bool {
+        *self == Lua::Lua51
+    }
+
+    /// Returns true if bitwise and floor division operators are available.
+    pub fn has_bitwise_ops(&self) -> bool {
+        *self == Lua::Lua53
+    }
+
+    /// Returns true if numbers with a fraction or an exponent are always floats.
+    pub fn has_float_literals(&self) -> bool {
+        *self == Lua::Lua53
+    }
+
+    /// Returns true if cdata number literals like `42LL` are available.
+    pub fn has_cdata_literals(&self) -> bool {
+        *self == Lua::LuaJIT
+    }
 }
 
 impl fmt::Debug for Lua {
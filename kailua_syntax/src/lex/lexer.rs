@@ -7,12 +7,25 @@ use kailua_env::{SourceData, Pos, Span, Spanned, WithLoc};
 use kailua_env::SourceData::{U8, U16, EOF};
 use kailua_diag::{Report, Reporter, Localize};
 
-use super::{Tok, Punct, Keyword};
+use super::{Tok, Punct, Keyword, NumSuffix};
 
 fn is_digit(c: SourceData) -> bool {
     match c { U8(b'0'...b'9') => true, _ => false }
 }
 
+fn is_hex_digit(c: SourceData) -> bool {
+    match c { U8(b'A'...b'F') | U8(b'a'...b'f') | U8(b'0'...b'9') => true, _ => false }
+}
+
+fn hex_digit_value(c: SourceData) -> u8 {
+    match c {
+        U8(d @ b'0'...b'9') => d - b'0',
+        U8(d @ b'A'...b'F') => d - b'A' + 10,
+        U8(d @ b'a'...b'f') => d - b'a' + 10,
+        _ => panic!("hex_digit_value with a non-hexadecimal digit"),
+    }
+}
+
 fn normalize_data(c: SourceData) -> SourceData {
     // normalize ASCII letters to U8, so that we can easily check against them
     match c {
@@ -269,9 +282,11 @@ impl<'a> Lexer<'a> {
         Ok(true)
     }
 
-    // assumes that the first quote is already read
-    fn scan_quoted_string<F>(&mut self, begin: Pos, quote: u8, mut f: F) -> diag::Result<()>
+    // assumes that the first quote is already read.
+    // returns true if the string has escape sequences only available since Lua 5.2.
+    fn scan_quoted_string<F>(&mut self, begin: Pos, quote: u8, mut f: F) -> diag::Result<bool>
             where F: FnMut(u8) {
+        let mut future_escapes = false;
         loop {
             let lastpos = self.pos();
             match self.read() {
@@ -305,6 +320,30 @@ impl<'a> Lexer<'a> {
                         }
                         f(n)
                     },
+                    U8(b'x') => { // exactly two hexadecimal digits [5.2+]
+                        let d1 = self.try(is_hex_digit);
+                        let d2 = if d1.is_some() { self.try(is_hex_digit) } else { None };
+                        if let (Some(d1), Some(d2)) = (d1, d2) {
+                            future_escapes = true;
+                            f(hex_digit_value(d1) << 4 | hex_digit_value(d2));
+                        } else {
+                            self.report.error(lastpos..self.pos(),
+                                              m::UnrecognizedEscapeInString {}).done()?;
+                        }
+                    },
+                    U8(b'z') => { // skips following whitespaces [5.2+]
+                        future_escapes = true;
+                        if self.meta {
+                            // a newline ends the meta block and thus cannot be skipped
+                            self.scan_while(|c| c == U8(b' ') || c == U8(b'\t'), |_| {});
+                        } else {
+                            self.scan_while(|c| match c {
+                                U8(b' ') | U8(b'\t') | U8(b'\r') | U8(b'\n') |
+                                U8(b'\x0b') | U8(b'\x0c') => true,
+                                _ => false,
+                            }, |_| {});
+                        }
+                    },
                     U8(_) | U16(_) => {
                         self.report.error(lastpos..self.pos(), m::UnrecognizedEscapeInString {})
                                    .done()?;
@@ -337,7 +376,7 @@ impl<'a> Lexer<'a> {
                 },
             }
         }
-        Ok(())
+        Ok(future_escapes)
     }
 
     pub fn next_token(&mut self) -> diag::Result<Option<Spanned<Tok>>> {
@@ -362,8 +401,9 @@ impl<'a> Lexer<'a> {
                 (@token Comment)          => (Tok::Comment);
                 (@token Keyword($e:expr)) => (Tok::Keyword($e));
                 (@token Name($e:expr))    => (Tok::Name($e));
-                (@token Num($e:expr))     => (Tok::Num($e));
-                (@token Str($e:expr))     => (Tok::Str($e));
+                (@token Num($e:expr, $f:expr)) => (Tok::Num($e, $f));
+                (@token CDataNum($e:expr, $s:expr)) => (Tok::CDataNum($e, $s));
+                (@token Str($e:expr, $f:expr)) => (Tok::Str($e, $f));
                 (@token $i:ident)         => (Tok::Punct(Punct::$i));
 
                 (meta: $($t:tt)*) => ({
@@ -397,23 +437,18 @@ impl<'a> Lexer<'a> {
 
                 // numbers
                 U8(c @ b'0'...b'9') => {
-                    if c == b'0' && self.try(|c| c == U8(b'x')).is_some() {
+                    let mut float = false;
+                    let v = if c == b'0' && self.try(|c| c == U8(b'x')).is_some() {
                         // hexadecimal
                         let mut num = Vec::new();
-                        self.scan_while(
-                            |c| match c {
-                                U8(b'A'...b'F') | U8(b'a'...b'f') | U8(b'0'...b'9') => true,
-                                _ => false,
-                            },
-                            |c| num.push(c.u8()));
+                        self.scan_while(is_hex_digit, |c| num.push(c.u8()));
 
                         let s = str::from_utf8(&num).unwrap();
                         if s.is_empty() {
-                            self.report.error(begin..self.pos(), m::InvalidNumber {}).done()?;
-                            // continue reading other tokens
+                            None
                         } else if s.len() <= 16 {
                             let v = u64::from_str_radix(s, 16).unwrap();
-                            return tok!(Num(v as f64));
+                            Some(v as f64)
                         } else {
                             // uh, this is possible when `0x` is followed by 17+ hex digits.
                             // it is still a valid number however,
@@ -424,16 +459,18 @@ impl<'a> Lexer<'a> {
                             // so we won't care.
                             let v = u64::from_str_radix(&s[..16], 16).unwrap();
                             let shift = 4 * (s.len() - 16);
-                            return tok!(Num(v as f64 * (shift as f64).exp2()));
+                            Some(v as f64 * (shift as f64).exp2())
                         }
                     } else {
                         let mut num = vec![c];
                         self.scan_while(is_digit, |c| num.push(c.u8()));
                         if let Some(c) = self.try(|c| c == U8(b'.')) {
+                            float = true;
                             num.push(c.u8());
                             self.scan_while(is_digit, |c| num.push(c.u8()));
                         }
                         if let Some(c) = self.try(|c| c == U8(b'e') || c == U8(b'E')) {
+                            float = true;
                             num.push(c.u8());
                             if let Some(c) = self.try(|c| c == U8(b'-')) {
                                 num.push(c.u8());
@@ -441,22 +478,40 @@ impl<'a> Lexer<'a> {
                             self.scan_while(is_digit, |c| num.push(c.u8()));
                         }
 
-                        if let Ok(s) = str::from_utf8(&num) {
-                            if let Ok(v) = s.parse::<f64>() {
-                                return tok!(Num(v));
-                            }
-                        }
+                        str::from_utf8(&num).ok().and_then(|s| s.parse::<f64>().ok())
+                    };
 
-                        self.report.error(begin..self.pos(), m::InvalidNumber {}).done()?;
-                        // continue reading other tokens
+                    // a number immediately followed by a name is either a LuaJIT cdata
+                    // literal (e.g. `42LL`) or malformed; the parser checks the language.
+                    // 64-bit integer literals cannot have a fraction or an exponent.
+                    let mut suffix = Vec::new();
+                    self.scan_while(
+                        |c| match c {
+                            U8(b'A'...b'Z') | U8(b'a'...b'z') | U8(b'0'...b'9') | U8(b'_') => true,
+                            _ => false,
+                        },
+                        |c| match c.u8() {
+                            c @ b'A'...b'Z' => suffix.push(c - b'A' + b'a'),
+                            c => suffix.push(c),
+                        });
+
+                    match (v, &suffix[..]) {
+                        (Some(v), b"") => return tok!(Num(v, float)),
+                        (Some(v), b"ll") if !float => return tok!(CDataNum(v, NumSuffix::Int64)),
+                        (Some(v), b"ull") if !float => return tok!(CDataNum(v, NumSuffix::UInt64)),
+                        (Some(v), b"i") => return tok!(CDataNum(v, NumSuffix::Imaginary)),
+                        (_, _) => {
+                            self.report.error(begin..self.pos(), m::InvalidNumber {}).done()?;
+                            // continue reading other tokens
+                        }
                     }
                 }
 
                 // strings
                 U8(q @ b'\'') | U8(q @ b'"') => {
                     let mut s = Vec::new();
-                    self.scan_quoted_string(begin, q, |c| s.push(c))?;
-                    return tok!(Str(s.into()));
+                    let future_escapes = self.scan_quoted_string(begin, q, |c| s.push(c))?;
+                    return tok!(Str(s.into(), future_escapes));
                 }
 
                 U8(b'[') => {
@@ -471,7 +526,7 @@ impl<'a> Lexer<'a> {
                             &m::LongStringStart {},
                             &m::NoNewlineInLongStringInMeta {},
                         )?;
-                        return tok!(Str(s.into()));
+                        return tok!(Str(s.into(), false));
                     }
                     return tok!(LBracket);
                 }
@@ -523,7 +578,10 @@ impl<'a> Lexer<'a> {
 
                 U8(b'+') => return tok!(Plus),
                 U8(b'*') => return tok!(Star),
-                U8(b'/') => return tok!(Slash),
+                U8(b'/') => {
+                    if let Some(_) = self.try(|c| c == U8(b'/')) { return tok!(SlashSlash); }
+                    return tok!(Slash);
+                },
                 U8(b'%') => return tok!(Percent),
                 U8(b'^') => return tok!(Caret),
                 U8(b'#') => return tok!(Hash),
@@ -533,15 +591,18 @@ impl<'a> Lexer<'a> {
                 },
                 U8(b'~') => {
                     if let Some(_) = self.try(|c| c == U8(b'=')) { return tok!(TildeEq); }
-                    self.report.error(begin..self.pos(), m::UnexpectedChar {}).done()?;
-                    // continue reading other tokens
+                    return tok!(Tilde);
                 },
+                U8(b'&') => return tok!(Amp),
+                U8(b'|') => return tok!(Pipe),
                 U8(b'<') => {
                     if let Some(_) = self.try(|c| c == U8(b'=')) { return tok!(LtEq); }
+                    if let Some(_) = self.try(|c| c == U8(b'<')) { return tok!(LtLt); }
                     return tok!(Lt);
                 },
                 U8(b'>') => {
                     if let Some(_) = self.try(|c| c == U8(b'=')) { return tok!(GtEq); }
+                    if let Some(_) = self.try(|c| c == U8(b'>')) { return tok!(GtGt); }
                     return tok!(Gt);
                 },
                 U8(b'(') => return tok!(LParen),
@@ -550,7 +611,10 @@ impl<'a> Lexer<'a> {
                 U8(b'}') => return tok!(RBrace),
                 U8(b']') => return tok!(RBracket),
                 U8(b';') => return tok!(Semicolon),
-                U8(b':') => return tok!(Colon),
+                U8(b':') => {
+                    if let Some(_) = self.try(|c| c == U8(b':')) { return tok!(ColonColon); }
+                    return tok!(Colon);
+                },
                 U8(b',') => return tok!(Comma),
                 U8(b'.') => {
                     if let Some(_) = self.try(|c| c == U8(b'.')) {
@@ -572,7 +636,6 @@ impl<'a> Lexer<'a> {
                 },
                 U8(b'?') if self.meta => return tok!(Ques),
                 U8(b'!') if self.meta => return tok!(Bang),
-                U8(b'|') if self.meta => return tok!(Pipe),
 
                 U8(_) | U16(_) => {
                     // try to consume more invalid multi-byte characters in a row
@@ -26,13 +26,24 @@ pub enum Tok {
     Keyword(Keyword),
 
     /// A number.
-    Num(f64),
+    ///
+    /// The flag is set when the number has a fraction or an exponent (e.g. `7.0` or `1e3`).
+    /// Lua 5.3 distinguishes such numbers from integers.
+    Num(f64, bool),
+
+    /// A LuaJIT cdata number with a suffix (e.g. `42LL`).
+    ///
+    /// This is always lexed, but the parser only accepts it in LuaJIT.
+    CDataNum(f64, NumSuffix),
 
     /// A name (either an identifier or a quoted name in the meta block).
     Name(Name),
 
     /// A string (either `"string"` or `[[string]]`).
-    Str(Str),
+    ///
+    /// The flag is set when the string has `\x` or `\z` escape sequences.
+    /// They are always lexed, but the parser only accepts them since Lua 5.2.
+    Str(Str, bool),
 
     /// The end of file.
     ///
@@ -49,12 +60,14 @@ impl Localize for Tok {
             (_,    &Tok::Comment)    => write!(f, "a comment"),
             (_,    &Tok::Punct(p))   => write!(f, "{}", Localized::new(&p, locale)),
             (_,    &Tok::Keyword(w)) => write!(f, "{}", Localized::new(&w, locale)),
-            ("ko", &Tok::Num(_))     => write!(f, "숫자"),
-            (_,    &Tok::Num(_))     => write!(f, "a number"),
+            ("ko", &Tok::Num(..))    => write!(f, "숫자"),
+            (_,    &Tok::Num(..))    => write!(f, "a number"),
+            ("ko", &Tok::CDataNum(..)) => write!(f, "cdata 숫자"),
+            (_,    &Tok::CDataNum(..)) => write!(f, "a cdata number"),
             ("ko", &Tok::Name(_))    => write!(f, "이름"),
             (_,    &Tok::Name(_))    => write!(f, "a name"),
-            ("ko", &Tok::Str(_))     => write!(f, "문자열 리터럴"),
-            (_,    &Tok::Str(_))     => write!(f, "a string literal"),
+            ("ko", &Tok::Str(..))    => write!(f, "문자열 리터럴"),
+            (_,    &Tok::Str(..))    => write!(f, "a string literal"),
             ("ko", &Tok::EOF)        => write!(f, "파일의 끝"),
             (_,    &Tok::EOF)        => write!(f, "the end of file"),
         }
@@ -67,6 +80,29 @@ impl<'a> Localize for &'a Tok {
     }
 }
 
+/// A suffix for LuaJIT cdata numbers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NumSuffix {
+    /// `LL`, which makes an `int64_t` cdata.
+    Int64,
+
+    /// `ULL`, which makes an `uint64_t` cdata.
+    UInt64,
+
+    /// `i`, which makes a `complex` cdata.
+    Imaginary,
+}
+
+impl NumSuffix {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            NumSuffix::Int64 => "LL",
+            NumSuffix::UInt64 => "ULL",
+            NumSuffix::Imaginary => "i",
+        }
+    }
+}
+
 macro_rules! define_puncts {
     ($ty:ident |$locale:ident|: $($i:ident $t:expr, #[$m:meta])*) => (
         /// A punctuation.
@@ -102,7 +138,7 @@ define_puncts! { Punct |locale|:
     Lt          "`<`",      /// `<`.
     Gt          "`>`",      /// `>`.
     Eq          "`=`",      /// `=`.
-    Amp         "`&`",      /// `&`. [5.3+]
+    Amp         "`&`",      /// `&`. [5.3+ or M]
     Tilde       "`~`",      /// `~`. [5.3+]
     Pipe        "`|`",      /// `|`. [5.3+ or M]
     LtLt        "`<<`",     /// `<<`. [5.3+]
@@ -33,6 +33,10 @@
 //!    The chunk also contains a list of spanned scopes, names in each scope,
 //!    globally assigned names and additional hints for each token.
 //!    This allows for basic analyses without even touching the type checker.
+//!
+//!    When the file has been changed and tokens are produced again,
+//!    `kailua_syntax::reparse` can update the previous chunk by parsing
+//!    only the function body containing the change.
 
 #[macro_use] extern crate parse_generics_shim;
 #[macro_use] extern crate kailua_diag;
@@ -53,6 +57,8 @@ pub mod lex;
 pub mod string;
 pub mod ast;
 mod parser;
+pub mod format;
+pub mod reparse;
 
 /// An one-off function to parse a chunk from a given span in the `Source`.
 ///
@@ -64,7 +70,9 @@ pub fn parse_chunk(source: &Source, span: Span, report: &Report) -> kailua_diag:
         let mut lexer = Lexer::new(&mut iter, &report);
         let mut nest = Nest::new(&mut lexer);
         let parser = Parser::new(&mut nest, &report);
-        parser.into_chunk()
+        let mut chunk = parser.into_chunk()?;
+        chunk.resolve_ignores(source);
+        Ok(chunk)
     } else {
         use kailua_diag::Reporter;
         report.fatal(span, message::NoFileForSpan {}).done()
@@ -1,4 +1,5 @@
 use std::ops;
+use std::mem;
 use std::iter;
 use std::u8;
 use std::i32;
@@ -16,7 +17,7 @@ use string::{Str, Name};
 use ast::{NameRef, RenameRef, Var, Seq, Sig, Attr, AttrValue, Args, Table};
 use ast::{Ex, Exp, UnOp, BinOp, SelfParam, TypeScope, St, Stmt, Block};
 use ast::{M, MM, K, Kind, SlotKind, FuncKind, TypeSpec, Varargs, Returns};
-use ast::{LocalName, LocalNameKind, TokenAux, Chunk};
+use ast::{LocalName, LocalNameKind, TokenAux, Ignore, IgnoreScope, Chunk};
 
 /// The parser.
 pub struct Parser<'a> {
@@ -55,6 +56,15 @@ pub struct Parser<'a> {
 
     // auxiliary info for each *input* token (i.e. including elided tokens)
     token_aux: Vec<TokenAux>,
+
+    // `goto`s in the current function not yet resolved to labels [5.2+]
+    pending_gotos: Vec<Spanned<Name>>,
+
+    // `--# ignore` directives read so far; `IgnoreScope::Rest` spans are completed at the end
+    ignores: Vec<Ignore>,
+
+    // true if `--# strict` has been read
+    strict: bool,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -249,6 +259,7 @@ impl Eq for IndexedName {
 #[derive(Clone, PartialEq)]
 struct Presig {
     prefix: Spanned<bool>, // true for `method`, false for `function` (span included)
+    tparams: Vec<Spanned<Name>>,
     args: Spanned<Seq<Spanned<TypeSpec<Spanned<IndexedName>>>, Spanned<Option<Spanned<Kind>>>>>,
     returns: Option<Returns>,
 }
@@ -353,9 +364,18 @@ impl<'a> Parser<'a> {
     /// Creates a new nesting analyzer with given stream of spanned tokens
     /// with nesting informations and the report receiver.
     pub fn new(iter: &'a mut Iterator<Item=NestedToken>, report: &'a Report) -> Parser<'a> {
+        // XXX should be configurable
+        Parser::with_language(iter, report, Language::new(Lua::Lua51, Kailua::Kailua10))
+    }
+
+    /// Same to `Parser::new` but starts with given language instead of Lua 5.1.
+    ///
+    /// The language can be still changed by `--# open` in the input.
+    pub fn with_language(iter: &'a mut Iterator<Item=NestedToken>, report: &'a Report,
+                         language: Language) -> Parser<'a> {
         let mut parser = Parser {
             iter: iter.fuse(),
-            language: Language::new(Lua::Lua51, Kailua::Kailua10), // XXX should be configurable
+            language: language,
             elided_newline: None,
             lookahead: None,
             lookahead2: None,
@@ -373,6 +393,9 @@ impl<'a> Parser<'a> {
             scope_stack: Vec::new(),
             block_depth: 0,
             token_aux: Vec::new(),
+            pending_gotos: Vec::new(),
+            ignores: Vec::new(),
+            strict: false,
         };
 
         // read the first token and fill the last_span
@@ -402,7 +425,7 @@ impl<'a> Parser<'a> {
 
                 // `goto` is converted to a name on Lua 5.1
                 let lua = self.language.lua();
-                if lua < Lua::Lua52 {
+                if !lua.has_goto() {
                     if let Tok::Keyword(kw @ Keyword::Goto) = t.tok.base {
                         // XXX don't want to make this failable
                         let _ = self.warn(t.tok.span,
@@ -413,6 +436,16 @@ impl<'a> Parser<'a> {
                     }
                 }
 
+                // `\x` and `\z` escapes are lexed regardless of the language
+                if !lua.has_new_escapes() {
+                    if let Tok::Str(_, true) = t.tok.base {
+                        let _ = self.error(t.tok.span,
+                                           m::FutureEscapeInString { current: lua,
+                                                                     future: Lua::Lua52 })
+                                    .done();
+                    }
+                }
+
                 Some((token_idx, t))
             } else {
                 None
@@ -872,6 +905,32 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // in Lua 5.2 and later a global name `x` is a shorthand for `_ENV.x`,
+    // which should be made explicit when `_ENV` is shadowed by a local variable.
+    // returns the local `_ENV` variable in that case.
+    fn resolve_local_env(&mut self) -> Option<ScopedId> {
+        if self.language.lua().has_env() {
+            self.resolve_local_name_without_idx(&Name::from(b"_ENV"[..].to_owned()))
+        } else {
+            None
+        }
+    }
+
+    // resolves the name and converts it to an expression (possibly indexing the local `_ENV`)
+    fn resolve_name_to_exp(&mut self, name: Spanned<IndexedName>) -> Spanned<Exp> {
+        let span = name.span;
+        let nameref = self.resolve_name(name.base);
+        if let NameRef::Global(name) = nameref {
+            if let Some(env) = self.resolve_local_env() {
+                let env = Box::new(Ex::Var(NameRef::Local(env).without_loc())).without_loc();
+                return Box::new(Ex::IndexName(env, name.with_loc(span))).with_loc(span);
+            }
+            Box::new(Ex::Var(NameRef::Global(name).with_loc(span))).with_loc(span)
+        } else {
+            Box::new(Ex::Var(nameref.with_loc(span))).with_loc(span)
+        }
+    }
+
     fn add_spanned_local_name_with_prev_span(&mut self, scope: Scope, name: Spanned<Name>,
                                              kind: LocalNameKind)
         -> Result<(Spanned<ScopedId>, Option<Span>)>
@@ -927,11 +986,19 @@ impl<'a> Parser<'a> {
             let end = self.pos();
             while self.scope_stack.len() > nscopes {
                 let (scope, scopebegin) = self.scope_stack.pop().unwrap();
-                self.scope_map.set_span(scope.with_loc(scopebegin..end));
+                self.set_scope_span(scope.with_loc(scopebegin..end));
             }
         }
     }
 
+    fn set_scope_span(&mut self, scope: Spanned<Scope>) {
+        // built-in definitions are parsed from the code without a source,
+        // so their local scopes cannot be located and are left unspanned
+        if scope.span.is_source_dependent() {
+            self.scope_map.set_span(scope);
+        }
+    }
+
     fn set_token_aux(&mut self, TokenIdx(idx): TokenIdx, aux: TokenAux) {
         self.token_aux[idx] = aux;
     }
@@ -958,21 +1025,66 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // returns true if the statement should be the final one in the block
+    fn is_last_stmt(&self, stmt: &St) -> bool {
+        match *stmt {
+            St::Return(..) => true,
+            // Lua 5.2 made `break` a normal statement (it is a disguised `goto`)
+            St::Break => self.language.lua().has_last_break(),
+            _ => false,
+        }
+    }
+
+    fn add_label(&mut self, labels: &mut HashMap<Name, Span>, stmt: &St) -> Result<()> {
+        if let St::Label(ref name) = *stmt {
+            match labels.entry(name.base.clone()) {
+                hash_map::Entry::Occupied(e) => {
+                    self.error(name, m::DuplicateLabelInSameBlock { name: &name.base })
+                        .note(*e.get(), m::PreviousLabelInSameBlock {})
+                        .done()?;
+                }
+                hash_map::Entry::Vacant(e) => {
+                    e.insert(name.span);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // removes pending `goto`s (only those after the `npending`-th) resolved by given labels.
+    // remaining `goto`s may be resolved later by outer blocks.
+    fn resolve_pending_gotos(&mut self, npending: usize, labels: &HashMap<Name, Span>) {
+        if !labels.is_empty() && self.pending_gotos.len() > npending {
+            let unresolved: Vec<_> = self.pending_gotos.drain(npending..)
+                                                       .filter(|name| !labels.contains_key(name))
+                                                       .collect();
+            self.pending_gotos.extend(unresolved);
+        }
+    }
+
+    // reports all remaining `goto`s, which cannot be resolved by any visible label
+    fn report_unresolved_gotos(&mut self, gotos: Vec<Spanned<Name>>) -> Result<()> {
+        for name in gotos {
+            self.error(&name, m::NoVisibleLabel { name: &name.base }).done()?;
+        }
+        Ok(())
+    }
+
     fn _parse_block(&mut self) -> Result<Spanned<Block>> {
         trace!("parsing block");
 
         let begin = self.pos();
+        let npending = self.pending_gotos.len();
         let mut stmts = Vec::new();
+        let mut labels = HashMap::new();
         let mut pastlast = false;
         let mut excessspan = None;
         while let Some(stmt) = self.try_parse_stmt()? {
             self.may_expect(Punct::Semicolon);
 
             // if the statement is the final one, further parsing is an error
-            let last = match &*stmt.base {
-                &St::Return(..) | &St::Break => true,
-                _ => false,
-            };
+            let last = self.is_last_stmt(&stmt.base);
+            self.add_label(&mut labels, &stmt.base)?;
 
             if pastlast {
                 excessspan = Some(excessspan.unwrap_or(Span::dummy()) | stmt.span);
@@ -981,6 +1093,7 @@ impl<'a> Parser<'a> {
             stmts.push(stmt);
         }
 
+        self.resolve_pending_gotos(npending, &labels);
         if let Some(span) = excessspan {
             self.error(span, m::StmtAfterReturnOrBreak {}).done()?;
         }
@@ -1023,7 +1136,9 @@ impl<'a> Parser<'a> {
         trace!("parsing block then EOF");
 
         let begin = self.pos();
+        let npending = self.pending_gotos.len();
         let mut stmts = Vec::new();
+        let mut labels = HashMap::new();
         let mut pastlast = false;
         let mut excessspan = None;
         loop {
@@ -1049,10 +1164,8 @@ impl<'a> Parser<'a> {
             self.may_expect(Punct::Semicolon);
 
             // if the statement is the final one, further parsing is an error
-            let last = match &*stmt.base {
-                &St::Return(..) | &St::Break => true,
-                _ => false,
-            };
+            let last = self.is_last_stmt(&stmt.base);
+            self.add_label(&mut labels, &stmt.base)?;
 
             if pastlast {
                 excessspan = Some(excessspan.unwrap_or(Span::dummy()) | stmt.span);
@@ -1061,6 +1174,7 @@ impl<'a> Parser<'a> {
             stmts.push(stmt);
         }
 
+        self.resolve_pending_gotos(npending, &labels);
         if let Some(span) = excessspan {
             self.error(span, m::StmtAfterReturnOrBreak {}).done()?;
         }
@@ -1109,6 +1223,17 @@ impl<'a> Parser<'a> {
         trace!("parsing stmt");
         let begin = self.pos();
 
+        // if there exists a spec stmt return it first.
+        // a spec may be empty, so loop until no spec exists or a spec is found.
+        // this should be done before the function spec, which may follow empty specs.
+        loop {
+            match self.try_parse_kailua_spec()? {
+                Some(Some(spec)) => return Ok(Some(spec)),
+                Some(None) => continue,
+                None => break,
+            }
+        }
+
         let funcspec = self.try_parse_kailua_func_spec()?;
         if let Some(ref funcspec) = funcspec {
             // limit the possible lookahead
@@ -1122,8 +1247,7 @@ impl<'a> Parser<'a> {
             }
         }
 
-        // if there exists a spec stmt return it first.
-        // a spec may be empty, so loop until no spec exists or a spec is found.
+        // a spec stmt may also follow a function spec without a declaration
         loop {
             match self.try_parse_kailua_spec()? {
                 Some(Some(spec)) => return Ok(Some(spec)),
@@ -1223,12 +1347,22 @@ impl<'a> Parser<'a> {
 
             Tok::Keyword(Keyword::Function) => {
                 let namesbegin = self.pos();
-                let rootname = self.parse_name()?.map(|name| self.resolve_name(name));
+                let mut names = Vec::new();
+                let mut rootname = self.parse_name()?.map(|name| self.resolve_name(name));
+                if let NameRef::Global(_) = rootname.base {
+                    if let Some(env) = self.resolve_local_env() {
+                        // `function NAME...` is `function _ENV.NAME...` here
+                        let rootspan = rootname.span;
+                        let name = mem::replace(&mut rootname.base, NameRef::Local(env));
+                        if let NameRef::Global(name) = name {
+                            names.push(name.with_loc(rootspan));
+                        }
+                    }
+                }
                 if let NameRef::Global(ref name) = rootname.base {
                     // this will assign a global name, and is handled like a global assignment
                     self.global_scope.entry(name.clone()).or_insert(rootname.span);
                 }
-                let mut names = Vec::new();
                 while self.may_expect(Punct::Dot) {
                     let name = self.parse_name()?;
                     names.push(name.map(|n| n.name));
@@ -1340,6 +1474,26 @@ impl<'a> Parser<'a> {
 
             Tok::Keyword(Keyword::Break) => Box::new(St::Break);
 
+            // `goto` is a keyword only in Lua 5.2 and later (see `Parser::_next`)
+            Tok::Keyword(Keyword::Goto) => {
+                let name = self.parse_name()?.map(|n| n.name);
+                self.pending_gotos.push(name.clone());
+                Box::new(St::Goto(name))
+            };
+
+            Tok::Punct(Punct::ColonColon) in span => {
+                let lua = self.language.lua();
+                if !lua.has_goto() {
+                    let read = Tok::Punct(Punct::ColonColon);
+                    self.error(span, m::FutureSyntax { read: &read, current: lua,
+                                                       future: Lua::Lua52 })
+                        .done()?;
+                }
+                let name = self.parse_name()?.map(|n| n.name);
+                self.expect(Punct::ColonColon)?;
+                Box::new(St::Label(name))
+            };
+
             'unread: _ => {
                 // only prefixexp can appear at this position, but it is very common that
                 // incomplete expression results in a (partial) non-prefix expression.
@@ -1547,7 +1701,7 @@ impl<'a> Parser<'a> {
         let end = end.unwrap_or_else(|| self.last_pos());
         returns = self.try_parse_kailua_rettype_spec()?;
 
-        let (attrs, args, returns) = match funcspec {
+        let (attrs, tparams, args, returns) = match funcspec {
             Some(Spanned { base: (attrs, Some(presig)), .. }) => {
                 // before any checking, we should ensure that every parameter has a type attached
                 // (`self` is a notable exception, but should have been removed by now)
@@ -1625,15 +1779,16 @@ impl<'a> Parser<'a> {
                 }
 
                 let args = Seq { head: combinedargs, tail: presig.base.args.base.tail };
-                (attrs, Ok(args.with_loc(presig.base.args.span)), presig.base.returns)
+                (attrs, presig.base.tparams, Ok(args.with_loc(presig.base.args.span)),
+                 presig.base.returns)
             },
 
             Some(Spanned { base: (attrs, None), .. }) => {
-                (attrs, Err(args), returns.map(|ret| ret.base))
+                (attrs, Vec::new(), Err(args), returns.map(|ret| ret.base))
             },
 
             None => {
-                (Vec::new(), Err(args), returns.map(|ret| ret.base))
+                (Vec::new(), Vec::new(), Err(args), returns.map(|ret| ret.base))
             },
         };
 
@@ -1646,9 +1801,11 @@ impl<'a> Parser<'a> {
             }.with_loc(begin..end)
         });
 
+        // labels are not visible across function boundaries
+        let outer_gotos = mem::replace(&mut self.pending_gotos, Vec::new());
+
         // resolve every parameter (including self)
-        let ((selfparam, args), scope, block) = self.parse_block_end_with_scope(move |parser,
-                                                                                      scope| {
+        let ret = self.parse_block_end_with_scope(move |parser, scope| {
             // attach all arguments to the function body scope
             // XXX should also mention all excess arguments
             // TODO should we add varargs?
@@ -1672,7 +1829,7 @@ impl<'a> Parser<'a> {
             }
 
             let tail = if let Some(varargspec) = args.base.tail {
-                let arg = if parser.language.lua() <= Lua::Lua51 { // legacy varargs
+                let arg = if parser.language.lua().has_legacy_arg() { // legacy varargs
                     let argname = Name::from(b"arg"[..].to_owned()).with_loc(&varargspec);
                     let (id, prevspan) = parser.add_spanned_local_name_with_prev_span(
                         scope, argname, LocalNameKind::ImplicitLegacyArg,
@@ -1693,9 +1850,13 @@ impl<'a> Parser<'a> {
 
             let args = Seq { head: head, tail: tail }.with_loc(args.span);
             Ok((selfparam, args))
-        })?;
+        });
 
-        let sig = Sig { attrs: attrs, args: args, returns: returns };
+        let inner_gotos = mem::replace(&mut self.pending_gotos, outer_gotos);
+        let ((selfparam, args), scope, block) = ret?;
+        self.report_unresolved_gotos(inner_gotos)?;
+
+        let sig = Sig { attrs: attrs, tparams: tparams, args: args, returns: returns };
         Ok(Some((selfparam, sig, scope, block)))
     }
 
@@ -1782,7 +1943,7 @@ impl<'a> Parser<'a> {
                 Ok(Some(Args::List(args).with_loc(span)))
             };
 
-            Tok::Str(s) in span => Ok(Some(Args::Str(s).with_loc(span)));
+            Tok::Str(s, _) in span => Ok(Some(Args::Str(s).with_loc(span)));
 
             Tok::Punct(Punct::LBrace) => {
                 let fields = self.parse_table_body()?;
@@ -1804,8 +1965,7 @@ impl<'a> Parser<'a> {
 
             Tok::Name(name) in span => {
                 let name = self.indexed_name_from(name, span);
-                let nameref = self.resolve_name(name.base);
-                Box::new(Ex::Var(nameref.with_loc(span))).with_loc(span)
+                self.resolve_name_to_exp(name)
             };
 
             'unread: _ => return Ok(None);
@@ -1919,8 +2079,22 @@ impl<'a> Parser<'a> {
             Tok::Keyword(Keyword::Nil) in span => Ok(Some(Box::new(Ex::Nil).with_loc(span)));
             Tok::Keyword(Keyword::False) in span => Ok(Some(Box::new(Ex::False).with_loc(span)));
             Tok::Keyword(Keyword::True) in span => Ok(Some(Box::new(Ex::True).with_loc(span)));
-            Tok::Num(v) in span => Ok(Some(Box::new(Ex::Num(v)).with_loc(span)));
-            Tok::Str(s) in span => Ok(Some(Box::new(Ex::Str(s)).with_loc(span)));
+            Tok::Num(v, float) in span => {
+                let exp = if float && self.language.lua().has_float_literals() {
+                    Ex::Float(v)
+                } else {
+                    Ex::Num(v)
+                };
+                Ok(Some(Box::new(exp).with_loc(span)))
+            };
+            Tok::CDataNum(v, suffix) in span => {
+                let lua = self.language.lua();
+                if !lua.has_cdata_literals() {
+                    self.error(span, m::CDataNumOnlyInLuaJIT { current: lua }).done()?;
+                }
+                Ok(Some(Box::new(Ex::CDataNum(v, suffix)).with_loc(span)))
+            };
+            Tok::Str(s, _) in span => Ok(Some(Box::new(Ex::Str(s)).with_loc(span)));
             Tok::Punct(Punct::DotDotDot) in span => Ok(Some(Box::new(Ex::Varargs).with_loc(span)));
 
             Tok::Keyword(Keyword::Function) => {
@@ -1973,6 +2147,7 @@ impl<'a> Parser<'a> {
             Tok::Punct(Punct::Dash) => Some(UnOp::Neg),
             Tok::Keyword(Keyword::Not) => Some(UnOp::Not),
             Tok::Punct(Punct::Hash) => Some(UnOp::Len),
+            Tok::Punct(Punct::Tilde) => Some(UnOp::BNot),
             _ => None,
         };
         op.map(|op| op.with_loc(tok))
@@ -1985,9 +2160,15 @@ impl<'a> Parser<'a> {
             Tok::Punct(Punct::Dash) => Some(BinOp::Sub),
             Tok::Punct(Punct::Star) => Some(BinOp::Mul),
             Tok::Punct(Punct::Slash) => Some(BinOp::Div),
+            Tok::Punct(Punct::SlashSlash) => Some(BinOp::IDiv),
             Tok::Punct(Punct::Caret) => Some(BinOp::Pow),
             Tok::Punct(Punct::Percent) => Some(BinOp::Mod),
             Tok::Punct(Punct::DotDot) => Some(BinOp::Cat),
+            Tok::Punct(Punct::Amp) => Some(BinOp::BAnd),
+            Tok::Punct(Punct::Pipe) => Some(BinOp::BOr),
+            Tok::Punct(Punct::Tilde) => Some(BinOp::BXor),
+            Tok::Punct(Punct::LtLt) => Some(BinOp::Shl),
+            Tok::Punct(Punct::GtGt) => Some(BinOp::Shr),
             Tok::Punct(Punct::Lt) => Some(BinOp::Lt),
             Tok::Punct(Punct::LtEq) => Some(BinOp::Le),
             Tok::Punct(Punct::Gt) => Some(BinOp::Gt),
@@ -2001,6 +2182,22 @@ impl<'a> Parser<'a> {
         op.map(|op| op.with_loc(tok))
     }
 
+    // bitwise and floor division operators are only available since Lua 5.3.
+    // they are always parsed (the lexer doesn't know the language) but reported here.
+    fn check_operator_availability(&self, tok: &Spanned<Tok>) -> Result<()> {
+        let lua = self.language.lua();
+        match tok.base {
+            Tok::Punct(Punct::Amp) | Tok::Punct(Punct::Tilde) | Tok::Punct(Punct::Pipe) |
+            Tok::Punct(Punct::LtLt) | Tok::Punct(Punct::GtGt) |
+            Tok::Punct(Punct::SlashSlash) if !lua.has_bitwise_ops() => {
+                self.error(tok, m::FutureSyntax { read: &tok.base, current: lua,
+                                                  future: Lua::Lua53 })
+                    .done()
+            }
+            _ => Ok(()),
+        }
+    }
+
     // the precedence level 0 is reserved and used at the top level
     fn try_parse_partial_exp(&mut self, minprec: u8) -> Result<Option<Spanned<Exp>>> {
         trace!("parsing exp with min prec {}", minprec);
@@ -2018,7 +2215,7 @@ impl<'a> Parser<'a> {
         fn unary_prec(op: UnOp) -> /*recursion*/ u8 {
             match op {
                 // binary ^ operator here
-                UnOp::Neg | UnOp::Not | UnOp::Len => 8,
+                UnOp::Neg | UnOp::Not | UnOp::Len | UnOp::BNot => 11,
                 // other binary operators here
             }
         }
@@ -2026,7 +2223,8 @@ impl<'a> Parser<'a> {
         let begin = self.pos();
         if let Some(op) = self.try_peek_unary_op() {
             // unop exp ...
-            self.read();
+            let tok = self.read().1;
+            self.check_operator_availability(&tok)?;
             let rprec = unary_prec(op.base);
             let exp = self.parse_partial_exp(rprec)?;
             Ok(Some(Box::new(Ex::Un(op, exp)).with_loc(begin..self.last_pos())))
@@ -2040,11 +2238,15 @@ impl<'a> Parser<'a> {
                                 mut exp: Spanned<Exp>) -> Result<Spanned<Exp>> {
         fn binary_prec(op: BinOp) -> (/*comparison*/ u8, /*recursion*/ u8) {
             match op {
-                BinOp::Pow => (10, 9),
+                BinOp::Pow => (13, 12),
                 // unary operators here
-                BinOp::Mul | BinOp::Div | BinOp::Mod => (7, 7),
-                BinOp::Add | BinOp::Sub => (6, 6),
-                BinOp::Cat => (5, 4),
+                BinOp::Mul | BinOp::Div | BinOp::IDiv | BinOp::Mod => (10, 10),
+                BinOp::Add | BinOp::Sub => (9, 9),
+                BinOp::Cat => (8, 7),
+                BinOp::Shl | BinOp::Shr => (7, 7),
+                BinOp::BAnd => (6, 6),
+                BinOp::BXor => (5, 5),
+                BinOp::BOr => (4, 4),
                 BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge | BinOp::Eq | BinOp::Ne => (3, 3),
                 BinOp::And => (2, 2),
                 BinOp::Or => (1, 1),
@@ -2055,7 +2257,8 @@ impl<'a> Parser<'a> {
         while let Some(op) = self.try_peek_binary_op() {
             let (cprec, rprec) = binary_prec(op.base);
             if cprec <= minprec { break; }
-            self.read();
+            let tok = self.read().1;
+            self.check_operator_availability(&tok)?;
             let exp2 = self.parse_partial_exp(rprec)?;
             exp = Box::new(Ex::Bin(exp, op, exp2)).with_loc(begin..self.last_pos());
         }
@@ -2088,8 +2291,7 @@ impl<'a> Parser<'a> {
         // |---- partial exp at minprec=0 -----|
 
         let begin = self.pos();
-        let nameref = self.resolve_name(name.base);
-        let exp = Box::new(Ex::Var(nameref.with_loc(name.span))).with_loc(name.span);
+        let exp = self.resolve_name_to_exp(name);
         let exp = self.parse_prefix_exp_suffix(begin, exp)?;
         self.parse_partial_binary_exp(0, begin, exp)
     }
@@ -2426,9 +2628,33 @@ impl<'a> Parser<'a> {
         Ok(Seq { head: specs, tail: tail }.with_loc(begin..end))
     }
 
+    // parses `<` NAME {`,` NAME} `>` if any, or returns an empty list otherwise.
+    fn try_parse_kailua_type_params(&mut self) -> Result<Vec<Spanned<Name>>> {
+        let mut tparams: Vec<Spanned<Name>> = Vec::new();
+        if self.may_expect(Punct::Lt) {
+            loop {
+                let name = self.parse_name()?.map(|n| n.name);
+                if self.builtin_kind(&name.base).is_some() {
+                    self.error(name.span, m::CannotRedefineBuiltin {}).done()?;
+                } else if let Some(prev) = tparams.iter().find(|p| p.base == name.base) {
+                    self.error(name.span, m::DuplicateTypeParam { name: &name.base })
+                        .note(prev.span, m::FirstTypeParam {})
+                        .done()?;
+                }
+                tparams.push(name);
+                if !self.may_expect(Punct::Comma) {
+                    break;
+                }
+            }
+            self.expect(Punct::Gt)?;
+        }
+        Ok(tparams)
+    }
+
     fn parse_kailua_funckind(&mut self) -> Result<Spanned<FuncKind>> {
         let begin = self.pos();
 
+        let tparams = self.try_parse_kailua_type_params()?;
         self.expect(Punct::LParen)?;
         let args = self.parse_kailua_kindlist(true)?;
         self.expect(Punct::RParen)?;
@@ -2449,7 +2675,7 @@ impl<'a> Parser<'a> {
         };
 
         let span = begin..self.last_pos();
-        Ok(FuncKind { args: args, returns: returns }.with_loc(span))
+        Ok(FuncKind { tparams: tparams, args: args, returns: returns }.with_loc(span))
     }
 
     fn try_parse_kailua_kind_params(&mut self)
@@ -2511,12 +2737,12 @@ impl<'a> Parser<'a> {
         let kind = if *name.base.name == b"error"[..] {
             // may follow an error reason
             let reason = match_next! { self;
-                Tok::Str(s) in span => Some(s.with_loc(span));
+                Tok::Str(s, _) in span => Some(s.with_loc(span));
                 'unread: _ => None;
             };
             Box::new(K::Error(reason)).with_loc(name.span)
         } else {
-            let namespan = name.span;
+            let mut namespan = name.span;
             let kind = match self.builtin_kind(&name.base.name) {
                 Some(Some(kind)) => kind,
                 Some(None) => {
@@ -2524,7 +2750,20 @@ impl<'a> Parser<'a> {
                     K::Oops
                 },
                 None => {
-                    K::Named(name.map(|n| n.name))
+                    // a generic type alias may follow type arguments
+                    if let Some(params) = self.try_parse_kailua_kind_params()? {
+                        namespan = namespan | params.span;
+                        let mut args = Vec::new();
+                        for (modf, kind) in params.base {
+                            if modf.base != M::None {
+                                self.error(&modf, m::WrongTypeArgsModf {}).done()?;
+                            }
+                            args.push(kind);
+                        }
+                        K::Generic(name.map(|n| n.name), args)
+                    } else {
+                        K::Named(name.map(|n| n.name))
+                    }
                 },
             };
             Box::new(kind).with_loc(namespan)
@@ -2542,11 +2781,24 @@ impl<'a> Parser<'a> {
         let kind = match_next! { self;
             Tok::Keyword(Keyword::Function) in span => {
                 // either a "function" type or a function signature
-                if self.lookahead(Punct::LParen) {
-                    // function `(` ... `)` [`-->` ...]
+                if self.lookahead(Punct::LParen) || self.lookahead(Punct::Lt) {
+                    // function [`<` ... `>`] `(` ... `)` [`-->` ...]
                     let func = self.parse_kailua_funckind()?;
+                    let kind = if self.lookahead(Punct::Amp) {
+                        // function (...) --> ... `&` function (...) --> ... `&` ...
+                        // the last return type (if any) does not consume `&`,
+                        // so the overloading is always looser than the return type
+                        let mut funcs = vec![func];
+                        while self.may_expect(Punct::Amp) {
+                            self.expect(Keyword::Function)?;
+                            funcs.push(self.parse_kailua_funckind()?);
+                        }
+                        K::Overloaded(funcs)
+                    } else {
+                        K::Func(func)
+                    };
                     // cannot be followed by postfix operators
-                    let kind = Box::new(K::Func(func)).with_loc(begin..self.last_pos());
+                    let kind = Box::new(kind).with_loc(begin..self.last_pos());
                     return Ok(Some(AtomicKind::Seq(Seq { head: vec![kind], tail: None })));
                 } else {
                     Box::new(K::Function).with_loc(span)
@@ -2700,7 +2952,7 @@ impl<'a> Parser<'a> {
                 return Ok(Some(AtomicKind::One(kind)));
             };
 
-            Tok::Num(v) in span => {
+            Tok::Num(v, _) in span => {
                 if i32::MIN as f64 <= v && v <= i32::MAX as f64 && v.floor() == v {
                     Box::new(K::IntegerLit(v as i32)).with_loc(span)
                 } else {
@@ -2709,7 +2961,7 @@ impl<'a> Parser<'a> {
                 }
             };
 
-            Tok::Str(s) in span => Box::new(K::StringLit(s)).with_loc(span);
+            Tok::Str(s, _) in span => Box::new(K::StringLit(s)).with_loc(span);
 
             'unread: _ => return Ok(None);
         };
@@ -2978,7 +3230,8 @@ impl<'a> Parser<'a> {
                     }
                 }
 
-                // function "(" [NAME ":" KIND] {"," NAME ":" KIND} ["," "..."] ")" ["-->" KIND]
+                // function ["<" NAME {"," NAME} ">"]
+                //          "(" [NAME ":" KIND] {"," NAME ":" KIND} ["," "..."] ")" ["-->" KIND]
                 let begin = parser.pos();
                 let sigprefix = match_next! { parser;
                     Tok::Keyword(Keyword::Function) in span => Some(false.with_loc(span));
@@ -2992,6 +3245,7 @@ impl<'a> Parser<'a> {
                     }
                 };
                 let sig = if let Some(prefix) = sigprefix {
+                    let tparams = parser.try_parse_kailua_type_params()?;
                     parser.expect(Punct::LParen)?;
                     let args = parser.parse_kailua_namekindlist()?;
                     parser.expect(Punct::RParen)?;
@@ -3001,7 +3255,8 @@ impl<'a> Parser<'a> {
                         Returns::Seq(Seq::empty())
                     };
                     let end = parser.last_pos();
-                    let presig = Presig { prefix: prefix, args: args, returns: Some(returns) };
+                    let presig = Presig { prefix: prefix, tparams: tparams,
+                                          args: args, returns: Some(returns) };
                     Some(presig.with_loc(begin..end))
                 } else {
                     None
@@ -3211,6 +3466,26 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn parse_kailua_ignore(&mut self, begin: Pos) -> Result<()> {
+        let scope = if self.may_expect(Punct::Dash) {
+            self.expect(FixedName("next"))?;
+            self.expect(Punct::Dash)?;
+            self.expect(FixedName("line"))?;
+            IgnoreScope::NextLine
+        } else {
+            IgnoreScope::Rest
+        };
+
+        let mut codes = vec![self.parse_name()?.map(|n| n.name)];
+        while self.may_expect(Punct::Comma) {
+            codes.push(self.parse_name()?.map(|n| n.name));
+        }
+
+        let span = Span::new(begin, self.last_pos());
+        self.ignores.push(Ignore { codes: codes, scope: scope, span: span });
+        Ok(())
+    }
+
     fn try_parse_kailua_spec(&mut self) -> Result<Option<Option<Spanned<Stmt>>>> {
         trace!("parsing kailua spec");
         let begin = self.pos();
@@ -3238,11 +3513,17 @@ impl<'a> Parser<'a> {
                     // open NAME
                     Tok::Keyword(Keyword::Open) => {
                         let name = parser.parse_name()?;
-                        // TODO also set the parser option
+
+                        // opening the library for particular Lua version also sets the language.
+                        // this should be done before `end_meta_comment` which reads the next token.
+                        if let Some(lua) = Lua::from_library_name(&name.base.name) {
+                            parser.language = Language::new(lua, Kailua::Kailua10);
+                        }
+
                         Some(Box::new(St::KailuaOpen(name.map(|n| n.name))))
                     };
 
-                    // type [local | global] NAME = KIND
+                    // type [local | global] NAME ["<" NAME {"," NAME} ">"] = KIND
                     Tok::Keyword(Keyword::Type) => {
                         let typescope = if parser.may_expect(Keyword::Local) {
                             TypeScope::Local
@@ -3253,6 +3534,7 @@ impl<'a> Parser<'a> {
                         };
 
                         let name = parser.parse_name()?;
+                        let tparams = parser.try_parse_kailua_type_params()?;
                         parser.expect(Punct::Eq)?;
                         let kind = parser.recover_upto(Self::parse_kailua_kind)?;
 
@@ -3275,10 +3557,21 @@ impl<'a> Parser<'a> {
                             }
                         }
 
-                        Some(Box::new(St::KailuaType(typescope, name.map(|n| n.name), kind)))
+                        Some(Box::new(St::KailuaType(typescope, name.map(|n| n.name),
+                                                     tparams, kind)))
                     };
 
-                    'unread: _ => None; // empty `--#` is valid
+                    'unread: _ => {
+                        // ignore ["-" "next" "-" "line"] NAME {"," NAME}
+                        // `ignore` is not a keyword to avoid breaking names in the meta block
+                        if parser.may_expect(FixedName("ignore")) {
+                            parser.parse_kailua_ignore(begin)?;
+                        } else if parser.may_expect(FixedName("strict")) {
+                            // strict (applies to the entire file)
+                            parser.strict = true;
+                        }
+                        None // empty `--#` is valid
+                    };
                 };
 
                 let end = parser.last_pos();
@@ -3308,25 +3601,84 @@ impl<'a> Parser<'a> {
         self.scope_stack = vec![];
         self.block_depth = 0;
         let ret = self.parse_block_until_eof();
+        let gotos = mem::replace(&mut self.pending_gotos, Vec::new());
+        let ret = ret.and_then(|block| {
+            self.report_unresolved_gotos(gotos)?;
+            Ok(block)
+        });
 
         // any remaining scope is considered to end at the last token read
         // (unlike normal cases of `pop_scope_upto`, as this might be past EOF)
         let end = self.last_pos();
         while let Some((scope, scopebegin)) = self.scope_stack.pop() {
-            self.scope_map.set_span(scope.with_loc(scopebegin..end));
+            self.set_scope_span(scope.with_loc(scopebegin..end));
+        }
+        for ignore in &mut self.ignores {
+            if ignore.scope == IgnoreScope::Rest {
+                ignore.span = Span::new(ignore.span.begin(), end);
+            }
         }
 
         if let Ok(block) = ret {
-            Ok(Chunk {
-                block: block,
-                global_scope: self.global_scope,
-                map: self.scope_map,
-                local_names: self.local_names,
-                token_aux: self.token_aux,
-            })
+            Ok(self.into_chunk_with_block(block))
         } else {
             Err(report::Stop)
         }
     }
+
+    /// Parses a function body in the existing scope map, used for the incremental reparsing.
+    ///
+    /// The input should be tokens inside the body followed by `Tok::EOF`
+    /// at the position of the closing `end`.
+    /// `map` and `local_names` should come from the existing chunk,
+    /// `scope` is the scope of the function (which has arguments in it)
+    /// and `last_span` is the span of the last token before the body.
+    ///
+    /// The resulting chunk has the body as a block and the updated scope map and local names,
+    /// but other informations are only collected from the body.
+    pub fn into_func_body(mut self, map: ScopeMap<Name>,
+                          local_names: HashMap<ScopedId, LocalName>, scope: Scope,
+                          last_span: Span) -> report::Result<Chunk> {
+        self.scope_map = map;
+        self.local_names = local_names;
+        self.scope_stack = vec![(scope, last_span.end())];
+        self.block_depth = 1;
+        self.last_span = last_span;
+
+        let ret = self._parse_block().and_then(|block| {
+            if !self.lookahead(EOF) {
+                error_with!(self, m::NoStmt);
+            }
+            Ok(block)
+        });
+
+        // the function scope has its span already set
+        self.pop_scope_upto(1);
+
+        // labels are not visible across function boundaries
+        let gotos = mem::replace(&mut self.pending_gotos, Vec::new());
+        let ret = ret.and_then(|block| {
+            self.report_unresolved_gotos(gotos)?;
+            Ok(block)
+        });
+
+        if let Ok(block) = ret {
+            Ok(self.into_chunk_with_block(block))
+        } else {
+            Err(report::Stop)
+        }
+    }
+
+    fn into_chunk_with_block(self, block: Spanned<Block>) -> Chunk {
+        Chunk {
+            block: block,
+            global_scope: self.global_scope,
+            map: self.scope_map,
+            local_names: self.local_names,
+            token_aux: self.token_aux,
+            ignores: self.ignores,
+            strict: self.strict,
+        }
+    }
 }
 
@@ -0,0 +1,898 @@
+//! Incremental reparsing.
+//!
+//! When a source file is only slightly changed, most of the previous chunk can be reused.
+//! `reparse_chunk` compares the previous and current tokens and finds the innermost
+//! function body containing all changed tokens, using the nesting informations from `Nest`.
+//! Only that body is parsed again in the scope of the function,
+//! and the result is spliced into the copy of the previous chunk
+//! while spans after the body are moved accordingly.
+//!
+//! The reparsing gives up (and the caller should parse the entire file)
+//! whenever the result might differ from the full parse:
+//!
+//! * The change is not contained in a single function body,
+//!   including the change to the function signature or the closing `end`.
+//!
+//! * The reparsed body reports anything.
+//!
+//! * The change adds or removes `--# ignore` or `--# open`, removes `--# strict` or
+//!   removes the first assignment to a global name.
+//!
+//! * Scopes discarded by prior reparsing outnumber live scopes (unless there are only a few).
+//!
+//! The previous chunk should have been parsed from the previous tokens without any report.
+
+use std::cmp;
+use std::mem;
+use std::u16;
+use std::cell::Cell;
+use std::collections::{hash_map, HashMap};
+
+use kailua_env::{Pos, Span, Spanned, Scope, ScopedId, ScopeMap};
+use kailua_diag::{self, Kind, Locale, Localize, Report};
+
+use string::{Str, Name};
+use lex::{NestedToken, NestingCategory, NestingSerial, Tok, Punct, Keyword};
+use ast::{NameRef, RenameRef, SelfParam, Attr, AttrValue, Seq, Var, TypeSpec, Returns};
+use ast::{Varargs, Sig, Table, Args, Ex, UnOp, BinOp, St, Block, SlotKind, FuncKind, K};
+use ast::{IgnoreScope, Chunk};
+use lang::{Language, Lua, Kailua};
+use parser::Parser;
+
+// a report that only records whether anything has been reported.
+// the reparsing is abandoned on any report, so it stops the parsing as well.
+struct AnyReport {
+    reported: Cell<bool>,
+}
+
+impl Report for AnyReport {
+    fn message_locale(&self) -> Locale {
+        Locale::dummy()
+    }
+
+    fn add_span(&self, _kind: Kind, _span: Span, _msg: &Localize) -> kailua_diag::Result<()> {
+        self.reported.set(true);
+        Err(kailua_diag::Stop)
+    }
+}
+
+// visits every span in the AST in the fixed order
+trait SpanVisitor {
+    fn visit_span(&mut self, span: &mut Span);
+
+    // the body of a function with given scope
+    fn visit_func_body(&mut self, _scope: Scope, body: &mut Spanned<Block>) where Self: Sized {
+        body.visit_spans(self);
+    }
+}
+
+trait VisitSpans {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V);
+}
+
+impl VisitSpans for Span {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        v.visit_span(self);
+    }
+}
+
+impl<T: VisitSpans> VisitSpans for Spanned<T> {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        v.visit_span(&mut self.span);
+        self.base.visit_spans(v);
+    }
+}
+
+impl<T: VisitSpans> VisitSpans for Box<T> {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        (**self).visit_spans(v);
+    }
+}
+
+impl<T: VisitSpans> VisitSpans for Vec<T> {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        for e in self { e.visit_spans(v); }
+    }
+}
+
+impl<T: VisitSpans> VisitSpans for Option<T> {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        if let Some(ref mut e) = *self { e.visit_spans(v); }
+    }
+}
+
+impl<A: VisitSpans, B: VisitSpans> VisitSpans for (A, B) {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        self.0.visit_spans(v);
+        self.1.visit_spans(v);
+    }
+}
+
+macro_rules! no_spans {
+    ($($t:ty),*) => ($(
+        impl VisitSpans for $t {
+            fn visit_spans<V: SpanVisitor>(&mut self, _v: &mut V) {}
+        }
+    )*)
+}
+
+no_spans!(Name, Str, NameRef, RenameRef, SelfParam, UnOp, BinOp, ScopedId);
+
+impl VisitSpans for Attr {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        self.name.visit_spans(v);
+        self.values.visit_spans(v);
+    }
+}
+
+impl VisitSpans for AttrValue {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        match *self {
+            AttrValue::Name(ref mut name) => name.visit_spans(v),
+        }
+    }
+}
+
+impl<Head: VisitSpans, Tail: VisitSpans> VisitSpans for Seq<Head, Tail> {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        self.head.visit_spans(v);
+        self.tail.visit_spans(v);
+    }
+}
+
+impl VisitSpans for Var {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        match *self {
+            Var::Name(ref mut name) => name.visit_spans(v),
+            Var::Index(ref mut e, ref mut i) => { e.visit_spans(v); i.visit_spans(v); }
+            Var::IndexName(ref mut e, ref mut i) => { e.visit_spans(v); i.visit_spans(v); }
+        }
+    }
+}
+
+impl<T: VisitSpans> VisitSpans for TypeSpec<T> {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        self.base.visit_spans(v);
+        self.kind.visit_spans(v);
+    }
+}
+
+impl VisitSpans for Returns {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        match *self {
+            Returns::Seq(ref mut seq) => seq.visit_spans(v),
+            Returns::Never(ref mut span) => span.visit_spans(v),
+        }
+    }
+}
+
+impl VisitSpans for Varargs {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        self.kind.visit_spans(v);
+        self.legacy_arg.visit_spans(v);
+    }
+}
+
+impl VisitSpans for Sig {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        self.attrs.visit_spans(v);
+        self.tparams.visit_spans(v);
+        self.args.visit_spans(v);
+        self.returns.visit_spans(v);
+    }
+}
+
+impl VisitSpans for Table {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        self.items.visit_spans(v);
+    }
+}
+
+impl VisitSpans for Args {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        match *self {
+            Args::List(ref mut args) => args.visit_spans(v),
+            Args::Str(_) => {}
+            Args::Table(ref mut tab) => tab.visit_spans(v),
+        }
+    }
+}
+
+impl VisitSpans for Ex {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        match *self {
+            Ex::Oops | Ex::Nil | Ex::False | Ex::True |
+            Ex::Num(_) | Ex::Float(_) | Ex::CDataNum(..) | Ex::Str(_) | Ex::Varargs => {}
+            Ex::Func(ref mut sig, scope, ref mut body) => {
+                sig.visit_spans(v);
+                v.visit_func_body(scope, body);
+            }
+            Ex::Table(ref mut tab) => tab.visit_spans(v),
+            Ex::Var(ref mut name) => name.visit_spans(v),
+            Ex::Exp(ref mut e) => e.visit_spans(v),
+            Ex::FuncCall(ref mut e, ref mut args) => { e.visit_spans(v); args.visit_spans(v); }
+            Ex::MethodCall(ref mut e, ref mut args) => { e.visit_spans(v); args.visit_spans(v); }
+            Ex::Index(ref mut e, ref mut i) => { e.visit_spans(v); i.visit_spans(v); }
+            Ex::IndexName(ref mut e, ref mut i) => { e.visit_spans(v); i.visit_spans(v); }
+            Ex::Un(ref mut op, ref mut e) => { op.visit_spans(v); e.visit_spans(v); }
+            Ex::Bin(ref mut l, ref mut op, ref mut r) => {
+                l.visit_spans(v);
+                op.visit_spans(v);
+                r.visit_spans(v);
+            }
+        }
+    }
+}
+
+impl VisitSpans for St {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        match *self {
+            St::Oops | St::Break => {}
+            St::Void(ref mut e) => e.visit_spans(v),
+            St::Assign(ref mut l, ref mut r) => { l.visit_spans(v); r.visit_spans(v); }
+            St::Do(ref mut b) => b.visit_spans(v),
+            St::While(ref mut e, ref mut b) => { e.visit_spans(v); b.visit_spans(v); }
+            St::Repeat(ref mut b, ref mut e) => { b.visit_spans(v); e.visit_spans(v); }
+            St::If(ref mut cases, ref mut else_) => {
+                cases.visit_spans(v);
+                else_.visit_spans(v);
+            }
+            St::For(ref mut i, ref mut start, ref mut end, ref mut step, _, ref mut b) => {
+                i.visit_spans(v);
+                start.visit_spans(v);
+                end.visit_spans(v);
+                step.visit_spans(v);
+                b.visit_spans(v);
+            }
+            St::ForIn(ref mut ii, ref mut ee, _, ref mut b) => {
+                ii.visit_spans(v);
+                ee.visit_spans(v);
+                b.visit_spans(v);
+            }
+            St::FuncDecl(ref mut i, ref mut sig, scope, ref mut b, _) => {
+                i.visit_spans(v);
+                sig.visit_spans(v);
+                v.visit_func_body(scope, b);
+            }
+            St::MethodDecl(ref mut ii, ref mut selfparam, ref mut sig, scope, ref mut b) => {
+                ii.visit_spans(v);
+                selfparam.visit_spans(v);
+                sig.visit_spans(v);
+                v.visit_func_body(scope, b);
+            }
+            St::Local(ref mut ii, ref mut ee, _) => { ii.visit_spans(v); ee.visit_spans(v); }
+            St::Return(ref mut ee) => ee.visit_spans(v),
+            St::Goto(ref mut i) => i.visit_spans(v),
+            St::Label(ref mut i) => i.visit_spans(v),
+            St::KailuaOpen(ref mut lib) => lib.visit_spans(v),
+            St::KailuaType(_, ref mut t, ref mut params, ref mut k) => {
+                t.visit_spans(v);
+                params.visit_spans(v);
+                k.visit_spans(v);
+            }
+            St::KailuaAssume(ref mut i, _, ref mut k, _) => { i.visit_spans(v); k.visit_spans(v); }
+            St::KailuaAssumeField(_, ref mut ii, _, ref mut k) => {
+                ii.visit_spans(v);
+                k.visit_spans(v);
+            }
+            St::KailuaAssumeMethod(ref mut ii, _, ref mut fk) => {
+                ii.visit_spans(v);
+                fk.visit_spans(v);
+            }
+            St::KailuaClassSystem(ref mut sys) => sys.visit_spans(v),
+            St::KailuaAssumeClass(ref mut sys, ref mut i, ref mut pi, _) => {
+                sys.visit_spans(v);
+                i.visit_spans(v);
+                pi.visit_spans(v);
+            }
+        }
+    }
+}
+
+impl VisitSpans for SlotKind {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        self.kind.visit_spans(v);
+    }
+}
+
+impl VisitSpans for FuncKind {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        self.tparams.visit_spans(v);
+        self.args.visit_spans(v);
+        self.returns.visit_spans(v);
+    }
+}
+
+impl VisitSpans for K {
+    fn visit_spans<V: SpanVisitor>(&mut self, v: &mut V) {
+        match *self {
+            K::Oops | K::Dynamic | K::Any | K::Nil | K::Boolean | K::BooleanLit(_) |
+            K::Number | K::Integer | K::IntegerLit(_) | K::String | K::StringLit(_) |
+            K::Table | K::EmptyTable | K::Function | K::Thread | K::UserData => {}
+            K::Record(ref mut fields, _) => fields.visit_spans(v),
+            K::Tuple(ref mut fields) => fields.visit_spans(v),
+            K::Array(ref mut v_) => v_.visit_spans(v),
+            K::Map(ref mut k, ref mut v_) => { k.visit_spans(v); v_.visit_spans(v); }
+            K::Func(ref mut func) => func.visit_spans(v),
+            K::Overloaded(ref mut funcs) => funcs.visit_spans(v),
+            K::Named(ref mut name) => name.visit_spans(v),
+            K::Generic(ref mut name, ref mut args) => { name.visit_spans(v); args.visit_spans(v); }
+            K::WithNil(ref mut k) => k.visit_spans(v),
+            K::WithoutNil(ref mut k) => k.visit_spans(v),
+            K::Union(ref mut kinds) => kinds.visit_spans(v),
+            K::Attr(ref mut k, ref mut a) => { k.visit_spans(v); a.visit_spans(v); }
+            K::Error(ref mut s) => s.visit_spans(v),
+        }
+    }
+}
+
+// returns true if `prev` is moved by `delta` to `cur`
+fn is_shifted(prev: Span, cur: Span, delta: isize) -> bool {
+    let diff = |prev: Pos, cur: Pos| cur.to_usize() as isize - prev.to_usize() as isize;
+    prev.unit() == cur.unit() &&
+        diff(prev.begin(), cur.begin()) == delta && diff(prev.end(), cur.end()) == delta
+}
+
+// moves spans after the reparsed function body and replaces the body itself
+struct Splice {
+    // the (previous) position of the closing `end`; positions from here are moved
+    end: Pos,
+    delta: isize,
+    scope: Scope,
+    body: Option<Spanned<Block>>,
+}
+
+impl Splice {
+    fn pos(&self, pos: Pos) -> Pos {
+        if pos.unit() == self.end.unit() && pos >= self.end { pos.shift(self.delta) } else { pos }
+    }
+
+    fn span(&self, span: Span) -> Span {
+        if span.unit() == self.end.unit() {
+            Span::new(self.pos(span.begin()), self.pos(span.end()))
+        } else {
+            span
+        }
+    }
+}
+
+impl SpanVisitor for Splice {
+    fn visit_span(&mut self, span: &mut Span) {
+        *span = self.span(*span);
+    }
+
+    fn visit_func_body(&mut self, scope: Scope, body: &mut Spanned<Block>) {
+        if scope == self.scope && self.body.is_some() {
+            *body = self.body.take().unwrap();
+        } else {
+            body.visit_spans(self);
+        }
+    }
+}
+
+// the function body to be reparsed, in the previous tokens
+struct FuncBody {
+    // the index to the first token in the body
+    begin: usize,
+    // the index to the closing `end`
+    end: usize,
+    // the scope of the function, which span covers the body
+    scope: Scope,
+}
+
+// finds the innermost function body containing every changed token `prev_tokens[lo..hi]`,
+// which will be replaced with `tokens[lo..newhi]`
+fn find_func_body(prev_tokens: &[NestedToken], prev_chunk: &Chunk, tokens: &[NestedToken],
+                  lo: usize, hi: usize, newhi: usize) -> Option<FuncBody> {
+    // scan `function` keywords backwards, which nesting should contain every changed token
+    let mut mindepth = u16::MAX;
+    let mut i = lo;
+    while i > 0 {
+        i -= 1;
+        let func = &prev_tokens[i];
+        if func.tok.base == Tok::Keyword(Keyword::Function) && func.depth <= mindepth {
+            if let Some(body) = check_func_body(prev_tokens, prev_chunk, tokens,
+                                                i, lo, hi, newhi) {
+                return Some(body);
+            }
+        }
+        mindepth = cmp::min(mindepth, func.depth);
+    }
+    None
+}
+
+fn check_func_body(prev_tokens: &[NestedToken], prev_chunk: &Chunk, tokens: &[NestedToken],
+                   func: usize, lo: usize, hi: usize, newhi: usize) -> Option<FuncBody> {
+    let depth = prev_tokens[func].depth;
+
+    // the closing `end` is the first token leaving the nesting, and should be unchanged
+    let end = match (lo..prev_tokens.len()).find(|&i| prev_tokens[i].depth < depth) {
+        Some(end) if end >= hi => end,
+        _ => return None,
+    };
+    if prev_tokens[end].tok.base != Tok::Keyword(Keyword::End) {
+        return None;
+    }
+    if tokens[lo..newhi].iter().any(|t| t.depth < depth) {
+        return None;
+    }
+
+    // the function scope is the outermost scope ending at the closing `end`
+    let endpos = prev_tokens[end].tok.span.begin();
+    let map = &prev_chunk.map;
+    let inner = match map.scope_from_pos(endpos) {
+        Some(scope) => scope,
+        None => return None,
+    };
+    let outer = map.ancestor_scopes(inner).filter(|&s| map.scope_span(s).end() == endpos).last();
+    let scope = match outer {
+        Some(scope) => scope,
+        None => return None,
+    };
+    let scopebegin = map.scope_span(scope).begin();
+    if scopebegin < prev_tokens[func].tok.span.end() {
+        return None;
+    }
+
+    // the body starts from the first token not ending before the scope,
+    // and any change before that (i.e. to the signature) cannot be handled
+    let (mut begin, mut limit) = (func + 1, end);
+    while begin < limit {
+        let mid = begin + (limit - begin) / 2;
+        if prev_tokens[mid].tok.span.end() > scopebegin { limit = mid; } else { begin = mid + 1; }
+    }
+    if begin > lo {
+        return None;
+    }
+
+    Some(FuncBody { begin: begin, end: end, scope: scope })
+}
+
+/// Reparses the chunk from the current tokens,
+/// given the previous chunk and tokens that the chunk has been parsed from.
+///
+/// Returns `None` when the entire tokens have to be parsed again.
+/// Otherwise the resulting chunk is identical to the full parse,
+/// except that the numbering of scopes and scoped identifiers can differ
+/// and the scope map may contain inaccessible scopes and names.
+///
+/// The resulting chunk has ignores already resolved (if the previous chunk had so).
+pub fn reparse_chunk(prev_tokens: &[NestedToken], prev_chunk: &Chunk,
+                     tokens: &[NestedToken]) -> Option<Chunk> {
+    let (prevlen, len) = (prev_tokens.len(), tokens.len());
+    if prevlen == 0 || len == 0 || prev_chunk.token_aux.len() != prevlen {
+        return None;
+    }
+
+    let prevunit = prev_tokens[prevlen-1].tok.span.unit();
+    let unit = tokens[len-1].tok.span.unit();
+    if prevunit != unit {
+        return None;
+    }
+    let delta = tokens[len-1].tok.span.end().to_usize() as isize -
+                prev_tokens[prevlen-1].tok.span.end().to_usize() as isize;
+
+    // find the changed tokens: `prev_tokens[lo..hi]` is replaced with `tokens[lo..newhi]`
+    let minlen = cmp::min(prevlen, len);
+    let mut lo = 0;
+    while lo < minlen && prev_tokens[lo] == tokens[lo] {
+        lo += 1;
+    }
+    if lo == prevlen && lo == len {
+        return Some(prev_chunk.clone());
+    }
+    let mut common = 0;
+    while common < minlen - lo && {
+        let prev = &prev_tokens[prevlen-1-common];
+        let cur = &tokens[len-1-common];
+        prev.tok.base == cur.tok.base && prev.depth == cur.depth &&
+            prev.category == cur.category && is_shifted(prev.tok.span, cur.tok.span, delta)
+    } {
+        common += 1;
+    }
+    let (hi, newhi) = (prevlen - common, len - common);
+
+    let body = match find_func_body(prev_tokens, prev_chunk, tokens, lo, hi, newhi) {
+        Some(body) => body,
+        None => return None,
+    };
+    let newend = body.end - hi + newhi;
+    let endpos = prev_tokens[body.end].tok.span.begin();
+    let regionbegin = prev_tokens[body.begin].tok.span.begin();
+    let in_region = |pos: Pos| pos.unit() == unit && regionbegin <= pos && pos < endpos;
+
+    // the previous body should have no side effects that cannot be easily undone
+    for ignore in &prev_chunk.ignores {
+        if in_region(ignore.span.begin()) {
+            return None;
+        }
+        if ignore.scope == IgnoreScope::NextLine &&
+           ignore.span.begin() < endpos && regionbegin <= ignore.span.end() {
+            return None; // the next line of `--# ignore-next-line` overlaps with the body
+        }
+    }
+    for w in prev_tokens[body.begin..body.end].windows(2) {
+        if let (&Tok::Punct(Punct::DashDashHash), &Tok::Name(ref name)) =
+               (&w[0].tok.base, &w[1].tok.base) {
+            if &name[..] == b"strict" {
+                return None;
+            }
+        }
+    }
+
+    // `--# open` may change the language for the rest of the file
+    let is_open = |w: &[NestedToken]| {
+        w[0].tok.base == Tok::Punct(Punct::DashDashHash) &&
+            w[1].tok.base == Tok::Keyword(Keyword::Open)
+    };
+    if prev_tokens[body.begin..body.end].windows(2).any(&is_open) ||
+       tokens[body.begin..newend].windows(2).any(&is_open) {
+        return None;
+    }
+    let mut language = Language::new(Lua::Lua51, Kailua::Kailua10);
+    for w in tokens[..body.begin].windows(3) {
+        if let (true, &Tok::Name(ref name)) = (is_open(w), &w[2].tok.base) {
+            if let Some(lua) = Lua::from_library_name(name) {
+                language = Language::new(lua, Kailua::Kailua10);
+            }
+        }
+    }
+
+    let mut chunk = prev_chunk.clone();
+    let map = mem::replace(&mut chunk.map, ScopeMap::new());
+    let mut local_names = mem::replace(&mut chunk.local_names, HashMap::new());
+
+    // scopes inside the previous body are no longer accessible
+    let firstnew = map.all_scopes().count() + 1;
+    let mut stale = vec![false; firstnew];
+    for scope in map.all_scopes() {
+        if let Some(parent) = map.parent_scope(scope.base) {
+            let parent = parent.to_usize();
+            stale[scope.base.to_usize()] = parent == body.scope.to_usize() || stale[parent];
+        }
+    }
+
+    // names defined in the previous body are also no longer accessible
+    let mut splice = Splice { end: endpos, delta: delta, scope: body.scope, body: None };
+    local_names.retain(|id, name| {
+        let scope = id.scope(&map).to_usize();
+        !(scope < firstnew && stale[scope]) && !in_region(name.def_span.begin())
+    });
+    for name in local_names.values_mut() {
+        name.def_span = splice.span(name.def_span);
+    }
+
+    // parse the new body, which ends with a fake EOF at the closing `end`
+    let mut input = tokens[body.begin..newend].to_vec();
+    input.push(NestedToken {
+        tok: Spanned { span: tokens[newend].tok.span.begin().into(), base: Tok::EOF },
+        depth: 0,
+        category: NestingCategory::Expr,
+        serial: NestingSerial::dummy(),
+    });
+    let report = AnyReport { reported: Cell::new(false) };
+    let sub = {
+        let mut iter = input.into_iter();
+        let parser = Parser::with_language(&mut iter, &report, language);
+        parser.into_func_body(map, local_names, body.scope, prev_tokens[body.begin-1].tok.span)
+    };
+    let sub = match sub {
+        Ok(sub) => if report.reported.get() { return None; } else { sub },
+        Err(_) => return None,
+    };
+    if !sub.ignores.is_empty() {
+        return None;
+    }
+
+    splice.body = Some(sub.block);
+    chunk.block.visit_spans(&mut splice);
+    if splice.body.is_some() {
+        return None; // the function was not in the AST
+    }
+
+    let mut map = sub.map;
+    let (mut live, mut dead) = (0, 0);
+    map.map_spans(|scope, span| {
+        let scope = scope.to_usize();
+        let span = if scope >= firstnew {
+            span
+        } else if stale[scope] {
+            Span::dummy()
+        } else {
+            splice.span(span)
+        };
+        if span.is_dummy() { dead += 1; } else { live += 1; }
+        span
+    });
+    if dead > live && dead > 64 {
+        return None; // too much garbage, better to start over
+    }
+
+    chunk.local_names = sub.local_names;
+    chunk.map = map;
+
+    // the sub-parse also has the auxiliary information for the fake EOF
+    assert_eq!(sub.token_aux.len(), newend - body.begin + 1);
+    let mut token_aux = Vec::with_capacity(len);
+    token_aux.extend_from_slice(&chunk.token_aux[..body.begin]);
+    token_aux.extend(sub.token_aux.into_iter().take(newend - body.begin));
+    token_aux.extend_from_slice(&chunk.token_aux[body.end..]);
+    assert_eq!(token_aux.len(), len);
+    chunk.token_aux = token_aux;
+
+    // global names first assigned in the previous body should be assigned again,
+    // otherwise we don't know where the next assignment is
+    let mut removed = Vec::new();
+    for (name, span) in &mut chunk.global_scope {
+        if in_region(span.begin()) {
+            removed.push(name.clone());
+        }
+        *span = splice.span(*span);
+    }
+    for name in &removed {
+        chunk.global_scope.remove(name);
+    }
+    for (name, span) in sub.global_scope {
+        match chunk.global_scope.entry(name) {
+            hash_map::Entry::Occupied(mut e) => {
+                if span.begin() < e.get().begin() { e.insert(span); }
+            }
+            hash_map::Entry::Vacant(e) => {
+                e.insert(span);
+            }
+        }
+    }
+    if !removed.iter().all(|name| chunk.global_scope.contains_key(name)) {
+        return None;
+    }
+
+    for ignore in &mut chunk.ignores {
+        ignore.codes.visit_spans(&mut splice);
+        ignore.span = splice.span(ignore.span);
+    }
+    chunk.strict |= sub.strict;
+
+    Some(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use kailua_env::{Span, Source, SourceFile};
+    use kailua_diag::NoReport;
+
+    use lex::{Lexer, Nest, NestedToken};
+    use ast::{Chunk, TokenAux};
+    use parser::Parser;
+    use super::{SpanVisitor, VisitSpans, reparse_chunk};
+
+    struct CollectSpans(Vec<Span>);
+
+    impl SpanVisitor for CollectSpans {
+        fn visit_span(&mut self, span: &mut Span) {
+            self.0.push(*span);
+        }
+    }
+
+    fn parse(source: &Source, span: Span) -> (Vec<NestedToken>, Chunk) {
+        let mut iter = source.iter_from_span(span).unwrap();
+        let tokens = {
+            let mut lexer = Lexer::new(&mut iter, &NoReport);
+            let nest = Nest::new(&mut lexer);
+            nest.collect::<Vec<_>>()
+        };
+        let mut chunk = {
+            let mut iter = tokens.clone().into_iter();
+            Parser::new(&mut iter, &NoReport).into_chunk().expect("parsing failed")
+        };
+        chunk.resolve_ignores(source);
+        (tokens, chunk)
+    }
+
+    // replaces `<id>` and `$scope` with the order of the first appearance
+    fn normalize_ids(s: &str) -> String {
+        let mut ids = HashMap::new();
+        let mut scopes = HashMap::new();
+        let mut out = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '<' && c != '$' {
+                out.push(c);
+                continue;
+            }
+            let mut num = String::new();
+            while let Some(&d) = chars.peek() {
+                if !d.is_digit(10) { break; }
+                num.push(d);
+                chars.next();
+            }
+            if num.is_empty() || (c == '<' && chars.peek() != Some(&'>')) {
+                out.push(c);
+                out.push_str(&num);
+                continue;
+            }
+            let table = if c == '<' { &mut ids } else { &mut scopes };
+            let next = table.len();
+            let n = *table.entry(num).or_insert(next);
+            out.push(c);
+            out.push_str(&n.to_string());
+        }
+        out
+    }
+
+    fn spans(chunk: &Chunk) -> Vec<Span> {
+        let mut collect = CollectSpans(Vec::new());
+        chunk.block.clone().visit_spans(&mut collect);
+        collect.0
+    }
+
+    // describes the local name or scope for each token and visible names at that point
+    fn token_infos(tokens: &[NestedToken], chunk: &Chunk) -> Vec<String> {
+        tokens.iter().zip(&chunk.token_aux).map(|(tok, aux)| {
+            let aux = match *aux {
+                TokenAux::None => format!("-"),
+                TokenAux::GlobalVarName => format!("global"),
+                TokenAux::LocalVarName(ref id) => {
+                    let name = &chunk.local_names[id];
+                    format!("{:?} {:#?} {:?}", id.name(&chunk.map), name.def_span, name.kind)
+                }
+            };
+
+            let pos = tok.tok.span.begin();
+            let mut names = Vec::new();
+            let mut scopespan = Span::dummy();
+            if let Some(scope) = chunk.map.scope_from_pos(pos) {
+                scopespan = chunk.map.scope_span(scope);
+                let mut seen = Vec::new();
+                for (name, _, id) in chunk.map.names_and_scopes(scope) {
+                    if seen.contains(&name) { continue; }
+                    seen.push(name);
+                    let defspan = chunk.local_names.get(&id).map(|n| n.def_span);
+                    names.push(format!("{:?}{:#?}", name, defspan.unwrap_or(Span::dummy())));
+                }
+                names.sort();
+            }
+            format!("{:#?}: {} in {:#?} with {:?}", tok.tok.span, aux, scopespan, names)
+        }).collect()
+    }
+
+    fn assert_same_chunk(tokens: &[NestedToken], reparsed: &Chunk, full: &Chunk) {
+        assert_eq!(normalize_ids(&format!("{:?}", reparsed.block)),
+                   normalize_ids(&format!("{:?}", full.block)));
+        assert_eq!(spans(reparsed), spans(full));
+        assert_eq!(token_infos(tokens, reparsed), token_infos(tokens, full));
+        assert_eq!(reparsed.global_scope, full.global_scope);
+        assert_eq!(reparsed.ignores, full.ignores);
+        assert_eq!(reparsed.strict, full.strict);
+    }
+
+    const SOURCE: &'static str = "\
+local a = 1 --: integer
+--# ignore-next-line foo
+local function f(x, y) --: string
+    local z = x + 1
+    if z then
+        local function g() return z end
+        return g
+    end
+    glob = function(w) return w end
+    return y
+end
+
+function h(q) --> string
+    local r = 'r'
+    for i = 1, 3 do r = r .. i end
+    return r
+end
+
+local t = {}
+function t:m() return self end
+";
+
+    fn check_edits(source_text: &str, edits: &[(&str, &str, bool)]) {
+        let mut nreparsed = 0;
+        for &(before, after, expected) in edits {
+            assert!(source_text.contains(before), "{:?} is not in the source", before);
+
+            let mut source = Source::new();
+            let file = SourceFile::from_u8("<test>".to_string(),
+                                           source_text.as_bytes().to_owned());
+            let span = source.add(file);
+            let (prev_tokens, prev_chunk) = parse(&source, span);
+
+            let at = source_text.find(before).unwrap();
+            let modified = format!("{}{}{}", &source_text[..at], after,
+                                   &source_text[at+before.len()..]);
+            let file = SourceFile::from_u8("<test>".to_string(), modified.as_bytes().to_owned());
+            let span = source.replace(span.unit(), file).unwrap();
+            let (tokens, full) = {
+                let mut iter = source.iter_from_span(span).unwrap();
+                let mut lexer = Lexer::new(&mut iter, &NoReport);
+                let nest = Nest::new(&mut lexer);
+                let tokens = nest.collect::<Vec<_>>();
+                let full = {
+                    let mut iter = tokens.clone().into_iter();
+                    Parser::new(&mut iter, &NoReport).into_chunk().ok().map(|mut chunk| {
+                        chunk.resolve_ignores(&source);
+                        chunk
+                    })
+                };
+                (tokens, full)
+            };
+
+            let reparsed = reparse_chunk(&prev_tokens, &prev_chunk, &tokens);
+            assert_eq!(reparsed.is_some(), expected,
+                       "unexpected reparsing result for {:?} -> {:?}", before, after);
+            if let Some(reparsed) = reparsed {
+                let full = full.expect("reparsing succeeded but the full parsing failed");
+                assert_same_chunk(&tokens, &reparsed, &full);
+                nreparsed += 1;
+
+                // the reparsed chunk can be used for the further reparsing
+                if let Some(back) = reparse_chunk(&tokens, &reparsed, &prev_tokens) {
+                    assert_same_chunk(&prev_tokens, &back, &prev_chunk);
+                }
+            }
+        }
+        assert!(nreparsed > 0);
+    }
+
+    #[test]
+    fn test_reparse() {
+        // (string to replace, replacement, whether the reparsing is expected)
+        let edits: &[(&str, &str, bool)] = &[
+            ("local z = x + 1", "local z = x + 12345", true),
+            ("local z = x + 1", "local zz, z = x, 1", true),
+            ("local r = 'r'", "local r = 'r' .. a", true),
+            ("local r = 'r'", "", true),
+            ("return z end", "return zzz, z end", true),
+            ("return self end", "local s = self; return s end", true),
+            ("return w end", "print(w) return w end", true),
+            ("return y\nend", "return y, x\nend", true),
+            ("return y\nend", "return y\nend\nfoo()", false),
+            ("local a = 1", "local a = 2", false),
+            ("function h(q)", "function h(q, s)", false),
+            ("local r = 'r'", "local r = 'r' end do", false),
+            ("local r = 'r'", "glob2 = 1", true),
+            ("local r = 'r'", "glob = 1", true),
+            ("local z = x + 1", "local z = x +", false),
+            ("local r = 'r'", "--# ignore foo", false),
+            ("local z = x + 1", "local z = x + 1 --: integer", true),
+            ("return w end", "--# assume w: integer\n return w end", true),
+            ("local r = 'r'",
+             "--v function(a: integer) --> integer\nlocal function k(a) return a end", true),
+            ("if z then", "while z do z = z - 1 end if z then", true),
+            ("    glob = function(w) return w end\n", "", false),
+        ];
+
+        check_edits(SOURCE, edits);
+    }
+
+    #[test]
+    fn test_reparse_language() {
+        // the body should be parsed in the language set before it;
+        // the legacy `arg` is local in Lua 5.1 but global in LuaJIT, which is silently different
+        let source = "\
+--# open luajit
+local function f(...)
+    return 1
+end
+";
+        let edits: &[(&str, &str, bool)] = &[
+            ("return 1", "local function g(...) return arg end return g", true),
+            ("return 1", "return 1LL", true),
+            ("return 1", "--# open lua51\nreturn 1", false),
+        ];
+        check_edits(source, edits);
+    }
+
+    #[test]
+    fn test_reparse_unchanged() {
+        let mut source = Source::new();
+        let file = SourceFile::from_u8("<test>".to_string(), SOURCE.as_bytes().to_owned());
+        let span = source.add(file);
+        let (tokens, chunk) = parse(&source, span);
+        let reparsed = reparse_chunk(&tokens, &chunk, &tokens).unwrap();
+        assert_same_chunk(&tokens, &reparsed, &chunk);
+    }
+}
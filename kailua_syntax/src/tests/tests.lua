@@ -999,6 +999,47 @@ do end --@< Error: Expected a name or `function` after `local`, got a keyword `d
 local x --: function(a: string, b: integer) --> (integer, string)
 --! [Local([`x`$1: _ Func((`a`: String, `b`: Integer) --> (Integer, String))], [])$1]
 
+--8<-- kind-func-generic
+local x --: function<T>(a: T) --> T
+--! [Local([`x`$1: _ Func(<`T`>(`a`: `T`) --> `T`)], [])$1]
+
+--8<-- kind-func-generic-multi
+local x --: function<T, U>(vector<T>, function(T) --> U) --> vector<U>
+--! [Local([`x`$1: _ Func(<`T`, `U`>(Array(_ `T`), Func((`T`) --> `U`)) --> Array(_ `U`))], [])$1]
+
+--8<-- kind-func-generic-duplicate
+local x --: function<T, T>(T) --> T --@< Error: Duplicate type parameter `T`
+                                    --@^ Note: The first duplicate appeared here
+--! [Local([`x`$1: _ Func(<`T`, `T`>(`T`) --> `T`)], [])$1]
+
+--8<-- kind-func-generic-builtin
+local x --: function<integer>(integer) --@< Error: Cannot redefine a builtin type
+--! [Local([`x`$1: _ Func(<`integer`>(Integer) --> ())], [])$1]
+
+--8<-- kind-func-overloaded
+local x --: function(integer) --> integer & function(string) --> string
+--! [Local([`x`$1: _ Overloaded([(Integer) --> Integer, (String) --> String])], [])$1]
+
+--8<-- kind-func-overloaded-3
+local x --: function() & function<T>(T) --> T & function(string, string)
+--! [Local([`x`$1: _ Overloaded([() --> (), <`T`>(`T`) --> `T`, (String, String) --> ()])], [])$1]
+
+--8<-- kind-func-overloaded-seq
+local x --: function() --> (integer, string) & function(integer) --> (string...)
+--! [Local([`x`$1: _ Overloaded([() --> (Integer, String), (Integer) --> (String...)])], [])$1]
+
+--8<-- kind-func-overloaded-non-func
+local x --: function() & string --@< Error: Expected a keyword `function`, got a name
+--! [Local([`x`$1: _ Oops], [])$1]
+
+--8<-- kind-generic
+local x --: Pair<integer, string?>
+--! [Local([`x`$1: _ `Pair`<Integer, String?>], [])$1]
+
+--8<-- kind-generic-modf
+local x --: Box<const integer> --@< Error: Type arguments to the generic type cannot have modifiers
+--! [Local([`x`$1: _ `Box`<Integer>], [])$1]
+
 --8<-- kind-func-named-args-partial
 local x --: function(a: string, integer) --> (integer, string)
 --@^ Error: Not all but only some arguments in the type are named
@@ -1502,6 +1543,11 @@ function foo(x) end
 function foo(x) end
 --! [FuncDecl(`foo`_, [`x`$1: Module EmptyTable], $1[])]
 
+--8<-- funcspec-generic
+--v function<T>(x: T) --> vector<T>
+function foo(x) end
+--! [FuncDecl(`foo`_, <`T`>[`x`$1: _ `T`] --> Array(_ `T`), $1[])]
+
 --8<-- rettype-module
 function foo() --> module {} --@< Error: Expected a single type or type sequence, got a keyword `module`
                              --@^ Error: Expected a newline, got a keyword `module`
@@ -1811,9 +1857,44 @@ f('foo\xyz') --@< Error: Unrecognized escape sequence in a string
 
 --8<-- string-wrong-escape-recover
 f('foo\xyz', 'bar\zyx') --@< Error: Unrecognized escape sequence in a string
-                        --@^ Error: Unrecognized escape sequence in a string
+                        --@^ Error: Escape sequences `\x` and `\z` are only available since Lua 5.2 and cannot be used in Lua 5.1
 --! [Void(`f`_("fooyz", "baryx"))]
 
+--8<-- string-hex-escape
+--# open lua52
+f('\x41\x6a\x5A')
+--! [KailuaOpen(`lua52`), Void(`f`_("AjZ"))]
+
+--8<-- string-hex-escape-lua51
+f('\x41\x6a\x5A') --@< Error: Escape sequences `\x` and `\z` are only available since Lua 5.2 and cannot be used in Lua 5.1
+--! [Void(`f`_("AjZ"))]
+
+--8<-- string-hex-escape-incomplete
+--# open lua52
+f('\x4g') --@< Error: Unrecognized escape sequence in a string
+--! [KailuaOpen(`lua52`), Void(`f`_("g"))]
+
+--8<-- string-skip-whitespace-escape
+--# open lua52
+f('foo\z
+   bar')
+--! [KailuaOpen(`lua52`), Void(`f`_("foobar"))]
+
+--8<-- string-skip-whitespace-escape-luajit
+--# open luajit
+f('foo\z
+   bar')
+--! [KailuaOpen(`luajit`), Void(`f`_("foobar"))]
+
+--8<-- string-skip-whitespace-escape-lua51
+f('foo\zbar') --@< Error: Escape sequences `\x` and `\z` are only available since Lua 5.2 and cannot be used in Lua 5.1
+--! [Void(`f`_("foobar"))]
+
+--8<-- string-skip-whitespace-escape-in-meta
+--# open lua52
+--# type x = 'foo\z    bar'
+--! [KailuaOpen(`lua52`), KailuaType(Exported, `x`, String("foobar"))]
+
 --8<-- string-incomplete-escape
 f('foo\ --@< Error: Premature end of file in a string
         --@^ Note: The string started here
@@ -1859,7 +1940,7 @@ f(3e+ --@< Error: Invalid number
 ) -- highlighting fix
 
 --8<-- invalid-char-1
-f(~3) --@< Error: Unexpected character
+f($3) --@< Error: Unexpected character
 --! [Void(`f`_(3))]
 
 --8<-- invalid-char-2
@@ -2215,6 +2296,18 @@ f()
 --# assume x: vector<any>
 --! [KailuaType(Exported, `any`, Integer), KailuaAssume(`x`_ => `x`$1, _, Array(_ Any))$1]
 
+--8<-- alias-generic
+--# type Pair<A, B> = {first: A, second: B}
+--# type local List<T> = vector<T>
+--# assume x: Pair<integer, List<string>>
+--! [KailuaType(Exported, `Pair`<`A`, `B`>, Record(["first": _ `A`, "second": _ `B`])), \
+--!  KailuaType(Local, `List`<`T`>, Array(_ `T`)), \
+--!  KailuaAssume(`x`_ => `x`$1, _, `Pair`<Integer, `List`<String>>)$1]
+
+--8<-- alias-generic-builtin
+--# type Box<string> = {value: string} --@< Error: Cannot redefine a builtin type
+--! [KailuaType(Exported, `Box`<`string`>, Record(["value": _ String]))]
+
 --8<-- alias-incomplete
 --# type Int =
 --# assume x: vector<Int> --@< Error: Expected a single type, got a keyword `assume`
@@ -2266,6 +2359,200 @@ f()
 --# type `goto` = integer
 --! [KailuaOpen(`lua51`), KailuaType(Exported, `goto`, Integer)]
 
+--8<-- lua52-goto
+--# open lua52
+goto foo
+::foo::
+--! [KailuaOpen(`lua52`), Goto(`foo`), Label(`foo`)]
+
+--8<-- lua52-goto-backward
+--# open lua52
+::foo::
+do goto foo end
+--! [KailuaOpen(`lua52`), Label(`foo`), Do([Goto(`foo`)])]
+
+--8<-- lua52-goto-no-label
+--# open lua52
+goto foo --@< Error: No visible label `foo` for `goto`
+--! [KailuaOpen(`lua52`), Goto(`foo`)]
+
+--8<-- lua52-goto-label-in-inner-block
+--# open lua52
+goto foo --@< Error: No visible label `foo` for `goto`
+do ::foo:: end
+--! [KailuaOpen(`lua52`), Goto(`foo`), Do([Label(`foo`)])]
+
+--8<-- lua52-goto-across-function
+--# open lua52
+::foo::
+local function f()
+    goto foo --@< Error: No visible label `foo` for `goto`
+end
+--! [KailuaOpen(`lua52`), Label(`foo`), FuncDecl(`f`$2, [] --> _, $1[Goto(`foo`)])$2]
+
+--8<-- lua52-goto-continue
+--# open lua52
+while f() do
+    if g() then goto continue end
+    h()
+    ::continue::
+end
+--! [KailuaOpen(`lua52`), While(`f`_(), [If((`g`_() => [Goto(`continue`)])), Void(`h`_()), Label(`continue`)])]
+
+--8<-- lua52-label-duplicate
+--# open lua52
+::foo::
+::foo:: --@< Error: The label `foo` is already defined in the same block
+        --@^^ Note: The previous label was here
+--! [KailuaOpen(`lua52`), Label(`foo`), Label(`foo`)]
+
+--8<-- lua52-label-shadowing
+--# open lua52
+::foo::
+do ::foo:: end
+--! [KailuaOpen(`lua52`), Label(`foo`), Do([Label(`foo`)])]
+
+--8<-- lua51-label
+::foo:: --@< Error: `::` is only available since Lua 5.2 and cannot be used in Lua 5.1
+--! [Label(`foo`)]
+
+--8<-- lua52-break-not-last
+--# open lua52
+while true do break; f() end
+--! [KailuaOpen(`lua52`), While(true, [Break, Void(`f`_())])]
+
+--8<-- lua51-break-not-last
+--# open lua51
+while true do break; f() end --@< Error: `return` or `break` cannot be followed by other statements
+--! [KailuaOpen(`lua51`), While(true, [Break, Void(`f`_())])]
+
+--8<-- lua52-env
+--# open lua52
+x = _ENV
+--! [KailuaOpen(`lua52`), Assign([`x`_], [`_ENV`_])]
+
+--8<-- lua52-env-local
+--# open lua52
+local _ENV = {}
+x = y
+--! [KailuaOpen(`lua52`), Local([`_ENV`$1], [{}])$1, Assign([`_ENV`$1.`x`], [`_ENV`$1.`y`])]
+
+--8<-- lua52-env-local-function
+--# open lua52
+local _ENV = {}
+function f() end
+function g.h() end
+--! [KailuaOpen(`lua52`), Local([`_ENV`$1], [{}])$1, \
+--!  MethodDecl((`_ENV`$1.`f`), None, [] --> _, $2[]), \
+--!  MethodDecl((`_ENV`$1.`g`.`h`), None, [] --> _, $3[])]
+
+--8<-- lua51-env-local
+--# open lua51
+local _ENV = {}
+x = y
+--! [KailuaOpen(`lua51`), Local([`_ENV`$1], [{}])$1, Assign([`x`_], [`y`_])]
+
+--8<-- lua53-bitwise-ops
+--# open lua53
+x = a & b | c ~ d << e >> f
+--! [KailuaOpen(`lua53`), Assign([`x`_], [((`a`_ & `b`_) | (`c`_ ~ ((`d`_ << `e`_) >> `f`_)))])]
+
+--8<-- lua53-bitwise-ops-prec
+--# open lua53
+x = a | b ~ c & d << e .. f
+--! [KailuaOpen(`lua53`), Assign([`x`_], [(`a`_ | (`b`_ ~ (`c`_ & (`d`_ << (`e`_ .. `f`_)))))])]
+
+--8<-- lua53-bitwise-ops-vs-comparison
+--# open lua53
+x = a & b == c | d
+--! [KailuaOpen(`lua53`), Assign([`x`_], [((`a`_ & `b`_) == (`c`_ | `d`_))])]
+
+--8<-- lua53-unary-bnot
+--# open lua53
+x = ~a ~ ~b
+--! [KailuaOpen(`lua53`), Assign([`x`_], [((~ `a`_) ~ (~ `b`_))])]
+
+--8<-- lua53-unary-bnot-vs-pow
+--# open lua53
+x = ~a ^ b
+--! [KailuaOpen(`lua53`), Assign([`x`_], [(~ (`a`_ ^ `b`_))])]
+
+--8<-- lua53-idiv
+--# open lua53
+x = a // b * c + d
+--! [KailuaOpen(`lua53`), Assign([`x`_], [(((`a`_ // `b`_) * `c`_) + `d`_)])]
+
+--8<-- lua53-not-equal
+--# open lua53
+x = a ~= b
+--! [KailuaOpen(`lua53`), Assign([`x`_], [(`a`_ ~= `b`_)])]
+
+--8<-- lua52-bitwise-ops
+--# open lua52
+x = a & b --@< Error: `&` is only available since Lua 5.3 and cannot be used in Lua 5.2
+--! [KailuaOpen(`lua52`), Assign([`x`_], [(`a`_ & `b`_)])]
+
+--8<-- lua51-bitwise-ops
+--# open lua51
+x = a // b --@< Error: `//` is only available since Lua 5.3 and cannot be used in Lua 5.1
+y = ~a --@< Error: `~` is only available since Lua 5.3 and cannot be used in Lua 5.1
+--! [KailuaOpen(`lua51`), Assign([`x`_], [(`a`_ // `b`_)]), Assign([`y`_], [(~ `a`_)])]
+
+--8<-- luajit-goto
+--# open luajit
+goto foo
+::foo::
+--! [KailuaOpen(`luajit`), Goto(`foo`), Label(`foo`)]
+
+--8<-- luajit-break-not-last
+--# open luajit
+while true do break; f() end --@< Error: `return` or `break` cannot be followed by other statements
+--! [KailuaOpen(`luajit`), While(true, [Break, Void(`f`_())])]
+
+--8<-- luajit-env-local
+--# open luajit
+local _ENV = {}
+x = y
+--! [KailuaOpen(`luajit`), Local([`_ENV`$1], [{}])$1, Assign([`x`_], [`y`_])]
+
+--8<-- luajit-bitwise-ops
+--# open luajit
+x = a & b --@< Error: `&` is only available since Lua 5.3 and cannot be used in LuaJIT 2.x
+--! [KailuaOpen(`luajit`), Assign([`x`_], [(`a`_ & `b`_)])]
+
+--8<-- luajit-cdata-numbers
+--# open luajit
+x = 1LL + 0x10ULL * 12i
+y = 3ll + 4Ull + 5I
+--! [KailuaOpen(`luajit`), Assign([`x`_], [(1LL + (16ULL * 12i))]), \
+--!  Assign([`y`_], [((3LL + 4ULL) + 5i)])]
+
+--8<-- lua51-cdata-number
+x = 1LL --@< Error: Cdata number literals are only available in LuaJIT and cannot be used in Lua 5.1
+--! [Assign([`x`_], [1LL])]
+
+--8<-- lua52-cdata-number
+--# open lua52
+x = 0x10ULL --@< Error: Cdata number literals are only available in LuaJIT and cannot be used in Lua 5.2
+--! [KailuaOpen(`lua52`), Assign([`x`_], [16ULL])]
+
+--8<-- luajit-invalid-number-suffix
+--# open luajit
+f(1LLU) --@< Error: Invalid number
+--! [KailuaOpen(`luajit`), Void(`f`_())]
+
+--8<-- luajit-float-cdata-number
+--# open luajit
+f(1.5LL) --@< Error: Invalid number
+g(1e3ULL) --@< Error: Invalid number
+x = 2.5i + 1e3i
+--! [KailuaOpen(`luajit`), Void(`f`_()), Void(`g`_()), Assign([`x`_], [(2.5i + 1000i)])]
+
+--8<-- lua53-kind-nested-params
+--# open lua53
+local x --: vector<vector<integer>>
+--! [KailuaOpen(`lua53`), Local([`x`$1: _ Array(_ Array(_ Integer))], [])$1]
+
 --8<-- type-spec-recover-negative-span
 local a = {} --: var { var { } } --@< Error: Expected a newline, got a keyword `var`
 local b --: var { var { } }      --@< Error: Expected a newline, got a keyword `var`
@@ -8,7 +8,7 @@ extern crate kailua_syntax;
 
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use clap::{App, Arg, ArgMatches};
 use kailua_env::{Source, Span};
 use kailua_diag::{Report, Reporter, TrackMaxKind};
@@ -77,7 +77,7 @@ impl kailua_test::Testing for Testing {
     }
 
     fn run(&self, source: Rc<RefCell<Source>>, span: Span, _filespans: &HashMap<String, Span>,
-           report: Rc<Report>) -> String {
+           _features: &HashSet<String>, report: Rc<Report>) -> String {
         let report = TrackMaxKind::new(&*report);
         if let Ok((tokens, chunk)) = lex_and_parse_chunk(&source.borrow(), span, &report) {
             assert_eq!(tokens.len(), chunk.token_aux.len());
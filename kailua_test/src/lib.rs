@@ -21,14 +21,15 @@
 //!
 //! use std::cell::RefCell;
 //! use std::rc::Rc;
-//! use std::collections::HashMap;
+//! use std::collections::{HashMap, HashSet};
 //! use kailua_env::{Source, Span};
 //! use kailua_diag::Report;
 //!
 //! struct Testing;
 //! impl kailua_test::Testing for Testing {
 //!     fn run(&self, source: Rc<RefCell<Source>>, span: Span,
-//!            filespans: &HashMap<String, Span>, report: Rc<Report>) -> String {
+//!            filespans: &HashMap<String, Span>, features: &HashSet<String>,
+//!            report: Rc<Report>) -> String {
 //!         format!("Expected test output")
 //!     }
 //! }
@@ -105,6 +106,10 @@
 //!
 //! * `feature:!FEATURE` enables the test only when a particular feature is disabled.
 //!
+//!   If the feature has been registered with `Tester::configurable_feature` instead,
+//!   these options do not block the test but turn the feature on or off for that test.
+//!   The resulting set of features is given to `Testing::run`.
+//!
 //! # Test invocation
 //!
 //! `cargo test` (or if you have multiple test binaries, `cargo test --test NAME`)
@@ -177,8 +182,9 @@ pub trait Testing {
     ///
     /// `source` holds all inputs. `span` is the span for the (unnamed) main file;
     /// `filespans` are for everything else.
+    /// `features` is a set of features enabled for this test.
     fn run(&self, source: Rc<RefCell<Source>>, span: Span, filespans: &HashMap<String, Span>,
-           report: Rc<Report>) -> String;
+           features: &HashSet<String>, report: Rc<Report>) -> String;
 
     /// Checks if the actual output and expected output matches.
     /// By default it is a simple string equivalence.
@@ -571,6 +577,7 @@ struct TestLog {
 pub struct Tester<T> {
     testing: T,
     features: HashSet<String>,
+    configurable_features: HashSet<String>,
     filter: Option<Regex>,
     term: Box<StderrTerminal>,
     verbose: bool,
@@ -648,7 +655,8 @@ impl<T: Testing> Tester<T> {
 
         let term = term::stderr().unwrap();
         Tester {
-            testing: testing, features: HashSet::new(), filter: filter, term: term,
+            testing: testing, features: HashSet::new(), configurable_features: HashSet::new(),
+            filter: filter, term: term,
             verbose: verbose, exact_diags: exact_diags, highlight_mismatch: highlight_mismatch,
             message_locale: message_locale, stop_on_panic: stop_on_panic > 0,
             force: force_level > 0, ignore_features: force_level > 1,
@@ -665,6 +673,22 @@ impl<T: Testing> Tester<T> {
         self
     }
 
+    /// Adds a feature which can be turned on or off by each test (via `feature:*` test options)
+    /// and sets its default value.
+    pub fn configurable_feature(mut self, name: &str, default: bool) -> Tester<T> {
+        info!("feature {} is configurable and turned {} by default",
+              name, if default { "on" } else { "off" });
+        self.configurable_features.insert(name.to_owned());
+        if default {
+            self.features.insert(name.to_owned());
+        }
+        self
+    }
+
+    fn feature_name(feat: &str) -> (&str, bool) {
+        if feat.starts_with("!") { (&feat[1..], false) } else { (feat, true) }
+    }
+
     /// Scans a given directory for test files and tests any of them in the current setting.
     pub fn scan<P: AsRef<Path>>(mut self, dir: P) -> Tester<T> {
         for f in fs::read_dir(dir).expect("failed to read the test directory") {
@@ -687,11 +711,9 @@ impl<T: Testing> Tester<T> {
                 let mut ignored = test.ignored && !self.force;
                 if !test.features.is_empty() && !self.ignore_features {
                     ignored |= !test.features.iter().all(|feat| {
-                        if feat.starts_with("!") {
-                            !self.features.contains(&feat[1..])
-                        } else {
-                            self.features.contains(feat)
-                        }
+                        let (name, value) = Self::feature_name(feat);
+                        self.configurable_features.contains(name) ||
+                            self.features.contains(name) == value
                     });
                 }
                 if ignored {
@@ -743,6 +765,19 @@ impl<T: Testing> Tester<T> {
             filespans.insert(file.to_owned(), source.add(srcfile));
         }
 
+        // configurable features can be overridden by the test
+        let mut features = self.features.clone();
+        for feat in &test.features {
+            let (name, value) = Self::feature_name(feat);
+            if self.configurable_features.contains(name) {
+                if value {
+                    features.insert(name.to_owned());
+                } else {
+                    features.remove(name);
+                }
+            }
+        }
+
         let source = Rc::new(RefCell::new(source));
         let collected = Rc::new(CollectedReport::new(self.message_locale));
         let output = {
@@ -753,7 +788,7 @@ impl<T: Testing> Tester<T> {
                 panic::set_hook(Box::new(|_| {})); // suppress the default panicking message
             }
             let output = panic::catch_unwind(move || {
-                testing.run(source.0, inputspan, &filespans, report.0)
+                testing.run(source.0, inputspan, &filespans, &features, report.0)
             });
             panic::take_hook();
             output
@@ -4,7 +4,6 @@ use std::mem;
 use std::str;
 use std::fmt;
 use std::collections::{HashMap, HashSet};
-use vec_map::{self, VecMap};
 use atomic::Atomic;
 use atomic::Ordering::Relaxed;
 
@@ -14,8 +13,10 @@ use ty::{Ty, T, Slot, TVar, RVar, Lattice, Key};
 use ty::{TypeContext, ClassId, ClassSystemId, DisplayState};
 use ty::flags::*;
 use self::partitions::{Partition, Partitions};
+use self::undo::UndoMap;
 
 mod partitions;
+mod undo;
 
 #[derive(Debug)]
 struct Bound {
@@ -24,6 +25,13 @@ struct Bound {
     bound: Option<Ty>,
 }
 
+impl Clone for Bound {
+    fn clone(&self) -> Bound {
+        Bound { parent: Atomic::new(self.parent.load(Relaxed)),
+                rank: self.rank, bound: self.bound.clone() }
+    }
+}
+
 // a set of constraints that can be organized as a tree
 #[derive(Debug)]
 struct Constraints {
@@ -93,7 +101,7 @@ impl Constraints {
         let rhs_ = self.bounds.find(rhs.0 as usize);
         if lhs_ == rhs_ { return true; }
 
-        fn take_bound(bounds: &mut VecMap<Box<Bound>>, i: usize) -> Option<Ty> {
+        fn take_bound(bounds: &mut UndoMap<Box<Bound>>, i: usize) -> Option<Ty> {
             if let Some(b) = bounds.get_mut(i) {
                 mem::replace(&mut b.bound, None)
             } else {
@@ -126,9 +134,21 @@ impl Constraints {
 
         true
     }
+
+    fn save(&mut self) -> usize {
+        self.bounds.save()
+    }
+
+    fn commit(&mut self) {
+        self.bounds.commit();
+    }
+
+    fn rollback(&mut self, mark: usize) {
+        self.bounds.rollback(mark);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct RowInfo {
     // the hashmap being None indicates that it is currently recursing;
     // the value can be Some(slot) for "positive" fields, which the row variable contains that key,
@@ -208,6 +228,16 @@ impl ClassProvider for DummyClassProvider {
     }
 }
 
+// positions in the undo logs saved by `TypeContext::save_constraints`.
+// type and row variables are never reused, so only changes to the constraints are undone.
+struct SavePoint {
+    tvar_sub: usize,
+    tvar_sup: usize,
+    tvar_eq: usize,
+    row_infos: usize,
+    row_metatables: usize,
+}
+
 /// The type environment.
 pub struct Types {
     message_locale: Locale,
@@ -217,13 +247,18 @@ pub struct Types {
     tvar_sub: Constraints, // upper bound
     tvar_sup: Constraints, // lower bound
     tvar_eq: Constraints, // tight bound
+    rigid_tvars: HashSet<TVar>, // cannot have any bound
 
     // row variable information
     next_rvar: RVar,
-    row_infos: VecMap<Box<RowInfo>>,
+    row_infos: UndoMap<Box<RowInfo>>,
+    row_metatables: UndoMap<Slot>,
 
     // classes and class systems are handled in a separate subsystem, encapsulated as ClassProvider
     classes: Box<ClassProvider>,
+
+    // a stack of save points, see `TypeContext::save_constraints`
+    saved: Vec<SavePoint>,
 }
 
 impl Types {
@@ -235,9 +270,12 @@ impl Types {
             tvar_sub: Constraints::new("<:"),
             tvar_sup: Constraints::new(":>"),
             tvar_eq: Constraints::new("="),
+            rigid_tvars: HashSet::new(),
             next_rvar: RVar::new(1), // RVar::new(0) == RVar::empty()
-            row_infos: VecMap::new(),
+            row_infos: UndoMap::new(),
+            row_metatables: UndoMap::new(),
             classes: classes,
+            saved: Vec::new(),
         }
     }
 
@@ -277,46 +315,43 @@ impl Types {
                     return Ok((present, r));
                 }
 
-                match ctx.row_infos.entry(r.to_usize()) {
-                    vec_map::Entry::Occupied(row) => {
-                        let row = row.get();
-
-                        let fields = if let Some(ref fields) = row.fields {
-                            fields
-                        } else {
-                            err = E::Recursive;
-                            break 'err;
-                        };
-
-                        for (k, v) in fields {
-                            if let Some(ref v) = *v {
-                                // positive field should be unique
-                                if present.insert(k.clone(), v.clone()).is_some() {
-                                    err = E::Duplicate(k.clone());
-                                    break 'err;
-                                }
-                            } else {
-                                // negative field will *not* be unique
-                                absent.insert(k.clone());
+                // do not use `entry` here, the undo log should only record actual changes
+                if let Some(row) = ctx.row_infos.get(r.to_usize()) {
+                    let fields = if let Some(ref fields) = row.fields {
+                        fields
+                    } else {
+                        err = E::Recursive;
+                        break 'err;
+                    };
+
+                    for (k, v) in fields {
+                        if let Some(ref v) = *v {
+                            // positive field should be unique
+                            if present.insert(k.clone(), v.clone()).is_some() {
+                                err = E::Duplicate(k.clone());
+                                break 'err;
                             }
-                        }
-
-                        if let Some(ref next) = row.next {
-                            r = next.clone();
                         } else {
-                            return Ok((present, r));
+                            // negative field will *not* be unique
+                            absent.insert(k.clone());
                         }
                     }
 
-                    vec_map::Entry::Vacant(e) => {
-                        // instantiate the row and implicitly fill negative fields
-                        let mut fields = HashMap::new();
-                        fields.extend(present.iter().map(|(k, _)| (k.clone(), None)));
-                        fields.extend(absent.into_iter().map(|k| (k, None)));
-                        e.insert(Box::new(RowInfo { fields: Some(fields), next: None }));
+                    if let Some(ref next) = row.next {
+                        r = next.clone();
+                        continue;
+                    } else {
                         return Ok((present, r));
                     }
                 }
+
+                // instantiate the row and implicitly fill negative fields
+                let mut fields = HashMap::new();
+                fields.extend(present.iter().map(|(k, _)| (k.clone(), None)));
+                fields.extend(absent.into_iter().map(|k| (k, None)));
+                ctx.row_infos.insert(r.to_usize(),
+                                     Box::new(RowInfo { fields: Some(fields), next: None }));
+                return Ok((present, r));
             }
 
             match err {
@@ -485,8 +520,18 @@ impl TypeContext for Types {
         self.next_tvar
     }
 
+    fn gen_rigid_tvar(&mut self) -> TVar {
+        let tvar = self.gen_tvar();
+        self.rigid_tvars.insert(tvar);
+        tvar
+    }
+
     fn copy_tvar(&mut self, tvar: TVar) -> TVar {
-        if self.tvar_eq.get_bound(tvar).map_or(false, |b| b.bound.is_some()) {
+        if self.rigid_tvars.contains(&tvar) {
+            // rigid variables are never extended
+            trace!("copying rigid {:?} is a no-op", tvar);
+            tvar
+        } else if self.tvar_eq.get_bound(tvar).map_or(false, |b| b.bound.is_some()) {
             // we have an equal bound, so tvar has no chance to be extended
             trace!("copying {:?} is a no-op", tvar);
             tvar
@@ -508,6 +553,12 @@ impl TypeContext for Types {
     fn assert_tvar_sub(&mut self, lhs: TVar, rhs0: &Ty) -> TypeResult<()> {
         let rhs = rhs0.clone().coerce();
         debug!("adding a constraint {:?} <: {:?} (coerced to {:?})", lhs, rhs0, rhs);
+        if self.rigid_tvars.contains(&lhs) {
+            return match *rhs {
+                T::All | T::Dynamic(_) => Ok(()),
+                _ => Err(self.gen_report().not_sub(Origin::TVar, "<tvar>", rhs0, self)),
+            };
+        }
         if let Some(eb) = self.tvar_eq.get_bound(lhs).and_then(|b| b.bound.clone()) {
             eb.assert_sub(&rhs, self)?;
         } else {
@@ -529,6 +580,12 @@ impl TypeContext for Types {
     fn assert_tvar_sup(&mut self, lhs: TVar, rhs: &Ty) -> TypeResult<()> {
         // no coercion here, as type coercion will always expand the type
         debug!("adding a constraint {:?} :> {:?}", lhs, rhs);
+        if self.rigid_tvars.contains(&lhs) {
+            return match **rhs {
+                T::None | T::Dynamic(_) => Ok(()),
+                _ => Err(self.gen_report().not_sub(Origin::TVar, rhs, "<tvar>", self)),
+            };
+        }
         if let Some(eb) = self.tvar_eq.get_bound(lhs).and_then(|b| b.bound.clone()) {
             rhs.assert_sub(&eb, self)?;
         } else {
@@ -550,6 +607,12 @@ impl TypeContext for Types {
     fn assert_tvar_eq(&mut self, lhs: TVar, rhs0: &Ty) -> TypeResult<()> {
         let rhs = rhs0.clone().coerce();
         debug!("adding a constraint {:?} = {:?} (coerced to {:?})", lhs, rhs0, rhs);
+        if self.rigid_tvars.contains(&lhs) {
+            return match *rhs {
+                T::Dynamic(_) => Ok(()),
+                _ => Err(self.gen_report().not_eq(Origin::TVar, "<tvar>", rhs0, self)),
+            };
+        }
         if let Some(eb) = self.tvar_eq.add_bound(lhs, &rhs).map(|b| b.clone()) {
             // the original bound is not consistent, bound = rhs still has to hold
             if let Err(e) = eb.assert_eq(&rhs, self) {
@@ -570,21 +633,43 @@ impl TypeContext for Types {
 
     fn assert_tvar_sub_tvar(&mut self, lhs: TVar, rhs: TVar) -> TypeResult<()> {
         debug!("adding a constraint {:?} <: {:?}", lhs, rhs);
-        if !self.tvar_eq.is(lhs, rhs) {
-            if !self.tvar_sub.add_relation(lhs, rhs) {
-                // TODO
-                return Err(self.gen_report().not_sub(Origin::TVar, "<tvar>", "<tvar>", self));
-            }
-            if !self.tvar_sup.add_relation(rhs, lhs) {
-                // TODO
+        if self.tvar_eq.is(lhs, rhs) {
+            return Ok(());
+        }
+        // a rigid variable can only bound the other variable
+        match (self.rigid_tvars.contains(&lhs), self.rigid_tvars.contains(&rhs)) {
+            (false, false) => {}
+            (true, false) => return self.assert_tvar_sup(rhs, &Ty::new(T::TVar(lhs))),
+            (false, true) => return self.assert_tvar_sub(lhs, &Ty::new(T::TVar(rhs))),
+            (true, true) => {
                 return Err(self.gen_report().not_sub(Origin::TVar, "<tvar>", "<tvar>", self));
             }
         }
+        if !self.tvar_sub.add_relation(lhs, rhs) {
+            // TODO
+            return Err(self.gen_report().not_sub(Origin::TVar, "<tvar>", "<tvar>", self));
+        }
+        if !self.tvar_sup.add_relation(rhs, lhs) {
+            // TODO
+            return Err(self.gen_report().not_sub(Origin::TVar, "<tvar>", "<tvar>", self));
+        }
         Ok(())
     }
 
     fn assert_tvar_eq_tvar(&mut self, lhs: TVar, rhs: TVar) -> TypeResult<()> {
         debug!("adding a constraint {:?} = {:?}", lhs, rhs);
+        if self.tvar_eq.is(lhs, rhs) {
+            return Ok(());
+        }
+        // a rigid variable can only bound the other variable
+        match (self.rigid_tvars.contains(&lhs), self.rigid_tvars.contains(&rhs)) {
+            (false, false) => {}
+            (true, false) => return self.assert_tvar_eq(rhs, &Ty::new(T::TVar(lhs))),
+            (false, true) => return self.assert_tvar_eq(lhs, &Ty::new(T::TVar(rhs))),
+            (true, true) => {
+                return Err(self.gen_report().not_eq(Origin::TVar, "<tvar>", "<tvar>", self));
+            }
+        }
         // do not update tvar_sub & tvar_sup, tvar_eq will be consulted first
         if !self.tvar_eq.add_relation(lhs, rhs) {
             // TODO
@@ -611,6 +696,10 @@ impl TypeContext for Types {
         self.tvar_eq.get_bound(tvar).and_then(|b| b.bound.as_ref()).cloned()
     }
 
+    fn get_tvar_lower_bound(&self, tvar: TVar) -> Option<Ty> {
+        self.tvar_sup.get_bound(tvar).and_then(|b| b.bound.as_ref()).cloned()
+    }
+
     fn gen_rvar(&mut self) -> RVar {
         let rvar = self.next_rvar.clone();
         self.next_rvar = RVar::new(rvar.to_usize() + 1);
@@ -657,6 +746,9 @@ impl TypeContext for Types {
         let rvar = self.gen_rvar();
         self.row_infos.insert(rvar.to_usize(),
                               Box::new(RowInfo { fields: Some(fields), next: None }));
+        if let Some(meta) = self.row_metatables.get(rvar0.to_usize()).cloned() {
+            self.row_metatables.insert(rvar.to_usize(), meta);
+        }
         rvar
     }
 
@@ -744,6 +836,52 @@ impl TypeContext for Types {
         }
     }
 
+    fn get_rvar_metatable(&self, rvar: RVar) -> Option<Slot> {
+        self.row_metatables.get(rvar.to_usize()).cloned()
+    }
+
+    fn set_rvar_metatable(&mut self, rvar: RVar, meta: Option<Slot>) {
+        // special row variables are shared and cannot have a metatable
+        if rvar == RVar::empty() || rvar == RVar::any() {
+            return;
+        }
+
+        if let Some(meta) = meta {
+            self.row_metatables.insert(rvar.to_usize(), meta);
+        } else {
+            self.row_metatables.remove(rvar.to_usize());
+        }
+    }
+
+    fn save_constraints(&mut self) {
+        let point = SavePoint {
+            tvar_sub: self.tvar_sub.save(),
+            tvar_sup: self.tvar_sup.save(),
+            tvar_eq: self.tvar_eq.save(),
+            row_infos: self.row_infos.save(),
+            row_metatables: self.row_metatables.save(),
+        };
+        self.saved.push(point);
+    }
+
+    fn commit_constraints(&mut self) {
+        self.saved.pop().expect("commit_constraints called without save_constraints");
+        self.tvar_sub.commit();
+        self.tvar_sup.commit();
+        self.tvar_eq.commit();
+        self.row_infos.commit();
+        self.row_metatables.commit();
+    }
+
+    fn rollback_constraints(&mut self) {
+        let point = self.saved.pop().expect("rollback_constraints called without save_constraints");
+        self.tvar_sub.rollback(point.tvar_sub);
+        self.tvar_sup.rollback(point.tvar_sup);
+        self.tvar_eq.rollback(point.tvar_eq);
+        self.row_infos.rollback(point.row_infos);
+        self.row_metatables.rollback(point.row_metatables);
+    }
+
     fn fmt_class_name(&self, cid: ClassId, f: &mut fmt::Formatter,
                       st: &DisplayState) -> fmt::Result {
         self.classes.fmt_class_name(cid, f, st)
@@ -828,5 +966,44 @@ fn test_types_tvar() {
         assert!(types.assert_tvar_sub(v3, &Ty::new(T::Number)).is_ok());
         assert!(types.assert_tvar_eq(v3, &Ty::new(T::String)).is_err());
     }
+
+    { // rigid variables
+        let v1 = types.gen_rigid_tvar();
+        assert!(types.assert_tvar_sub(v1, &Ty::new(T::Integer)).is_err());
+        assert!(types.assert_tvar_sup(v1, &Ty::new(T::Integer)).is_err());
+        assert!(types.assert_tvar_sub(v1, &Ty::new(T::All)).is_ok());
+
+        let v2 = types.gen_tvar();
+        assert!(types.assert_tvar_sub_tvar(v2, v1).is_ok());
+        assert!(types.assert_tvar_sup(v2, &Ty::new(T::Integer)).is_err());
+
+        let v3 = types.gen_rigid_tvar();
+        assert!(types.assert_tvar_sub_tvar(v1, v1).is_ok());
+        assert!(types.assert_tvar_sub_tvar(v1, v3).is_err());
+    }
+
+    { // saved constraints
+        let v1 = types.gen_tvar();
+        types.save_constraints();
+        assert!(types.assert_tvar_sub(v1, &Ty::new(T::Integer)).is_ok());
+        types.rollback_constraints();
+        assert!(types.assert_tvar_sub(v1, &Ty::new(T::String)).is_ok());
+
+        types.save_constraints();
+        assert!(types.assert_tvar_sup(v1, &Ty::new(T::String)).is_ok());
+        types.commit_constraints();
+        assert!(types.assert_tvar_sup(v1, &Ty::new(T::Integer)).is_err());
+
+        let v2 = types.gen_tvar();
+        let v3 = types.gen_tvar();
+        types.save_constraints();
+        assert!(types.assert_tvar_sub_tvar(v2, v3).is_ok());
+        types.save_constraints();
+        assert!(types.assert_tvar_sub(v3, &Ty::new(T::Integer)).is_ok());
+        types.commit_constraints();
+        types.rollback_constraints();
+        assert!(types.assert_tvar_sub(v3, &Ty::new(T::Integer)).is_ok());
+        assert!(types.assert_tvar_sub(v2, &Ty::new(T::String)).is_ok());
+    }
 }
 
@@ -1,5 +1,5 @@
 use std::ops;
-use vec_map::VecMap;
+use super::undo::UndoMap;
 
 pub trait Partition {
     fn create(parent: usize, rank: usize) -> Self;
@@ -10,24 +10,27 @@ pub trait Partition {
 
 #[derive(Debug)]
 pub struct Partitions<T> {
-    map: VecMap<T>,
+    map: UndoMap<T>,
 }
 
-impl<T: Partition> Partitions<T> {
+impl<T: Partition + Clone> Partitions<T> {
     pub fn new() -> Partitions<T> {
-        Partitions { map: VecMap::new() }
+        Partitions { map: UndoMap::new() }
     }
 
     pub fn find(&self, i: usize) -> usize {
         if let Some(u) = self.map.get(i) {
             let (mut parent, _) = u.read();
-            if parent != i { // path compression
+            if parent != i {
                 while let Some(v) = self.map.get(parent) {
                     let (newparent, _) = v.read();
                     if newparent == parent { break; }
                     parent = newparent;
                 }
-                u.write_parent(parent);
+                // path compression, which cannot be undone (and is not required) while saving
+                if !self.map.is_saving() {
+                    u.write_parent(parent);
+                }
             }
             parent
         } else {
@@ -63,11 +66,11 @@ impl<T: Partition> Partitions<T> {
 }
 
 impl<T> ops::Deref for Partitions<T> {
-    type Target = VecMap<T>;
-    fn deref(&self) -> &VecMap<T> { &self.map }
+    type Target = UndoMap<T>;
+    fn deref(&self) -> &UndoMap<T> { &self.map }
 }
 
 impl<T> ops::DerefMut for Partitions<T> {
-    fn deref_mut(&mut self) -> &mut VecMap<T> { &mut self.map }
+    fn deref_mut(&mut self) -> &mut UndoMap<T> { &mut self.map }
 }
 
@@ -0,0 +1,82 @@
+use std::ops;
+use vec_map::{self, VecMap};
+
+// a map that can undo every change made after a save point.
+// changes are only recorded while there is a save point, so this has no overhead otherwise.
+#[derive(Debug)]
+pub struct UndoMap<T> {
+    map: VecMap<T>,
+    // previous values of changed entries, in the order of changes
+    log: Vec<(usize, Option<T>)>,
+    // the number of save points not yet committed or rolled back
+    saves: usize,
+}
+
+impl<T: Clone> UndoMap<T> {
+    pub fn new() -> UndoMap<T> {
+        UndoMap { map: VecMap::new(), log: Vec::new(), saves: 0 }
+    }
+
+    pub fn is_saving(&self) -> bool {
+        self.saves > 0
+    }
+
+    fn record(&mut self, i: usize) {
+        if self.saves > 0 {
+            let prev = self.map.get(i).cloned();
+            self.log.push((i, prev));
+        }
+    }
+
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        self.record(i);
+        self.map.get_mut(i)
+    }
+
+    pub fn entry(&mut self, i: usize) -> vec_map::Entry<T> {
+        self.record(i);
+        self.map.entry(i)
+    }
+
+    pub fn insert(&mut self, i: usize, value: T) -> Option<T> {
+        self.record(i);
+        self.map.insert(i, value)
+    }
+
+    pub fn remove(&mut self, i: usize) -> Option<T> {
+        self.record(i);
+        self.map.remove(i)
+    }
+
+    // returns a mark that should be later passed to `rollback` (or discarded by `commit`)
+    pub fn save(&mut self) -> usize {
+        self.saves += 1;
+        self.log.len()
+    }
+
+    pub fn commit(&mut self) {
+        assert!(self.saves > 0, "commit called without save");
+        self.saves -= 1;
+        if self.saves == 0 {
+            // no one will roll back any more
+            self.log.clear();
+        }
+    }
+
+    pub fn rollback(&mut self, mark: usize) {
+        while self.log.len() > mark {
+            let (i, prev) = self.log.pop().unwrap();
+            if let Some(prev) = prev {
+                self.map.insert(i, prev);
+            } else {
+                self.map.remove(i);
+            }
+        }
+        self.commit();
+    }
+}
+
+impl<T> ops::Deref for UndoMap<T> {
+    type Target = VecMap<T>;
+    fn deref(&self) -> &VecMap<T> { &self.map }
+}
@@ -15,8 +15,8 @@
 //! The type system *does* have types for not allowing `nil` or accesses to unknown labels,
 //! but they exist mostly to avoid mistakes and not to make the type system sound.
 //!
-//! Generics are planned but not yet implemented;
-//! this is probably the biggest missing type system feature.
+//! Generics are limited to type parameters of functions and type aliases,
+//! and type parameters in function types are instantiated per call without any bounds.
 //! Many recursive types are not properly checked too,
 //! which requires proper occurs check that the type system still doesn't implement (ugh).
 
@@ -22,6 +22,9 @@ pub struct DisplayState<'a> {
     // rvars are one of the biggest source of type display bloats,
     // so they are controlled via {:.<maxlevel>} (0 means that no records are printed)
     max_rvar_level: Option<usize>,
+
+    // display hints are ignored everywhere if requested via {:-}
+    ignore_hints: bool,
 }
 
 impl<'a> DisplayState<'a> {
@@ -34,9 +37,14 @@ impl<'a> DisplayState<'a> {
             tvars_seen: RefCell::new(HashSet::new()),
             rvars_seen: RefCell::new(HashSet::new()),
             max_rvar_level: f.precision(),
+            ignore_hints: f.sign_minus(),
         }
     }
 
+    pub fn ignores_hints(&self) -> bool {
+        self.ignore_hints
+    }
+
     pub fn is_top_level(&self) -> bool {
         self.rvars_seen.borrow().is_empty()
     }
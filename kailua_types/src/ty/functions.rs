@@ -5,11 +5,18 @@ use kailua_syntax::Name;
 use kailua_syntax::ast::{FuncKind, Returns};
 
 use diag::{Origin, TypeReport, TypeResult};
-use super::{Display, DisplayState, Ty, TySeq, TypeContext, TypeResolver, Lattice};
+use super::{Display, DisplayState, DisplayName, T, Ty, TySeq, TVar, TypeContext, TypeResolver};
+use super::Lattice;
+use super::TypeParamResolver;
 
 /// A function type.
 #[derive(Clone, PartialEq)]
 pub struct Function {
+    /// Type parameters and corresponding type variables, empty if the function is not generic.
+    ///
+    /// Type variables are only placeholders to be replaced on each call.
+    pub tparams: Vec<(Spanned<Name>, TVar)>,
+
     /// Argument types.
     pub args: TySeq,
 
@@ -22,6 +29,11 @@ pub struct Function {
 
 impl Function {
     pub fn from_kind(func: &Spanned<FuncKind>, resolv: &mut TypeResolver) -> Result<Function> {
+        let tparams: Vec<_> = func.tparams.iter().map(|name| {
+            (name.clone(), resolv.context_mut().gen_tvar())
+        }).collect();
+        let resolv = &mut TypeParamResolver::new(resolv, &tparams);
+
         let args = TySeq::from_kind_seq(&func.args, |namekind| &namekind.1, resolv)?;
         let mut argnames = Vec::new();
         for (i, &(ref name, _)) in func.args.head.iter().enumerate() {
@@ -34,10 +46,73 @@ impl Function {
             Returns::Seq(ref seq) => Some(TySeq::from_kind_seq(seq, |kind| kind, resolv)?),
             Returns::Never(_span) => None,
         };
-        Ok(Function { args: args, argnames: argnames, returns: returns })
+        Ok(Function { tparams: tparams.clone(), args: args, argnames: argnames, returns: returns })
+    }
+
+    /// Replaces given type variables to corresponding types, recursively.
+    /// Returns `None` if nothing has been replaced.
+    pub fn substitute(&self, tvars: &[(TVar, Ty)], ctx: &mut TypeContext) -> Option<Function> {
+        let args = self.args.substitute(tvars, ctx);
+        let returns = self.returns.as_ref().and_then(|returns| returns.substitute(tvars, ctx));
+        if args.is_none() && returns.is_none() {
+            return None;
+        }
+        Some(Function {
+            tparams: self.tparams.clone(),
+            args: args.unwrap_or_else(|| self.args.clone()),
+            argnames: self.argnames.clone(),
+            returns: if returns.is_some() { returns } else { self.returns.clone() },
+        })
+    }
+
+    /// Replaces type parameters to fresh type variables if the function is generic.
+    ///
+    /// Returns a list of fresh type variables (in the order of type parameters)
+    /// and the resulting function type without type parameters.
+    pub fn instantiate(&self, ctx: &mut TypeContext) -> (Vec<TVar>, Function) {
+        if self.tparams.is_empty() {
+            return (Vec::new(), self.clone());
+        }
+
+        // fresh type variables are still displayed as type parameters
+        let tvars: Vec<_> = self.tparams.iter().map(|&(ref name, tv)| {
+            let ty = Ty::new(T::TVar(ctx.gen_tvar())).and_display(DisplayName::Type(name.clone()));
+            (tv, ty)
+        }).collect();
+        let mut func = self.substitute(&tvars, ctx).unwrap_or_else(|| self.clone());
+        func.tparams.clear();
+        let fresh = tvars.into_iter().map(|(_, ty)| ty.get_tvar().unwrap()).collect();
+        (fresh, func)
+    }
+
+    /// Replaces type parameters to fresh rigid type variables if the function is generic.
+    ///
+    /// Unlike `instantiate`, the resulting function type still accepts any type argument,
+    /// so this is used for the function type that others should be compatible to.
+    pub fn instantiate_rigid(&self, ctx: &mut TypeContext) -> Function {
+        if self.tparams.is_empty() {
+            return self.clone();
+        }
+
+        let tvars: Vec<_> = self.tparams.iter().map(|&(ref name, tv)| {
+            let ty = Ty::new(T::TVar(ctx.gen_rigid_tvar()))
+                .and_display(DisplayName::Type(name.clone()));
+            (tv, ty)
+        }).collect();
+        let mut func = self.substitute(&tvars, ctx).unwrap_or_else(|| self.clone());
+        func.tparams.clear();
+        func
     }
 
     fn assert_sub(&self, other: &Self, ctx: &mut TypeContext) -> TypeResult<()> {
+        // generic functions are compared after instantiation,
+        // where the expected function should work for every type argument
+        if !self.tparams.is_empty() || !other.tparams.is_empty() {
+            let (_, lhs) = self.instantiate(ctx);
+            let rhs = other.instantiate_rigid(ctx);
+            return lhs.assert_sub(&rhs, ctx);
+        }
+
         // contravariant
         other.args.assert_sub(&self.args, ctx)?;
 
@@ -52,6 +127,12 @@ impl Function {
     }
 
     fn assert_eq(&self, other: &Self, ctx: &mut TypeContext) -> TypeResult<()> {
+        if !self.tparams.is_empty() || !other.tparams.is_empty() {
+            let (_, lhs) = self.instantiate(ctx);
+            let rhs = other.instantiate_rigid(ctx);
+            return lhs.assert_eq(&rhs, ctx);
+        }
+
         self.args.assert_eq(&other.args, ctx)?;
 
         match (&self.returns, &other.returns) {
@@ -72,7 +153,17 @@ impl Function {
             where WriteTy: FnMut(&Ty, &mut fmt::Formatter, bool) -> fmt::Result,
                   WriteTySeq: FnMut(&TySeq, &mut fmt::Formatter) -> fmt::Result {
         // we cannot directly print self.args as they should be interleaved with self.argnames
-        write!(f, "function(")?;
+        write!(f, "function")?;
+        if !self.tparams.is_empty() {
+            write!(f, "<")?;
+            let mut first = true;
+            for &(ref name, _) in &self.tparams {
+                if first { first = false; } else { write!(f, ", ")?; }
+                write!(f, "{:+}", name)?;
+            }
+            write!(f, ">")?;
+        }
+        write!(f, "(")?;
         let mut first = true;
         let mut names = self.argnames.iter();
         for t in &self.args.head {
@@ -139,17 +230,61 @@ pub enum Functions {
     /// A single, not overloaded function.
     Simple(Function),
 
+    /// An overloaded function, `function(...) --> ... & function(...) --> ... & ...`.
+    ///
+    /// Always contains at least two functions.
+    /// When called, the first function accepting given arguments is chosen.
+    Overloaded(Vec<Function>),
+
     /// Any function. (This is not really callable.)
     All,
 }
 
 impl Functions {
+    /// Replaces given type variables to corresponding types, recursively.
+    /// Returns `None` if nothing has been replaced.
+    pub fn substitute(&self, tvars: &[(TVar, Ty)], ctx: &mut TypeContext) -> Option<Functions> {
+        match *self {
+            Functions::Simple(ref f) => f.substitute(tvars, ctx).map(Functions::Simple),
+            Functions::Overloaded(ref fs) => {
+                let substituted: Vec<_> = fs.iter().map(|f| f.substitute(tvars, ctx)).collect();
+                if substituted.iter().all(|f| f.is_none()) {
+                    return None;
+                }
+                let fs = substituted.into_iter().zip(fs.iter()).map(|(newf, f)| {
+                    newf.unwrap_or_else(|| f.clone())
+                }).collect();
+                Some(Functions::Overloaded(fs))
+            },
+            Functions::All => None,
+        }
+    }
+
+    /// Returns a list of all function types that can be called, in the order of preference.
+    ///
+    /// This is empty for `Functions::All`.
+    pub fn candidates(&self) -> Vec<&Function> {
+        match *self {
+            Functions::Simple(ref f) => vec![f],
+            Functions::Overloaded(ref fs) => fs.iter().collect(),
+            Functions::All => Vec::new(),
+        }
+    }
+
     fn fmt_generic<WriteFunc>(&self, f: &mut fmt::Formatter,
                               mut write_func: WriteFunc) -> fmt::Result
             where WriteFunc: FnMut(&Function, &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Functions::All => write!(f, "function"),
             Functions::Simple(ref fty) => write_func(fty, f),
+            Functions::Overloaded(ref ftys) => {
+                let mut first = true;
+                for fty in ftys {
+                    if first { first = false; } else { write!(f, " & ")?; }
+                    write_func(fty, f)?;
+                }
+                Ok(())
+            },
         }
     }
 }
@@ -162,6 +297,27 @@ impl Lattice for Functions {
                 (&Functions::All, _) => Err(ctx.gen_report()),
 
                 (&Functions::Simple(ref a), &Functions::Simple(ref b)) => a.assert_sub(b, ctx),
+
+                // every function in the rhs should be implemented by some function in the lhs.
+                // constraints from non-matching functions should not affect the others.
+                (_, _) => {
+                    for b in other.candidates() {
+                        let matched = self.candidates().iter().any(|a| {
+                            ctx.save_constraints();
+                            if a.assert_sub(b, ctx).is_ok() {
+                                ctx.commit_constraints();
+                                true
+                            } else {
+                                ctx.rollback_constraints();
+                                false
+                            }
+                        });
+                        if !matched {
+                            return Err(ctx.gen_report());
+                        }
+                    }
+                    Ok(())
+                },
             }
         })().map_err(|r: TypeReport| r.not_sub(Origin::Functions, self, other, ctx))
     }
@@ -171,6 +327,13 @@ impl Lattice for Functions {
             match (self, other) {
                 (&Functions::All, &Functions::All) => Ok(()),
                 (&Functions::Simple(ref a), &Functions::Simple(ref b)) => a.assert_eq(b, ctx),
+                (&Functions::Overloaded(ref a), &Functions::Overloaded(ref b))
+                        if a.len() == b.len() => {
+                    for (a, b) in a.iter().zip(b.iter()) {
+                        a.assert_eq(b, ctx)?;
+                    }
+                    Ok(())
+                },
                 (_, _) => Err(ctx.gen_report()),
             }
         })().map_err(|r: TypeReport| r.not_eq(Origin::Functions, self, other, ctx))
@@ -182,6 +345,7 @@ impl PartialEq for Functions {
         match (self, other) {
             (&Functions::All, &Functions::All) => true,
             (&Functions::Simple(ref a), &Functions::Simple(ref b)) => *a == *b,
+            (&Functions::Overloaded(ref a), &Functions::Overloaded(ref b)) => *a == *b,
             (_, _) => false,
         }
     }
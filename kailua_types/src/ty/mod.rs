@@ -3,9 +3,10 @@
 use std::fmt;
 use std::result;
 use diag::{TypeReport, TypeResult};
-use kailua_env::Spanned;
-use kailua_diag::{Result, Locale, Report};
+use kailua_env::{Span, Spanned};
+use kailua_diag::{Result, Kind, Locale, Localize, Report, Reporter};
 use kailua_syntax::Name;
+use message as m;
 
 pub use self::display::{Display, Displayed, DisplayState, DisplayName};
 pub use self::literals::{Numbers, Strings};
@@ -154,8 +155,18 @@ pub trait TypeResolver: Report {
     /// Resolves a type name to a type if any. The span is used for error reporting.
     fn ty_from_name(&self, name: &Spanned<Name>) -> Result<Ty>;
 
+    /// Resolves a generic type name applied to given type arguments to a type if any.
+    /// The span is used for error reporting.
+    fn ty_from_generic_name(&mut self, name: &Spanned<Name>, args: &[Ty]) -> Result<Ty>;
+
     /// Resolves a class system name to an identifier if any. The span is used for error reporting.
     fn class_system_from_name(&self, name: &Spanned<Name>) -> Result<Option<ClassSystemId>>;
+
+    /// Called for each `WHATEVER` type in the kind, so that the resolver can reject it.
+    /// The span is used for error reporting. Accepts everything by default.
+    fn check_dynamic(&mut self, _span: Span) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl<'a, R: TypeResolver + ?Sized> TypeResolver for &'a mut R {
@@ -168,9 +179,80 @@ impl<'a, R: TypeResolver + ?Sized> TypeResolver for &'a mut R {
     fn ty_from_name(&self, name: &Spanned<Name>) -> Result<Ty> {
         (**self).ty_from_name(name)
     }
+    fn ty_from_generic_name(&mut self, name: &Spanned<Name>, args: &[Ty]) -> Result<Ty> {
+        (**self).ty_from_generic_name(name, args)
+    }
     fn class_system_from_name(&self, name: &Spanned<Name>) -> Result<Option<ClassSystemId>> {
         (**self).class_system_from_name(name)
     }
+    fn check_dynamic(&mut self, span: Span) -> Result<()> {
+        (**self).check_dynamic(span)
+    }
+}
+
+/// A type resolver that additionally resolves type parameters to given type variables.
+///
+/// This is used to convert the kind inside generic functions and type aliases,
+/// where the type parameters shadow any other type names.
+pub struct TypeParamResolver<'a> {
+    resolv: &'a mut TypeResolver,
+    tparams: &'a [(Spanned<Name>, TVar)],
+}
+
+impl<'a> TypeParamResolver<'a> {
+    pub fn new(resolv: &'a mut TypeResolver,
+               tparams: &'a [(Spanned<Name>, TVar)]) -> TypeParamResolver<'a> {
+        TypeParamResolver { resolv: resolv, tparams: tparams }
+    }
+
+    fn tparam(&self, name: &Name) -> Option<&'a (Spanned<Name>, TVar)> {
+        self.tparams.iter().find(|&&(ref n, _)| n.base == *name)
+    }
+}
+
+impl<'a> Report for TypeParamResolver<'a> {
+    fn message_locale(&self) -> Locale {
+        self.resolv.message_locale()
+    }
+
+    fn add_span(&self, kind: Kind, span: Span, msg: &Localize) -> Result<()> {
+        self.resolv.add_span(kind, span, msg)
+    }
+}
+
+impl<'a> TypeResolver for TypeParamResolver<'a> {
+    fn context(&self) -> &TypeContext {
+        self.resolv.context()
+    }
+
+    fn context_mut(&mut self) -> &mut TypeContext {
+        self.resolv.context_mut()
+    }
+
+    fn ty_from_name(&self, name: &Spanned<Name>) -> Result<Ty> {
+        if let Some(&(ref pname, tvar)) = self.tparam(&name.base) {
+            Ok(Ty::new(T::TVar(tvar)).and_display(DisplayName::Type(pname.clone())))
+        } else {
+            self.resolv.ty_from_name(name)
+        }
+    }
+
+    fn ty_from_generic_name(&mut self, name: &Spanned<Name>, args: &[Ty]) -> Result<Ty> {
+        if let Some(&(ref pname, _)) = self.tparam(&name.base) {
+            self.error(name, m::TypeParamWithArgs { name: &pname.base }).done()?;
+            Ok(Ty::dummy())
+        } else {
+            self.resolv.ty_from_generic_name(name, args)
+        }
+    }
+
+    fn class_system_from_name(&self, name: &Spanned<Name>) -> Result<Option<ClassSystemId>> {
+        self.resolv.class_system_from_name(name)
+    }
+
+    fn check_dynamic(&mut self, span: Span) -> Result<()> {
+        self.resolv.check_dynamic(span)
+    }
 }
 
 /// A trait that provides every type-related operations.
@@ -187,6 +269,12 @@ pub trait TypeContext {
     /// Generates a new fresh type variable.
     fn gen_tvar(&mut self) -> TVar;
 
+    /// Generates a new fresh rigid type variable.
+    ///
+    /// A rigid type variable stands for an unknown but fixed type (e.g. a type parameter),
+    /// so it cannot be bounded by other types but it can bound other non-rigid type variables.
+    fn gen_rigid_tvar(&mut self) -> TVar;
+
     /// Copies a type variable so that a new variable has the same constraints to the original
     /// but is no longer connected to the original.
     ///
@@ -214,6 +302,11 @@ pub trait TypeContext {
     /// Resolves a given type variable if there is a tight bound.
     fn get_tvar_exact_type(&self, tvar: TVar) -> Option<Ty>;
 
+    /// Returns a lower bound of given type variable if any.
+    ///
+    /// Mainly used to determine the instantiated type parameters from the function arguments.
+    fn get_tvar_lower_bound(&self, tvar: TVar) -> Option<Ty>;
+
     /// Generates a new fresh row variable.
     fn gen_rvar(&mut self) -> RVar;
 
@@ -252,6 +345,28 @@ pub trait TypeContext {
         &self, rvar: RVar, f: &mut FnMut(&Key, &Slot) -> result::Result<(), ()>
     ) -> result::Result<RVar, ()>;
 
+    /// Returns the metatable associated to the row variable, if any.
+    fn get_rvar_metatable(&self, rvar: RVar) -> Option<Slot>;
+
+    /// Associates the metatable to the row variable, or removes it if `meta` is `None`.
+    ///
+    /// This is used to track `setmetatable` calls to records,
+    /// so that subsequent indexing and operators can use metamethods.
+    fn set_rvar_metatable(&mut self, rvar: RVar, meta: Option<Slot>);
+
+    /// Saves the current constraints to type and row variables.
+    ///
+    /// Each call should be paired with either `commit_constraints` or `rollback_constraints`.
+    /// This is used to try a constraint that may fail, e.g. each overload of a function.
+    fn save_constraints(&mut self);
+
+    /// Discards the last saved constraints and keeps the current constraints.
+    fn commit_constraints(&mut self);
+
+    /// Restores the last saved constraints,
+    /// forgetting every constraint made after the corresponding `save_constraints`.
+    fn rollback_constraints(&mut self);
+
     /// Collects and returns a list of all known fields in the row variable.
     fn get_rvar_fields(&self, rvar: RVar) -> Vec<(Key, Slot)> {
         let mut fields = Vec::new();
@@ -395,6 +510,9 @@ impl TypeContext for NoTypeContext {
     fn gen_tvar(&mut self) -> TVar {
         panic!("gen_tvar is not supposed to be called here");
     }
+    fn gen_rigid_tvar(&mut self) -> TVar {
+        panic!("gen_rigid_tvar is not supposed to be called here");
+    }
     fn copy_tvar(&mut self, tvar: TVar) -> TVar {
         panic!("copy_tvar({:?}) is not supposed to be called here", tvar);
     }
@@ -419,6 +537,9 @@ impl TypeContext for NoTypeContext {
     fn get_tvar_exact_type(&self, tvar: TVar) -> Option<Ty> {
         panic!("get_tvar_exact_type({:?}) is not supposed to be called here", tvar);
     }
+    fn get_tvar_lower_bound(&self, tvar: TVar) -> Option<Ty> {
+        panic!("get_tvar_lower_bound({:?}) is not supposed to be called here", tvar);
+    }
 
     fn gen_rvar(&mut self) -> RVar {
         panic!("gen_rvar is not supposed to be called here");
@@ -443,6 +564,17 @@ impl TypeContext for NoTypeContext {
     ) -> result::Result<RVar, ()> {
         panic!("list_rvar_fields({:?}, ...) is not supposed to be called here", rvar)
     }
+    fn get_rvar_metatable(&self, rvar: RVar) -> Option<Slot> {
+        panic!("get_rvar_metatable({:?}) is not supposed to be called here", rvar)
+    }
+    fn set_rvar_metatable(&mut self, rvar: RVar, meta: Option<Slot>) {
+        panic!("set_rvar_metatable({:?}, {:?}) is not supposed to be called here", rvar, meta)
+    }
+
+    // there are no constraints to save
+    fn save_constraints(&mut self) {}
+    fn commit_constraints(&mut self) {}
+    fn rollback_constraints(&mut self) {}
 
     fn fmt_class_name(&self, cid: ClassId, _f: &mut fmt::Formatter,
                       _st: &DisplayState) -> fmt::Result {
@@ -8,7 +8,7 @@ use kailua_env::{Span, Spanned, WithLoc};
 use kailua_diag;
 use kailua_syntax::ast::{Seq, Kind};
 use diag::{Origin, TypeReport, TypeResult};
-use super::{T, Ty, Slot, Lattice, Union, Dummy};
+use super::{T, Ty, Slot, Lattice, Union, Dummy, TVar};
 use super::{Display, DisplayState, TypeContext, TypeResolver};
 
 /// Yields each element type.
@@ -681,6 +681,23 @@ impl TySeq {
     pub fn all_without_loc(self) -> SpannedTySeq {
         self.all_with_loc(Span::dummy())
     }
+
+    /// Replaces given type variables to corresponding types, recursively.
+    /// Returns `None` if nothing has been replaced.
+    pub fn substitute(&self, tvars: &[(TVar, Ty)], ctx: &mut TypeContext) -> Option<TySeq> {
+        let mut replaced = false;
+        let mut subst = |t: &Ty, ctx: &mut TypeContext| {
+            if let Some(t) = t.substitute(tvars, ctx) {
+                replaced = true;
+                t
+            } else {
+                t.clone()
+            }
+        };
+        let head = self.head.iter().map(|t| subst(t, ctx)).collect();
+        let tail = self.tail.as_ref().map(|t| subst(t, ctx));
+        if replaced { Some(TySeq { head: head, tail: tail }) } else { None }
+    }
 }
 
 impl SlotSeq {
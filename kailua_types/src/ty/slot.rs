@@ -541,6 +541,11 @@ impl Slot {
         Ok(())
     }
 
+    /// Returns true if both slots share the same storage, i.e. they are the same slot.
+    pub fn is_same_as(&self, other: &Slot) -> bool {
+        self.0.deref() as *const _ == other.0.deref() as *const _
+    }
+
     // following methods are direct analogues to value type's ones, whenever applicable
 
     pub fn flex(&self) -> F { self.0.flex() }
@@ -576,6 +581,13 @@ impl Slot {
         Slot::from((*self.0).clone().generalize(ctx))
     }
 
+    /// Replaces given type variables to corresponding types, recursively.
+    /// Returns `None` if nothing has been replaced.
+    pub fn substitute(&self, tvars: &[(TVar, Ty)], ctx: &mut TypeContext) -> Option<Slot> {
+        let ty = self.unlift().substitute(tvars, ctx)?;
+        Some(Slot::new(self.flex(), ty))
+    }
+
     // should *not* create a new slot! (the resulting slot is not a different type,
     // but a same type with a display hint; the hint *should* be global.)
     pub fn set_display(self, disp: DisplayName) -> Slot {
@@ -5,7 +5,7 @@ use std::collections::BTreeMap;
 
 use kailua_syntax::Str;
 use diag::{Origin, TypeReport, TypeResult};
-use super::{Display, DisplayState, T, Ty, Slot, TypeContext, Union, Lattice, RVar};
+use super::{Display, DisplayState, T, Ty, Slot, TypeContext, Union, Lattice, TVar, RVar};
 
 /// A key allowed in the row variable.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -116,6 +116,54 @@ impl Tables {
         }
     }
 
+    /// Replaces given type variables to corresponding types, recursively.
+    ///
+    /// Returns `None` if nothing has been replaced.
+    /// Records are copied to a fresh row variable only when some field has been replaced.
+    pub fn substitute(&self, tvars: &[(TVar, Ty)], ctx: &mut TypeContext) -> Option<Tables> {
+        match *self {
+            Tables::Fields(ref r) => {
+                let mut replaced = false;
+                let fields: Vec<_> = ctx.get_rvar_fields(r.clone()).into_iter().map(|(k, v)| {
+                    if let Some(v) = v.substitute(tvars, ctx) {
+                        replaced = true;
+                        (k, v)
+                    } else {
+                        (k, v)
+                    }
+                }).collect();
+                if !replaced {
+                    return None;
+                }
+
+                let last = ctx.list_rvar_fields(r.clone(), &mut |_, _| Ok(()));
+                let rvar = ctx.gen_rvar();
+                ctx.assert_rvar_includes(rvar.clone(), &fields).expect(
+                    "cannot insert disjoint fields into a fresh row variable"
+                );
+                if last == Ok(RVar::empty()) {
+                    ctx.assert_rvar_closed(rvar.clone()).expect(
+                        "cannot make a fresh row variable not extensible"
+                    );
+                }
+                Some(Tables::Fields(rvar))
+            },
+            Tables::Array(ref v) => v.substitute(tvars, ctx).map(Tables::Array),
+            Tables::ArrayN(ref v) => v.substitute(tvars, ctx).map(Tables::ArrayN),
+            Tables::Map(ref k, ref v) => {
+                let newk = k.substitute(tvars, ctx);
+                let newv = v.substitute(tvars, ctx);
+                if newk.is_none() && newv.is_none() {
+                    None
+                } else {
+                    Some(Tables::Map(newk.unwrap_or_else(|| k.clone()),
+                                     newv.unwrap_or_else(|| v.clone())))
+                }
+            },
+            Tables::All => None,
+        }
+    }
+
     fn fmt_generic<WriteTy, WriteSlot>(&self, f: &mut fmt::Formatter,
                                        st: Option<&DisplayState>,
                                        mut write_ty: WriteTy,
@@ -67,6 +67,14 @@ pub enum Tag {
     /// *that* parameter so that `ipairs` (which T should be integer) works for any table types.
     GenericPairs,
 
+    /// `function(table, table?) -> table`
+    ///
+    /// Returns the first argument with the second argument as its metatable.
+    /// When the first argument is a record, the metatable is associated to that record
+    /// so that any missing field is looked up through the `__index` metamethod,
+    /// and operators and calls to the record go through corresponding metamethods.
+    SetMetatable,
+
     /// `table`
     ///
     /// A table mirroring the global environment.
@@ -106,6 +114,13 @@ pub enum Tag {
     /// there is no other valid way to get a table with such a type.
     StringMeta,
 
+    /// `userdata`
+    ///
+    /// A LuaJIT 64-bit integer cdata, made by number literals like `42LL` or `0x10ULL`.
+    /// It can be used in arithmetic and comparison with numbers and other such integers,
+    /// but otherwise it is as opaque as other cdata.
+    Int64,
+
     /// `function(<class prototype type>?) -> <class prototype type>`
     ///
     /// A function that makes a class prototype. If the argument is given, it should be
@@ -169,12 +184,14 @@ impl Tag {
             b"assert_not"    => no_values(resolv, Tag::AssertNot),
             b"assert_type"   => no_values(resolv, Tag::AssertType),
             b"generic_pairs" => no_values(resolv, Tag::GenericPairs),
+            b"setmetatable"  => no_values(resolv, Tag::SetMetatable),
             b"genv"          => no_values(resolv, Tag::GlobalEnv),
             b"geval"         => no_values(resolv, Tag::GlobalEval),
             b"become_module" => no_values(resolv, Tag::BecomeModule),
             b"package_path"  => no_values(resolv, Tag::PackagePath),
             b"package_cpath" => no_values(resolv, Tag::PackageCpath),
             b"string_meta"   => no_values(resolv, Tag::StringMeta),
+            b"int64"         => no_values(resolv, Tag::Int64),
 
             b"make_class" => {
                 let values = values(resolv, 1)?;
@@ -204,12 +221,14 @@ impl Tag {
             Tag::AssertNot    => "assert_not",
             Tag::AssertType   => "assert_type",
             Tag::GenericPairs => "generic_pairs",
+            Tag::SetMetatable => "setmetatable",
             Tag::GlobalEnv    => "genv",
             Tag::GlobalEval   => "geval",
             Tag::BecomeModule => "become_module",
             Tag::PackagePath  => "package_path",
             Tag::PackageCpath => "package_cpath",
             Tag::StringMeta   => "string_meta",
+            Tag::Int64        => "int64",
             Tag::MakeClass(_) => "make_class",
 
             Tag::_Subtype         => "internal subtype",
@@ -232,6 +251,7 @@ impl Tag {
             Tag::AssertNot |
             Tag::AssertType |
             Tag::GenericPairs |
+            Tag::SetMetatable |
             Tag::MakeClass(_) |
             Tag::KailuaGenTvar |
             Tag::KailuaAssertTvar => true,
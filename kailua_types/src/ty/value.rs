@@ -365,6 +365,35 @@ impl<'a> T<'a> {
         }
     }
 
+    /// Replaces given type variables inside tables and functions to corresponding types.
+    ///
+    /// Returns `None` if nothing has been replaced. A bare type variable is handled by
+    /// `Ty::substitute` instead, as the replacement may change the nilability.
+    /// Primarily used for instantiating type parameters of generic functions and type aliases.
+    pub fn substitute(&self, tvars: &[(TVar, Ty)], ctx: &mut TypeContext) -> Option<T<'static>> {
+        match *self {
+            T::Tables(ref tab) => {
+                tab.substitute(tvars, ctx).map(|tab| T::Tables(Cow::Owned(tab)))
+            },
+            T::Functions(ref func) => {
+                func.substitute(tvars, ctx).map(|func| T::Functions(Cow::Owned(func)))
+            },
+            T::Union(ref u) => {
+                let tables = u.tables.as_ref().and_then(|tab| tab.substitute(tvars, ctx));
+                let functions = u.functions.as_ref().and_then(|func| func.substitute(tvars, ctx));
+                if tables.is_none() && functions.is_none() {
+                    return None;
+                }
+                let mut u = u.clone().into_owned();
+                if tables.is_some() { u.tables = tables; }
+                if functions.is_some() { u.functions = functions; }
+                u.display_hints.clear();
+                Some(T::Union(Cow::Owned(u)))
+            },
+            _ => None,
+        }
+    }
+
     pub fn into_send(self) -> T<'static> {
         match self {
             T::Dynamic(dyn) => T::Dynamic(dyn),
@@ -1193,7 +1222,10 @@ impl Ty {
 
         let ty = match *kind.base {
             K::Oops              => Ty::new(T::Dynamic(Dyn::Oops)), // typically from a parser error
-            K::Dynamic           => Ty::new(T::Dynamic(Dyn::User)),
+            K::Dynamic           => {
+                resolv.check_dynamic(kind.span)?;
+                Ty::new(T::Dynamic(Dyn::User))
+            }
             K::Any               => Ty::new(T::All),
             K::Nil               => Ty::new(T::None),
             K::Boolean           => Ty::new(T::Boolean),
@@ -1205,6 +1237,13 @@ impl Ty {
             K::Thread            => Ty::new(T::Thread),
             K::UserData          => Ty::new(T::UserData),
             K::Named(ref name)   => resolv.ty_from_name(name)?,
+            K::Generic(ref name, ref args) => {
+                let mut argtys = Vec::new();
+                for arg in args {
+                    argtys.push(Ty::from_kind(arg, resolv)?);
+                }
+                resolv.ty_from_generic_name(name, &argtys)?
+            },
             K::WithNil(ref k)    => Ty::from_kind(k, resolv)?.or_nil(Nil::Noisy),
             K::WithoutNil(ref k) => Ty::from_kind(k, resolv)?.or_nil(Nil::Absent),
             // XXX think about the possibility of nil? and nil! more
@@ -1299,6 +1338,14 @@ impl Ty {
                 Ty::new(T::Functions(Cow::Owned(Functions::Simple(func))))
             }
 
+            K::Overloaded(ref funcs) => {
+                let mut overloads = Vec::with_capacity(funcs.len());
+                for func in funcs {
+                    overloads.push(Function::from_kind(func, resolv)?);
+                }
+                Ty::new(T::Functions(Cow::Owned(Functions::Overloaded(overloads))))
+            }
+
             K::Union(ref kinds) => {
                 assert!(!kinds.is_empty());
                 // put (inaccurate) spans to report correctly
@@ -1436,6 +1483,25 @@ impl Ty {
         self
     }
 
+    /// Replaces given type variables to corresponding types, recursively.
+    ///
+    /// Returns `None` if nothing has been replaced. The display hint is removed otherwise,
+    /// as it would be no longer accurate.
+    pub fn substitute(&self, tvars: &[(TVar, Ty)], ctx: &mut TypeContext) -> Option<Ty> {
+        if let T::TVar(tv) = *self.inner.ty() {
+            tvars.iter().find(|&&(v, _)| v == tv).map(|&(_, ref ty)| {
+                let tag = self.tag().or(ty.tag());
+                ty.clone().union_nil(self.nil()).with_tag(tag)
+            })
+        } else {
+            self.inner.ty().substitute(tvars, ctx).map(|t| {
+                let mut ty = self.clone();
+                ty.inner.remap_ty_and_hint(|_| Cow::Owned(t));
+                ty
+            })
+        }
+    }
+
     pub fn flags(&self) -> Flags {
         let mut flags = self.inner.ty().flags();
         if self.inner.nil() == Nil::Noisy {
@@ -1447,8 +1513,8 @@ impl Ty {
     pub fn filter_by_flags(mut self, flags: Flags, ctx: &mut TypeContext) -> TypeResult<Ty> {
         self.inner.remap_ty_and_hint_res(|t| t.filter_by_flags(flags, ctx))?;
         if !flags.contains(T_NOISY_NIL) {
-            let nil = self.inner.nil().without_nil();
-            self.inner.set_nil(nil);
+            // the filtered type is known to be not nil (e.g. `T?` becomes `T!`)
+            self.inner.set_nil(Nil::Absent);
         }
         Ok(self)
     }
@@ -1465,7 +1531,9 @@ impl Ty {
 
         // we should ignore display hints at the top level if requested (by `{:0}`).
         // the request is required because we may want to inspect the actual type for named types.
-        if st.is_top_level() && f.sign_aware_zero_pad() {
+        // hints can be also ignored altogether (by `{:-}`), e.g. when the type names are not
+        // available to the reader.
+        if (st.is_top_level() && f.sign_aware_zero_pad()) || st.ignores_hints() {
             return (nil, tag, name);
         }
 
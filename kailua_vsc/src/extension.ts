@@ -8,8 +8,8 @@ import { spawn, ChildProcess } from 'child_process';
 
 import * as vscode from 'vscode';
 import {
-    LanguageClient, LanguageClientOptions, SettingMonitor, StreamInfo
-} from 'vscode-languageclient';
+    LanguageClient, LanguageClientOptions, StreamInfo
+} from 'vscode-languageclient/node';
 
 declare var v8debug: any;
 
@@ -128,7 +128,8 @@ function initializeLanguageServer(context: vscode.ExtensionContext) {
                 resolve({ reader: socket, writer: socket });
             });
             server.listen(0 /*random port*/, '127.0.0.1', () => {
-                const cp = spawnWithArgs('langsvr', `--tcp=127.0.0.1:${server.address().port}`);
+                const port = (server.address() as net.AddressInfo).port;
+                const cp = spawnWithArgs('langsvr', `--tcp=127.0.0.1:${port}`);
                 // if we are using TCP the stdout is no longer used and any output should be logged as well
                 cp.stdout.on('data', data => languageClient.outputChannel.append(_.isString(data) ? data : data.toString('utf-8')));
             });
@@ -152,7 +153,9 @@ function initializeLanguageServer(context: vscode.ExtensionContext) {
 	};
 	
 	languageClient = new LanguageClient('kailuaLanguageServer', serverName, serverOptions, clientOptions);
-    return languageClient.start();
+    // semantic tokens (with the legend) and inlay hints are registered from the server capabilities
+    languageClient.start();
+    return languageClient;
 }
 
 export function activate(context: vscode.ExtensionContext) {
@@ -4,19 +4,29 @@
 // This file is providing the test runner to use when running extension tests.
 // By default the test runner in use is Mocha based.
 //
-// You can provide your own test runner if you want to override it by exporting
-// a function run(testRoot: string, clb: (error:Error) => void) that the extension
-// host can call to run the tests. The test runner is expected to use console.log
-// to report the results back to the caller. When the tests are finished, return
-// a possible error to the callback or null if none.
+// The extension host calls the exported function `run` after loading this file.
+// The tests are run inside the extension host and the returned promise should be
+// rejected when any test has failed.
 
-var testRunner = require('vscode/lib/testrunner');
+import * as path from 'path';
+import Mocha = require('mocha');
 
-// You can directly control Mocha options by uncommenting the following lines
-// See https://github.com/mochajs/mocha/wiki/Using-mocha-programmatically#set-options for more info
-testRunner.configure({
-    ui: 'tdd', 		// the TDD UI is being used in extension.test.ts (suite, test, etc.)
-    useColors: true // colored output from test results
-});
+export function run(): Promise<void> {
+    // You can directly control Mocha options by changing the following lines
+    // See https://mochajs.org/api/mocha for more info
+    const mocha = new Mocha({
+        ui: 'tdd',   // the TDD UI is being used in extension.test.ts (suite, test, etc.)
+        color: true  // colored output from test results
+    });
+    mocha.addFile(path.resolve(__dirname, 'extension.test'));
 
-module.exports = testRunner;
\ No newline at end of file
+    return new Promise<void>((resolve, reject) => {
+        mocha.run(failures => {
+            if (failures > 0) {
+                reject(new Error(`${failures} tests failed.`));
+            } else {
+                resolve();
+            }
+        });
+    });
+}
@@ -0,0 +1,13 @@
+// downloads VS Code and runs the extension tests (`test/index.ts`) inside it
+
+import * as path from 'path';
+import { runTests } from '@vscode/test-electron';
+
+// the compiled script is at `out/test/runTest.js`
+const extensionDevelopmentPath = path.resolve(__dirname, '..', '..');
+const extensionTestsPath = path.resolve(__dirname, 'index');
+
+runTests({ extensionDevelopmentPath, extensionTestsPath }).catch(e => {
+    console.error(`failed to run tests: ${e}`);
+    process.exit(1);
+});
@@ -0,0 +1,636 @@
+//! An on-disk cache of checked modules.
+//!
+//! When a required module has been checked without any report and without any effect to
+//! the environment, its result can be written as an exact `.kailua` stub
+//! (see `kailua_check::stub`) and used in place of the module later.
+//! Each entry is keyed by the hash of the module path, its contents and the environment
+//! (globals and configurations) that the module saw, and records every file it has read,
+//! so that the entry is only used when none of them have been changed.
+//!
+//! Entries are kept in a `CacheStore`, which is either a directory (`DirCacheStore`)
+//! or a memory shared across multiple checks (`MemoryCacheStore`).
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use parking_lot::RwLock;
+use kailua_env::{Spanned, WithLoc};
+use kailua_diag::{Report, Stop};
+use kailua_syntax::Chunk;
+use kailua_types::ty::TypeContext;
+use kailua_check::env::Module;
+use kailua_check::options::FsSource;
+use kailua_check::stub::module_to_exact_stub;
+
+/// The name of the cache directory, relative to the workspace root.
+pub const CACHE_DIR: &'static str = ".kailua-cache";
+
+const DEP_PREFIX: &'static str = "-- dep ";
+
+/// A storage for cache entries, each of them being a `.kailua` file.
+pub trait CacheStore {
+    /// Should return the contents of the entry with given key, if any.
+    fn read(&self, key: u64) -> Option<String>;
+
+    /// Should store the entry with given key. Failures can be silently ignored.
+    fn write(&self, key: u64, data: String);
+
+    /// Should remove the entry with given key if any.
+    fn remove(&self, key: u64);
+
+    /// Should return a path to the entry with given key.
+    ///
+    /// The entry will be loaded with `FsSource::chunk_from_path` with this path,
+    /// so the source should be able to read it (even when the path doesn't really exist).
+    fn path(&self, key: u64) -> PathBuf;
+}
+
+/// A `CacheStore` which stores each entry to a file in given directory.
+pub struct DirCacheStore {
+    dir: PathBuf,
+}
+
+impl DirCacheStore {
+    pub fn new(dir: PathBuf) -> DirCacheStore {
+        DirCacheStore { dir: dir }
+    }
+}
+
+impl CacheStore for DirCacheStore {
+    fn read(&self, key: u64) -> Option<String> {
+        let mut data = String::new();
+        File::open(self.path(key)).and_then(|mut f| f.read_to_string(&mut data)).ok()?;
+        Some(data)
+    }
+
+    fn write(&self, key: u64, data: String) {
+        // write to a temporary file first, so that the entry is never partially written
+        let path = self.path(key);
+        let temppath = path.with_extension("tmp");
+        let _ = fs::create_dir_all(&self.dir).and_then(|_| {
+            File::create(&temppath)?.write_all(data.as_bytes())?;
+            fs::rename(&temppath, &path)
+        });
+    }
+
+    fn remove(&self, key: u64) {
+        let _ = fs::remove_file(self.path(key));
+    }
+
+    fn path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.kailua", key))
+    }
+}
+
+/// A `CacheStore` which stores entries in the memory.
+///
+/// The store can be cloned and shared across threads; all clones refer to the same entries.
+/// Entries are given virtual paths in given directory, which can be read by `MemoryCacheStore::get`.
+///
+/// The store can be optionally backed by another store (e.g. `DirCacheStore`),
+/// so that entries persist across sessions while being still reused from the memory.
+#[derive(Clone)]
+pub struct MemoryCacheStore {
+    dir: PathBuf,
+    entries: Arc<RwLock<HashMap<PathBuf, String>>>,
+    backing: Option<Arc<CacheStore + Send + Sync>>,
+}
+
+impl MemoryCacheStore {
+    pub fn new(dir: PathBuf) -> MemoryCacheStore {
+        MemoryCacheStore {
+            dir: dir,
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            backing: None,
+        }
+    }
+
+    /// Returns a store sharing entries with this store, which also writes entries to
+    /// given store and reads entries missing from the memory from it.
+    pub fn with_backing_store<S>(&self, store: S) -> MemoryCacheStore
+        where S: CacheStore + Send + Sync + 'static
+    {
+        MemoryCacheStore {
+            dir: self.dir.clone(),
+            entries: self.entries.clone(),
+            backing: Some(Arc::new(store)),
+        }
+    }
+
+    /// Returns the contents of the entry with given virtual path, if any.
+    pub fn get(&self, path: &Path) -> Option<String> {
+        self.entries.read().get(path).cloned()
+    }
+
+    /// Removes every entry which has read given path while checking.
+    ///
+    /// Entries are never looked up again once their files have been changed,
+    /// so they should be evicted when the change is known.
+    pub fn evict(&self, path: &Path) {
+        self.entries.write().retain(|_, data| !depends_on(data, path));
+    }
+}
+
+impl CacheStore for MemoryCacheStore {
+    fn read(&self, key: u64) -> Option<String> {
+        let path = self.path(key);
+        if let Some(data) = self.get(&path) {
+            return Some(data);
+        }
+
+        // the entry is kept in the memory, so that it can be read from the virtual path
+        let data = self.backing.as_ref()?.read(key)?;
+        self.entries.write().insert(path, data.clone());
+        Some(data)
+    }
+
+    fn write(&self, key: u64, data: String) {
+        if let Some(ref backing) = self.backing {
+            backing.write(key, data.clone());
+        }
+        self.entries.write().insert(self.path(key), data);
+    }
+
+    fn remove(&self, key: u64) {
+        if let Some(ref backing) = self.backing {
+            backing.remove(key);
+        }
+        self.entries.write().remove(&self.path(key));
+    }
+
+    fn path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.kailua", key))
+    }
+}
+
+// 64-bit FNV-1a, which is stable across runs unlike `DefaultHasher`
+struct Hasher(u64);
+
+impl Hasher {
+    fn new() -> Hasher {
+        let mut hasher = Hasher(0xcbf29ce484222325);
+        // any change to the checker may invalidate the previous results
+        hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x100000001b3);
+        }
+        // separate consecutive writes
+        self.0 = (self.0 ^ 0xff).wrapping_mul(0x100000001b3);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = Hasher::new();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+// a file read while checking a module, with the hash of its contents
+type Dep = (PathBuf, u64);
+
+// returns the dependencies recorded in the entry, or `None` if the entry is malformed
+fn read_deps(data: &str) -> Option<Vec<Dep>> {
+    let mut deps = Vec::new();
+    for line in data.lines().take_while(|line| line.starts_with(DEP_PREFIX)) {
+        let line = &line[DEP_PREFIX.len()..];
+        let (hash, path) = (line.get(..16)?, line.get(17..)?);
+        deps.push((PathBuf::from(path), u64::from_str_radix(hash, 16).ok()?));
+    }
+    Some(deps)
+}
+
+// returns true if the entry records given path as a dependency
+fn depends_on(data: &str, path: &Path) -> bool {
+    read_deps(data).map_or(false, |deps| deps.iter().any(|dep| dep.0 == path))
+}
+
+// a module being checked
+struct Frame {
+    // the hash of the environment as given by `Options::module_environment`
+    environment: u64,
+
+    // the cache key, or `None` if the module should not be written
+    key: Option<u64>,
+
+    // the key to the cache entry that the chunk has been loaded from, if any
+    entry: Option<u64>,
+
+    // files read while checking the module, including required modules
+    deps: Vec<Dep>,
+
+    // false if neither this module nor its requirers can be cached
+    cacheable: bool,
+}
+
+/// The shared state of the cache, driven by `WorkspaceOptions`.
+pub struct Cache {
+    store: Box<CacheStore>,
+    environment: u64,
+    frames: Vec<Frame>,
+    done: HashMap<Vec<u8>, Vec<Dep>>,
+    strict: bool,
+}
+
+impl Cache {
+    pub fn new(store: Box<CacheStore>) -> Cache {
+        Cache {
+            store: store,
+            environment: 0,
+            frames: Vec::new(),
+            done: HashMap::new(),
+            strict: false,
+        }
+    }
+
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn set_environment(&mut self, environment: &str) {
+        self.environment = hash(environment.as_bytes());
+    }
+
+    pub fn begin(&mut self) {
+        self.frames.push(Frame {
+            environment: self.environment,
+            key: None,
+            entry: None,
+            deps: Vec::new(),
+            cacheable: true,
+        });
+    }
+
+    // called when the module cannot be found
+    pub fn abort(&mut self) {
+        self.frames.pop();
+        self.invalidate();
+    }
+
+    // called when the checking has any effect not visible to the checker itself
+    pub fn invalidate(&mut self) {
+        for frame in &mut self.frames {
+            frame.cacheable = false;
+        }
+    }
+
+    pub fn end(&mut self, path: &[u8], module: Option<&Module>,
+               types: &TypeContext, reusable: bool) {
+        let frame = match self.frames.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+
+        if let Some(entry) = frame.entry {
+            // the entry is somehow unusable, do not try it again
+            if !reusable {
+                self.store.remove(entry);
+            }
+        } else if let (true, true, Some(key), Some(module)) =
+                (reusable, frame.cacheable, frame.key, module) {
+            if let Some(stub) = module_to_exact_stub(module, types) {
+                // the strict mode would reject `WHATEVER` in the stub when it gets reused
+                if !(self.strict && stub.contains("WHATEVER")) {
+                    self.write_entry(key, &frame.deps, stub);
+                }
+            }
+        }
+
+        if let Some(parent) = self.frames.last_mut() {
+            parent.deps.extend(frame.deps.iter().cloned());
+            parent.cacheable &= frame.cacheable;
+        }
+        self.done.insert(path.to_owned(), frame.deps);
+    }
+
+    pub fn reuse(&mut self, path: &[u8]) {
+        if let Some(parent) = self.frames.last_mut() {
+            match self.done.get(path) {
+                Some(deps) => parent.deps.extend(deps.iter().cloned()),
+                None => parent.cacheable = false,
+            }
+        }
+    }
+
+    fn write_entry(&self, key: u64, deps: &[Dep], stub: String) {
+        let mut data = String::new();
+        for &(ref path, hash) in deps {
+            match path.to_str() {
+                Some(path) => data.push_str(&format!("{}{:016x} {}\n", DEP_PREFIX, hash, path)),
+                None => return,
+            }
+        }
+        data.push_str(&stub);
+        self.store.write(key, data);
+    }
+
+    // returns the recorded dependencies if the entry exists and is up to date.
+    // outdated entries are removed, as the same dependencies are unlikely to appear again.
+    fn read_entry<S: FsSource>(&self, key: u64, source: &S) -> Option<Vec<Dep>> {
+        let data = self.store.read(key)?;
+        let deps = read_deps(&data).and_then(|deps| {
+            let uptodate = deps.iter().all(|&(ref path, hash)| {
+                source.data_from_path(path).map(|data| self::hash(&data)) == Some(hash)
+            });
+            if uptodate { Some(deps) } else { None }
+        });
+        if deps.is_none() {
+            self.store.remove(key);
+        }
+        deps
+    }
+
+    fn chunk_from_path<S: FsSource>(&mut self, resolved_path: Spanned<&Path>, source: &S,
+                                    report: &Report) -> Result<Option<Chunk>, Option<Stop>> {
+        let data = match source.data_from_path(resolved_path.base) {
+            Some(data) => data,
+            None => {
+                // the contents are not fixed, so the module is never cached
+                let chunk = source.chunk_from_path(resolved_path, report)?;
+                if chunk.is_some() {
+                    if let Some(frame) = self.frames.last_mut() {
+                        frame.cacheable = false;
+                    }
+                }
+                return Ok(chunk);
+            }
+        };
+
+        let dephash = hash(&data);
+        let key = {
+            let frame = match self.frames.last_mut() {
+                Some(frame) => frame,
+                None => return source.chunk_from_path(resolved_path, report),
+            };
+            frame.deps.push((resolved_path.base.to_owned(), dephash));
+
+            // declaration files are cheap to check, so they are not cached
+            if resolved_path.base.extension().map_or(false, |ext| ext == "kailua") {
+                return source.chunk_from_path(resolved_path, report);
+            }
+
+            let mut hasher = Hasher::new();
+            hasher.write(&u64_to_bytes(frame.environment));
+            hasher.write(resolved_path.base.to_string_lossy().as_bytes());
+            hasher.write(&u64_to_bytes(dephash));
+            hasher.finish()
+        };
+
+        if let Some(deps) = self.read_entry(key, source) {
+            let entrypath = self.store.path(key);
+            if let Some(chunk) = source.chunk_from_path((&*entrypath).with_loc(resolved_path.span),
+                                                        report)? {
+                let frame = self.frames.last_mut().unwrap();
+                frame.entry = Some(key);
+                frame.deps.extend(deps);
+                return Ok(Some(chunk));
+            }
+        }
+
+        let chunk = source.chunk_from_path(resolved_path, report)?;
+        if chunk.is_some() {
+            self.frames.last_mut().unwrap().key = Some(key);
+        }
+        Ok(chunk)
+    }
+}
+
+fn u64_to_bytes(v: u64) -> [u8; 8] {
+    let mut bytes = [0; 8];
+    for i in 0..8 {
+        bytes[i] = (v >> (i * 8)) as u8;
+    }
+    bytes
+}
+
+/// An `FsSource` wrapper which consults the cache before loading a module.
+pub struct CachingFsSource<S> {
+    source: S,
+    cache: Rc<RefCell<Option<Cache>>>,
+}
+
+impl<S> CachingFsSource<S> {
+    pub fn new(source: S, cache: Rc<RefCell<Option<Cache>>>) -> CachingFsSource<S> {
+        CachingFsSource { source: source, cache: cache }
+    }
+}
+
+impl<S: FsSource> FsSource for CachingFsSource<S> {
+    fn chunk_from_path(&self, resolved_path: Spanned<&Path>,
+                       report: &Report) -> Result<Option<Chunk>, Option<Stop>> {
+        if let Some(ref mut cache) = *self.cache.borrow_mut() {
+            cache.chunk_from_path(resolved_path, &self.source, report)
+        } else {
+            self.source.chunk_from_path(resolved_path, report)
+        }
+    }
+
+    fn data_from_path(&self, resolved_path: &Path) -> Option<Vec<u8>> {
+        self.source.data_from_path(resolved_path)
+    }
+
+    fn files_in_dir(&self, resolved_path: &Path) -> Option<Vec<PathBuf>> {
+        self.source.files_in_dir(resolved_path)
+    }
+
+    fn to_path_buf(&self, path: Spanned<&[u8]>, report: &Report) -> Result<PathBuf, Option<Stop>> {
+        self.source.to_path_buf(path, report)
+    }
+}
+
+#[cfg(test)]
+fn test_cache_with(memory: bool) {
+    use std::env;
+    use kailua_env::{Source, SourceFile};
+    use kailua_diag::{Locale, CollectedReport};
+    use kailua_syntax::parse_chunk;
+    use kailua_check::check_from_chunk;
+    use kailua_check::env::Context;
+    use {Config, Workspace, WorkspaceOptions};
+
+    struct MemorySource {
+        source: Rc<RefCell<Source>>,
+        files: Rc<RefCell<HashMap<PathBuf, &'static str>>>,
+        loaded: Rc<RefCell<Vec<PathBuf>>>,
+        store: Option<MemoryCacheStore>,
+    }
+
+    impl FsSource for MemorySource {
+        fn chunk_from_path(&self, path: Spanned<&Path>,
+                           report: &Report) -> Result<Option<Chunk>, Option<Stop>> {
+            let data = match self.data_from_path(path.base) {
+                Some(data) => data,
+                None => return Ok(None),
+            };
+            self.loaded.borrow_mut().push(path.base.to_owned());
+            let name = path.base.display().to_string();
+            let span = self.source.borrow_mut().add(SourceFile::from_u8(name, data));
+            parse_chunk(&self.source.borrow(), span, report).map(Some).map_err(Some)
+        }
+
+        fn data_from_path(&self, path: &Path) -> Option<Vec<u8>> {
+            // cache entries are read from the store or the file system
+            if path.extension().map_or(false, |ext| ext == "kailua") {
+                if let Some(ref store) = self.store {
+                    return store.get(path).map(|data| data.into_bytes());
+                }
+                let mut data = Vec::new();
+                return File::open(path).and_then(|mut f| f.read_to_end(&mut data)).ok()
+                                       .map(|_| data);
+            }
+            self.files.borrow().get(path).map(|data| data.as_bytes().to_owned())
+        }
+    }
+
+    let base_dir = env::temp_dir().join(format!("kailua-cache-test-{}-{}",
+                                                ::std::process::id(), memory));
+    let _ = fs::remove_dir_all(&base_dir);
+
+    let mut config = Config::from_base_dir(base_dir.clone());
+    config.start_paths = vec![base_dir.join("main.lua")];
+    config.cache = !memory;
+    let workspace = Workspace::new(&config, Locale::dummy()).unwrap();
+    let store = if memory { Some(MemoryCacheStore::new(base_dir.join(CACHE_DIR))) } else { None };
+
+    let source = Rc::new(RefCell::new(Source::new()));
+    let files = Rc::new(RefCell::new(HashMap::new()));
+    files.borrow_mut().insert(base_dir.join("a.lua"), "\
+        local b = require 'b'\n\
+        local M = {}\n\
+        M.twice = b.twice\n\
+        M.name = 'a' --: string\n\
+        return M\n");
+    files.borrow_mut().insert(base_dir.join("b.lua"), "\
+        local M = {}\n\
+        --v function(x: integer) --> integer\n\
+        function M.twice(x) return x * 2 end\n\
+        return M\n");
+
+    // returns paths loaded by `chunk_from_path` and the number of reports
+    let check = || {
+        let loaded = Rc::new(RefCell::new(Vec::new()));
+        let fssource = MemorySource {
+            source: source.clone(), files: files.clone(), loaded: loaded.clone(),
+            store: store.clone(),
+        };
+        let mut opts = WorkspaceOptions::new(fssource, &base_dir.join("main.lua"), &workspace);
+        if let Some(ref store) = store {
+            opts.set_cache_store(Box::new(store.clone()));
+        }
+
+        let report = Rc::new(CollectedReport::new(Locale::dummy()));
+        let span = source.borrow_mut().add(SourceFile::from_u8("main.lua".to_string(), b"\
+            --# open lua51\n\
+            local a = require 'a'\n\
+            local x = a.twice(21) --: integer\n\
+            local y = a.name --: string\n\
+            a.new = 1\n".to_vec()));
+        let chunk = parse_chunk(&source.borrow(), span, &*report).unwrap();
+        {
+            let mut context = Context::new(report.clone());
+            check_from_chunk(&mut context, chunk, Rc::new(RefCell::new(opts))).unwrap();
+        }
+        let reports = Rc::try_unwrap(report).ok().unwrap().into_reports();
+
+        let loaded = loaded.borrow().iter().map(|path| {
+            let path = path.strip_prefix(&base_dir).unwrap_or(path);
+            let path = path.strip_prefix(CACHE_DIR).unwrap_or(path);
+            if path.extension().map_or(false, |ext| ext == "kailua") {
+                "<cache>".to_string()
+            } else {
+                path.display().to_string()
+            }
+        }).collect::<Vec<_>>();
+        (loaded, reports.len())
+    };
+
+    // the first check fills the cache, and the second check uses it.
+    // the cached module should be as extensible as the original one.
+    assert_eq!(check(), (vec!["a.lua".to_string(), "b.lua".to_string()], 0));
+    assert_eq!(check(), (vec!["<cache>".to_string()], 0));
+
+    // a change to the requirer alone should not affect the dependency
+    files.borrow_mut().insert(base_dir.join("a.lua"), "\
+        local b = require 'b'\n\
+        local M = {}\n\
+        M.twice = b.twice\n\
+        M.name = 'A' --: string\n\
+        return M\n");
+    assert_eq!(check(), (vec!["a.lua".to_string(), "<cache>".to_string()], 0));
+    assert_eq!(check(), (vec!["<cache>".to_string()], 0));
+
+    // a change to the dependency should invalidate the requirer as well
+    files.borrow_mut().insert(base_dir.join("b.lua"), "\
+        local M = {}\n\
+        --v function(x: integer) --> integer\n\
+        function M.twice(x) return x + x end\n\
+        return M\n");
+    if let Some(ref store) = store {
+        // the entry for the previous requirer is stale but kept until evicted
+        assert_eq!(store.entries.read().len(), 3);
+        store.evict(&base_dir.join("b.lua"));
+        assert_eq!(store.entries.read().len(), 0);
+    }
+    assert_eq!(check(), (vec!["a.lua".to_string(), "b.lua".to_string()], 0));
+    assert_eq!(check(), (vec!["<cache>".to_string()], 0));
+
+    // modules with errors are not cached
+    files.borrow_mut().insert(base_dir.join("b.lua"), "\
+        local M = {}\n\
+        --v function(x: integer) --> integer\n\
+        function M.twice(x) return 'x' end\n\
+        return M\n");
+    assert_eq!(check().0, vec!["a.lua".to_string(), "b.lua".to_string()]);
+    assert_eq!(check().0, vec!["a.lua".to_string(), "b.lua".to_string()]);
+
+    let _ = fs::remove_dir_all(&base_dir);
+}
+
+#[test]
+fn test_dir_cache() {
+    test_cache_with(false);
+}
+
+#[test]
+fn test_memory_cache() {
+    test_cache_with(true);
+}
+
+#[test]
+fn test_memory_cache_with_backing_store() {
+    use std::env;
+
+    let dir = env::temp_dir().join(format!("kailua-cache-test-{}-backing", ::std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    // entries are written to both stores
+    let store = MemoryCacheStore::new(dir.clone());
+    let backed = store.with_backing_store(DirCacheStore::new(dir.clone()));
+    backed.write(42, "-- entry\n".to_string());
+    assert_eq!(store.get(&store.path(42)), Some("-- entry\n".to_string()));
+    assert_eq!(DirCacheStore::new(dir.clone()).read(42), Some("-- entry\n".to_string()));
+
+    // a new session reads the entry from the backing store into the memory
+    let store = MemoryCacheStore::new(dir.clone());
+    assert_eq!(store.read(42), None);
+    let backed = store.with_backing_store(DirCacheStore::new(dir.clone()));
+    assert_eq!(backed.read(42), Some("-- entry\n".to_string()));
+    assert_eq!(store.get(&store.path(42)), Some("-- entry\n".to_string()));
+
+    backed.remove(42);
+    assert_eq!(store.get(&store.path(42)), None);
+    assert_eq!(DirCacheStore::new(dir.clone()).read(42), None);
+
+    let _ = fs::remove_dir_all(&dir);
+}
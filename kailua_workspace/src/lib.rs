@@ -9,23 +9,35 @@ extern crate serde;
 extern crate serde_json;
 #[macro_use] extern crate parse_generics_shim;
 extern crate regex;
+extern crate parking_lot;
 #[macro_use] extern crate lazy_static;
 extern crate kailua_env;
 #[macro_use] extern crate kailua_diag;
 extern crate kailua_syntax;
+extern crate kailua_types;
 extern crate kailua_check;
 
 use std::error::Error;
 use std::io::{self, Read};
 use std::fs::File;
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::path::{Path, PathBuf};
+use std::collections::HashMap;
 use kailua_env::{Spanned, WithLoc};
 use kailua_diag::{Report, NoReport, Reporter, Stop, Locale};
 use kailua_syntax::Chunk;
+use kailua_types::ty::TypeContext;
 use kailua_check::Preload;
+use kailua_check::env::{CheckerConfig, Module};
 use kailua_check::options::{Options, FsSource, FsOptions};
+use cache::{Cache, CachingFsSource};
+pub use cache::{CacheStore, DirCacheStore, MemoryCacheStore};
 
 mod message;
+mod cache;
+
+pub use cache::CACHE_DIR;
 
 /// A configuration being built.
 ///
@@ -61,8 +73,27 @@ pub struct Config {
     /// Preloading options.
     pub preload: Preload,
 
+    /// User-registered libraries for `--# open`, mapped to definition files or directories.
+    ///
+    /// Built-in libraries take precedence over them.
+    pub libraries: HashMap<String, Vec<PathBuf>>,
+
     /// A preferred message locale, if any.
     pub message_locale: Option<Locale>,
+
+    /// Message codes to be ignored everywhere.
+    pub ignored_codes: Vec<String>,
+
+    /// Optional checks to be enabled or disabled.
+    ///
+    /// The configuration file only overrides given switches,
+    /// so the caller can set the defaults before reading it.
+    pub checker: CheckerConfig,
+
+    /// True if checked modules should be cached to `BASE_DIR/.kailua-cache`.
+    ///
+    /// The configuration file only overrides this when given.
+    pub cache: bool,
 }
 
 impl Config {
@@ -75,7 +106,11 @@ impl Config {
             package_path: None,
             package_cpath: None,
             preload: Preload::default(),
+            libraries: HashMap::new(),
             message_locale: None,
+            ignored_codes: Vec::new(),
+            checker: CheckerConfig::default(),
+            cache: false,
         }
     }
 
@@ -87,7 +122,11 @@ impl Config {
             package_path: None,
             package_cpath: None,
             preload: Preload::default(),
+            libraries: HashMap::new(),
             message_locale: None,
+            ignored_codes: Vec::new(),
+            checker: CheckerConfig::default(),
+            cache: false,
         }
     }
 
@@ -107,18 +146,34 @@ impl Config {
             package_cpath: Option<String>,
             message_lang: Option<String>,
             preload: Option<Preload>,
+            #[serde(default)] libraries: HashMap<String, LibraryPath>,
+            #[serde(default)] ignore: Vec<String>,
+            checker: Option<Checker>,
+            cache: Option<bool>,
         }
 
         #[derive(Deserialize, Clone, Debug)]
         #[serde(untagged)]
         enum StartPath { Single(PathBuf), Multi(Vec<PathBuf>) }
 
+        #[derive(Deserialize, Clone, Debug)]
+        #[serde(untagged)]
+        enum LibraryPath { Single(PathBuf), Multi(Vec<PathBuf>) }
+
         #[derive(Deserialize, Clone, Debug)]
         struct Preload {
             #[serde(default)] open: Vec<String>,
             #[serde(default)] require: Vec<String>,
         }
 
+        #[derive(Deserialize, Clone, Debug)]
+        struct Checker {
+            warn_on_dead_code: Option<bool>,
+            warn_on_useless_conds: Option<bool>,
+            no_implicit_func_sig: Option<bool>,
+            strict: Option<bool>,
+        }
+
         fn invalid_data<E: Into<Box<Error + Send + Sync>>>(e: E) -> io::Error {
             io::Error::new(io::ErrorKind::InvalidData, e)
         }
@@ -181,6 +236,31 @@ impl Config {
                 s.into_bytes().without_loc()
             }).collect();
         }
+        self.libraries = data.libraries.into_iter().map(|(name, path)| {
+            let paths = match path {
+                LibraryPath::Single(p) => vec![self.base_dir.join(p)],
+                LibraryPath::Multi(pp) => pp.into_iter().map(|p| self.base_dir.join(p)).collect(),
+            };
+            (name, paths)
+        }).collect();
+        self.ignored_codes = data.ignore;
+        if let Some(checker) = data.checker {
+            if let Some(value) = checker.warn_on_dead_code {
+                self.checker.warn_on_dead_code = value;
+            }
+            if let Some(value) = checker.warn_on_useless_conds {
+                self.checker.warn_on_useless_conds = value;
+            }
+            if let Some(value) = checker.no_implicit_func_sig {
+                self.checker.no_implicit_func_sig = value;
+            }
+            if let Some(value) = checker.strict {
+                self.checker.strict = value;
+            }
+        }
+        if let Some(cache) = data.cache {
+            self.cache = cache;
+        }
 
         Ok(true)
     }
@@ -205,7 +285,11 @@ pub struct Workspace {
     package_path: Option<Vec<u8>>,
     package_cpath: Option<Vec<u8>>,
     preload: Preload,
+    libraries: HashMap<String, Vec<PathBuf>>,
     message_locale: Locale,
+    ignored_codes: Vec<String>,
+    checker_config: CheckerConfig,
+    cache: bool,
 }
 
 impl Workspace {
@@ -221,7 +305,11 @@ impl Workspace {
             package_path: config.package_path.clone(),
             package_cpath: config.package_cpath.clone(),
             preload: config.preload.clone(),
+            libraries: config.libraries.clone(),
             message_locale: config.message_locale.unwrap_or(default_locale),
+            ignored_codes: config.ignored_codes.clone(),
+            checker_config: config.checker.clone(),
+            cache: config.cache,
         })
     }
 
@@ -241,20 +329,49 @@ impl Workspace {
         &self.preload
     }
 
+    pub fn libraries(&self) -> &HashMap<String, Vec<PathBuf>> {
+        &self.libraries
+    }
+
     pub fn message_locale(&self) -> Locale {
         self.message_locale
     }
+
+    pub fn ignored_codes(&self) -> &[String] {
+        &self.ignored_codes
+    }
+
+    pub fn checker_config(&self) -> &CheckerConfig {
+        &self.checker_config
+    }
+
+    /// Returns a path to the cache directory if the cache is enabled.
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        if self.cache { Some(self.base_dir.join(CACHE_DIR)) } else { None }
+    }
 }
 
 /// An extension to `FsOptions` that is initialized from an workspace.
+///
+/// If the workspace has the cache enabled, required modules are also cached to the disk.
 pub struct WorkspaceOptions<S> {
-    options: FsOptions<S>,
+    options: FsOptions<CachingFsSource<S>>,
+    cache: Rc<RefCell<Option<Cache>>>,
     can_update_package_path: bool,
     can_update_package_cpath: bool,
+    strict: bool,
 }
 
 impl<S: FsSource> WorkspaceOptions<S> {
     pub fn new(source: S, start_path: &Path, workspace: &Workspace) -> WorkspaceOptions<S> {
+        let strict = workspace.checker_config().strict;
+        let cache = workspace.cache_dir().map(|dir| {
+            let mut cache = Cache::new(Box::new(DirCacheStore::new(dir)));
+            cache.set_strict(strict);
+            cache
+        });
+        let cache = Rc::new(RefCell::new(cache));
+        let source = CachingFsSource::new(source, cache.clone());
         let mut options = FsOptions::new(source, workspace.base_dir.clone());
         if let Some(ref path) = workspace.package_path {
             let path = apply_search_paths_template(path, start_path).expect(
@@ -268,19 +385,43 @@ impl<S: FsSource> WorkspaceOptions<S> {
             );
             let _ = options.set_package_cpath((&path[..]).without_loc(), &NoReport);
         }
+        for (name, paths) in &workspace.libraries {
+            options.add_library(name.as_bytes().to_owned(), paths.clone());
+        }
 
         WorkspaceOptions {
             options: options,
+            cache: cache,
             can_update_package_path: workspace.package_path.is_none(),
             can_update_package_cpath: workspace.package_cpath.is_none(),
+            strict: strict,
         }
     }
+
+    /// Replaces the cache store, which defaults to `DirCacheStore` if the workspace has
+    /// the cache enabled. This should be called before the checking.
+    pub fn set_cache_store(&mut self, store: Box<CacheStore>) {
+        let mut cache = Cache::new(store);
+        cache.set_strict(self.strict);
+        *self.cache.borrow_mut() = Some(cache);
+    }
+
+    /// Disables the cache even when the workspace has the cache enabled,
+    /// so that every module is checked from the source code.
+    /// This should be called before the checking.
+    pub fn disable_cache(&mut self) {
+        *self.cache.borrow_mut() = None;
+    }
 }
 
 impl<S: FsSource> Options for WorkspaceOptions<S> {
     fn set_package_path(&mut self, path: Spanned<&[u8]>,
                         report: &Report) -> Result<(), Option<Stop>> {
         if self.can_update_package_path {
+            // modules requiring the changed module will see a different search path
+            if let Some(ref mut cache) = *self.cache.borrow_mut() {
+                cache.invalidate();
+            }
             self.options.set_package_path(path, report)
         } else {
             report.warn(path.span, message::PackagePathIsExplicitlySet {}).done()?;
@@ -291,6 +432,9 @@ impl<S: FsSource> Options for WorkspaceOptions<S> {
     fn set_package_cpath(&mut self, path: Spanned<&[u8]>,
                          report: &Report) -> Result<(), Option<Stop>> {
         if self.can_update_package_cpath {
+            if let Some(ref mut cache) = *self.cache.borrow_mut() {
+                cache.invalidate();
+            }
             self.options.set_package_cpath(path, report)
         } else {
             report.warn(path.span, message::PackageCpathIsExplicitlySet {}).done()?;
@@ -300,7 +444,40 @@ impl<S: FsSource> Options for WorkspaceOptions<S> {
 
     fn require_chunk(&mut self, path: Spanned<&[u8]>,
                      report: &Report) -> Result<Chunk, Option<Stop>> {
-        self.options.require_chunk(path, report)
+        if let Some(ref mut cache) = *self.cache.borrow_mut() {
+            cache.begin();
+        }
+        let ret = self.options.require_chunk(path, report);
+        if ret.is_err() {
+            if let Some(ref mut cache) = *self.cache.borrow_mut() {
+                cache.abort();
+            }
+        }
+        ret
+    }
+
+    fn library_chunks(&mut self, name: Spanned<&[u8]>,
+                      report: &Report) -> Result<Vec<Chunk>, Option<Stop>> {
+        self.options.library_chunks(name, report)
+    }
+
+    fn module_environment(&mut self, _path: Spanned<&[u8]>, environment: &str) {
+        if let Some(ref mut cache) = *self.cache.borrow_mut() {
+            cache.set_environment(environment);
+        }
+    }
+
+    fn module_checked(&mut self, path: Spanned<&[u8]>, module: Option<&Module>,
+                      types: &TypeContext, reusable: bool) {
+        if let Some(ref mut cache) = *self.cache.borrow_mut() {
+            cache.end(&path, module, types, reusable);
+        }
+    }
+
+    fn module_reused(&mut self, path: Spanned<&[u8]>) {
+        if let Some(ref mut cache) = *self.cache.borrow_mut() {
+            cache.reuse(&path);
+        }
     }
 }
 
@@ -10,105 +10,366 @@ extern crate kailua_workspace;
 extern crate kailua_langsvr;
 
 use std::io;
-use std::path::Path;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::collections::{HashMap, BTreeMap};
+use std::path::{Path, PathBuf};
 use clap::{App, Error, ErrorKind};
+use kailua_env::{Spanned, Source, SourceFile};
+use kailua_diag::{Report, Stop};
+use kailua_diag::structured::Format;
+use kailua_syntax::{parse_chunk, Chunk};
+use kailua_check::options::FsSource;
 use kailua_workspace::Workspace;
 
-fn parse_and_check(workspace: &Workspace, quiet: bool) -> Result<(), String> {
-    use std::str;
-    use std::io;
-    use std::cell::RefCell;
-    use std::rc::Rc;
-    use std::path::Path;
+// parsed chunks are shared across clones, so that each file is parsed only once
+#[derive(Clone)]
+struct LocalFsSource {
+    source: Rc<RefCell<Source>>,
+    chunks: Rc<RefCell<HashMap<PathBuf, Option<Chunk>>>>, // None if failed to parse
+}
+
+impl LocalFsSource {
+    fn new(source: Rc<RefCell<Source>>) -> LocalFsSource {
+        LocalFsSource { source: source, chunks: Rc::new(RefCell::new(HashMap::new())) }
+    }
+}
+
+impl FsSource for LocalFsSource {
+    fn chunk_from_path(&self, resolved_path: Spanned<&Path>,
+                       report: &Report) -> Result<Option<Chunk>, Option<Stop>> {
+        if let Some(chunk) = self.chunks.borrow().get(resolved_path.base) {
+            return chunk.clone().map(Some).ok_or(Some(Stop));
+        }
+
+        match SourceFile::from_file(&resolved_path) {
+            Ok(file) => {
+                let span = self.source.borrow_mut().add(file);
+                let chunk = parse_chunk(&self.source.borrow(), span, report).ok();
+                self.chunks.borrow_mut().insert(resolved_path.base.to_owned(), chunk.clone());
+                // we have already reported parsing errors
+                chunk.map(Some).ok_or(Some(Stop))
+            }
+            Err(e) => {
+                if e.kind() == io::ErrorKind::NotFound {
+                    Ok(None)
+                } else {
+                    Err(None)
+                }
+            }
+        }
+    }
+}
 
-    use kailua_env::{Span, Spanned, Source, SourceFile, WithLoc};
+fn parse_and_check(workspace: &Workspace, quiet: bool, format: Option<Format>,
+                   stats: bool) -> Result<(), String> {
+    use kailua_env::{Span, WithLoc};
     use kailua_diag::message::{Locale, Localize};
-    use kailua_diag::report::{Stop, Kind, Report, ConsoleReport, TrackMaxKind};
-    use kailua_syntax::{parse_chunk, Chunk};
+    use std::cell::Cell;
+    use kailua_diag::report::{Kind, ConsoleReport, TrackMaxKind, DedupReport};
+    use kailua_diag::structured::StructuredReport;
     use kailua_check::check_from_chunk_with_preloading;
     use kailua_check::env::Context;
-    use kailua_check::options::FsSource;
+    use kailua_check::stats::{Stats, stats_by_unit};
     use kailua_workspace::WorkspaceOptions;
 
-    struct LocalFsSource {
-        source: Rc<RefCell<Source>>,
+    enum OutputReport {
+        Quiet(Locale),
+        Console(ConsoleReport),
+        Structured(Rc<StructuredReport>),
     }
 
-    impl FsSource for LocalFsSource {
-        fn chunk_from_path(&self, resolved_path: Spanned<&Path>,
-                           report: &Report) -> Result<Option<Chunk>, Option<Stop>> {
-            match SourceFile::from_file(&resolved_path) {
-                Ok(file) => {
-                    let span = self.source.borrow_mut().add(file);
-                    if let Ok(chunk) = parse_chunk(&self.source.borrow(), span, report) {
-                        Ok(Some(chunk))
-                    } else {
-                        Err(Some(Stop)) // we have already reported parsing errors
-                    }
-                }
-                Err(e) => {
-                    if e.kind() == io::ErrorKind::NotFound {
-                        Ok(None)
-                    } else {
-                        Err(None)
-                    }
-                }
+    impl Report for OutputReport {
+        fn message_locale(&self) -> Locale {
+            match *self {
+                OutputReport::Quiet(locale) => locale,
+                OutputReport::Console(ref report) => report.message_locale(),
+                OutputReport::Structured(ref report) => report.message_locale(),
+            }
+        }
+
+        fn add_span(&self, kind: Kind, span: Span, msg: &Localize) -> kailua_diag::Result<()> {
+            match *self {
+                OutputReport::Quiet(_) => Ok(()),
+                OutputReport::Console(ref report) => report.add_span(kind, span, msg),
+                OutputReport::Structured(ref report) => report.add_span(kind, span, msg),
             }
         }
     }
 
-    struct OptionalConsoleReport {
-        quiet: bool,
-        report: ConsoleReport,
+    // counts errors and warnings for each start path, including duplicates
+    struct CountingReport<R> {
+        report: R,
+        errors: Cell<usize>,
+        warnings: Cell<usize>,
+        fatal: Cell<bool>,
     }
 
-    impl Report for OptionalConsoleReport {
+    impl<R: Report> Report for CountingReport<R> {
         fn message_locale(&self) -> Locale {
             self.report.message_locale()
         }
 
         fn add_span(&self, kind: Kind, span: Span, msg: &Localize) -> kailua_diag::Result<()> {
-            if self.quiet {
-                Ok(())
-            } else {
-                self.report.add_span(kind, span, msg)
+            match kind {
+                Kind::Error => self.errors.set(self.errors.get() + 1),
+                Kind::Fatal => {
+                    self.errors.set(self.errors.get() + 1);
+                    self.fatal.set(true);
+                }
+                Kind::Warning => self.warnings.set(self.warnings.get() + 1),
+                _ => {}
             }
+            self.report.add_span(kind, span, msg)
         }
     }
 
+    fn plural(n: usize, what: &str) -> String {
+        format!("{} {}{}", n, what, if n == 1 { "" } else { "s" })
+    }
+
     let source = Rc::new(RefCell::new(Source::new()));
-    let report = Rc::new(TrackMaxKind::new(OptionalConsoleReport {
-        quiet: quiet,
-        report: ConsoleReport::with_locale(source.clone(), workspace.message_locale()),
-    }));
-
-    // TODO multiple outputs should deduplicate warnings if possible
-    for start_path in workspace.start_paths() {
-        let mut context = Context::new(report.clone());
-
-        let fssource = LocalFsSource { source: source.clone() };
-        let filechunk = match fssource.chunk_from_path((**start_path).without_loc(), &report) {
-            Ok(Some(chunk)) => chunk,
-            _ => {
-                return Err(format!("Couldn't open a start path `{}`", start_path.display()));
+    let locale = workspace.message_locale();
+    let structured = format.map(|_| Rc::new(StructuredReport::new(source.clone(), locale)));
+    let output_report = if let Some(ref structured) = structured {
+        OutputReport::Structured(structured.clone())
+    } else if quiet {
+        OutputReport::Quiet(locale)
+    } else {
+        OutputReport::Console(ConsoleReport::with_locale(source.clone(), locale))
+    };
+
+    // files shared by multiple start paths are parsed once and reported once
+    let report = Rc::new(TrackMaxKind::new(DedupReport::new(output_report)));
+    let fssource = LocalFsSource::new(source.clone());
+    let multiple = workspace.start_paths().len() > 1;
+
+    // files shared by multiple start paths are counted once
+    let mut stats_by_path = BTreeMap::new();
+
+    let result = (|| {
+        for start_path in workspace.start_paths() {
+            let start_report = CountingReport {
+                report: report.clone(),
+                errors: Cell::new(0),
+                warnings: Cell::new(0),
+                fatal: Cell::new(false),
+            };
+            let duplicates_before = report.get_ref().num_duplicates();
+
+            let ok = {
+                let mut context = Context::new(&start_report);
+                context.set_config(workspace.checker_config().clone());
+                for code in workspace.ignored_codes() {
+                    context.ignore_code(&code[..]);
+                }
+
+                let filechunk = match fssource.chunk_from_path((**start_path).without_loc(),
+                                                               &start_report) {
+                    Ok(Some(chunk)) => Some(chunk),
+                    Ok(None) | Err(None) => {
+                        return Err(format!("Couldn't open a start path `{}`",
+                                           start_path.display()));
+                    }
+                    Err(Some(Stop)) => None, // parsing errors
+                };
+
+                let ok = filechunk.map_or(false, |filechunk| {
+                    let opts = Rc::new(RefCell::new(WorkspaceOptions::new(fssource.clone(),
+                                                                          start_path,
+                                                                          workspace)));
+                    check_from_chunk_with_preloading(&mut context, filechunk, opts,
+                                                     workspace.preload()).is_ok()
+                });
+
+                if stats {
+                    let source = source.borrow();
+                    for (unit, unitstats) in stats_by_unit(&context) {
+                        if let Some(file) = source.get_file(unit) {
+                            stats_by_path.entry(file.path().to_owned()).or_insert(unitstats);
+                        }
+                    }
+                }
+
+                ok
+            };
+
+            if multiple && !quiet {
+                let duplicates = report.get_ref().num_duplicates() - duplicates_before;
+                eprintln!("{}: {}, {}{}",
+                          start_path.display(),
+                          plural(start_report.errors.get(), "error"),
+                          plural(start_report.warnings.get(), "warning"),
+                          if duplicates > 0 {
+                              format!(" ({} already shown)", duplicates)
+                          } else {
+                              String::new()
+                          });
             }
-        };
 
-        // stop after parsing errors (not very useful for CLI usage)
-        if !report.can_continue() {
-            return Err(format!("Stopped due to prior errors"));
+            // fatal errors stop the checking altogether
+            if !ok && start_report.fatal.get() {
+                return Err(format!("Stopped due to prior errors"));
+            }
         }
 
-        let opts = Rc::new(RefCell::new(WorkspaceOptions::new(fssource, start_path, workspace)));
+        if report.can_continue() {
+            Ok(())
+        } else {
+            Err(format!("Stopped due to prior errors"))
+        }
+    })();
+
+    // statistics are also printed even when the checking has failed
+    if stats {
+        fn percent(ratio: Option<f64>) -> String {
+            ratio.map_or("-".to_string(), |r| format!("{:.1}%", r * 100.0))
+        }
+
+        fn print_row(name: &str, stats: &Stats) {
+            println!("{:<32} {:>7} {:>8} {:>5} {:>5} {:>7} {:>8} {:>7} {:>10}",
+                     name, stats.exprs, stats.whatever_exprs, stats.any_exprs,
+                     stats.oops_exprs, percent(stats.typed_exprs_ratio()),
+                     stats.no_check_funcs, stats.names,
+                     percent(stats.annotated_names_ratio()));
+        }
+
+        println!("{:<32} {:>7} {:>8} {:>5} {:>5} {:>7} {:>8} {:>7} {:>10}",
+                 "file", "exprs", "WHATEVER", "any", "oops", "typed",
+                 "NO_CHECK", "names", "annotated");
+        let mut total = Stats::new();
+        for (path, stats) in &stats_by_path {
+            print_row(path, stats);
+            total += stats;
+        }
+        print_row("(total)", &total);
+    }
 
-        let output = check_from_chunk_with_preloading(&mut context, filechunk, opts,
-                                                      workspace.preload());
-        if !(output.is_ok() && report.can_continue()) {
-            return Err(format!("Stopped due to prior errors"));
+    // structured reports are written all at once, even when the checking has failed
+    if let (Some(format), Some(structured)) = (format, structured) {
+        let stdout = io::stdout();
+        if let Err(e) = structured.write_to(format, &mut stdout.lock()) {
+            return Err(format!("Couldn't write reports: {}", e));
         }
     }
 
-    Ok(())
+    result
+}
+
+// returns a list of files that have been (or would be, if `check` is set) changed,
+// and a list of errors for files that have been skipped.
+// an error in one file does not stop formatting remaining files.
+fn format_files(paths: &[&Path], check: bool) -> (Vec<String>, Vec<String>) {
+    use std::fs::File;
+    use std::io::Write;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use kailua_env::{Source, SourceFile, SourceSlice};
+    use kailua_diag::message::{Locale, get_message_locale};
+    use kailua_diag::report::ConsoleReport;
+    use kailua_syntax::format::{format_span, FormatOptions};
+
+    let source = Rc::new(RefCell::new(Source::new()));
+    let locale = get_message_locale().unwrap_or_else(|| Locale::dummy());
+    let report = ConsoleReport::with_locale(source.clone(), locale);
+    let options = FormatOptions::default();
+
+    let mut changed = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        let file = match SourceFile::from_file(path) {
+            Ok(file) => file,
+            Err(e) => {
+                errors.push(format!("Couldn't open `{}`: {}", path.display(), e));
+                continue;
+            }
+        };
+        let original = match file.data() {
+            SourceSlice::U8(s) => s.to_owned(),
+            SourceSlice::U16(s) => String::from_utf16_lossy(s).into_bytes(),
+        };
+        let span = source.borrow_mut().add(file);
+
+        let formatted = match format_span(&source.borrow(), span, &options, &report) {
+            Ok(Some(formatted)) => formatted,
+            Ok(None) => {
+                errors.push(format!("Couldn't format `{}` without changing its meaning",
+                                    path.display()));
+                continue;
+            }
+            Err(_) => {
+                errors.push(format!("Couldn't format `{}` due to prior errors",
+                                    path.display()));
+                continue;
+            }
+        };
+
+        if original != formatted {
+            if !check {
+                if let Err(e) = File::create(path).and_then(|mut f| f.write_all(&formatted)) {
+                    errors.push(format!("Couldn't write to `{}`: {}", path.display(), e));
+                    continue;
+                }
+            }
+            changed.push(path.display().to_string());
+        }
+    }
+
+    (changed, errors)
+}
+
+// checks a module at `path` and writes its declarations to `output`
+fn generate_stub(workspace: &Workspace, path: &Path, output: &Path) -> Result<(), String> {
+    use std::fs::File;
+    use std::io::Write;
+
+    use kailua_env::WithLoc;
+    use kailua_diag::report::{ConsoleReport, TrackMaxKind};
+    use kailua_check::check_module_from_chunk_with_preloading;
+    use kailua_check::env::Context;
+    use kailua_check::stub::module_to_stub;
+    use kailua_workspace::WorkspaceOptions;
+
+    let source = Rc::new(RefCell::new(Source::new()));
+    let report = Rc::new(TrackMaxKind::new(
+        ConsoleReport::with_locale(source.clone(), workspace.message_locale())
+    ));
+
+    let mut context = Context::new(report.clone());
+    context.set_config(workspace.checker_config().clone());
+    for code in workspace.ignored_codes() {
+        context.ignore_code(&code[..]);
+    }
+
+    let fssource = LocalFsSource::new(source.clone());
+    let chunk = match fssource.chunk_from_path(path.without_loc(), &report) {
+        Ok(Some(chunk)) => chunk,
+        _ => return Err(format!("Couldn't open a module `{}`", path.display())),
+    };
+    if !report.can_continue() {
+        return Err(format!("Stopped due to prior errors"));
+    }
+
+    let opts = Rc::new(RefCell::new(WorkspaceOptions::new(fssource, path, workspace)));
+    let modname = path.display().to_string();
+    let module = check_module_from_chunk_with_preloading(&mut context, chunk,
+                                                         modname.as_bytes().without_loc(),
+                                                         opts, workspace.preload());
+    let module = match module {
+        Ok(Some(ref module)) if report.can_continue() => module.clone(),
+        _ => return Err(format!("Stopped due to prior errors")),
+    };
+
+    let output_data = context.into_output();
+    let stub = module_to_stub(&module, output_data.types());
+    let contents = format!("-- generated by `kailua stub` from `{}`\n\n{}",
+                           path.file_name().map_or(modname.clone(), |name| {
+                               name.to_string_lossy().into_owned()
+                           }), stub);
+    File::create(output).and_then(|mut f| f.write_all(contents.as_bytes())).map_err(|e| {
+        format!("Couldn't write to `{}`: {}", output.display(), e)
+    })
 }
 
 fn build_app() -> App<'static, 'static> {
@@ -130,6 +391,8 @@ fn build_app() -> App<'static, 'static> {
                  \n\
                  Prints reports (can be suppressed with `-q`) to the standard error,\n\
                  then terminates with an exit code 1 on error.\n\
+                 Machine-readable reports can be requested with `-f`, \
+                 in which case they are printed to the standard output instead.\n\
                  The configuration can be either given as JSON or command-line options.")
             (@arg config: -c --config [PATH]
                 "Overrides a default configuration path.\n\
@@ -153,8 +416,34 @@ fn build_app() -> App<'static, 'static> {
                  Similar to `--add-package-cpath` but will ignore the default values.")
             (@arg quiet: -q --quiet
                 "Suppresses all reports.")
+            (@arg format: -f --format [FORMAT]
+                possible_values(&["console", "json", "sarif"])
+                conflicts_with[quiet]
+                "Sets the report format. Defaults to `console`.\n\
+                 `json` prints one JSON object per line for each report, \
+                 with related causes and notes nested in `related`.\n\
+                 `sarif` prints a single SARIF 2.1.0 log.")
             (@arg message_locale: -l --("message-locale") [LOCALE]
                 "Sets the message locales. Defaults to the system language.")
+            (@arg warn_on_dead_code: --("warn-on-dead-code")
+                "Warns about statements and loop bodies that can never be executed.")
+            (@arg warn_on_useless_conds: --("warn-on-useless-conds")
+                "Warns about `if` conditions that are always truthy or falsy.")
+            (@arg strict: --strict
+                "Checks every file in the strict mode, as if it had a `--# strict` directive.")
+            (@arg allow_implicit_func_sig: --("allow-implicit-func-sig")
+                "Allows functions without argument types and infers them from the uses.\n\
+                 By default they are errors unless the types can be inferred from the context.")
+            (@arg stats: --stats
+                conflicts_with[format cache]
+                "Prints the type coverage for each file and in total after checking: \
+                 the number of expressions inferred as `WHATEVER`, `any` or erroneous types, \
+                 the number of `[NO_CHECK]` functions and \
+                 the ratio of annotated local variables and arguments.\n\
+                 The cache is disabled in this mode.")
+            (@arg cache: --cache
+                "Caches required modules checked without any report to `BASE_DIR/.kailua-cache`, \
+                 and reuses them until the modules and their dependencies are changed.")
             (@arg path:
                 "A path to start checking. \
                  This can be either a path to the base directory \
@@ -162,6 +451,43 @@ fn build_app() -> App<'static, 'static> {
                  or a path to the Lua file in which case the configuration path should be given. \
                  Defaults to the current directory.")
         )
+        (@subcommand fmt =>
+            (@setting UnifiedHelpMessage)
+            (@setting NextLineHelp)
+            (about:
+                "Formats Lua source files in place.\n\
+                 \n\
+                 Only whitespace between tokens is changed; comments and meta comments \
+                 are kept as is.\n\
+                 Files with parsing errors are skipped and reported, \
+                 and the command terminates with an exit code 1 after formatting other files.")
+            (@arg check: --check
+                "Does not write any file, but prints paths to files that are not formatted \
+                 and terminates with an exit code 1 if any.")
+            (@arg paths: +required +multiple
+                "Paths to Lua files to format.")
+        )
+        (@subcommand stub =>
+            (@setting UnifiedHelpMessage)
+            (@setting NextLineHelp)
+            (about:
+                "Checks a module and writes a declaration file for it.\n\
+                 \n\
+                 The declaration file contains `--# type` declarations for exported types \
+                 and an `--# assume` declaration for the returned value.\n\
+                 It is read in place of the module when `require`d \
+                 (`F.kailua` is tried before `F`).")
+            (@arg config: -c --config [PATH]
+                "Overrides a default configuration path.\n\
+                 Defaults to `kailua.json` or `.vscode/kailua.json` \
+                 in the directory containing the module, whichever comes first.")
+            (@arg output: -o --output [PATH]
+                "Sets the path to the declaration file. Defaults to `PATH.kailua`.")
+            (@arg message_locale: -l --("message-locale") [LOCALE]
+                "Sets the message locales. Defaults to the system language.")
+            (@arg path: +required
+                "A path to the Lua module to check.")
+        )
         (@subcommand langsvr =>
             (about: "Launches a language server. Not to be used directly.")
             (@group target =>
@@ -244,7 +570,30 @@ pub fn main() {
         config.package_cpath =
             parse_package_paths("set_package_cpath", "add_package_cpath").or(config.package_cpath);
 
+        if matches.is_present("warn_on_dead_code") {
+            config.checker.warn_on_dead_code = true;
+        }
+        if matches.is_present("warn_on_useless_conds") {
+            config.checker.warn_on_useless_conds = true;
+        }
+        if matches.is_present("allow_implicit_func_sig") {
+            config.checker.no_implicit_func_sig = false;
+        }
+        if matches.is_present("strict") {
+            config.checker.strict = true;
+        }
+        if matches.is_present("cache") {
+            config.cache = true;
+        }
+
+        // cached modules are not checked and would be missing from the statistics
+        let stats = matches.is_present("stats");
+        if stats {
+            config.cache = false;
+        }
+
         let quiet = matches.is_present("quiet");
+        let format = matches.value_of("format").and_then(Format::from_name);
 
         let message_locale = if let Some(locale) = matches.value_of("message_locale") {
             if let Some(locale) = Locale::new(locale) {
@@ -257,7 +606,7 @@ pub fn main() {
         };
 
         if let Some(workspace) = Workspace::new(&config, message_locale) {
-            if let Err(e) = parse_and_check(&workspace, quiet) {
+            if let Err(e) = parse_and_check(&workspace, quiet, format, stats) {
                 // clap does not have something like ErrorKind::Other :(
                 io_error(&e);
             }
@@ -273,6 +622,70 @@ pub fn main() {
         return;
     }
 
+    if let Some(ref matches) = matches.subcommand_matches("fmt") {
+        let paths: Vec<&Path> = matches.values_of("paths").unwrap().map(Path::new).collect();
+        let check = matches.is_present("check");
+
+        let (changed, errors) = format_files(&paths, check);
+        if check {
+            for path in &changed {
+                println!("{}", path);
+            }
+        }
+        for e in &errors {
+            eprintln!("error: {}", e);
+        }
+        if !errors.is_empty() || (check && !changed.is_empty()) {
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    if let Some(ref matches) = matches.subcommand_matches("stub") {
+        let path = Path::new(matches.value_of("path").unwrap());
+        let output = if let Some(output) = matches.value_of("output") {
+            Path::new(output).to_owned()
+        } else {
+            let mut output = path.as_os_str().to_owned();
+            output.push(".kailua");
+            output.into()
+        };
+
+        let mut config = Config::from_start_path(path.to_owned());
+        if let Some(config_path) = matches.value_of("config") {
+            match config.set_config_path(Path::new(config_path).to_owned()) {
+                Ok(true) => {}
+                Ok(false) => {
+                    io_error(&format!("Couldn't open a configuration file `{}`", config_path));
+                }
+                Err(e) => {
+                    io_error_while(e, &format!("opening a configuration file `{}`", config_path));
+                }
+            }
+        } else {
+            config.use_default_config_paths();
+        }
+
+        let message_locale = if let Some(locale) = matches.value_of("message_locale") {
+            if let Some(locale) = Locale::new(locale) {
+                locale
+            } else {
+                invalid_value(&format!("Unrecognized message locale `{}`", locale))
+            }
+        } else {
+            get_message_locale().unwrap_or_else(|| Locale::dummy())
+        };
+
+        // the start path is always given, so the workspace can be always created
+        let workspace = Workspace::new(&config, message_locale).unwrap();
+        if let Err(e) = generate_stub(&workspace, path, &output) {
+            io_error(&e);
+        }
+
+        return;
+    }
+
     if let Some(ref matches) = matches.subcommand_matches("langsvr") {
         // delegate to kailua_langsvr
         let target = if let Some(addr) = matches.value_of("tcp") {
@@ -294,3 +707,42 @@ pub fn main() {
     }
 }
 
+
+#[test]
+fn test_format_files_continues_after_errors() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::{Read, Write};
+
+    let base_dir = env::temp_dir().join(format!("kailua-fmt-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&base_dir);
+    fs::create_dir_all(&base_dir).unwrap();
+
+    let write = |name: &str, data: &str| {
+        let path = base_dir.join(name);
+        File::create(&path).and_then(|mut f| f.write_all(data.as_bytes())).unwrap();
+        path
+    };
+    let read = |path: &Path| {
+        let mut data = String::new();
+        File::open(path).and_then(|mut f| f.read_to_string(&mut data)).unwrap();
+        data
+    };
+
+    let a = write("a.lua", "local  x=1\n");
+    let b = write("b.lua", "local x = \n");
+    let c = write("c.lua", "local  y=2\n");
+    let missing = base_dir.join("missing.lua");
+
+    // formatting `b.lua` and `missing.lua` fails, but others are still formatted
+    let (changed, errors) = format_files(&[&a, &b, &missing, &c], false);
+    assert_eq!(changed, [a.display().to_string(), c.display().to_string()]);
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("b.lua"));
+    assert!(errors[1].contains("missing.lua"));
+    assert_eq!(read(&a), "local x = 1\n");
+    assert_eq!(read(&b), "local x = \n");
+    assert_eq!(read(&c), "local y = 2\n");
+
+    let _ = fs::remove_dir_all(&base_dir);
+}